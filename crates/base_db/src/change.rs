@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use rustc_hash::FxHashMap;
+use syntax::LuaVersion;
 use vfs::FileId;
 
 use crate::SourceDatabase;
@@ -8,6 +10,8 @@ use crate::SourceDatabase;
 #[derive(Debug, Default)]
 pub struct Change {
     pub files_changed: Vec<(FileId, Option<Arc<String>>)>,
+    pub lua_version: Option<LuaVersion>,
+    pub file_lua_versions: Vec<(FileId, Option<LuaVersion>)>,
 }
 
 impl Change {
@@ -16,14 +20,50 @@ impl Change {
     }
 
     pub fn apply(self, db: &mut dyn SourceDatabase) {
+        if let Some(version) = self.lua_version {
+            db.set_lua_version(version);
+        }
+        if !self.file_lua_versions.is_empty() {
+            let mut overrides = FxHashMap::clone(&db.lua_version_overrides());
+            for (file_id, version) in self.file_lua_versions {
+                match version {
+                    Some(version) => overrides.insert(file_id, version),
+                    None => overrides.remove(&file_id),
+                };
+            }
+            db.set_lua_version_overrides(Arc::new(overrides));
+        }
+        if self.files_changed.is_empty() {
+            return;
+        }
+
+        let mut files = Vec::clone(&db.files());
         for (file_id, text) in self.files_changed {
+            match files.binary_search(&file_id) {
+                Ok(idx) if text.is_none() => {
+                    files.remove(idx);
+                }
+                Err(idx) if text.is_some() => files.insert(idx, file_id),
+                _ => (),
+            }
             // XXX: can't actually remove the file, just reset the text
             let text = text.unwrap_or_default();
             db.set_file_text(file_id, text)
         }
+        db.set_files(Arc::new(files));
     }
 
     pub fn change_file(&mut self, file_id: FileId, new_text: Option<Arc<String>>) {
         self.files_changed.push((file_id, new_text))
     }
+
+    pub fn set_lua_version(&mut self, version: LuaVersion) {
+        self.lua_version = Some(version)
+    }
+
+    /// Overrides the version of Lua of a file, or resets it to the default
+    /// one with `None`.
+    pub fn set_file_lua_version(&mut self, file_id: FileId, version: Option<LuaVersion>) {
+        self.file_lua_versions.push((file_id, version))
+    }
 }
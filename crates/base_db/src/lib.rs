@@ -6,13 +6,26 @@ pub use change::Change;
 pub use salsa;
 pub use vfs::FileId;
 
+use rustc_hash::FxHashMap;
 use syntax::{
-    ast::{self, AstNode, SourceFile},
-    Parse, SyntaxNode,
+    ast::{self, SourceFile},
+    LuaVersion, Parse, TextRange, TextSize,
 };
 
 use std::{panic, sync::Arc};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FilePosition {
+    pub file_id: FileId,
+    pub offset: TextSize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileRange {
+    pub file_id: FileId,
+    pub range: TextRange,
+}
+
 pub trait Upcast<T: ?Sized> {
     fn upcast(&self) -> &T;
 }
@@ -63,9 +76,30 @@ pub trait SourceDatabase: CheckCanceled + std::fmt::Debug {
 
     #[salsa::input]
     fn file_text(&self, file_id: FileId) -> Arc<String>;
+
+    /// All of the files in the workspace, sorted by id.
+    #[salsa::input]
+    fn files(&self) -> Arc<Vec<FileId>>;
+
+    /// The version of Lua the files without one of their own are parsed as.
+    #[salsa::input]
+    fn lua_version(&self) -> LuaVersion;
+
+    /// The files written in another version of Lua than `lua_version`, such
+    /// as the ones under a root or matching a glob configured with their own.
+    #[salsa::input]
+    fn lua_version_overrides(&self) -> Arc<FxHashMap<FileId, LuaVersion>>;
+
+    /// The version of Lua the file is parsed as.
+    #[salsa::invoke(file_lua_version_query)]
+    fn file_lua_version(&self, file_id: FileId) -> LuaVersion;
 }
 
 fn parse_query(db: &dyn SourceDatabase, file_id: FileId) -> Parse<ast::SourceFile> {
     let text = db.file_text(file_id);
-    SourceFile::parse(&*text)
+    SourceFile::parse(&text, db.file_lua_version(file_id))
+}
+
+fn file_lua_version_query(db: &dyn SourceDatabase, file_id: FileId) -> LuaVersion {
+    db.lua_version_overrides().get(&file_id).copied().unwrap_or_else(|| db.lua_version())
 }
@@ -0,0 +1,196 @@
+//! The LuaCATS annotations of statements, and the classes and aliases they
+//! declare for a whole file.
+
+use std::sync::Arc;
+
+use base_db::FileId;
+use syntax::doc::{self, DocTag};
+
+use crate::{name::Name, type_ref::TypeRef, DefDatabase};
+
+/// The annotations in the comments above a statement.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotations {
+    /// The text of the lines that are not annotations.
+    pub doc: Option<String>,
+    /// The types of `---@type`, one for each name the statement declares.
+    pub types: Vec<TypeRef>,
+    pub params: Vec<ParamAnnotation>,
+    pub returns: Vec<ReturnAnnotation>,
+    /// The names of `---@generic`, which can be used as types by the other
+    /// annotations.
+    pub generics: Vec<Name>,
+    /// The class of `---@class`, of which the table the statement assigns is
+    /// the definition.
+    pub class: Option<Name>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamAnnotation {
+    /// The name of the parameter, which is `...` for varargs.
+    pub name: Name,
+    /// The type of the parameter, including `nil` when it is optional.
+    pub ty: TypeRef,
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReturnAnnotation {
+    pub ty: TypeRef,
+    pub name: Option<Name>,
+    pub doc: Option<String>,
+}
+
+impl Annotations {
+    /// Lowers the annotations of a block, which is `None` if it only has
+    /// text.
+    pub fn lower(block: &doc::DocBlock) -> Option<Annotations> {
+        let mut res = Annotations { doc: block.description(), ..Annotations::default() };
+        let mut has_tags = false;
+        for tag in block.tags() {
+            has_tags = true;
+            match tag {
+                DocTag::Param(tag) => {
+                    let name = match tag.name_token() {
+                        Some(it) => Name::resolve(it.text()),
+                        None => continue,
+                    };
+                    let mut ty = TypeRef::from_ast_opt(tag.ty());
+                    if tag.is_optional() {
+                        ty = ty.union(TypeRef::named("nil"));
+                    }
+                    let doc = tag.description().map(|it| it.text());
+                    res.params.push(ParamAnnotation { name, ty, doc });
+                }
+                DocTag::Return(tag) => {
+                    let doc = tag.description().map(|it| it.text());
+                    for item in tag.items() {
+                        res.returns.push(ReturnAnnotation {
+                            ty: TypeRef::from_ast_opt(item.ty()),
+                            name: item.name_token().map(|it| Name::resolve(it.text())),
+                            doc: doc.clone(),
+                        });
+                    }
+                }
+                DocTag::Type(tag) => res.types.extend(tag.types().map(TypeRef::from_ast)),
+                DocTag::Generic(tag) => res.generics.extend(
+                    tag.params().filter_map(|it| Some(Name::resolve(it.name_token()?.text()))),
+                ),
+                DocTag::Class(tag) => {
+                    res.class = tag.name_token().map(|it| Name::resolve(it.text()));
+                }
+                DocTag::Field(_) | DocTag::Alias(_) | DocTag::Other(_) => {}
+            }
+        }
+        has_tags.then_some(res)
+    }
+
+    pub fn param(&self, name: &Name) -> Option<&ParamAnnotation> {
+        self.params.iter().find(|it| it.name == *name)
+    }
+}
+
+/// The classes and aliases declared in a file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FileAnnotations {
+    pub classes: Vec<ClassData>,
+    pub aliases: Vec<AliasData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassData {
+    pub name: Name,
+    pub parents: Vec<TypeRef>,
+    pub fields: Vec<FieldData>,
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldData {
+    pub name: Name,
+    /// The type of the field, including `nil` when it is optional.
+    pub ty: TypeRef,
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasData {
+    pub name: Name,
+    /// The aliased type, which is the union of the `---|` variants.
+    pub ty: TypeRef,
+}
+
+impl FileAnnotations {
+    pub(crate) fn file_annotations_query(
+        db: &dyn DefDatabase,
+        file_id: FileId,
+    ) -> Arc<FileAnnotations> {
+        let file = db.parse(file_id).tree();
+        let mut res = FileAnnotations::default();
+        for block in doc::doc_blocks(&file) {
+            res.collect_block(&block.tree());
+        }
+        Arc::new(res)
+    }
+
+    fn collect_block(&mut self, block: &doc::DocBlock) {
+        for tag in block.tags() {
+            match tag {
+                DocTag::Class(tag) => {
+                    let name = match tag.name_token() {
+                        Some(it) => Name::resolve(it.text()),
+                        None => continue,
+                    };
+                    self.classes.push(ClassData {
+                        name,
+                        parents: tag.parents().map(TypeRef::from_ast).collect(),
+                        fields: Vec::new(),
+                        doc: block.description(),
+                    });
+                }
+                // fields belong to the last class declared before them
+                DocTag::Field(tag) => {
+                    let (class, name) = match (self.classes.last_mut(), tag.name_token()) {
+                        (Some(class), Some(name)) => (class, Name::resolve(name.text())),
+                        _ => continue,
+                    };
+                    let mut ty = TypeRef::from_ast_opt(tag.ty());
+                    if tag.is_optional() {
+                        ty = ty.union(TypeRef::named("nil"));
+                    }
+                    let doc = tag.description().map(|it| it.text());
+                    class.fields.push(FieldData { name, ty, doc });
+                }
+                DocTag::Alias(tag) => {
+                    let name = match tag.name_token() {
+                        Some(it) => Name::resolve(it.text()),
+                        None => continue,
+                    };
+                    let ty = tag
+                        .ty()
+                        .into_iter()
+                        .chain(tag.variants().filter_map(|it| it.ty()))
+                        .map(TypeRef::from_ast)
+                        .reduce(TypeRef::union)
+                        .unwrap_or(TypeRef::Error);
+                    self.aliases.push(AliasData { name, ty });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn class(&self, name: &Name) -> Option<&ClassData> {
+        self.classes.iter().find(|it| it.name == *name)
+    }
+
+    pub fn alias(&self, name: &Name) -> Option<&AliasData> {
+        self.aliases.iter().find(|it| it.name == *name)
+    }
+}
+
+impl ClassData {
+    pub fn field(&self, name: &Name) -> Option<&FieldData> {
+        self.fields.iter().find(|it| it.name == *name)
+    }
+}
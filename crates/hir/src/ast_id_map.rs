@@ -64,7 +64,7 @@ pub struct AstIdMap {
 
 impl AstIdMap {
     pub(crate) fn from_source(node: &SyntaxNode) -> AstIdMap {
-        assert!(node.parent().is_some());
+        assert!(node.parent().is_none());
         let mut res = AstIdMap::default();
         bdfs(node, |it| {
             match_ast! {
@@ -152,6 +152,7 @@ local function testing(first, second, third)
     end
 end
 "#,
+        syntax::LuaVersion::default(),
     )
     .ok()
     .unwrap();
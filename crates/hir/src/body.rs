@@ -1,19 +1,23 @@
 mod lower;
+pub mod scope;
+#[cfg(test)]
+mod tests;
 
 use std::sync::Arc;
 
 use base_db::FileId;
-use la_arena::Arena;
-use syntax::ast;
+use la_arena::{Arena, ArenaMap};
+use rustc_hash::FxHashMap;
+use syntax::{ast, AstPtr};
 
 use crate::{
-    ast_id_map::AstIdMap,
-    expr::{Expr, Label, ParamList},
+    annotations::Annotations,
+    expr::{Expr, ExprId, Label, Params},
     item_tree::HasSource,
-    name::Name,
+    name::{Name, NameId},
     stmt::{BlockLoc, Stmt, StmtId},
     with_body::WithBodyId,
-    DefDatabase, Lookup,
+    DefDatabase, InFile, Lookup,
 };
 
 #[derive(Debug, Eq, PartialEq)]
@@ -22,14 +26,91 @@ pub struct Body {
     pub stmts: Arena<Stmt>,
     pub labels: Arena<Label>,
     pub names: Arena<Name>,
-    pub params: ParamList,
+    pub params: Params,
     pub body_stmt: StmtId,
+    /// The annotations of the statements that have some.
+    pub annotations: ArenaMap<StmtId, Annotations>,
     block_scopes: Vec<BlockLoc>,
 }
 
+pub type ExprPtr = AstPtr<ast::Expr>;
+pub type ExprSource = InFile<ExprPtr>;
+
+pub type StmtPtr = AstPtr<ast::Stmt>;
+pub type StmtSource = InFile<StmtPtr>;
+
+pub type NamePtr = AstPtr<ast::Name>;
+pub type NameSource = InFile<NamePtr>;
+
+/// An item body together with the mapping from syntax nodes to HIR expression
+/// IDs. This is needed to go from e.g. a position in a file to the HIR
+/// expression containing it; but for type inference etc., we want to operate on
+/// a structure that is agnostic to the actual positions of expressions in the
+/// file, so that we don't recompute types whenever some whitespace is typed.
+///
+/// One complication here is that, due to desugaring, one syntax node can
+/// correspond to several expression nodes, or the other way around, and some
+/// nodes, such as the implicit `self` parameter, have no syntax at all.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct BodySourceMap {
+    expr_map: FxHashMap<ExprSource, ExprId>,
+    expr_map_back: ArenaMap<ExprId, ExprSource>,
+    stmt_map: FxHashMap<StmtSource, StmtId>,
+    stmt_map_back: ArenaMap<StmtId, StmtSource>,
+    name_map: FxHashMap<NameSource, NameId>,
+    name_map_back: ArenaMap<NameId, NameSource>,
+}
+
+impl BodySourceMap {
+    pub fn expr_syntax(&self, expr: ExprId) -> Option<ExprSource> {
+        self.expr_map_back.get(expr).cloned()
+    }
+
+    pub fn node_expr(&self, node: InFile<&ast::Expr>) -> Option<ExprId> {
+        let src = node.map(AstPtr::new);
+        self.expr_map.get(&src).copied()
+    }
+
+    pub fn stmt_syntax(&self, stmt: StmtId) -> Option<StmtSource> {
+        self.stmt_map_back.get(stmt).cloned()
+    }
+
+    pub fn node_stmt(&self, node: InFile<&ast::Stmt>) -> Option<StmtId> {
+        let src = node.map(AstPtr::new);
+        self.stmt_map.get(&src).copied()
+    }
+
+    pub fn name_syntax(&self, name: NameId) -> Option<NameSource> {
+        self.name_map_back.get(name).cloned()
+    }
+
+    pub fn node_name(&self, node: InFile<&ast::Name>) -> Option<NameId> {
+        let src = node.map(AstPtr::new);
+        self.name_map.get(&src).copied()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        let Self {
+            expr_map,
+            expr_map_back: _,
+            stmt_map,
+            stmt_map_back: _,
+            name_map,
+            name_map_back: _,
+        } = self;
+        expr_map.shrink_to_fit();
+        stmt_map.shrink_to_fit();
+        name_map.shrink_to_fit();
+    }
+}
+
 impl Body {
-    pub(crate) fn body_query(db: &dyn DefDatabase, def: WithBodyId) -> Arc<Body> {
+    pub(crate) fn body_with_source_map_query(
+        db: &dyn DefDatabase,
+        def: WithBodyId,
+    ) -> (Arc<Body>, Arc<BodySourceMap>) {
         let mut params = None;
+        let mut has_self_param = false;
 
         let (file_id, body) = match def {
             WithBodyId::ModuleId(file_id) => {
@@ -39,6 +120,7 @@ impl Body {
             WithBodyId::FunctionId(f) => {
                 let f = f.lookup(db);
                 let src = f.source(db);
+                has_self_param = f.item_tree(db)[f.value].is_method;
                 params = src.value.param_list();
                 (src.file_id, src.value.body().map(ast::Stmt::from))
             }
@@ -50,22 +132,37 @@ impl Body {
             }
         };
 
-        let mut body = Body::new(db, file_id, params, body);
+        let (mut body, mut source_map) = Body::new(db, file_id, params, has_self_param, body);
         body.shrink_to_fit();
-        Arc::new(body)
+        source_map.shrink_to_fit();
+        (Arc::new(body), Arc::new(source_map))
+    }
+
+    pub(crate) fn body_query(db: &dyn DefDatabase, def: WithBodyId) -> Arc<Body> {
+        db.body_with_source_map(def).0
     }
 
     fn new(
         db: &dyn DefDatabase,
         file_id: FileId,
         params: Option<ast::ParamList>,
+        has_self_param: bool,
         body: Option<ast::Stmt>,
-    ) -> Body {
-        lower::lower(db, file_id, params, body)
+    ) -> (Body, BodySourceMap) {
+        lower::lower(db, file_id, params, has_self_param, body)
     }
 
     fn shrink_to_fit(&mut self) {
-        let Self { exprs, stmts, names, labels, params, body_stmt: _, block_scopes } = self;
+        let Self {
+            exprs,
+            stmts,
+            names,
+            labels,
+            params,
+            body_stmt: _,
+            annotations: _,
+            block_scopes,
+        } = self;
         exprs.shrink_to_fit();
         stmts.shrink_to_fit();
         names.shrink_to_fit();
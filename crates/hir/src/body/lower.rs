@@ -1,26 +1,34 @@
 use std::sync::Arc;
 
 use base_db::FileId;
-use la_arena::Arena;
-use syntax::ast::{self, AstNode};
+use la_arena::{Arena, ArenaMap};
+use syntax::{
+    ast::{self, AstNode, AstToken, LiteralKind},
+    doc, AstPtr,
+};
 
-use super::Body;
+use super::{Body, BodySourceMap, ExprPtr, NamePtr, StmtPtr};
 use crate::{
+    annotations::Annotations,
     ast_id_map::AstIdMap,
-    expr::{Expr, ExprId, ParamList},
+    expr::{
+        BinaryOp, Call, CallArgs, Expr, ExprId, Label, Literal, Number, Params, Table, TableField,
+        UnaryOp,
+    },
     item_tree::AstId,
-    stmt::{self, Stmt, StmtId},
-    DefDatabase,
+    name::{AsName, Name, NameId},
+    stmt::{self, Block, BlockLoc, ForContent, Stmt, StmtId},
+    DefDatabase, InFile,
 };
 
 pub(super) fn lower(
     db: &dyn DefDatabase,
     file_id: FileId,
     params: Option<ast::ParamList>,
+    has_self_param: bool,
     body: Option<ast::Stmt>,
-) -> Body {
+) -> (Body, BodySourceMap) {
     Collector {
-        db,
         file_id,
         ast_id_map: db.ast_id_map(file_id),
         body: Body {
@@ -28,25 +36,48 @@ pub(super) fn lower(
             stmts: Arena::default(),
             labels: Arena::default(),
             names: Arena::default(),
-            params: ParamList::default(),
+            params: Params::default(),
             body_stmt: stmt::dummy_id(),
+            annotations: ArenaMap::default(),
             block_scopes: Vec::default(),
         },
+        source_map: BodySourceMap::default(),
     }
-    .collect(params, body)
+    .collect(params, has_self_param, body)
 }
 
-struct Collector<'db> {
-    db: &'db dyn DefDatabase,
+struct Collector {
     file_id: FileId,
     ast_id_map: Arc<AstIdMap>,
     body: Body,
+    source_map: BodySourceMap,
 }
 
-impl<'db> Collector<'db> {
-    fn collect(mut self, params_list: Option<ast::ParamList>, body: Option<ast::Stmt>) -> Body {
-        if let Some(param_list) = params_list {}
-        todo!()
+impl Collector {
+    fn collect(
+        mut self,
+        param_list: Option<ast::ParamList>,
+        has_self_param: bool,
+        body: Option<ast::Stmt>,
+    ) -> (Body, BodySourceMap) {
+        self.body.params = self.collect_params(param_list, has_self_param);
+        self.body.body_stmt = self.collect_stmt_opt(body);
+        (self.body, self.source_map)
+    }
+
+    fn collect_params(
+        &mut self,
+        param_list: Option<ast::ParamList>,
+        has_self_param: bool,
+    ) -> Params {
+        let self_param = if has_self_param {
+            Some(self.alloc_name_desugared(Name::resolve("self")))
+        } else {
+            None
+        };
+        let names = self.collect_multi_name_opt(param_list.as_ref().and_then(|it| it.multi_name()));
+        let vararg = param_list.and_then(|it| it.triple_dot_token()).is_some();
+        Params { self_param, names, vararg }
     }
 
     fn collect_stmt_opt(&mut self, stmt: Option<ast::Stmt>) -> StmtId {
@@ -58,28 +89,368 @@ impl<'db> Collector<'db> {
     }
 
     fn collect_stmt(&mut self, stmt: ast::Stmt) -> StmtId {
-        todo!()
+        let syntax_ptr = AstPtr::new(&stmt);
+        let annotations =
+            doc::doc_block(stmt.syntax()).and_then(|it| Annotations::lower(&it.tree()));
+        let stmt = match stmt {
+            ast::Stmt::Block(block) => Stmt::Block(self.collect_block(block)),
+            ast::Stmt::AssignStmt(stmt) => {
+                let lhs = self.collect_multival_expr_opt(stmt.lhs());
+                let rhs = self.collect_multival_expr_opt(stmt.rhs());
+                Stmt::Assign { lhs, rhs }
+            }
+            ast::Stmt::LocalAssignStmt(stmt) => {
+                // the values are lowered first as they cannot see the names being declared
+                let exprs = self.collect_multival_expr_opt(stmt.multival_expr());
+                let names = self.collect_multi_name_opt(stmt.multi_name());
+                Stmt::Local { names, exprs }
+            }
+            ast::Stmt::LocalFunctionDefStmt(stmt) => {
+                let name = self.collect_name_opt(stmt.name());
+                let func = self.collect_function(stmt.param_list(), false, stmt.body());
+                let func = self.alloc_expr_desugared(func);
+                Stmt::LocalFunction { name, func }
+            }
+            ast::Stmt::FunctionDefStmt(stmt) => {
+                let (target, is_method) = self.collect_function_target(stmt.function_def_content());
+                let func = self.collect_function(stmt.param_list(), is_method, stmt.body());
+                let func = self.alloc_expr_desugared(func);
+                Stmt::FunctionDef { target, is_method, func }
+            }
+            ast::Stmt::ForStmt(stmt) => {
+                let content = self.collect_for_content(stmt.content());
+                let body = self.collect_block_opt(stmt.block());
+                Stmt::For { content, body }
+            }
+            ast::Stmt::IfStmt(stmt) => {
+                let condition = self.collect_expr_opt(stmt.cond());
+                let then_branch = self.collect_block_opt(stmt.block());
+                let else_branch = self.collect_else(stmt.else_if_branch(), stmt.else_branch());
+                Stmt::If { condition, then_branch, else_branch }
+            }
+            ast::Stmt::ReturnStmt(stmt) => {
+                let exprs = self.collect_multival_expr_opt(stmt.multival_expr());
+                Stmt::Return { exprs }
+            }
+            ast::Stmt::WhileStmt(stmt) => {
+                let condition = self.collect_expr_opt(stmt.cond());
+                let body = self.collect_block_opt(stmt.body());
+                Stmt::While { condition, body }
+            }
+            ast::Stmt::ExprStmt(stmt) => {
+                let expr = stmt.multival_expr().and_then(|it| it.exprs().next());
+                Stmt::Expr(self.collect_expr_opt(expr))
+            }
+            ast::Stmt::DoStmt(stmt) => Stmt::Do(self.collect_block_opt(stmt.body())),
+            ast::Stmt::BreakStmt(_) => Stmt::Break,
+            ast::Stmt::RepeatUntilStmt(stmt) => {
+                let body = self.collect_block_opt(stmt.block());
+                let condition = self.collect_expr_opt(stmt.expr());
+                Stmt::Repeat { body, condition }
+            }
+            ast::Stmt::LabelStmt(stmt) => {
+                let name = stmt.name().map_or_else(Name::missing, |it| it.as_name());
+                let label = self.body.labels.alloc(Label { name });
+                Stmt::Label { label }
+            }
+            ast::Stmt::GotoStmt(stmt) => {
+                let name = stmt.name_ref().map_or_else(Name::missing, |it| it.as_name());
+                Stmt::Goto { name }
+            }
+        };
+        let id = self.alloc_stmt(stmt, syntax_ptr);
+        if let Some(annotations) = annotations {
+            self.body.annotations.insert(id, annotations);
+        }
+        id
+    }
+
+    fn collect_block_opt(&mut self, block: Option<ast::Block>) -> Block {
+        if let Some(block) = block {
+            self.collect_block(block)
+        } else {
+            Block { block_loc: None, stmts: Vec::new() }
+        }
+    }
+
+    fn collect_block(&mut self, block: ast::Block) -> Block {
+        let block_loc = BlockLoc { ast_id: self.ast_id(&block) };
+        self.body.block_scopes.push(block_loc);
+        let stmts = block.stmts().map(|stmt| self.collect_stmt(stmt)).collect();
+        Block { block_loc: Some(block_loc), stmts }
+    }
+
+    fn collect_else(
+        &mut self,
+        else_if_branch: Option<ast::ElseIfBranch>,
+        else_branch: Option<ast::ElseBranch>,
+    ) -> Option<StmtId> {
+        if let Some(branch) = else_if_branch {
+            let condition = self.collect_expr_opt(branch.cond());
+            let then_branch = self.collect_block_opt(branch.block());
+            let else_branch = self.collect_else(branch.else_if_branch(), branch.else_branch());
+            Some(self.alloc_stmt_desugared(Stmt::If { condition, then_branch, else_branch }))
+        } else {
+            else_branch.map(|branch| self.collect_stmt_opt(branch.block().map(ast::Stmt::from)))
+        }
+    }
+
+    fn collect_for_content(&mut self, content: Option<ast::ForContent>) -> ForContent {
+        match content {
+            Some(ast::ForContent::NumericFor(content)) => {
+                let mut exprs = self.collect_multival_expr_opt(content.expr()).into_iter();
+                let start = exprs.next().unwrap_or_else(|| self.missing_expr());
+                let end = exprs.next().unwrap_or_else(|| self.missing_expr());
+                let step = exprs.next();
+                let name = self.collect_name_opt(content.name());
+                ForContent::Numeric { name, start, end, step }
+            }
+            Some(ast::ForContent::GenericFor(content)) => {
+                let expr = self.collect_expr_opt(content.expr());
+                let names = self.collect_multi_name_opt(content.multi_name());
+                ForContent::Generic { names, expr }
+            }
+            None => {
+                let name = self.alloc_name_desugared(Name::missing());
+                let start = self.missing_expr();
+                let end = self.missing_expr();
+                ForContent::Numeric { name, start, end, step: None }
+            }
+        }
+    }
+
+    /// Lowers the name of a function definition, such as `a.b:c`, into the
+    /// expression that is assigned to.
+    fn collect_function_target(
+        &mut self,
+        content: Option<ast::FunctionDefContent>,
+    ) -> (ExprId, bool) {
+        let (index_path, name, is_method) = match content {
+            Some(ast::FunctionDefContent::Name(name)) => {
+                let expr = self.alloc_expr_desugared(Expr::Name(name.as_name()));
+                return (expr, false);
+            }
+            Some(ast::FunctionDefContent::FunctionStatic(it)) => {
+                (it.index_path(), it.name(), false)
+            }
+            Some(ast::FunctionDefContent::FunctionMethod(it)) => (it.index_path(), it.name(), true),
+            None => return (self.missing_expr(), false),
+        };
+
+        // every name of the path is a `NameRef`, so the access up to it can point at it
+        let mut name_refs = index_path.into_iter().flat_map(|it| it.name_refs());
+        let mut base = match name_refs.next() {
+            Some(name_ref) => {
+                let expr = Expr::Name(name_ref.as_name());
+                self.alloc_expr(expr, AstPtr::new(&ast::Expr::from(name_ref)))
+            }
+            None => self.missing_expr(),
+        };
+        for name_ref in name_refs {
+            let expr = Expr::Dot { base, index: name_ref.as_name() };
+            base = self.alloc_expr(expr, AstPtr::new(&ast::Expr::from(name_ref)));
+        }
+        let index = name.map_or_else(Name::missing, |it| it.as_name());
+        (self.alloc_expr_desugared(Expr::Dot { base, index }), is_method)
+    }
+
+    fn collect_function(
+        &mut self,
+        param_list: Option<ast::ParamList>,
+        has_self_param: bool,
+        body: Option<ast::Block>,
+    ) -> Expr {
+        let params = self.collect_params(param_list, has_self_param);
+        let body = self.collect_stmt_opt(body.map(ast::Stmt::from));
+        Expr::Function { params, body }
+    }
+
+    fn collect_multival_expr_opt(&mut self, multival: Option<ast::MultivalExpr>) -> Vec<ExprId> {
+        multival
+            .map_or_else(Vec::new, |it| it.exprs().map(|expr| self.collect_expr(expr)).collect())
+    }
+
+    fn collect_expr_opt(&mut self, expr: Option<ast::Expr>) -> ExprId {
+        if let Some(expr) = expr {
+            self.collect_expr(expr)
+        } else {
+            self.missing_expr()
+        }
+    }
+
+    fn collect_expr(&mut self, expr: ast::Expr) -> ExprId {
+        let syntax_ptr = AstPtr::new(&expr);
+        let expr = match expr {
+            ast::Expr::Literal(lit) => lower_literal(&lit).map_or(Expr::Missing, Expr::Literal),
+            ast::Expr::TableExpr(table) => Expr::Table(self.collect_table(table)),
+            ast::Expr::InfixExpr(expr) => {
+                let lhs = self.collect_expr_opt(expr.lhs());
+                let rhs = self.collect_expr_opt(expr.rhs());
+                match expr.op_kind().and_then(BinaryOp::from_lua_op) {
+                    Some(op) => Expr::BinaryOp { lhs, op, rhs },
+                    None => Expr::Missing,
+                }
+            }
+            ast::Expr::PrefixExpr(e) => {
+                let expr = self.collect_expr_opt(e.expr());
+                match e.op_kind().and_then(UnaryOp::from_lua_op) {
+                    Some(op) => Expr::UnaryOp { op, expr },
+                    None => Expr::Missing,
+                }
+            }
+            ast::Expr::IndexExpr(expr) => {
+                let base = self.collect_expr_opt(expr.base());
+                let index = self.collect_expr_opt(expr.index().and_then(|it| it.expr()));
+                Expr::Index { base, index }
+            }
+            ast::Expr::DotExpr(expr) => {
+                let base = self.collect_expr_opt(expr.expr());
+                let index =
+                    expr.ident_token().map_or_else(Name::missing, |it| Name::resolve(it.text()));
+                Expr::Dot { base, index }
+            }
+            ast::Expr::FunctionExpr(expr) => {
+                self.collect_function(expr.params(), false, expr.body())
+            }
+            ast::Expr::CallExpr(expr) => {
+                let callee = self.collect_expr_opt(expr.fun());
+                let args = self.collect_call_args(expr.call_args());
+                Expr::Call(Call { callee, method_name: None, args })
+            }
+            ast::Expr::MethodCallExpr(expr) => {
+                let callee = self.collect_expr_opt(expr.fun());
+                let method_name = expr.method_name().map(|it| it.as_name());
+                let args = self.collect_call_args(expr.call_args());
+                Expr::Call(Call { callee, method_name, args })
+            }
+            ast::Expr::ParenExpr(expr) => Expr::Paren(self.collect_expr_opt(expr.expr())),
+            ast::Expr::NameRef(name_ref) => Expr::Name(name_ref.as_name()),
+        };
+        self.alloc_expr(expr, syntax_ptr)
+    }
+
+    fn collect_call_args(&mut self, call_args: Option<ast::CallArgs>) -> CallArgs {
+        let call_args = match call_args {
+            Some(it) => it,
+            None => return CallArgs::ArgList { args: Vec::new() },
+        };
+        if let Some(table) = call_args.table_expr() {
+            CallArgs::Table(self.collect_table(table))
+        } else if let Some(str) = call_args.str_token().and_then(ast::Str::cast) {
+            CallArgs::Str(str.value().into_owned())
+        } else {
+            let args = self
+                .collect_multival_expr_opt(call_args.arg_list().and_then(|it| it.multival_expr()));
+            CallArgs::ArgList { args }
+        }
+    }
+
+    fn collect_table(&mut self, table: ast::TableExpr) -> Table {
+        let fields = table
+            .table_contents()
+            .map(|content| match content {
+                ast::TableContent::PositionalValue(it) => {
+                    TableField::Positional(self.collect_expr_opt(it.expr()))
+                }
+                ast::TableContent::KeyValue(it) => match it.key() {
+                    Some(ast::TableKey::Name(name)) => {
+                        let value = self.collect_expr_opt(it.value());
+                        TableField::Named { name: name.as_name(), value }
+                    }
+                    Some(ast::TableKey::Index(index)) => {
+                        let key = self.collect_expr_opt(index.expr());
+                        let value = self.collect_expr_opt(it.value());
+                        TableField::Indexed { key, value }
+                    }
+                    None => {
+                        let key = self.missing_expr();
+                        let value = self.collect_expr_opt(it.value());
+                        TableField::Indexed { key, value }
+                    }
+                },
+            })
+            .collect();
+        Table { fields }
+    }
+
+    fn collect_multi_name_opt(&mut self, multi_name: Option<ast::MultiName>) -> Vec<NameId> {
+        multi_name
+            .map_or_else(Vec::new, |it| it.names().map(|name| self.collect_name(name)).collect())
+    }
+
+    fn collect_name_opt(&mut self, name: Option<ast::Name>) -> NameId {
+        match name {
+            Some(name) => self.collect_name(name),
+            None => self.alloc_name_desugared(Name::missing()),
+        }
     }
 
-    fn collect_block(&mut self, block: ast::Block) -> StmtId {
-        let ast_id = self.ast_id(&block);
-        todo!()
+    fn collect_name(&mut self, name: ast::Name) -> NameId {
+        let syntax_ptr = AstPtr::new(&name);
+        self.alloc_name(name.as_name(), syntax_ptr)
     }
 
     fn missing_stmt(&mut self) -> StmtId {
-        self.alloc_stmt(Stmt::Missing)
+        self.alloc_stmt_desugared(Stmt::Missing)
+    }
+
+    fn missing_expr(&mut self) -> ExprId {
+        self.alloc_expr_desugared(Expr::Missing)
     }
 
-    fn alloc_stmt(&mut self, stmt: Stmt) -> StmtId {
+    fn alloc_stmt(&mut self, stmt: Stmt, ptr: StmtPtr) -> StmtId {
+        let src = InFile::new(self.file_id, ptr);
+        let id = self.alloc_stmt_desugared(stmt);
+        self.source_map.stmt_map.insert(src.clone(), id);
+        self.source_map.stmt_map_back.insert(id, src);
+        id
+    }
+
+    fn alloc_stmt_desugared(&mut self, stmt: Stmt) -> StmtId {
         self.body.stmts.alloc(stmt)
     }
 
-    fn alloc_expr(&mut self, expr: Expr) -> ExprId {
+    fn alloc_expr(&mut self, expr: Expr, ptr: ExprPtr) -> ExprId {
+        let src = InFile::new(self.file_id, ptr);
+        let id = self.alloc_expr_desugared(expr);
+        self.source_map.expr_map.insert(src.clone(), id);
+        self.source_map.expr_map_back.insert(id, src);
+        id
+    }
+
+    fn alloc_expr_desugared(&mut self, expr: Expr) -> ExprId {
         self.body.exprs.alloc(expr)
     }
 
+    fn alloc_name(&mut self, name: Name, ptr: NamePtr) -> NameId {
+        let src = InFile::new(self.file_id, ptr);
+        let id = self.alloc_name_desugared(name);
+        self.source_map.name_map.insert(src.clone(), id);
+        self.source_map.name_map_back.insert(id, src);
+        id
+    }
+
+    fn alloc_name_desugared(&mut self, name: Name) -> NameId {
+        self.body.names.alloc(name)
+    }
+
     fn ast_id<N: AstNode>(&self, item: &N) -> AstId<N> {
         let file_local_id = self.ast_id_map.ast_id(item);
         AstId::new(self.file_id, file_local_id)
     }
 }
+
+fn lower_literal(lit: &ast::Literal) -> Option<Literal> {
+    let res = match lit.kind() {
+        LiteralKind::Str(it) => Literal::Str(it.value().into_owned()),
+        LiteralKind::Number(it) => match it.value().ok()? {
+            ast::NumberValue::Int(it) => Literal::Number(Number::Int(it)),
+            ast::NumberValue::Float(it) => Literal::Number(Number::Float(it.into())),
+            ast::NumberValue::Imaginary(_) => return None,
+        },
+        LiteralKind::Bool(it) => Literal::Bool(it),
+        LiteralKind::Nil => Literal::Nil,
+        LiteralKind::Vararg => Literal::Vararg,
+    };
+    Some(res)
+}
@@ -0,0 +1,577 @@
+//! Name resolution for expressions.
+//!
+//! Lua locals come into scope at the end of the statement that declares them,
+//! so every `local` statement opens a new scope that the rest of the block
+//! lives in. This is what makes `local x = x` refer to the outer `x`.
+
+use std::sync::Arc;
+
+use la_arena::{Arena, Idx};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    body::Body,
+    expr::{Expr, ExprId},
+    name::{Name, NameId},
+    stmt::{Block, BlockLoc, ForContent, Stmt, StmtId},
+    with_body::WithBodyId,
+    DefDatabase,
+};
+
+pub type ScopeId = Idx<ScopeData>;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExprScopes {
+    scopes: Arena<ScopeData>,
+    scope_by_expr: FxHashMap<ExprId, ScopeId>,
+    scope_by_stmt: FxHashMap<StmtId, ScopeId>,
+    /// The scope the statements following a statement of a block are in.
+    scope_after_stmt: FxHashMap<StmtId, ScopeId>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScopeData {
+    parent: Option<ScopeId>,
+    /// Set for the scope holding the parameters of a function, anything
+    /// resolved above it is an upvalue.
+    is_function: bool,
+    block: Option<BlockLoc>,
+    entries: Vec<ScopeEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeEntry {
+    name: Name,
+    name_id: NameId,
+    kind: BindingKind,
+}
+
+impl ScopeEntry {
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    pub fn name_id(&self) -> NameId {
+        self.name_id
+    }
+
+    pub fn kind(&self) -> BindingKind {
+        self.kind
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingKind {
+    /// `local x`
+    Local,
+    /// `local function f() end`
+    LocalFunction,
+    /// A function parameter, including the implicit `self` of methods.
+    Param,
+    /// `for i = 1, 10` or `for k, v in pairs(t)`
+    ForVar,
+}
+
+/// What a name used in an expression refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// A binding of the function the name is used in.
+    Local { name: NameId, kind: BindingKind },
+    /// A binding of an enclosing function.
+    Upvalue { name: NameId, kind: BindingKind },
+    /// No binding is in scope, so the name is a field of `_ENV`.
+    Global(Name),
+}
+
+impl Resolution {
+    pub fn name_id(&self) -> Option<NameId> {
+        match self {
+            Resolution::Local { name, .. } | Resolution::Upvalue { name, .. } => Some(*name),
+            Resolution::Global(_) => None,
+        }
+    }
+}
+
+impl ExprScopes {
+    pub(crate) fn expr_scopes_query(db: &dyn DefDatabase, def: WithBodyId) -> Arc<ExprScopes> {
+        let body = db.body(def);
+        Arc::new(ExprScopes::new(&body))
+    }
+
+    fn new(body: &Body) -> ExprScopes {
+        let mut scopes = ExprScopes {
+            scopes: Arena::default(),
+            scope_by_expr: FxHashMap::default(),
+            scope_by_stmt: FxHashMap::default(),
+            scope_after_stmt: FxHashMap::default(),
+        };
+        let root = scopes.root_scope();
+        scopes.add_params_bindings(body, root);
+        compute_stmt_scopes(body.body_stmt, body, &mut scopes, root);
+        scopes
+    }
+
+    pub fn entries(&self, scope: ScopeId) -> &[ScopeEntry] {
+        &self.scopes[scope].entries
+    }
+
+    pub fn scope_chain(&self, scope: Option<ScopeId>) -> impl Iterator<Item = ScopeId> + '_ {
+        std::iter::successors(scope, move |&scope| self.scopes[scope].parent)
+    }
+
+    pub fn scope_for(&self, expr: ExprId) -> Option<ScopeId> {
+        self.scope_by_expr.get(&expr).copied()
+    }
+
+    pub fn scope_for_stmt(&self, stmt: StmtId) -> Option<ScopeId> {
+        self.scope_by_stmt.get(&stmt).copied()
+    }
+
+    /// The scope of the statements following `stmt` in its block, which sees
+    /// the locals declared by `stmt`.
+    pub fn scope_after_stmt(&self, stmt: StmtId) -> Option<ScopeId> {
+        self.scope_after_stmt.get(&stmt).copied()
+    }
+
+    pub fn scope_for_block(&self, block: BlockLoc) -> Option<ScopeId> {
+        self.scopes.iter().find_map(|(id, data)| (data.block == Some(block)).then_some(id))
+    }
+
+    /// Finds the innermost binding of `name` visible from `scope`.
+    pub fn resolve_name_in_scope(&self, scope: ScopeId, name: &Name) -> Option<Resolution> {
+        self.resolve_in_scope_by(scope, |entry| entry.name == *name)
+    }
+
+    /// Like `resolve_name_in_scope`, but as if the binding `renamed` was
+    /// called `new_name`. This is what a name would refer to after a rename.
+    pub fn resolve_name_in_scope_renamed(
+        &self,
+        scope: ScopeId,
+        name: &Name,
+        renamed: NameId,
+        new_name: &Name,
+    ) -> Option<Resolution> {
+        self.resolve_in_scope_by(scope, |entry| {
+            let entry_name = if entry.name_id == renamed { new_name } else { &entry.name };
+            entry_name == name
+        })
+    }
+
+    fn resolve_in_scope_by(
+        &self,
+        scope: ScopeId,
+        mut matches: impl FnMut(&ScopeEntry) -> bool,
+    ) -> Option<Resolution> {
+        let mut is_upvalue = false;
+        for scope in self.scope_chain(Some(scope)) {
+            let data = &self.scopes[scope];
+            // later entries of the same scope shadow earlier ones, as in `local x, x`
+            if let Some(entry) = data.entries.iter().rev().find(|it| matches(it)) {
+                let (name, kind) = (entry.name_id, entry.kind);
+                return Some(if is_upvalue {
+                    Resolution::Upvalue { name, kind }
+                } else {
+                    Resolution::Local { name, kind }
+                });
+            }
+            is_upvalue |= data.is_function;
+        }
+        None
+    }
+
+    /// Resolves a name expression, returns `None` if `expr` is not a name.
+    pub fn resolve_expr(&self, body: &Body, expr: ExprId) -> Option<Resolution> {
+        let name = match &body.exprs[expr] {
+            Expr::Name(name) => name,
+            _ => return None,
+        };
+        let res = self
+            .scope_for(expr)
+            .and_then(|scope| self.resolve_name_in_scope(scope, name))
+            .unwrap_or_else(|| Resolution::Global(name.clone()));
+        Some(res)
+    }
+
+    fn root_scope(&mut self) -> ScopeId {
+        self.scopes.alloc(ScopeData {
+            parent: None,
+            is_function: true,
+            block: None,
+            entries: vec![],
+        })
+    }
+
+    fn new_scope(&mut self, parent: ScopeId) -> ScopeId {
+        self.scopes.alloc(ScopeData {
+            parent: Some(parent),
+            is_function: false,
+            block: None,
+            entries: vec![],
+        })
+    }
+
+    fn new_function_scope(&mut self, parent: ScopeId) -> ScopeId {
+        self.scopes.alloc(ScopeData {
+            parent: Some(parent),
+            is_function: true,
+            block: None,
+            entries: vec![],
+        })
+    }
+
+    fn new_block_scope(&mut self, parent: ScopeId, block: &Block) -> ScopeId {
+        self.scopes.alloc(ScopeData {
+            parent: Some(parent),
+            is_function: false,
+            block: block.block_loc,
+            entries: vec![],
+        })
+    }
+
+    fn add_binding(&mut self, body: &Body, scope: ScopeId, name_id: NameId, kind: BindingKind) {
+        let name = body.names[name_id].clone();
+        self.scopes[scope].entries.push(ScopeEntry { name, name_id, kind });
+    }
+
+    fn add_params_bindings(&mut self, body: &Body, scope: ScopeId) {
+        for param in body.params.iter() {
+            self.add_binding(body, scope, param, BindingKind::Param);
+        }
+    }
+
+    fn set_scope(&mut self, expr: ExprId, scope: ScopeId) {
+        self.scope_by_expr.insert(expr, scope);
+    }
+}
+
+/// Computes the scopes of a statement, returning the scope that the statements
+/// following it are in.
+fn compute_stmt_scopes(
+    stmt: StmtId,
+    body: &Body,
+    scopes: &mut ExprScopes,
+    scope: ScopeId,
+) -> ScopeId {
+    scopes.scope_by_stmt.insert(stmt, scope);
+    match &body.stmts[stmt] {
+        Stmt::Local { names, exprs } => {
+            exprs.iter().for_each(|&expr| compute_expr_scopes(expr, body, scopes, scope));
+            let scope = scopes.new_scope(scope);
+            for &name in names {
+                scopes.add_binding(body, scope, name, BindingKind::Local);
+            }
+            scope
+        }
+        Stmt::LocalFunction { name, func } => {
+            // the function is in scope inside of its own body
+            let scope = scopes.new_scope(scope);
+            scopes.add_binding(body, scope, *name, BindingKind::LocalFunction);
+            compute_expr_scopes(*func, body, scopes, scope);
+            scope
+        }
+        Stmt::Block(block) | Stmt::Do(block) | Stmt::While { body: block, .. } => {
+            body.stmts[stmt]
+                .walk_child_exprs(|expr| compute_expr_scopes(expr, body, scopes, scope));
+            compute_block_scopes(block, body, scopes, scope);
+            scope
+        }
+        Stmt::Repeat { body: block, condition } => {
+            // the condition can see the locals of the loop body
+            let block_scope = compute_block_scopes(block, body, scopes, scope);
+            compute_expr_scopes(*condition, body, scopes, block_scope);
+            scope
+        }
+        Stmt::For { content, body: block } => {
+            body.stmts[stmt]
+                .walk_child_exprs(|expr| compute_expr_scopes(expr, body, scopes, scope));
+            let loop_scope = scopes.new_scope(scope);
+            match content {
+                ForContent::Numeric { name, .. } => {
+                    scopes.add_binding(body, loop_scope, *name, BindingKind::ForVar)
+                }
+                ForContent::Generic { names, .. } => {
+                    for &name in names {
+                        scopes.add_binding(body, loop_scope, name, BindingKind::ForVar);
+                    }
+                }
+            }
+            compute_block_scopes(block, body, scopes, loop_scope);
+            scope
+        }
+        Stmt::If { condition, then_branch, else_branch } => {
+            compute_expr_scopes(*condition, body, scopes, scope);
+            compute_block_scopes(then_branch, body, scopes, scope);
+            if let Some(else_branch) = else_branch {
+                compute_stmt_scopes(*else_branch, body, scopes, scope);
+            }
+            scope
+        }
+        Stmt::Missing
+        | Stmt::FunctionDef { .. }
+        | Stmt::Assign { .. }
+        | Stmt::Expr(_)
+        | Stmt::Break
+        | Stmt::Return { .. }
+        | Stmt::Label { .. }
+        | Stmt::Goto { .. } => {
+            body.stmts[stmt]
+                .walk_child_exprs(|expr| compute_expr_scopes(expr, body, scopes, scope));
+            scope
+        }
+    }
+}
+
+/// Computes the scopes of a block, returning the innermost scope at its end.
+fn compute_block_scopes(
+    block: &Block,
+    body: &Body,
+    scopes: &mut ExprScopes,
+    scope: ScopeId,
+) -> ScopeId {
+    let mut scope = scopes.new_block_scope(scope, block);
+    for &stmt in &block.stmts {
+        scope = compute_stmt_scopes(stmt, body, scopes, scope);
+        scopes.scope_after_stmt.insert(stmt, scope);
+    }
+    scope
+}
+
+fn compute_expr_scopes(expr: ExprId, body: &Body, scopes: &mut ExprScopes, scope: ScopeId) {
+    scopes.set_scope(expr, scope);
+    match &body.exprs[expr] {
+        Expr::Function { params, body: func_body } => {
+            let scope = scopes.new_function_scope(scope);
+            for param in params.iter() {
+                scopes.add_binding(body, scope, param, BindingKind::Param);
+            }
+            compute_stmt_scopes(*func_body, body, scopes, scope);
+        }
+        e => e.walk_child_exprs(|e| compute_expr_scopes(e, body, scopes, scope)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base_db::SourceDatabase;
+    use syntax::ast::{self, AstNode};
+    use test_utils::extract_offset;
+
+    use super::Resolution;
+    use crate::{test_db::TestDB, with_body::WithBodyId, DefDatabase, InFile};
+
+    /// Resolves the name reference at `$0`, printing how it was resolved and
+    /// the line that declares it.
+    fn check(ra_fixture: &str, expected: &str) {
+        let (offset, text) = extract_offset(ra_fixture);
+        let (db, file_id) = TestDB::with_single_file(&text);
+        let def = WithBodyId::ModuleId(file_id);
+        let (body, source_map) = db.body_with_source_map(def);
+        let scopes = db.expr_scopes(def);
+
+        let root = db.parse(file_id).syntax_node();
+        let name_ref = root
+            .token_at_offset(offset)
+            .right_biased()
+            .and_then(|it| it.parent().ancestors().find_map(ast::NameRef::cast))
+            .expect("no name reference at offset");
+        let expr = source_map
+            .node_expr(InFile::new(file_id, &name_ref.into()))
+            .expect("name reference was not lowered");
+
+        let (label, kind, name) = match scopes.resolve_expr(&body, expr).unwrap() {
+            Resolution::Global(name) => {
+                assert_eq!(expected, format!("global {}", name));
+                return;
+            }
+            Resolution::Local { name, kind } => ("local", kind, name),
+            Resolution::Upvalue { name, kind } => ("upvalue", kind, name),
+        };
+        let decl = match source_map.name_syntax(name) {
+            Some(src) => {
+                let offset = src.value.to_node(&root).syntax().text_range().start();
+                let offset = usize::from(offset);
+                let start = text[..offset].rfind('\n').map_or(0, |it| it + 1);
+                let end = text[offset..].find('\n').map_or(text.len(), |it| it + offset);
+                text[start..end].trim().to_string()
+            }
+            None => "<desugared>".to_string(),
+        };
+        assert_eq!(expected, format!("{} {:?}: {}", label, kind, decl));
+    }
+
+    #[test]
+    fn resolve_local() {
+        check(
+            r#"
+local x = 1
+local y = 2
+print($0x)
+"#,
+            "local Local: local x = 1",
+        );
+    }
+
+    #[test]
+    fn local_initializer_sees_outer_binding() {
+        check(
+            r#"
+local x = 1
+while true do
+    local x = $0x + 1
+end
+"#,
+            "local Local: local x = 1",
+        );
+    }
+
+    #[test]
+    fn local_is_not_visible_before_declaration() {
+        check(
+            r#"
+print($0x)
+local x = 1
+"#,
+            "global x",
+        );
+    }
+
+    #[test]
+    fn block_locals_do_not_escape() {
+        check(
+            r#"
+if true then
+    local x = 1
+end
+print($0x)
+"#,
+            "global x",
+        );
+    }
+
+    #[test]
+    fn do_block_locals_do_not_escape() {
+        check(
+            r#"
+local x = 1
+do
+    local x = 2
+end
+print($0x)
+"#,
+            "local Local: local x = 1",
+        );
+    }
+
+    #[test]
+    fn shadowing_in_same_block() {
+        check(
+            r#"
+local x = 1
+local x = 2
+print($0x)
+"#,
+            "local Local: local x = 2",
+        );
+    }
+
+    #[test]
+    fn resolve_param() {
+        check(
+            r#"
+local function f(a, b)
+    print($0b)
+end
+"#,
+            "local Param: local function f(a, b)",
+        );
+    }
+
+    #[test]
+    fn resolve_self_param() {
+        check(
+            r#"
+function t:method()
+    print($0self)
+end
+"#,
+            "local Param: <desugared>",
+        );
+    }
+
+    #[test]
+    fn resolve_for_vars() {
+        check(
+            r#"
+for i = 1, 10 do
+    print($0i)
+end
+"#,
+            "local ForVar: for i = 1, 10 do",
+        );
+        check(
+            r#"
+for k, v in pairs(t) do
+    print($0v)
+end
+"#,
+            "local ForVar: for k, v in pairs(t) do",
+        );
+    }
+
+    #[test]
+    fn for_range_does_not_see_loop_var() {
+        check(
+            r#"
+for i = 1, $0i do end
+"#,
+            "global i",
+        );
+    }
+
+    #[test]
+    fn resolve_upvalue() {
+        check(
+            r#"
+local count = 0
+local function incr()
+    count = $0count + 1
+end
+"#,
+            "upvalue Local: local count = 0",
+        );
+    }
+
+    #[test]
+    fn local_function_is_recursive() {
+        check(
+            r#"
+local function loop(n)
+    $0loop(n + 1)
+end
+"#,
+            "upvalue LocalFunction: local function loop(n)",
+        );
+    }
+
+    #[test]
+    fn repeat_condition_sees_body_locals() {
+        check(
+            r#"
+repeat
+    local done = true
+until $0done
+"#,
+            "local Local: local done = true",
+        );
+    }
+
+    #[test]
+    fn resolve_global() {
+        check(
+            r#"
+$0print("hi")
+"#,
+            "global print",
+        );
+    }
+}
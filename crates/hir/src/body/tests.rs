@@ -0,0 +1,314 @@
+use std::sync::Arc;
+
+use base_db::SourceDatabase;
+use syntax::ast::{self, AstNode};
+
+use super::Body;
+use crate::{
+    annotations::{Annotations, ParamAnnotation, ReturnAnnotation},
+    expr::{BinaryOp, BitOp, CallArgs, Expr, Literal, Number, UnaryOp},
+    stmt::{ForContent, Stmt},
+    test_db::TestDB,
+    type_ref::TypeRef,
+    with_body::WithBodyId,
+    DefDatabase, InFile, Name,
+};
+
+fn lower(text: &str) -> Arc<Body> {
+    let (db, file_id) = TestDB::with_single_file(text);
+    db.body(WithBodyId::ModuleId(file_id))
+}
+
+fn top_level_stmts(body: &Body) -> Vec<Stmt> {
+    match &body.stmts[body.body_stmt] {
+        Stmt::Block(block) => block.stmts.iter().map(|&id| body.stmts[id].clone()).collect(),
+        it => panic!("expected module body to be a block, got {:?}", it),
+    }
+}
+
+fn names(body: &Body) -> Vec<&str> {
+    body.names.iter().map(|(_, name)| name.as_str()).collect()
+}
+
+#[test]
+fn lower_locals() {
+    let body = lower("local a, b = 1, 'two'");
+    let stmts = top_level_stmts(&body);
+    assert_eq!(names(&body), ["a", "b"]);
+    match &stmts[..] {
+        [Stmt::Local { names, exprs }] => {
+            assert_eq!(names.len(), 2);
+            assert_eq!(body.exprs[exprs[0]], Expr::Literal(Literal::Number(Number::Int(1))));
+            assert_eq!(body.exprs[exprs[1]], Expr::Literal(Literal::Str("two".into())));
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn lower_numbers() {
+    let body = lower("local a, b, c = 0x10, 1.5e1, 9223372036854775808");
+    match &top_level_stmts(&body)[..] {
+        [Stmt::Local { exprs, .. }] => {
+            let numbers: Vec<_> = exprs.iter().map(|&it| body.exprs[it].clone()).collect();
+            assert_eq!(
+                numbers,
+                [
+                    Expr::Literal(Literal::Number(Number::Int(16))),
+                    Expr::Literal(Literal::Number(Number::Float(15.0.into()))),
+                    Expr::Literal(Literal::Number(Number::Float(9223372036854775808.0.into()))),
+                ]
+            );
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn lower_strings() {
+    let body = lower("local a, b = require 'a\\x2eb', [[\nline\\n]]");
+    match &top_level_stmts(&body)[..] {
+        [Stmt::Local { exprs, .. }] => {
+            match &body.exprs[exprs[0]] {
+                Expr::Call(call) => assert_eq!(call.args, CallArgs::Str("a.b".into())),
+                it => panic!("{:?}", it),
+            }
+            assert_eq!(body.exprs[exprs[1]], Expr::Literal(Literal::Str("line\\n".into())));
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn lower_functions() {
+    let body = lower(
+        r#"
+local function f(a, ...) return a end
+function t.x:y(b) end
+"#,
+    );
+    let stmts = top_level_stmts(&body);
+    assert_eq!(names(&body), ["f", "a", "self", "b"]);
+    match &stmts[..] {
+        [Stmt::LocalFunction { func, .. }, Stmt::FunctionDef { target, is_method: true, .. }] => {
+            match &body.exprs[*func] {
+                Expr::Function { params, .. } => {
+                    assert!(params.vararg);
+                    assert_eq!(params.iter().count(), 1);
+                }
+                it => panic!("{:?}", it),
+            }
+            match &body.exprs[*target] {
+                Expr::Dot { base, index } => {
+                    assert_eq!(index.as_str(), "y");
+                    assert!(matches!(body.exprs[*base], Expr::Dot { .. }));
+                }
+                it => panic!("{:?}", it),
+            }
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn lower_if_chain() {
+    let body = lower("if a then elseif b then else end");
+    let stmts = top_level_stmts(&body);
+    let else_if = match &stmts[..] {
+        [Stmt::If { else_branch: Some(else_if), .. }] => *else_if,
+        it => panic!("{:?}", it),
+    };
+    match &body.stmts[else_if] {
+        Stmt::If { else_branch: Some(else_branch), .. } => {
+            assert!(matches!(body.stmts[*else_branch], Stmt::Block(_)))
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn lower_numeric_for() {
+    let body = lower("for i = 1, n + 1 do end");
+    let stmts = top_level_stmts(&body);
+    match &stmts[..] {
+        [Stmt::For { content: ForContent::Numeric { step: None, end, .. }, .. }] => {
+            assert!(matches!(body.exprs[*end], Expr::BinaryOp { op: BinaryOp::ArithOp(_), .. }))
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn lower_do_block() {
+    let text = "do local x = 1 function f() end end";
+    let body = lower(text);
+    assert_eq!(names(&body), ["x"]);
+    match &top_level_stmts(&body)[..] {
+        [Stmt::Do(block)] => assert_eq!(block.stmts.len(), 2),
+        it => panic!("{:?}", it),
+    }
+
+    let (db, file_id) = TestDB::with_single_file(text);
+    assert_eq!(db.file_item_tree(file_id).functions().count(), 1);
+}
+
+#[test]
+fn lower_return_values() {
+    let body = lower("return 1, a, ...");
+    match &top_level_stmts(&body)[..] {
+        [Stmt::Return { exprs }] => {
+            assert_eq!(exprs.len(), 3);
+            assert!(matches!(body.exprs[exprs[1]], Expr::Name(_)));
+            assert_eq!(body.exprs[exprs[2]], Expr::Literal(Literal::Vararg));
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn lower_bitwise_ops() {
+    let body = lower("local x = ~a | b >> 1");
+    let stmts = top_level_stmts(&body);
+    let expr = match &stmts[..] {
+        [Stmt::Local { exprs, .. }] => exprs[0],
+        it => panic!("{:?}", it),
+    };
+    match &body.exprs[expr] {
+        Expr::BinaryOp { lhs, op: BinaryOp::BitOp(BitOp::Or), rhs } => {
+            assert!(matches!(body.exprs[*lhs], Expr::UnaryOp { op: UnaryOp::BitNot, .. }));
+            assert!(matches!(
+                body.exprs[*rhs],
+                Expr::BinaryOp { op: BinaryOp::BitOp(BitOp::Shr), .. }
+            ));
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn lower_with_errors() {
+    let body = lower("local x = \nfoo(");
+    assert_eq!(names(&body), ["x"]);
+}
+
+#[test]
+fn source_map_round_trip() {
+    let (db, file_id) = TestDB::with_single_file("local x = foo(1) + 2\nif x then x = nil end");
+    let (body, source_map) = db.body_with_source_map(WithBodyId::ModuleId(file_id));
+    let root = db.parse(file_id).syntax_node();
+
+    for (id, _) in body.exprs.iter() {
+        if let Some(src) = source_map.expr_syntax(id) {
+            let node = src.value.to_node(&root);
+            assert_eq!(source_map.node_expr(src.with_value(&node)), Some(id));
+        }
+    }
+    for (id, _) in body.stmts.iter() {
+        if let Some(src) = source_map.stmt_syntax(id) {
+            let node = src.value.to_node(&root);
+            assert_eq!(source_map.node_stmt(src.with_value(&node)), Some(id));
+        }
+    }
+
+    let name = root.descendants().find_map(ast::Name::cast).unwrap();
+    let name_id = source_map.node_name(InFile::new(file_id, &name)).unwrap();
+    assert_eq!(body.names[name_id].as_str(), "x");
+    assert_eq!(source_map.name_syntax(name_id).unwrap().value.to_node(&root), name);
+
+    let call = root.descendants().find_map(ast::CallExpr::cast).unwrap();
+    let call_id = source_map.node_expr(InFile::new(file_id, &call.into())).unwrap();
+    assert!(matches!(body.exprs[call_id], Expr::Call(_)));
+}
+
+#[test]
+fn lower_annotations() {
+    let body = lower(
+        r#"
+---Adds one.
+---@param n integer
+---@param step? number
+---@return integer, string | nil
+local function inc(n, step) end
+
+-- a plain comment
+local x = 1
+
+---@type table<string, boolean>[]
+local seen = {}
+"#,
+    );
+    let stmts: Vec<_> = match &body.stmts[body.body_stmt] {
+        Stmt::Block(block) => block.stmts.clone(),
+        it => panic!("{:?}", it),
+    };
+    let named = |name: &str| TypeRef::named(name);
+
+    assert_eq!(
+        body.annotations.get(stmts[0]),
+        Some(&Annotations {
+            doc: Some("Adds one.".to_string()),
+            params: vec![
+                ParamAnnotation { name: Name::resolve("n"), ty: named("integer"), doc: None },
+                ParamAnnotation {
+                    name: Name::resolve("step"),
+                    ty: TypeRef::Union(vec![named("number"), named("nil")]),
+                    doc: None,
+                },
+            ],
+            returns: vec![
+                ReturnAnnotation { ty: named("integer"), name: None, doc: None },
+                ReturnAnnotation {
+                    ty: TypeRef::Union(vec![named("string"), named("nil")]),
+                    name: None,
+                    doc: None,
+                },
+            ],
+            ..Annotations::default()
+        })
+    );
+    assert_eq!(body.annotations.get(stmts[1]), None);
+    let table = TypeRef::Name {
+        name: Name::resolve("table"),
+        args: vec![named("string"), named("boolean")],
+    };
+    assert_eq!(
+        body.annotations.get(stmts[2]).map(|it| &it.types[..]),
+        Some(&[TypeRef::Array(Box::new(table))][..])
+    );
+}
+
+#[test]
+fn lower_file_annotations() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+---A point.
+---@class Point : Shape
+---@field x number
+---@field y? number
+
+---@alias Mode "r" | "w"
+
+---@alias Side
+---| "left"
+---| "right"
+"#,
+    );
+    let annotations = db.file_annotations(file_id);
+    let point = annotations.class(&Name::resolve("Point")).unwrap();
+    assert_eq!(point.doc.as_deref(), Some("A point."));
+    assert_eq!(point.parents, [TypeRef::named("Shape")]);
+    let fields: Vec<_> = point.fields.iter().map(|it| (it.name.as_str(), &it.ty)).collect();
+    assert_eq!(
+        fields,
+        [
+            ("x", &TypeRef::named("number")),
+            ("y", &TypeRef::Union(vec![TypeRef::named("number"), TypeRef::named("nil")])),
+        ]
+    );
+
+    let literals = |a: &str, b: &str| {
+        TypeRef::Union(vec![TypeRef::StrLiteral(a.into()), TypeRef::StrLiteral(b.into())])
+    };
+    assert_eq!(annotations.alias(&Name::resolve("Mode")).unwrap().ty, literals("r", "w"));
+    assert_eq!(annotations.alias(&Name::resolve("Side")).unwrap().ty, literals("left", "right"));
+}
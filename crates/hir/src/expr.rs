@@ -1,33 +1,83 @@
+use std::fmt;
+
 use la_arena::Idx;
 use ordered_float::OrderedFloat;
+use syntax::LuaOp;
 
 use crate::{
-    name::{MultiName, Name},
+    name::{MultiName, Name, NameId},
     stmt::StmtId,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Label {
-    name: Name,
+    pub name: Name,
 }
 
+pub type LabelId = Idx<Label>;
+
 pub type ExprId = Idx<Expr>;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Expr {
     Missing,
+    Name(Name),
     Call(Call),
     Literal(Literal),
+    Paren(ExprId),
     BinaryOp { lhs: ExprId, op: BinaryOp, rhs: ExprId },
     UnaryOp { op: UnaryOp, expr: ExprId },
     Index { base: ExprId, index: ExprId },
     Dot { base: ExprId, index: Name },
-    Function { params: ParamList, body: StmtId },
+    Function { params: Params, body: StmtId },
     Table(Table),
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct MultivalExpr;
+impl Expr {
+    pub fn walk_child_exprs(&self, mut f: impl FnMut(ExprId)) {
+        match self {
+            Expr::Missing | Expr::Name(_) | Expr::Literal(_) | Expr::Function { .. } => (),
+            Expr::Call(call) => {
+                f(call.callee);
+                call.args.walk_child_exprs(f);
+            }
+            Expr::Paren(expr) | Expr::UnaryOp { expr, .. } | Expr::Dot { base: expr, .. } => {
+                f(*expr)
+            }
+            Expr::BinaryOp { lhs, rhs, .. } => {
+                f(*lhs);
+                f(*rhs);
+            }
+            Expr::Index { base, index } => {
+                f(*base);
+                f(*index);
+            }
+            Expr::Table(table) => table.walk_child_exprs(f),
+        }
+    }
+}
+
+/// The parameters of a function inside of a `Body`.
+///
+/// Unlike `ParamList`, every parameter is allocated in the names arena of the
+/// body so that it can be resolved to.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Params {
+    /// The implicit `self` parameter of `function a:b() end`.
+    pub self_param: Option<NameId>,
+    pub names: Vec<NameId>,
+    pub vararg: bool,
+}
+
+impl Params {
+    pub fn iter(&self) -> impl Iterator<Item = NameId> + '_ {
+        self.self_param.iter().chain(self.names.iter()).copied()
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.names.shrink_to_fit()
+    }
+}
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
 pub struct ParamList {
@@ -41,11 +91,19 @@ impl ParamList {
     }
 }
 
+impl fmt::Display for ParamList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.names.iter().map(|it| it.as_str());
+        let params: Vec<_> = names.chain(self.vararg.then_some("...")).collect();
+        write!(f, "({})", params.join(", "))
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Call {
-    callee: ExprId,
-    method_name: Option<Name>,
-    args: CallArgs,
+    pub callee: ExprId,
+    pub method_name: Option<Name>,
+    pub args: CallArgs,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -55,20 +113,152 @@ pub enum CallArgs {
     Table(Table),
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct BinaryOp {}
+impl CallArgs {
+    pub fn walk_child_exprs(&self, f: impl FnMut(ExprId)) {
+        match self {
+            CallArgs::ArgList { args } => args.iter().copied().for_each(f),
+            CallArgs::Str(_) => (),
+            CallArgs::Table(table) => table.walk_child_exprs(f),
+        }
+    }
+}
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct UnaryOp {}
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BinaryOp {
+    LogicOp(LogicOp),
+    CmpOp(CmpOp),
+    ArithOp(ArithOp),
+    BitOp(BitOp),
+    Concat,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum LogicOp {
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum CmpOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    FloorDiv,
+    Mod,
+    Pow,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
+}
+
+impl BinaryOp {
+    pub fn from_lua_op(op: LuaOp) -> Option<BinaryOp> {
+        let res = match op {
+            LuaOp::Or => BinaryOp::LogicOp(LogicOp::Or),
+            LuaOp::And => BinaryOp::LogicOp(LogicOp::And),
+            LuaOp::Eq => BinaryOp::CmpOp(CmpOp::Eq),
+            LuaOp::NotEq => BinaryOp::CmpOp(CmpOp::NotEq),
+            LuaOp::Lt => BinaryOp::CmpOp(CmpOp::Lt),
+            LuaOp::LtEq => BinaryOp::CmpOp(CmpOp::LtEq),
+            LuaOp::Gt => BinaryOp::CmpOp(CmpOp::Gt),
+            LuaOp::GtEq => BinaryOp::CmpOp(CmpOp::GtEq),
+            LuaOp::Plus => BinaryOp::ArithOp(ArithOp::Add),
+            LuaOp::Minus => BinaryOp::ArithOp(ArithOp::Sub),
+            LuaOp::Mul => BinaryOp::ArithOp(ArithOp::Mul),
+            LuaOp::Div => BinaryOp::ArithOp(ArithOp::Div),
+            LuaOp::FloorDiv => BinaryOp::ArithOp(ArithOp::FloorDiv),
+            LuaOp::Mod => BinaryOp::ArithOp(ArithOp::Mod),
+            LuaOp::Power => BinaryOp::ArithOp(ArithOp::Pow),
+            LuaOp::BitAnd => BinaryOp::BitOp(BitOp::And),
+            LuaOp::BitOr => BinaryOp::BitOp(BitOp::Or),
+            LuaOp::BitXor => BinaryOp::BitOp(BitOp::Xor),
+            LuaOp::Shl => BinaryOp::BitOp(BitOp::Shl),
+            LuaOp::Shr => BinaryOp::BitOp(BitOp::Shr),
+            LuaOp::Concat => BinaryOp::Concat,
+            LuaOp::Not | LuaOp::Len | LuaOp::__LAST => return None,
+        };
+        Some(res)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+    Len,
+    BitNot,
+}
+
+impl UnaryOp {
+    pub fn from_lua_op(op: LuaOp) -> Option<UnaryOp> {
+        let res = match op {
+            LuaOp::Not => UnaryOp::Not,
+            LuaOp::Minus => UnaryOp::Neg,
+            LuaOp::Len => UnaryOp::Len,
+            LuaOp::BitXor => UnaryOp::BitNot,
+            _ => return None,
+        };
+        Some(res)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Table {
+    pub fields: Vec<TableField>,
+}
+
+impl Table {
+    pub fn walk_child_exprs(&self, mut f: impl FnMut(ExprId)) {
+        for field in &self.fields {
+            match field {
+                TableField::Positional(value) | TableField::Named { value, .. } => f(*value),
+                TableField::Indexed { key, value } => {
+                    f(*key);
+                    f(*value);
+                }
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Table {}
+pub enum TableField {
+    /// `{ value }`
+    Positional(ExprId),
+    /// `{ name = value }`
+    Named { name: Name, value: ExprId },
+    /// `{ [key] = value }`
+    Indexed { key: ExprId, value: ExprId },
+}
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Literal {
     Str(String),
     Bool(bool),
-    Number(OrderedFloat<f64>),
+    Number(Number),
     Nil,
     Vararg,
 }
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Number {
+    Int(i64),
+    Float(OrderedFloat<f64>),
+}
@@ -47,6 +47,21 @@ impl ItemTree {
         Arc::new(item_tree)
     }
 
+    /// The `local` statements of the file, including nested ones.
+    pub fn local_assigns(&self) -> impl Iterator<Item = &LocalAssign> {
+        self.data.iter().flat_map(|data| data.local_assigns.iter().map(|(_, it)| it))
+    }
+
+    /// The `local function` statements of the file, including nested ones.
+    pub fn local_functions(&self) -> impl Iterator<Item = &LocalFunction> {
+        self.data.iter().flat_map(|data| data.local_functions.iter().map(|(_, it)| it))
+    }
+
+    /// The `function` statements of the file, including nested ones.
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.data.iter().flat_map(|data| data.functions.iter().map(|(_, it)| it))
+    }
+
     fn data(&self) -> &ItemTreeData {
         self.data.as_ref().expect("attempted to access data of empty ItemTree")
     }
@@ -191,14 +206,26 @@ impl IndexPath {
     pub fn new(segments: Vec<Name>) -> Self {
         Self { segments }
     }
+
+    pub fn segments(&self) -> &[Name] {
+        &self.segments
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct LocalAssign {
     pub multi_name: MultiName,
+    /// The `<const>` or `<close>` of every name, in the same order.
+    pub attribs: Vec<Option<Attrib>>,
     pub ast_id: FileAstId<ast::LocalAssignStmt>,
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Attrib {
+    Const,
+    Close,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct LocalFunction {
     pub name: Name,
@@ -211,6 +238,7 @@ pub struct Function {
     pub path: IndexPath,
     pub name: Name,
     pub is_method: bool,
+    pub params: ParamList,
     pub ast_id: FileAstId<ast::FunctionDefStmt>,
 }
 
@@ -10,7 +10,7 @@ use syntax::{
 use crate::{ast_id_map::AstIdMap, expr::ParamList, name::AsName, DefDatabase};
 
 use super::{
-    FileItemTreeId, Function, IndexPath, ItemTree, ItemTreeData, ItemTreeNode, LocalAssign,
+    Attrib, FileItemTreeId, Function, IndexPath, ItemTree, ItemTreeData, ItemTreeNode, LocalAssign,
     LocalFunction, ModItem, MultiName, Name,
 };
 
@@ -34,8 +34,15 @@ impl Ctx {
     }
 
     pub(super) fn lower_module_items(mut self, module: &SourceFile) -> ItemTree {
-        self.tree.top_level =
-            module.stmt_items().flat_map(|item| self.lower_mod_item(&item, false)).collect();
+        let stmts: Vec<_> = module.body().into_iter().flat_map(|block| block.stmts()).collect();
+        for stmt in &stmts {
+            self.collect_inner_items(stmt.syntax());
+        }
+        self.tree.top_level = stmts
+            .iter()
+            .filter_map(|stmt| ast::StmtItem::cast(stmt.syntax().clone()))
+            .flat_map(|item| self.lower_mod_item(&item, false))
+            .collect();
 
         self.tree
     }
@@ -91,10 +98,19 @@ impl Ctx {
         &mut self,
         assign: &ast::LocalAssignStmt,
     ) -> Option<FileItemTreeId<LocalAssign>> {
-        let multi_name = self.lower_multi_name(&assign.multi_name()?);
+        let ast_multi_name = assign.multi_name()?;
+        let multi_name = self.lower_multi_name(&ast_multi_name);
+        let attribs = ast_multi_name
+            .names_with_attribs()
+            .into_iter()
+            .map(|(_, attrib)| match attrib?.kind()? {
+                ast::AttribKind::Const => Some(Attrib::Const),
+                ast::AttribKind::Close => Some(Attrib::Close),
+            })
+            .collect();
         let ast_id = self.ast_id_map.ast_id(assign);
 
-        let res = LocalAssign { multi_name, ast_id };
+        let res = LocalAssign { multi_name, attribs, ast_id };
 
         Some(id(self.data().local_assigns.alloc(res)))
     }
@@ -104,7 +120,7 @@ impl Ctx {
         func: &ast::LocalFunctionDefStmt,
     ) -> Option<FileItemTreeId<LocalFunction>> {
         let name = func.name()?.as_name();
-        let params = self.lower_params(&func.param_list()?)?;
+        let params = self.lower_params(&func.param_list()?);
         let ast_id = self.ast_id_map.ast_id(func);
 
         let res = LocalFunction { name, params, ast_id };
@@ -114,9 +130,10 @@ impl Ctx {
 
     fn lower_function(&mut self, func: &ast::FunctionDefStmt) -> Option<FileItemTreeId<Function>> {
         let (path, name, is_method) = self.lower_function_content(&func.function_def_content()?)?;
+        let params = self.lower_params(&func.param_list()?);
         let ast_id = self.ast_id_map.ast_id(func);
 
-        let res = Function { path, name, is_method, ast_id };
+        let res = Function { path, name, is_method, params, ast_id };
 
         Some(id(self.data().functions.alloc(res)))
     }
@@ -155,10 +172,9 @@ impl Ctx {
         MultiName::new(names)
     }
 
-    fn lower_params(&mut self, params: &ast::ParamList) -> Option<ParamList> {
-        let names = self.lower_multi_name(&params.multi_name()?);
+    fn lower_params(&mut self, params: &ast::ParamList) -> ParamList {
+        let names = params.multi_name().map(|it| self.lower_multi_name(&it)).unwrap_or_default();
         let vararg = params.triple_dot_token().is_some();
-        let res = ParamList { names, vararg };
-        Some(res)
+        ParamList { names, vararg }
     }
 }
@@ -1,26 +1,33 @@
+pub mod annotations;
 mod ast_id_map;
 pub mod body;
 pub mod expr;
 mod ids;
-mod item_tree;
+pub mod item_tree;
 mod name;
 mod semantics;
 pub mod stmt;
+pub mod type_ref;
 mod with_body;
 
+#[cfg(test)]
+mod test_db;
+
 use std::sync::Arc;
 
 use base_db::{salsa, FileId, SourceDatabase, Upcast};
-use body::Body;
+use body::{scope::ExprScopes, Body, BodySourceMap};
 use syntax::{ast::AstNode, SyntaxNode};
 
+use annotations::FileAnnotations;
 use ast_id_map::AstIdMap;
 use ids::{
     FunctionId, FunctionLoc, LocalAssignId, LocalAssignLoc, LocalFunctionId, LocalFunctionLoc,
 };
 use item_tree::ItemTree;
-pub use semantics::Semantics;
-use with_body::WithBodyId;
+pub use name::{AsName, Name, NameId};
+pub use semantics::{AccessPath, Label, Local, PathRoot, Semantics};
+pub use with_body::WithBodyId;
 
 #[salsa::query_group(InternDatabaseStorage)]
 pub trait InternDatabase: SourceDatabase {
@@ -47,10 +54,7 @@ fn syntax_node(db: &dyn SourceDatabase, file_id: FileId) -> SyntaxNode {
 }
 
 fn ast_id_map(db: &dyn AstDatabase, file_id: FileId) -> Arc<AstIdMap> {
-    let map = db
-        .parse(file_id)
-        .ok()
-        .map_or_else(|_| AstIdMap::default(), |it| AstIdMap::from_source(&it.syntax()));
+    let map = AstIdMap::from_source(db.parse(file_id).tree().syntax());
     Arc::new(map)
 }
 
@@ -61,8 +65,17 @@ pub trait DefDatabase:
     #[salsa::invoke(ItemTree::file_item_tree_query)]
     fn file_item_tree(&self, file_id: FileId) -> Arc<ItemTree>;
 
+    #[salsa::invoke(Body::body_with_source_map_query)]
+    fn body_with_source_map(&self, def: WithBodyId) -> (Arc<Body>, Arc<BodySourceMap>);
+
     #[salsa::invoke(Body::body_query)]
-    fn body(&self, thing: WithBodyId) -> Arc<Body>;
+    fn body(&self, def: WithBodyId) -> Arc<Body>;
+
+    #[salsa::invoke(ExprScopes::expr_scopes_query)]
+    fn expr_scopes(&self, def: WithBodyId) -> Arc<ExprScopes>;
+
+    #[salsa::invoke(FileAnnotations::file_annotations_query)]
+    fn file_annotations(&self, file_id: FileId) -> Arc<FileAnnotations>;
 }
 
 #[salsa::query_group(HirDatabaseStorage)]
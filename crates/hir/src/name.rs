@@ -1,5 +1,8 @@
+use std::fmt;
+
+use la_arena::Idx;
 use smol_str::SmolStr;
-use syntax::ast::{self, AstNode};
+use syntax::ast;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
 pub struct MultiName {
@@ -11,11 +14,18 @@ impl MultiName {
         Self { names }
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &Name> {
+        self.names.iter()
+    }
+
     pub fn shrink_to_fit(&mut self) {
         self.names.shrink_to_fit()
     }
 }
 
+/// A name bound inside of a `Body`, such as a local or a parameter.
+pub type NameId = Idx<Name>;
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Name(SmolStr);
 
@@ -24,9 +34,29 @@ impl Name {
         Self(text)
     }
 
-    fn resolve(raw_text: &str) -> Self {
+    pub fn resolve(raw_text: &str) -> Self {
         Name::new_text(raw_text.into())
     }
+
+    /// A fake name used when the syntax tree is missing one, so that lowering
+    /// can always produce a name.
+    pub(crate) fn missing() -> Self {
+        Name::new_text("[missing name]".into())
+    }
+
+    pub fn is_missing(&self) -> bool {
+        *self == Name::missing()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
 }
 
 pub trait AsName {
@@ -35,12 +65,13 @@ pub trait AsName {
 
 impl AsName for ast::Name {
     fn as_name(&self) -> Name {
-        Name::resolve(&self.text())
+        self.ident_token().map_or_else(Name::missing, |it| Name::resolve(it.text()))
     }
 }
 
 impl AsName for ast::NameRef {
     fn as_name(&self) -> Name {
-        Name::resolve(&self.text())
+        // the name of `a:` is still being typed and has no identifier
+        self.ident_token().map_or_else(Name::missing, |it| Name::resolve(it.text()))
     }
 }
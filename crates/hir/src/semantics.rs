@@ -1,12 +1,130 @@
-use crate::HirDatabase;
+use std::sync::Arc;
 
-use base_db::{FileId, SourceDatabase};
-use syntax::{ast, SourceFile};
+use base_db::FileId;
+use syntax::{
+    ast::{self, AstNode},
+    TextSize,
+};
+
+use crate::{
+    body::{
+        scope::{ExprScopes, Resolution, ScopeId},
+        Body, BodySourceMap,
+    },
+    expr::{Expr, ExprId, LabelId},
+    name::{AsName, Name, NameId},
+    stmt::{BlockLoc, Stmt},
+    with_body::WithBodyId,
+    HirDatabase, InFile,
+};
 
 pub struct Semantics<'db> {
     pub db: &'db dyn HirDatabase,
 }
 
+/// A binding declared in the body of a file, such as a `local` or a parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Local {
+    pub file_id: FileId,
+    pub name: NameId,
+}
+
+/// A `::label::` declared in the body of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Label {
+    pub file_id: FileId,
+    pub label: LabelId,
+}
+
+/// What the first name of an access path refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathRoot {
+    Local(Local),
+    Global(Name),
+}
+
+/// A name followed by any number of field accesses, such as `a.b.c`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AccessPath {
+    pub root: PathRoot,
+    pub fields: Vec<Name>,
+}
+
+impl AccessPath {
+    /// Computes the access path of an expression of the body of `file_id`,
+    /// returns `None` if it is not a name or a field access.
+    pub fn of_expr(
+        file_id: FileId,
+        body: &Body,
+        scopes: &ExprScopes,
+        expr: ExprId,
+    ) -> Option<AccessPath> {
+        match &body.exprs[expr] {
+            Expr::Name(_) => {
+                let root = match scopes.resolve_expr(body, expr)? {
+                    Resolution::Local { name, .. } | Resolution::Upvalue { name, .. } => {
+                        PathRoot::Local(Local { file_id, name })
+                    }
+                    Resolution::Global(name) => PathRoot::Global(name),
+                };
+                Some(AccessPath { root, fields: Vec::new() })
+            }
+            Expr::Dot { base, index } => {
+                let mut path = AccessPath::of_expr(file_id, body, scopes, *base)?;
+                path.fields.push(index.clone());
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+
+    /// The last name of the path.
+    pub fn name(&self, db: &dyn HirDatabase) -> Name {
+        match (self.fields.last(), &self.root) {
+            (Some(field), _) => field.clone(),
+            (None, PathRoot::Local(local)) => local.name(db),
+            (None, PathRoot::Global(name)) => name.clone(),
+        }
+    }
+}
+
+impl Local {
+    pub fn name(&self, db: &dyn HirDatabase) -> Name {
+        let body = db.body(WithBodyId::ModuleId(self.file_id));
+        body.names[self.name].clone()
+    }
+
+    /// The name that declares the binding, the implicit `self` parameter has
+    /// none.
+    pub fn source(&self, db: &dyn HirDatabase) -> Option<InFile<ast::Name>> {
+        let (_, source_map) = db.body_with_source_map(WithBodyId::ModuleId(self.file_id));
+        let src = source_map.name_syntax(self.name)?;
+        let root = db.parse(self.file_id).syntax_node();
+        Some(src.map(|ptr| ptr.to_node(&root)))
+    }
+}
+
+impl Label {
+    pub fn name(&self, db: &dyn HirDatabase) -> Name {
+        let body = db.body(WithBodyId::ModuleId(self.file_id));
+        body.labels[self.label].name.clone()
+    }
+
+    pub fn source(&self, db: &dyn HirDatabase) -> Option<InFile<ast::LabelStmt>> {
+        let (body, source_map) = db.body_with_source_map(WithBodyId::ModuleId(self.file_id));
+        let stmt = body.stmts.iter().find_map(|(id, stmt)| match stmt {
+            Stmt::Label { label } if *label == self.label => Some(id),
+            _ => None,
+        })?;
+        let src = source_map.stmt_syntax(stmt)?;
+        let root = db.parse(self.file_id).syntax_node();
+        match src.value.to_node(&root) {
+            ast::Stmt::LabelStmt(it) => Some(InFile::new(self.file_id, it)),
+            _ => None,
+        }
+    }
+}
+
 impl<'db> Semantics<'db> {
     pub fn new(db: &'db dyn HirDatabase) -> Semantics<'_> {
         Semantics { db }
@@ -15,4 +133,101 @@ impl<'db> Semantics<'db> {
     pub fn parse(&self, file_id: FileId) -> ast::SourceFile {
         self.db.parse(file_id).tree()
     }
+
+    /// The body of the whole file, which contains the bodies of all of the
+    /// functions defined in it.
+    pub fn file_body(&self, file_id: FileId) -> (Arc<Body>, Arc<BodySourceMap>) {
+        self.db.body_with_source_map(WithBodyId::ModuleId(file_id))
+    }
+
+    pub fn file_scopes(&self, file_id: FileId) -> Arc<ExprScopes> {
+        self.db.expr_scopes(WithBodyId::ModuleId(file_id))
+    }
+
+    pub fn access_path(&self, file_id: FileId, expr: ExprId) -> Option<AccessPath> {
+        let body = self.db.body(WithBodyId::ModuleId(file_id));
+        let scopes = self.file_scopes(file_id);
+        AccessPath::of_expr(file_id, &body, &scopes, expr)
+    }
+
+    /// The scope a name written at `offset` would be resolved in.
+    pub fn scope_at_offset(&self, file_id: FileId, offset: TextSize) -> Option<ScopeId> {
+        let (_, source_map) = self.file_body(file_id);
+        let scopes = self.file_scopes(file_id);
+        let file = self.parse(file_id);
+        let token = file.syntax().token_at_offset(offset).left_biased()?;
+        let start = match ast::Block::before_trivia(&token) {
+            Some(block) => block.syntax().clone(),
+            None => token.parent(),
+        };
+        for node in start.ancestors() {
+            let scope = if let Some(expr) = ast::Expr::cast(node.clone()) {
+                let expr = source_map.node_expr(InFile::new(file_id, &expr));
+                expr.and_then(|it| scopes.scope_for(it))
+            } else if let Some(block) = ast::Block::cast(node.clone()) {
+                // the locals of the statements before the offset are visible
+                let prev =
+                    block.stmts().take_while(|it| it.syntax().text_range().end() <= offset).last();
+                match prev {
+                    Some(stmt) => source_map
+                        .node_stmt(InFile::new(file_id, &stmt))
+                        .and_then(|it| scopes.scope_after_stmt(it)),
+                    None => {
+                        let ast_id = self.db.ast_id_map(file_id).ast_id(&block);
+                        scopes.scope_for_block(BlockLoc { ast_id: InFile::new(file_id, ast_id) })
+                    }
+                }
+            } else if let Some(stmt) = ast::Stmt::cast(node) {
+                let stmt = source_map.node_stmt(InFile::new(file_id, &stmt));
+                stmt.and_then(|it| scopes.scope_for_stmt(it))
+            } else {
+                None
+            };
+            if scope.is_some() {
+                return scope;
+            }
+        }
+        None
+    }
+
+    pub fn resolve_local(&self, file_id: FileId, name: &ast::Name) -> Option<Local> {
+        let (_, source_map) = self.file_body(file_id);
+        let name = source_map.node_name(InFile::new(file_id, name))?;
+        Some(Local { file_id, name })
+    }
+
+    /// Finds the label a `goto` jumps to, which is the one declared in the
+    /// innermost enclosing block of the same function.
+    pub fn resolve_goto(&self, file_id: FileId, goto: &ast::GotoStmt) -> Option<Label> {
+        let name = goto.name_ref()?.as_name();
+        let label = goto
+            .syntax()
+            .ancestors()
+            .take_while(|it| {
+                !ast::FunctionExpr::can_cast(it.kind())
+                    && !ast::FunctionDefStmt::can_cast(it.kind())
+                    && !ast::LocalFunctionDefStmt::can_cast(it.kind())
+            })
+            .filter_map(ast::Block::cast)
+            .find_map(|block| {
+                block.stmts().find_map(|stmt| match stmt {
+                    ast::Stmt::LabelStmt(label)
+                        if label.name().map(|it| it.as_name()).as_ref() == Some(&name) =>
+                    {
+                        Some(label)
+                    }
+                    _ => None,
+                })
+            })?;
+        self.resolve_label(file_id, &label)
+    }
+
+    pub fn resolve_label(&self, file_id: FileId, label: &ast::LabelStmt) -> Option<Label> {
+        let (body, source_map) = self.file_body(file_id);
+        let stmt = source_map.node_stmt(InFile::new(file_id, &label.clone().into()))?;
+        match body.stmts[stmt] {
+            Stmt::Label { label } => Some(Label { file_id, label }),
+            _ => None,
+        }
+    }
 }
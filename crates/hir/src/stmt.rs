@@ -2,9 +2,9 @@ use la_arena::{Idx, RawIdx};
 use syntax::ast;
 
 use crate::{
-    expr::{Call, ExprId, MultivalExpr},
+    expr::{ExprId, LabelId},
     item_tree::AstId,
-    name::{MultiName, Name},
+    name::{Name, NameId},
 };
 
 pub(crate) fn dummy_id() -> StmtId {
@@ -16,31 +16,134 @@ pub type StmtId = Idx<Stmt>;
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Stmt {
     Missing,
-    Assign { lhs: MultivalExpr, rhs: MultivalExpr },
+    /// A block that is not introduced by any keyword, such as the body of a
+    /// function or of an `else` branch.
+    Block(Block),
+    Local {
+        names: Vec<NameId>,
+        exprs: Vec<ExprId>,
+    },
+    LocalFunction {
+        name: NameId,
+        func: ExprId,
+    },
+    /// `function a.b:c() end`, `target` is the expression being assigned to.
+    FunctionDef {
+        target: ExprId,
+        is_method: bool,
+        func: ExprId,
+    },
+    Assign {
+        lhs: Vec<ExprId>,
+        rhs: Vec<ExprId>,
+    },
+    Expr(ExprId),
     Do(Block),
-    While { condition: ExprId, body: Block },
-    For { content: ForContent, body: Block },
-    Call(Call),
-    If { condition: ExprId, body: Block },
+    While {
+        condition: ExprId,
+        body: Block,
+    },
+    Repeat {
+        body: Block,
+        condition: ExprId,
+    },
+    For {
+        content: ForContent,
+        body: Block,
+    },
+    /// The else branch is either a `Stmt::Block` for `else` or another
+    /// `Stmt::If` for `elseif`.
+    If {
+        condition: ExprId,
+        then_branch: Block,
+        else_branch: Option<StmtId>,
+    },
     Break,
-    Return { expr: ExprId },
-    Label { name: Name },
-    Goto { name: Name },
+    Return {
+        exprs: Vec<ExprId>,
+    },
+    Label {
+        label: LabelId,
+    },
+    Goto {
+        name: Name,
+    },
+}
+
+impl Stmt {
+    pub fn walk_child_exprs(&self, mut f: impl FnMut(ExprId)) {
+        match self {
+            Stmt::Missing
+            | Stmt::Block(_)
+            | Stmt::Do(_)
+            | Stmt::Break
+            | Stmt::Label { .. }
+            | Stmt::Goto { .. } => (),
+            Stmt::Local { exprs, .. } => exprs.iter().copied().for_each(f),
+            Stmt::LocalFunction { func, .. } => f(*func),
+            Stmt::FunctionDef { target, func, .. } => {
+                f(*target);
+                f(*func);
+            }
+            Stmt::Assign { lhs, rhs } => lhs.iter().chain(rhs).copied().for_each(f),
+            Stmt::Expr(expr) => f(*expr),
+            Stmt::Return { exprs } => exprs.iter().copied().for_each(f),
+            Stmt::While { condition, .. }
+            | Stmt::Repeat { condition, .. }
+            | Stmt::If { condition, .. } => f(*condition),
+            Stmt::For { content, .. } => match content {
+                ForContent::Numeric { start, end, step, .. } => {
+                    f(*start);
+                    f(*end);
+                    if let Some(step) = step {
+                        f(*step);
+                    }
+                }
+                ForContent::Generic { expr, .. } => f(*expr),
+            },
+        }
+    }
+
+    pub fn walk_child_stmts(&self, mut f: impl FnMut(StmtId)) {
+        match self {
+            Stmt::Block(block)
+            | Stmt::Do(block)
+            | Stmt::While { body: block, .. }
+            | Stmt::Repeat { body: block, .. }
+            | Stmt::For { body: block, .. } => block.stmts.iter().copied().for_each(f),
+            Stmt::If { then_branch, else_branch, .. } => {
+                then_branch.stmts.iter().copied().for_each(&mut f);
+                if let Some(else_branch) = else_branch {
+                    f(*else_branch);
+                }
+            }
+            Stmt::Missing
+            | Stmt::Local { .. }
+            | Stmt::LocalFunction { .. }
+            | Stmt::FunctionDef { .. }
+            | Stmt::Assign { .. }
+            | Stmt::Expr(_)
+            | Stmt::Break
+            | Stmt::Return { .. }
+            | Stmt::Label { .. }
+            | Stmt::Goto { .. } => (),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ForContent {
-    Numeric { name: Name, start: ExprId, end: ExprId, increment: Option<ExprId> },
-    Generic { multi_name: MultiName, expr: ExprId },
+    Numeric { name: NameId, start: ExprId, end: ExprId, step: Option<ExprId> },
+    Generic { names: Vec<NameId>, expr: ExprId },
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Block {
-    block_loc: BlockLoc,
-    stmts: Vec<StmtId>,
+    pub block_loc: Option<BlockLoc>,
+    pub stmts: Vec<StmtId>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BlockLoc {
-    ast_id: AstId<ast::Block>,
+    pub ast_id: AstId<ast::Block>,
 }
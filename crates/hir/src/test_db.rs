@@ -0,0 +1,53 @@
+//! Database used for testing `hir`.
+
+use std::{fmt, panic};
+
+use base_db::{salsa, FileId, SourceDatabase, Upcast};
+
+use crate::{AstDatabase, DefDatabase};
+
+#[salsa::database(
+    base_db::SourceDatabaseStorage,
+    crate::InternDatabaseStorage,
+    crate::AstDatabaseStorage,
+    crate::DefDatabaseStorage,
+    crate::HirDatabaseStorage
+)]
+#[derive(Default)]
+pub(crate) struct TestDB {
+    storage: salsa::Storage<TestDB>,
+}
+
+impl Upcast<dyn AstDatabase> for TestDB {
+    fn upcast(&self) -> &(dyn AstDatabase + 'static) {
+        self
+    }
+}
+
+impl Upcast<dyn DefDatabase> for TestDB {
+    fn upcast(&self) -> &(dyn DefDatabase + 'static) {
+        self
+    }
+}
+
+impl salsa::Database for TestDB {}
+
+impl fmt::Debug for TestDB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestDB").finish()
+    }
+}
+
+impl panic::RefUnwindSafe for TestDB {}
+
+impl TestDB {
+    pub(crate) fn with_single_file(text: &str) -> (TestDB, FileId) {
+        let mut db = TestDB::default();
+        let file_id = FileId(0);
+        db.set_file_text(file_id, text.to_string().into());
+        db.set_files(vec![file_id].into());
+        db.set_lua_version(Default::default());
+        db.set_lua_version_overrides(Default::default());
+        (db, file_id)
+    }
+}
@@ -0,0 +1,129 @@
+//! The types written in annotations, as opposed to the types inferred for
+//! values.
+
+use syntax::doc;
+
+use crate::name::Name;
+
+/// A type written in an annotation, such as `integer[]` or
+/// `fun(a: string): boolean`, which only refers to other types by name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeRef {
+    /// A builtin type such as `integer`, a class, an alias or a generic
+    /// parameter, with the arguments of `table<K, V>`.
+    Name {
+        name: Name,
+        args: Vec<TypeRef>,
+    },
+    Array(Box<TypeRef>),
+    Union(Vec<TypeRef>),
+    Function {
+        params: Vec<(Name, TypeRef)>,
+        vararg: bool,
+        returns: Vec<TypeRef>,
+    },
+    /// A table with these fields, as in `{ x: number }`.
+    Table(Vec<(Name, TypeRef)>),
+    /// A string that is its own type, as in `"left" | "right"`.
+    StrLiteral(String),
+    /// A number that is its own type.
+    NumLiteral(String),
+    /// A type that could not be parsed.
+    Error,
+}
+
+impl TypeRef {
+    pub fn from_ast_opt(ty: Option<doc::DocType>) -> TypeRef {
+        ty.map_or(TypeRef::Error, TypeRef::from_ast)
+    }
+
+    pub fn from_ast(ty: doc::DocType) -> TypeRef {
+        match ty {
+            doc::DocType::Name(it) => match it.name_token() {
+                Some(name) => TypeRef::Name {
+                    name: Name::resolve(name.text()),
+                    args: it.args().map(TypeRef::from_ast).collect(),
+                },
+                None => TypeRef::Error,
+            },
+            doc::DocType::Array(it) => TypeRef::Array(Box::new(TypeRef::from_ast_opt(it.elem()))),
+            doc::DocType::Optional(it) => {
+                TypeRef::from_ast_opt(it.inner()).union(TypeRef::named("nil"))
+            }
+            doc::DocType::Union(it) => {
+                it.types().map(TypeRef::from_ast).reduce(TypeRef::union).unwrap_or(TypeRef::Error)
+            }
+            doc::DocType::Fun(it) => {
+                let mut vararg = false;
+                let mut params = Vec::new();
+                for param in it.params() {
+                    let name = match param.name_token() {
+                        Some(name) if name.text() == "..." => {
+                            vararg = true;
+                            continue;
+                        }
+                        Some(name) => Name::resolve(name.text()),
+                        None => continue,
+                    };
+                    let mut ty = param.ty().map_or(TypeRef::named("any"), TypeRef::from_ast);
+                    if param.is_optional() {
+                        ty = ty.union(TypeRef::named("nil"));
+                    }
+                    params.push((name, ty));
+                }
+                let returns = it.returns().map(TypeRef::from_ast).collect();
+                TypeRef::Function { params, vararg, returns }
+            }
+            doc::DocType::Table(it) => {
+                let fields = it
+                    .fields()
+                    .filter_map(|field| {
+                        let name = Name::resolve(field.name_token()?.text());
+                        let mut ty = TypeRef::from_ast_opt(field.ty());
+                        if field.is_optional() {
+                            ty = ty.union(TypeRef::named("nil"));
+                        }
+                        Some((name, ty))
+                    })
+                    .collect();
+                TypeRef::Table(fields)
+            }
+            doc::DocType::Literal(it) => match it.token() {
+                Some(token) if token.kind() == doc::DocKind::String => {
+                    let text = token.text();
+                    let text = text.get(1..text.len() - 1).unwrap_or_default();
+                    TypeRef::StrLiteral(text.to_string())
+                }
+                Some(token) => TypeRef::NumLiteral(token.text().to_string()),
+                None => TypeRef::Error,
+            },
+            doc::DocType::Paren(it) => TypeRef::from_ast_opt(it.inner()),
+        }
+    }
+
+    /// A type without arguments, such as `nil`.
+    pub fn named(name: &str) -> TypeRef {
+        TypeRef::Name { name: Name::resolve(name), args: Vec::new() }
+    }
+
+    /// A type that is either type, with the members of unions flattened.
+    pub fn union(self, other: TypeRef) -> TypeRef {
+        let mut members = match self {
+            TypeRef::Union(it) => it,
+            it => vec![it],
+        };
+        let others = match other {
+            TypeRef::Union(it) => it,
+            it => vec![it],
+        };
+        for ty in others {
+            if !members.contains(&ty) {
+                members.push(ty);
+            }
+        }
+        if members.len() == 1 {
+            return members.pop().unwrap();
+        }
+        TypeRef::Union(members)
+    }
+}
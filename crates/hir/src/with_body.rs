@@ -1,6 +1,9 @@
 use base_db::FileId;
 
-use crate::ids::{FunctionId, LocalFunctionId};
+use crate::{
+    ids::{FunctionId, LocalFunctionId},
+    DefDatabase, Lookup,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum WithBodyId {
@@ -8,3 +11,13 @@ pub enum WithBodyId {
     FunctionId(FunctionId),
     LocalFunctionId(LocalFunctionId),
 }
+
+impl WithBodyId {
+    pub fn file_id(self, db: &dyn DefDatabase) -> FileId {
+        match self {
+            WithBodyId::ModuleId(file_id) => file_id,
+            WithBodyId::FunctionId(it) => it.lookup(db).file_id(),
+            WithBodyId::LocalFunctionId(it) => it.lookup(db).file_id(),
+        }
+    }
+}
@@ -0,0 +1,1275 @@
+//! Infers the types of the expressions and bindings of a body.
+//!
+//! Lua has no declarations of types, so the type of a binding is the union of
+//! everything assigned to it anywhere in the body, regardless of control flow,
+//! and the shape of a table is every field assigned to it. A value can be used
+//! before the statement that assigns it is reached, as in a function calling a
+//! local that is defined after it, so the body is walked again until nothing
+//! new is learned.
+//!
+//! The types declared by LuaCATS annotations are trusted instead: a binding
+//! with a `---@type` or `---@param` and a function with `---@return`s keep
+//! their declared types whatever is assigned or returned.
+//!
+//! Once nothing new is learned, the body is walked a last time to check the
+//! uses of the inferred types, such as calls of values that are not functions.
+
+use std::sync::Arc;
+
+use hir::{
+    annotations::{Annotations, FileAnnotations},
+    body::{
+        scope::{ExprScopes, Resolution},
+        Body,
+    },
+    expr::{
+        ArithOp, BinaryOp, Call, CallArgs, Expr, ExprId, Literal, LogicOp, Number, Params, Table,
+        TableField, UnaryOp,
+    },
+    stmt::{Block, ForContent, Stmt, StmtId},
+    type_ref::TypeRef,
+    Name, NameId, WithBodyId,
+};
+use la_arena::ArenaMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::LuaVersion;
+
+use crate::{
+    ty::{FnSig, TableShape, Ty},
+    HirTyDatabase,
+};
+
+/// Walking a body again should only ever learn about values used before they
+/// are assigned, this is a bound in case it keeps learning anyway.
+const MAX_PASSES: usize = 16;
+
+/// How deep aliases are expanded, in case they refer to themselves.
+const MAX_ALIAS_DEPTH: usize = 8;
+
+/// The types inferred for a body.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InferenceResult {
+    type_of_expr: ArenaMap<ExprId, Ty>,
+    type_of_name: ArenaMap<NameId, Ty>,
+    globals: FxHashMap<Name, Ty>,
+    tables: ArenaMap<ExprId, TableShape>,
+    functions: ArenaMap<ExprId, FnSig>,
+    classes: FxHashMap<Name, TableShape>,
+    returns: Vec<Ty>,
+    diagnostics: Vec<InferenceDiagnostic>,
+}
+
+/// A use of a value that its type does not allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InferenceDiagnostic {
+    /// A call of a value that is neither a function nor a table, which can
+    /// have a `__call` metamethod.
+    NotCallable { call: ExprId, ty: Ty },
+    /// Indexing a value that is neither a table nor a string. `base` is the
+    /// indexed expression.
+    NotIndexable { base: ExprId, ty: Ty },
+    /// An argument of a call to a function with a declared type for the
+    /// parameter, which the argument is not.
+    ArgTypeMismatch { arg: ExprId, expected: Ty, actual: Ty },
+    /// A value returned by a function with declared returns, which it is not.
+    ReturnTypeMismatch { expr: ExprId, expected: Ty, actual: Ty },
+    /// An assignment to a field of an instance of a class that neither the
+    /// class nor its parents declare.
+    UndeclaredField { target: ExprId, class: Name, field: Name },
+}
+
+static UNKNOWN: Ty = Ty::Unknown;
+
+impl InferenceResult {
+    pub fn type_of_expr(&self, expr: ExprId) -> &Ty {
+        self.type_of_expr.get(expr).unwrap_or(&UNKNOWN)
+    }
+
+    /// The type of a local or parameter, which is every value assigned to it.
+    pub fn type_of_name(&self, name: NameId) -> &Ty {
+        self.type_of_name.get(name).unwrap_or(&UNKNOWN)
+    }
+
+    /// The type of the values assigned to a global in the body.
+    pub fn type_of_global(&self, name: &Name) -> &Ty {
+        self.globals.get(name).unwrap_or(&UNKNOWN)
+    }
+
+    /// The signature of the function created by an `Expr::Function`.
+    pub fn fn_sig(&self, func: ExprId) -> Option<&FnSig> {
+        self.functions.get(func)
+    }
+
+    /// The shape of the table created by an `Expr::Table`.
+    pub fn table(&self, table: ExprId) -> Option<&TableShape> {
+        self.tables.get(table)
+    }
+
+    /// The fields of the instances of a class of the file, those declared by
+    /// `---@field` followed by those assigned to the table defining it.
+    pub fn class(&self, name: &Name) -> Option<&TableShape> {
+        self.classes.get(name)
+    }
+
+    /// The values returned by the body itself, outside of any function.
+    pub fn returns(&self) -> &[Ty] {
+        &self.returns
+    }
+
+    pub fn diagnostics(&self) -> &[InferenceDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// The type of the field `name` of a value of type `ty`.
+    pub fn field_ty(&self, ty: &Ty, name: &Name) -> Ty {
+        let fields = ty.members().iter().filter_map(|ty| match ty {
+            Ty::Table(table) => self.table(*table)?.field(name).cloned(),
+            Ty::Class(class) => Some(
+                self.class(class).and_then(|it| it.field(name)).cloned().unwrap_or(Ty::Unknown),
+            ),
+            Ty::Map(key, value) if matches!(**key, Ty::Unknown | Ty::String) => {
+                Some((**value).clone())
+            }
+            Ty::Unknown | Ty::String => Some(Ty::Unknown),
+            _ => None,
+        });
+        fields.reduce(Ty::union).unwrap_or(Ty::Unknown)
+    }
+
+    /// The named fields of the tables and classes that a value of type `ty`
+    /// can be.
+    pub fn fields(&self, ty: &Ty) -> Vec<(Name, Ty)> {
+        let mut res: Vec<(Name, Ty)> = Vec::new();
+        for ty in ty.members() {
+            let shape = match ty {
+                Ty::Table(table) => self.table(*table),
+                Ty::Class(class) => self.class(class),
+                _ => None,
+            };
+            for (name, ty) in shape.into_iter().flat_map(|it| &it.fields) {
+                match res.iter_mut().find(|(it, _)| it == name) {
+                    Some((_, old)) => *old = old.clone().union(ty.clone()),
+                    None => res.push((name.clone(), ty.clone())),
+                }
+            }
+        }
+        res
+    }
+}
+
+pub(crate) fn infer_query(db: &dyn HirTyDatabase, def: WithBodyId) -> Arc<InferenceResult> {
+    let body = db.body(def);
+    let scopes = db.expr_scopes(def);
+    let file_id = def.file_id(db.upcast());
+    let annotations = db.file_annotations(file_id);
+    let ctx = InferenceContext::new(&body, &scopes, &annotations, db.file_lua_version(file_id));
+    Arc::new(ctx.infer())
+}
+
+/// The values of a list of expressions, which has more values than expressions
+/// when the last one is a call.
+///
+/// A `None` type is one nothing is known about yet, unlike `Ty::Unknown`
+/// which is known to be anything. It is left out of unions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Values {
+    tys: Vec<Option<Ty>>,
+    /// The type of every value after `tys`.
+    rest: Option<Ty>,
+}
+
+impl Values {
+    fn none() -> Values {
+        Values { tys: Vec::new(), rest: Some(Ty::Nil) }
+    }
+
+    fn single(ty: Option<Ty>) -> Values {
+        Values { tys: vec![ty], rest: Some(Ty::Nil) }
+    }
+
+    fn get(&self, idx: usize) -> Option<Ty> {
+        self.tys.get(idx).cloned().unwrap_or_else(|| self.rest.clone())
+    }
+
+    fn union(&self, other: &Values) -> Values {
+        let len = self.tys.len().max(other.tys.len());
+        let tys = (0..len).map(|idx| join(self.get(idx), other.get(idx))).collect();
+        Values { tys, rest: join(self.rest.clone(), other.rest.clone()) }
+    }
+}
+
+fn join(a: Option<Ty>, b: Option<Ty>) -> Option<Ty> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// The union of `old` and `ty` if it is not `old`.
+fn widen(old: Option<&Ty>, ty: Ty) -> Option<Ty> {
+    let new = match old {
+        Some(old) => old.clone().union(ty),
+        None => ty,
+    };
+    (Some(&new) != old).then_some(new)
+}
+
+/// The members of `ty` that `keep` accepts.
+fn filter_members(ty: Option<Ty>, keep: impl Fn(&Ty) -> bool) -> Option<Ty> {
+    ty?.members().iter().filter(|it| keep(it)).cloned().reduce(Ty::union)
+}
+
+struct InferenceContext<'a> {
+    body: &'a Body,
+    scopes: &'a ExprScopes,
+    annotations: &'a FileAnnotations,
+    version: LuaVersion,
+    type_of_expr: ArenaMap<ExprId, Ty>,
+    bindings: ArenaMap<NameId, Ty>,
+    globals: FxHashMap<Name, Ty>,
+    tables: ArenaMap<ExprId, TableShape>,
+    returns: ArenaMap<ExprId, Values>,
+    body_returns: Option<Values>,
+    /// The bindings and globals with a declared type, which nothing widens.
+    declared_bindings: FxHashSet<NameId>,
+    declared_globals: FxHashSet<Name>,
+    /// The functions with declared returns.
+    declared_returns: FxHashSet<ExprId>,
+    /// The tables defining each class, whose fields are those of the
+    /// instances of the class.
+    class_tables: FxHashMap<Name, Vec<ExprId>>,
+    /// The bindings and globals assigned the tables defining classes, and the
+    /// `self` of their methods, through which fields are declared.
+    class_bindings: FxHashSet<NameId>,
+    class_globals: FxHashSet<Name>,
+    /// The fields assigned through the definitions of each class.
+    class_fields: FxHashMap<Name, FxHashSet<Name>>,
+    /// The innermost function being walked, `None` for the body itself.
+    function: Option<ExprId>,
+    /// Whether anything was learned during the current pass.
+    changed: bool,
+    /// Whether the passes have reached a fixpoint once, after which the
+    /// values nothing is known about, such as unassigned globals and the
+    /// results of their calls, are anything.
+    settled: bool,
+    /// Whether the current pass is the last one, which checks the uses of
+    /// the types.
+    checking: bool,
+    diagnostics: Vec<InferenceDiagnostic>,
+}
+
+impl<'a> InferenceContext<'a> {
+    fn new(
+        body: &'a Body,
+        scopes: &'a ExprScopes,
+        annotations: &'a FileAnnotations,
+        version: LuaVersion,
+    ) -> InferenceContext<'a> {
+        InferenceContext {
+            body,
+            scopes,
+            annotations,
+            version,
+            type_of_expr: ArenaMap::default(),
+            bindings: ArenaMap::default(),
+            globals: FxHashMap::default(),
+            tables: ArenaMap::default(),
+            returns: ArenaMap::default(),
+            body_returns: None,
+            declared_bindings: FxHashSet::default(),
+            declared_globals: FxHashSet::default(),
+            declared_returns: FxHashSet::default(),
+            class_tables: FxHashMap::default(),
+            class_bindings: FxHashSet::default(),
+            class_globals: FxHashSet::default(),
+            class_fields: FxHashMap::default(),
+            function: None,
+            changed: false,
+            settled: false,
+            checking: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn infer(mut self) -> InferenceResult {
+        let body = self.body;
+        for (stmt, annotations) in body.annotations.iter() {
+            self.declare(stmt, annotations);
+        }
+        for name in self.body.params.iter() {
+            self.add_binding(name, Some(Ty::Unknown));
+        }
+        self.infer_passes();
+        self.settled = true;
+        self.infer_passes();
+        self.checking = true;
+        self.infer_stmt(self.body.body_stmt);
+        self.finish()
+    }
+
+    /// Walks the body until nothing more is learned.
+    fn infer_passes(&mut self) {
+        for _ in 0..MAX_PASSES {
+            self.changed = false;
+            self.infer_stmt(self.body.body_stmt);
+            if !self.changed {
+                break;
+            }
+        }
+    }
+
+    /// The type of a value nothing is known about yet.
+    fn unresolved(&self) -> Option<Ty> {
+        self.settled.then_some(Ty::Unknown)
+    }
+
+    fn finish(self) -> InferenceResult {
+        let body = self.body;
+        let returns = |values: Option<&Values>| -> Vec<Ty> {
+            let values = match values {
+                Some(it) => it,
+                None => return Vec::new(),
+            };
+            let mut tys: Vec<Ty> =
+                values.tys.iter().map(|it| it.clone().unwrap_or(Ty::Unknown)).collect();
+            // what follows a call that returns anything is shown as one value
+            if values.rest == Some(Ty::Unknown) {
+                tys.push(Ty::Unknown);
+            }
+            tys
+        };
+
+        let mut functions = ArenaMap::default();
+        for (expr, data) in body.exprs.iter() {
+            if let Expr::Function { params, .. } = data {
+                let vararg = params.vararg;
+                let params = params
+                    .iter()
+                    .map(|name| {
+                        let ty = self.bindings.get(name).cloned().unwrap_or(Ty::Unknown);
+                        (body.names[name].clone(), ty)
+                    })
+                    .collect();
+                let returns = returns(self.returns.get(expr));
+                functions.insert(expr, FnSig { params, vararg, returns });
+            }
+        }
+
+        let mut classes = FxHashMap::default();
+        let names = self.annotations.classes.iter().map(|it| &it.name);
+        for name in names.chain(self.class_tables.keys()) {
+            if classes.contains_key(name) {
+                continue;
+            }
+            let mut shape = TableShape::default();
+            if let Some(class) = self.annotations.class(name) {
+                for field in &class.fields {
+                    shape.fields.push((field.name.clone(), self.lower_type_ref(&field.ty, &[])));
+                }
+            }
+            for &table in self.class_tables.get(name).into_iter().flatten() {
+                for (field, ty) in self.tables.get(table).into_iter().flat_map(|it| &it.fields) {
+                    if shape.field(field).is_none() {
+                        shape.fields.push((field.clone(), ty.clone()));
+                    }
+                }
+            }
+            classes.insert(name.clone(), shape);
+        }
+
+        InferenceResult {
+            type_of_expr: self.type_of_expr,
+            type_of_name: self.bindings,
+            globals: self.globals,
+            tables: self.tables,
+            functions,
+            classes,
+            returns: returns(self.body_returns.as_ref()),
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    /// Records the types declared by the annotations of a statement.
+    fn declare(&mut self, stmt: StmtId, annotations: &Annotations) {
+        let body = self.body;
+        let (targets, values, func) = match &body.stmts[stmt] {
+            Stmt::Local { names, exprs } => {
+                let targets = names.iter().map(|&it| Target::Binding(it)).collect();
+                (targets, &exprs[..], None)
+            }
+            Stmt::Assign { lhs, rhs } => {
+                let targets = lhs
+                    .iter()
+                    .map(|&target| match self.scopes.resolve_expr(body, target) {
+                        Some(Resolution::Local { name, .. } | Resolution::Upvalue { name, .. }) => {
+                            Target::Binding(name)
+                        }
+                        Some(Resolution::Global(name)) => Target::Global(name),
+                        None => Target::Other,
+                    })
+                    .collect();
+                (targets, &rhs[..], None)
+            }
+            Stmt::LocalFunction { func, .. } | Stmt::FunctionDef { func, .. } => {
+                (Vec::new(), &[][..], Some(*func))
+            }
+            _ => return,
+        };
+
+        let generics = &annotations.generics;
+        for (target, ty) in targets.iter().zip(&annotations.types) {
+            let ty = self.lower_type_ref(ty, generics);
+            self.declare_target(target, ty);
+        }
+        if let Some(class) = &annotations.class {
+            if let Some(target) = targets.first() {
+                self.declare_target(target, Ty::Class(class.clone()));
+                match target {
+                    Target::Binding(name) => {
+                        self.class_bindings.insert(*name);
+                    }
+                    Target::Global(name) => {
+                        self.class_globals.insert(name.clone());
+                    }
+                    Target::Other => {}
+                }
+            }
+            if let Some(&table) = values.first() {
+                if let Expr::Table(_) = body.exprs[table] {
+                    self.class_tables.entry(class.clone()).or_default().push(table);
+                }
+            }
+        }
+
+        let func = func.or_else(|| values.first().copied());
+        if let Some(func) = func {
+            if let Expr::Function { params, .. } = &body.exprs[func] {
+                for &name in &params.names {
+                    if let Some(param) = annotations.param(&body.names[name]) {
+                        let ty = self.lower_type_ref(&param.ty, generics);
+                        self.declare_target(&Target::Binding(name), ty);
+                    }
+                }
+                if !annotations.returns.is_empty() {
+                    let tys = annotations
+                        .returns
+                        .iter()
+                        .map(|it| Some(self.lower_type_ref(&it.ty, generics)))
+                        .collect();
+                    self.returns.insert(func, Values { tys, rest: Some(Ty::Nil) });
+                    self.declared_returns.insert(func);
+                }
+            }
+        }
+    }
+
+    fn declare_target(&mut self, target: &Target, ty: Ty) {
+        match target {
+            Target::Binding(name) => {
+                self.bindings.insert(*name, ty);
+                self.declared_bindings.insert(*name);
+            }
+            Target::Global(name) => {
+                self.globals.insert(name.clone(), ty);
+                self.declared_globals.insert(name.clone());
+            }
+            Target::Other => {}
+        }
+    }
+
+    /// The type of the values of a declared type, in which the names of
+    /// `generics` can be anything.
+    fn lower_type_ref(&self, ty: &TypeRef, generics: &[Name]) -> Ty {
+        self.lower_type_ref_inner(ty, generics, 0)
+    }
+
+    fn lower_type_ref_inner(&self, ty: &TypeRef, generics: &[Name], depth: usize) -> Ty {
+        let lower = |ty: &TypeRef| self.lower_type_ref_inner(ty, generics, depth);
+        match ty {
+            TypeRef::Name { name, args } => match name.as_str() {
+                _ if generics.contains(name) => Ty::Unknown,
+                "nil" => Ty::Nil,
+                "boolean" | "true" | "false" => Ty::Boolean,
+                "integer" => self.integer(),
+                "number" => Ty::Number,
+                "string" => Ty::String,
+                "function" => Ty::Signature(Box::new(FnSig::any())),
+                "table" => match &args[..] {
+                    [key, value] => Ty::Map(Box::new(lower(key)), Box::new(lower(value))),
+                    _ => Ty::Map(Box::new(Ty::Unknown), Box::new(Ty::Unknown)),
+                },
+                "any" | "unknown" | "userdata" | "lightuserdata" | "thread" | "self" => Ty::Unknown,
+                _ => match self.annotations.alias(name) {
+                    Some(alias) if depth < MAX_ALIAS_DEPTH => {
+                        self.lower_type_ref_inner(&alias.ty, generics, depth + 1)
+                    }
+                    Some(_) => Ty::Unknown,
+                    None => Ty::Class(name.clone()),
+                },
+            },
+            TypeRef::Array(elem) => Ty::Array(Box::new(lower(elem))),
+            TypeRef::Union(members) => {
+                members.iter().map(lower).reduce(Ty::union).unwrap_or(Ty::Unknown)
+            }
+            TypeRef::Function { params, vararg, returns } => Ty::Signature(Box::new(FnSig {
+                params: params.iter().map(|(name, ty)| (name.clone(), lower(ty))).collect(),
+                vararg: *vararg,
+                returns: returns.iter().map(lower).collect(),
+            })),
+            TypeRef::Table(fields) => {
+                let value = fields.iter().map(|(_, ty)| lower(ty)).reduce(Ty::union);
+                match value {
+                    Some(value) => Ty::Map(Box::new(Ty::String), Box::new(value)),
+                    None => Ty::Map(Box::new(Ty::Unknown), Box::new(Ty::Unknown)),
+                }
+            }
+            TypeRef::StrLiteral(_) => Ty::String,
+            TypeRef::NumLiteral(it) if it.contains('.') => self.float(),
+            TypeRef::NumLiteral(_) => self.integer(),
+            TypeRef::Error => Ty::Unknown,
+        }
+    }
+
+    fn infer_block(&mut self, block: &Block) {
+        for &stmt in &block.stmts {
+            self.infer_stmt(stmt);
+        }
+    }
+
+    fn infer_stmt(&mut self, stmt: StmtId) {
+        let body = self.body;
+        match &body.stmts[stmt] {
+            Stmt::Missing | Stmt::Break | Stmt::Label { .. } | Stmt::Goto { .. } => {}
+            Stmt::Block(block) | Stmt::Do(block) => self.infer_block(block),
+            Stmt::Local { names, exprs } => {
+                let values = self.infer_values(exprs);
+                for (idx, &name) in names.iter().enumerate() {
+                    self.add_binding(name, values.get(idx));
+                }
+            }
+            Stmt::LocalFunction { name, func } => {
+                // the function is in scope in its own body
+                self.add_binding(*name, Some(Ty::Function(*func)));
+                self.infer_expr(*func);
+            }
+            Stmt::FunctionDef { target, is_method, func } => {
+                self.assign(*target, Some(Ty::Function(*func)));
+                if *is_method {
+                    if let (Expr::Dot { base, .. }, Expr::Function { params, .. }) =
+                        (&body.exprs[*target], &body.exprs[*func])
+                    {
+                        if let Some(self_param) = params.self_param {
+                            let ty = self.infer_expr(*base);
+                            self.add_binding(self_param, ty);
+                            if self.is_class_definition(*base) {
+                                self.class_bindings.insert(self_param);
+                            }
+                        }
+                    }
+                }
+                self.infer_expr(*func);
+            }
+            Stmt::Assign { lhs, rhs } => {
+                let values = self.infer_values(rhs);
+                for (idx, &target) in lhs.iter().enumerate() {
+                    self.assign(target, values.get(idx));
+                }
+            }
+            Stmt::Expr(expr) => {
+                self.infer_expr(*expr);
+            }
+            Stmt::While { condition, body } => {
+                self.infer_expr(*condition);
+                self.infer_block(body);
+            }
+            Stmt::Repeat { body, condition } => {
+                self.infer_block(body);
+                self.infer_expr(*condition);
+            }
+            Stmt::For { content, body } => {
+                match content {
+                    ForContent::Numeric { name, start, end, step } => {
+                        let start = self.infer_expr(*start);
+                        self.infer_expr(*end);
+                        let step = match step {
+                            Some(step) => self.infer_expr(*step),
+                            None => Some(self.integer()),
+                        };
+                        let ty = self.arith(start, step);
+                        self.add_binding(*name, Some(ty));
+                    }
+                    ForContent::Generic { names, expr } => {
+                        self.infer_expr(*expr);
+                        for &name in names {
+                            self.add_binding(name, Some(Ty::Unknown));
+                        }
+                    }
+                }
+                self.infer_block(body);
+            }
+            Stmt::If { condition, then_branch, else_branch } => {
+                self.infer_expr(*condition);
+                self.infer_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.infer_stmt(*else_branch);
+                }
+            }
+            Stmt::Return { exprs } => {
+                let values = self.infer_values(exprs);
+                if self.checking {
+                    self.check_returns(exprs);
+                }
+                self.add_returns(values);
+            }
+        }
+    }
+
+    /// Infers the values of an expression list, such as the right-hand side of
+    /// an assignment or the arguments of a `return`.
+    fn infer_values(&mut self, exprs: &[ExprId]) -> Values {
+        let (last, init) = match exprs.split_last() {
+            Some(it) => it,
+            None => return Values::none(),
+        };
+        let mut tys: Vec<_> = init.iter().map(|&it| self.infer_expr(it)).collect();
+        let last = self.infer_expr_values(*last);
+        tys.extend(last.tys);
+        Values { tys, rest: last.rest }
+    }
+
+    fn infer_expr(&mut self, expr: ExprId) -> Option<Ty> {
+        self.infer_expr_values(expr).get(0)
+    }
+
+    /// Infers every value of an expression, of which calls and `...` can have
+    /// several.
+    fn infer_expr_values(&mut self, expr: ExprId) -> Values {
+        let body = self.body;
+        let values = match &body.exprs[expr] {
+            Expr::Call(call) => self.infer_call(expr, call),
+            Expr::Literal(Literal::Vararg) => Values { tys: Vec::new(), rest: Some(Ty::Unknown) },
+            _ => Values::single(self.infer_expr_inner(expr)),
+        };
+        self.type_of_expr.insert(expr, values.get(0).unwrap_or(Ty::Unknown));
+        values
+    }
+
+    fn infer_expr_inner(&mut self, expr: ExprId) -> Option<Ty> {
+        let body = self.body;
+        match &body.exprs[expr] {
+            Expr::Missing => Some(Ty::Unknown),
+            Expr::Name(_) => match self.scopes.resolve_expr(body, expr)? {
+                Resolution::Local { name, .. } | Resolution::Upvalue { name, .. } => {
+                    self.bindings.get(name).cloned()
+                }
+                Resolution::Global(name) => {
+                    self.globals.get(&name).cloned().or_else(|| self.unresolved())
+                }
+            },
+            Expr::Literal(literal) => Some(self.literal(literal)),
+            Expr::Paren(inner) => self.infer_expr(*inner),
+            Expr::BinaryOp { lhs, op, rhs } => {
+                let lhs = self.infer_expr(*lhs);
+                let rhs = self.infer_expr(*rhs);
+                self.binary_op(*op, lhs, rhs)
+            }
+            Expr::UnaryOp { op, expr } => {
+                let ty = self.infer_expr(*expr);
+                Some(self.unary_op(*op, ty))
+            }
+            Expr::Index { base: base_expr, index } => {
+                let base = self.infer_expr(*base_expr);
+                let key = self.infer_expr(*index);
+                self.check_indexable(*base_expr, &base);
+                match self.str_key(*index) {
+                    Some(name) => self.field(base, &name),
+                    None => self.item(base, key),
+                }
+            }
+            Expr::Dot { base: base_expr, index } => {
+                let base = self.infer_expr(*base_expr);
+                self.check_indexable(*base_expr, &base);
+                self.field(base, index)
+            }
+            Expr::Function { params, body } => {
+                self.infer_function(expr, params, *body);
+                Some(Ty::Function(expr))
+            }
+            Expr::Table(table) => {
+                self.infer_table(expr, table);
+                Some(Ty::Table(expr))
+            }
+            Expr::Call(call) => self.infer_call(expr, call).get(0),
+        }
+    }
+
+    fn infer_call(&mut self, expr: ExprId, call: &Call) -> Values {
+        let mut callee = self.infer_expr(call.callee);
+        if let Some(method_name) = &call.method_name {
+            self.check_indexable(call.callee, &callee);
+            callee = self.field(callee, method_name);
+        }
+        let mut args = Vec::new();
+        call.args.walk_child_exprs(|it| args.push(it));
+        for &arg in &args {
+            self.infer_expr(arg);
+        }
+
+        let callee = match callee {
+            Some(it) => it,
+            None => return Values { tys: Vec::new(), rest: self.unresolved() },
+        };
+        if self.checking {
+            self.check_call(expr, call, &callee);
+        }
+        let mut res: Option<Values> = None;
+        for ty in callee.members() {
+            let values = match ty {
+                // tables can be called through their metatable
+                Ty::Unknown | Ty::Table(_) | Ty::Array(_) | Ty::Map(..) | Ty::Class(_) => {
+                    Values { tys: Vec::new(), rest: Some(Ty::Unknown) }
+                }
+                Ty::Function(func) => match self.returns.get(*func) {
+                    Some(it) => it.clone(),
+                    None if self.settled => Values { tys: Vec::new(), rest: Some(Ty::Unknown) },
+                    None => continue,
+                },
+                Ty::Signature(sig) => Values {
+                    tys: sig.returns.iter().cloned().map(Some).collect(),
+                    // a bare `function` may return any number of values
+                    rest: Some(if **sig == FnSig::any() { Ty::Unknown } else { Ty::Nil }),
+                },
+                _ => continue,
+            };
+            res = Some(match res {
+                Some(res) => res.union(&values),
+                None => values,
+            });
+        }
+        res.unwrap_or(Values { tys: Vec::new(), rest: self.unresolved() })
+    }
+
+    fn infer_function(&mut self, func: ExprId, params: &Params, body: StmtId) {
+        for &name in &params.names {
+            self.add_binding(name, Some(Ty::Unknown));
+        }
+        let outer = self.function.replace(func);
+        self.infer_stmt(body);
+        self.function = outer;
+
+        // a function without `return`s returns nothing
+        if self.returns.get(func).is_none() {
+            self.returns.insert(func, Values::none());
+            self.changed = true;
+        }
+    }
+
+    fn infer_table(&mut self, table_expr: ExprId, table: &Table) {
+        if self.tables.get(table_expr).is_none() {
+            self.tables.insert(table_expr, TableShape::default());
+            self.changed = true;
+        }
+        for field in &table.fields {
+            match field {
+                TableField::Positional(value) => {
+                    let ty = self.infer_expr(*value);
+                    self.add_item(table_expr, ty);
+                }
+                TableField::Named { name, value } => {
+                    let ty = self.infer_expr(*value);
+                    self.add_field(table_expr, name, ty);
+                }
+                TableField::Indexed { key, value } => {
+                    let key_ty = self.infer_expr(*key);
+                    let ty = self.infer_expr(*value);
+                    self.add_index(table_expr, *key, key_ty, ty);
+                }
+            }
+        }
+    }
+
+    /// Records that `ty` is assigned to the place `target`.
+    fn assign(&mut self, target: ExprId, ty: Option<Ty>) {
+        let body = self.body;
+        match &body.exprs[target] {
+            Expr::Name(_) => match self.scopes.resolve_expr(body, target) {
+                Some(Resolution::Local { name, .. }) | Some(Resolution::Upvalue { name, .. }) => {
+                    self.add_binding(name, ty)
+                }
+                Some(Resolution::Global(name)) => self.add_global(name, ty),
+                None => {}
+            },
+            Expr::Dot { base: base_expr, index } => {
+                let base = self.infer_expr(*base_expr);
+                self.assign_class_field(target, *base_expr, &base, index);
+                for table in self.tables_of(&base) {
+                    self.add_field(table, index, ty.clone());
+                }
+            }
+            Expr::Index { base: base_expr, index } => {
+                let base = self.infer_expr(*base_expr);
+                let key_ty = self.infer_expr(*index);
+                if let Some(name) = self.str_key(*index) {
+                    self.assign_class_field(target, *base_expr, &base, &name);
+                }
+                for table in self.tables_of(&base) {
+                    self.add_index(table, *index, key_ty.clone(), ty.clone());
+                }
+            }
+            _ => {}
+        }
+        self.infer_expr(target);
+    }
+
+    fn add_binding(&mut self, name: NameId, ty: Option<Ty>) {
+        if self.declared_bindings.contains(&name) {
+            return;
+        }
+        if let Some(new) = ty.and_then(|ty| widen(self.bindings.get(name), ty)) {
+            self.bindings.insert(name, new);
+            self.changed = true;
+        }
+    }
+
+    fn add_global(&mut self, name: Name, ty: Option<Ty>) {
+        if self.declared_globals.contains(&name) {
+            return;
+        }
+        if let Some(new) = ty.and_then(|ty| widen(self.globals.get(&name), ty)) {
+            self.globals.insert(name, new);
+            self.changed = true;
+        }
+    }
+
+    fn add_field(&mut self, table: ExprId, name: &Name, ty: Option<Ty>) {
+        let (ty, shape) = match (ty, self.tables.get_mut(table)) {
+            (Some(ty), Some(shape)) => (ty, shape),
+            _ => return,
+        };
+        match shape.fields.iter_mut().find(|(it, _)| it == name) {
+            Some((_, old)) => {
+                if let Some(new) = widen(Some(old), ty) {
+                    *old = new;
+                    self.changed = true;
+                }
+            }
+            None => {
+                shape.fields.push((name.clone(), ty));
+                self.changed = true;
+            }
+        }
+    }
+
+    fn add_item(&mut self, table: ExprId, ty: Option<Ty>) {
+        let (ty, shape) = match (ty, self.tables.get_mut(table)) {
+            (Some(ty), Some(shape)) => (ty, shape),
+            _ => return,
+        };
+        if let Some(new) = widen(shape.items.as_ref(), ty) {
+            shape.items = Some(new);
+            self.changed = true;
+        }
+    }
+
+    /// Records the assignment of `ty` to `table[key]`, which is either a named
+    /// field or an item depending on the key.
+    fn add_index(&mut self, table: ExprId, key: ExprId, key_ty: Option<Ty>, ty: Option<Ty>) {
+        if let Some(name) = self.str_key(key) {
+            self.add_field(table, &name, ty);
+        } else if matches!(key_ty, Some(Ty::Integer | Ty::Float | Ty::Number)) {
+            self.add_item(table, ty);
+        }
+    }
+
+    fn add_returns(&mut self, values: Values) {
+        if self.function.is_some_and(|it| self.declared_returns.contains(&it)) {
+            return;
+        }
+        let old = match self.function {
+            Some(func) => self.returns.get(func),
+            None => self.body_returns.as_ref(),
+        };
+        let new = match old {
+            Some(old) => old.union(&values),
+            None => values,
+        };
+        if old == Some(&new) {
+            return;
+        }
+        self.changed = true;
+        match self.function {
+            Some(func) => self.returns.insert(func, new),
+            None => self.body_returns = Some(new),
+        }
+    }
+
+    fn field(&self, base: Option<Ty>, name: &Name) -> Option<Ty> {
+        let base = base?;
+        let mut res = None;
+        for ty in base.members() {
+            let ty = match ty {
+                // strings have the methods of the `string` library
+                Ty::Unknown | Ty::String => Some(Ty::Unknown),
+                Ty::Table(table) => self.tables.get(*table).and_then(|it| it.field(name)).cloned(),
+                Ty::Class(class) => self.class_field(class, name, 0),
+                Ty::Map(key, value) if matches!(**key, Ty::Unknown | Ty::String) => {
+                    Some((**value).clone())
+                }
+                _ => None,
+            };
+            res = join(res, ty);
+        }
+        res
+    }
+
+    /// The type of a field of the instances of a class, declared by the class
+    /// or one of its parents or assigned to the tables defining it.
+    fn class_field(&self, class: &Name, name: &Name, depth: usize) -> Option<Ty> {
+        if let Some(data) = self.annotations.class(class) {
+            if let Some(field) = data.field(name) {
+                return Some(self.lower_type_ref(&field.ty, &[]));
+            }
+        }
+        let tables = self.class_tables.get(class).into_iter().flatten();
+        let assigned =
+            tables.filter_map(|&it| self.tables.get(it)?.field(name).cloned()).reduce(Ty::union);
+        if assigned.is_some() || depth >= MAX_ALIAS_DEPTH {
+            return assigned;
+        }
+        let parents = self.annotations.class(class).into_iter().flat_map(|it| &it.parents);
+        parents
+            .filter_map(|parent| match parent {
+                TypeRef::Name { name: parent, .. } => self.class_field(parent, name, depth + 1),
+                _ => None,
+            })
+            .reduce(Ty::union)
+    }
+
+    fn item(&self, base: Option<Ty>, key: Option<Ty>) -> Option<Ty> {
+        let base = base?;
+        let is_number = matches!(key, Some(Ty::Integer | Ty::Float | Ty::Number));
+        let mut res = None;
+        for ty in base.members() {
+            let ty = match ty {
+                Ty::Table(table) if is_number => {
+                    self.tables.get(*table).and_then(|it| it.items.clone())
+                }
+                Ty::Array(elem) if is_number => Some((**elem).clone()),
+                Ty::Map(_, value) => Some((**value).clone()),
+                Ty::Unknown | Ty::String | Ty::Table(_) | Ty::Array(_) | Ty::Class(_) => {
+                    Some(Ty::Unknown)
+                }
+                _ => None,
+            };
+            res = join(res, ty);
+        }
+        res
+    }
+
+    /// The name of the field indexed by a string literal key, as in `t["a"]`.
+    fn str_key(&self, key: ExprId) -> Option<Name> {
+        match &self.body.exprs[key] {
+            Expr::Literal(Literal::Str(it)) => Some(Name::resolve(it)),
+            _ => None,
+        }
+    }
+
+    fn literal(&self, literal: &Literal) -> Ty {
+        match literal {
+            Literal::Str(_) => Ty::String,
+            Literal::Bool(_) => Ty::Boolean,
+            Literal::Number(Number::Int(_)) => self.integer(),
+            Literal::Number(Number::Float(_)) => self.float(),
+            Literal::Nil => Ty::Nil,
+            Literal::Vararg => Ty::Unknown,
+        }
+    }
+
+    fn binary_op(&self, op: BinaryOp, lhs: Option<Ty>, rhs: Option<Ty>) -> Option<Ty> {
+        let ty = match op {
+            // `a and b` is `a` when it is falsy, `a or b` is `a` when it is not
+            BinaryOp::LogicOp(LogicOp::And) => {
+                let falsy = filter_members(lhs, |it| it.is_falsy());
+                return join(falsy, rhs);
+            }
+            BinaryOp::LogicOp(LogicOp::Or) => {
+                let truthy = filter_members(lhs, |it| *it != Ty::Nil);
+                return join(truthy, rhs);
+            }
+            BinaryOp::CmpOp(_) => Ty::Boolean,
+            BinaryOp::Concat => Ty::String,
+            BinaryOp::BitOp(_) => self.integer(),
+            BinaryOp::ArithOp(ArithOp::Div | ArithOp::Pow) => self.float(),
+            BinaryOp::ArithOp(_) => self.arith(lhs, rhs),
+        };
+        Some(ty)
+    }
+
+    fn unary_op(&self, op: UnaryOp, ty: Option<Ty>) -> Ty {
+        match op {
+            UnaryOp::Not => Ty::Boolean,
+            UnaryOp::Len | UnaryOp::BitNot => self.integer(),
+            UnaryOp::Neg => match ty {
+                Some(ty @ (Ty::Integer | Ty::Float)) => ty,
+                _ => Ty::Number,
+            },
+        }
+    }
+
+    /// The type of `+`, `-`, `*`, `//` and `%`, which are only integers when
+    /// both operands are.
+    fn arith(&self, lhs: Option<Ty>, rhs: Option<Ty>) -> Ty {
+        match (lhs, rhs) {
+            (Some(Ty::Integer), Some(Ty::Integer)) => Ty::Integer,
+            (Some(Ty::Integer | Ty::Float), Some(Ty::Integer | Ty::Float)) => Ty::Float,
+            _ => Ty::Number,
+        }
+    }
+
+    /// The tables created in the body that a value of type `ty` can be,
+    /// including those defining the classes it can be an instance of.
+    fn tables_of(&self, ty: &Option<Ty>) -> Vec<ExprId> {
+        let members = ty.as_ref().map_or(&[][..], |it| it.members());
+        members
+            .iter()
+            .flat_map(|it| match it {
+                Ty::Table(table) => vec![*table],
+                Ty::Class(class) => self.class_tables.get(class).cloned().unwrap_or_default(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Whether `expr` is a name for the table defining a class, or the `self`
+    /// of one of its methods.
+    fn is_class_definition(&self, expr: ExprId) -> bool {
+        match self.scopes.resolve_expr(self.body, expr) {
+            Some(Resolution::Local { name, .. } | Resolution::Upvalue { name, .. }) => {
+                self.class_bindings.contains(&name)
+            }
+            Some(Resolution::Global(name)) => self.class_globals.contains(&name),
+            None => false,
+        }
+    }
+
+    /// Records the assignment of the field `name` of `base`, which declares
+    /// the field when `base` is the definition of a class, and is checked
+    /// against the declared fields otherwise.
+    fn assign_class_field(
+        &mut self,
+        target: ExprId,
+        base_expr: ExprId,
+        base: &Option<Ty>,
+        name: &Name,
+    ) {
+        let members = base.as_ref().map_or(&[][..], |it| it.members());
+        let classes = members.iter().filter_map(|it| match it {
+            Ty::Class(class) => Some(class),
+            _ => None,
+        });
+        if self.is_class_definition(base_expr) {
+            for class in classes {
+                self.class_fields.entry(class.clone()).or_default().insert(name.clone());
+            }
+            return;
+        }
+        if !self.checking {
+            return;
+        }
+        let undeclared = classes
+            .filter(|class| self.annotations.class(class).is_some())
+            .find(|class| !self.declares_field(class, name, 0))
+            .cloned();
+        if let Some(class) = undeclared {
+            self.report(InferenceDiagnostic::UndeclaredField {
+                target,
+                class,
+                field: name.clone(),
+            });
+        }
+    }
+
+    /// Whether a class or one of its parents declares a field, either with
+    /// `---@field` or by assigning it to the table defining the class.
+    fn declares_field(&self, class: &Name, name: &Name, depth: usize) -> bool {
+        let data = self.annotations.class(class);
+        if data.is_some_and(|it| it.field(name).is_some())
+            || self.class_fields.get(class).is_some_and(|it| it.contains(name))
+        {
+            return true;
+        }
+        let tables = self.class_tables.get(class).into_iter().flatten();
+        let in_constructor = tables.into_iter().any(|&table| match &self.body.exprs[table] {
+            Expr::Table(table) => table.fields.iter().any(|field| match field {
+                TableField::Named { name: it, .. } => it == name,
+                TableField::Indexed { key, .. } => self.str_key(*key).as_ref() == Some(name),
+                TableField::Positional(_) => false,
+            }),
+            _ => false,
+        });
+        if in_constructor {
+            return true;
+        }
+        if depth >= MAX_ALIAS_DEPTH {
+            // the parents refer to each other, which no field can be missing from
+            return true;
+        }
+        let mut parents = data.into_iter().flat_map(|it| &it.parents);
+        parents.any(|parent| match parent {
+            TypeRef::Name { name: parent, .. } => self.declares_field(parent, name, depth + 1),
+            _ => true,
+        })
+    }
+
+    fn report(&mut self, diagnostic: InferenceDiagnostic) {
+        // assignments infer their targets twice
+        if !self.diagnostics.contains(&diagnostic) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn check_indexable(&mut self, base_expr: ExprId, base: &Option<Ty>) {
+        let base = match base {
+            Some(it) if self.checking => it,
+            _ => return,
+        };
+        let indexable = base.members().iter().any(|it| {
+            matches!(
+                it,
+                Ty::Unknown | Ty::String | Ty::Table(_) | Ty::Array(_) | Ty::Map(..) | Ty::Class(_)
+            )
+        });
+        if !indexable {
+            self.report(InferenceDiagnostic::NotIndexable { base: base_expr, ty: base.clone() });
+        }
+    }
+
+    /// Checks that the callee can be called, and the arguments against the
+    /// declared types of the parameters.
+    fn check_call(&mut self, expr: ExprId, call: &Call, callee: &Ty) {
+        let callable = callee.members().iter().any(|it| {
+            !matches!(it, Ty::Nil | Ty::Boolean | Ty::Integer | Ty::Float | Ty::Number | Ty::String)
+        });
+        if !callable {
+            self.report(InferenceDiagnostic::NotCallable { call: expr, ty: callee.clone() });
+            return;
+        }
+
+        // only the parameters of functions that are known for sure are checked
+        let params: Vec<Option<Ty>> = match callee {
+            Ty::Function(func) => match &self.body.exprs[*func] {
+                Expr::Function { params, .. } => params
+                    .iter()
+                    .map(|name| {
+                        self.declared_bindings
+                            .contains(&name)
+                            .then(|| self.bindings.get(name).cloned())
+                            .flatten()
+                    })
+                    .collect(),
+                _ => return,
+            },
+            Ty::Signature(sig) => sig.params.iter().map(|(_, ty)| Some(ty.clone())).collect(),
+            _ => return,
+        };
+        let args = match &call.args {
+            CallArgs::ArgList { args } => args,
+            _ => return,
+        };
+        // the implicit `self` of a method call is the first parameter
+        let skip = if call.method_name.is_some() { 1 } else { 0 };
+        for (&arg, expected) in args.iter().zip(params.into_iter().skip(skip)) {
+            let expected = match expected {
+                Some(it) => it,
+                None => continue,
+            };
+            let actual = self.type_of_expr.get(arg).cloned().unwrap_or(Ty::Unknown);
+            if !self.fits(&actual, &expected) {
+                self.report(InferenceDiagnostic::ArgTypeMismatch { arg, expected, actual });
+            }
+        }
+    }
+
+    /// Checks the values of a `return` against the declared returns of the
+    /// function.
+    fn check_returns(&mut self, exprs: &[ExprId]) {
+        let declared = match self.function {
+            Some(func) if self.declared_returns.contains(&func) => match self.returns.get(func) {
+                Some(it) => it.tys.clone(),
+                None => return,
+            },
+            _ => return,
+        };
+        for (&expr, expected) in exprs.iter().zip(declared) {
+            let expected = match expected {
+                Some(it) => it,
+                None => continue,
+            };
+            let actual = self.type_of_expr.get(expr).cloned().unwrap_or(Ty::Unknown);
+            if !self.fits(&actual, &expected) {
+                self.report(InferenceDiagnostic::ReturnTypeMismatch { expr, expected, actual });
+            }
+        }
+    }
+
+    /// Whether every value of type `actual` is a value of type `expected`.
+    ///
+    /// The signatures of functions and the fields of tables are not compared,
+    /// only whether values are functions or tables at all.
+    fn fits(&self, actual: &Ty, expected: &Ty) -> bool {
+        actual.members().iter().all(|actual| {
+            expected.members().iter().any(|expected| match (actual, expected) {
+                (Ty::Unknown, _) | (_, Ty::Unknown) => true,
+                (Ty::Integer | Ty::Float | Ty::Number, Ty::Number) => true,
+                // a number can be an integer as far as inference knows
+                (Ty::Number, Ty::Integer | Ty::Float) => true,
+                (Ty::Function(_) | Ty::Signature(_), Ty::Signature(_)) => true,
+                (Ty::Class(actual), Ty::Class(expected)) => self.is_subclass(actual, expected, 0),
+                (
+                    Ty::Table(_) | Ty::Array(_) | Ty::Map(..) | Ty::Class(_),
+                    Ty::Array(_) | Ty::Map(..) | Ty::Class(_),
+                ) => true,
+                (Ty::Table(_), Ty::Table(_)) => true,
+                _ => actual == expected,
+            })
+        })
+    }
+
+    fn is_subclass(&self, class: &Name, parent: &Name, depth: usize) -> bool {
+        if class == parent {
+            return true;
+        }
+        if depth >= MAX_ALIAS_DEPTH {
+            return false;
+        }
+        let parents = self.annotations.class(class).into_iter().flat_map(|it| &it.parents);
+        parents.into_iter().any(|it| match it {
+            TypeRef::Name { name, .. } => self.is_subclass(name, parent, depth + 1),
+            _ => false,
+        })
+    }
+
+    fn integer(&self) -> Ty {
+        if self.version.has_integers() {
+            Ty::Integer
+        } else {
+            Ty::Number
+        }
+    }
+
+    fn float(&self) -> Ty {
+        if self.version.has_integers() {
+            Ty::Float
+        } else {
+            Ty::Number
+        }
+    }
+}
+
+/// What the annotations of a statement declare the type of.
+enum Target {
+    Binding(NameId),
+    Global(Name),
+    Other,
+}
@@ -0,0 +1,21 @@
+mod infer;
+mod ty;
+
+#[cfg(test)]
+mod test_db;
+#[cfg(test)]
+mod tests;
+
+use std::sync::Arc;
+
+use base_db::salsa;
+use hir::{HirDatabase, WithBodyId};
+
+pub use infer::{InferenceDiagnostic, InferenceResult};
+pub use ty::{FnSig, TableShape, Ty, TyDisplay};
+
+#[salsa::query_group(HirTyDatabaseStorage)]
+pub trait HirTyDatabase: HirDatabase {
+    #[salsa::invoke(infer::infer_query)]
+    fn infer(&self, def: WithBodyId) -> Arc<InferenceResult>;
+}
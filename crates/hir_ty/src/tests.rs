@@ -0,0 +1,457 @@
+use base_db::SourceDatabase;
+use hir::{DefDatabase, WithBodyId};
+use syntax::{ast::AstNode, LuaVersion};
+use test_utils::assert_eq_text;
+
+use crate::{test_db::TestDB, HirTyDatabase, InferenceDiagnostic};
+
+/// Checks the types of the bindings of the file, in the order they are
+/// declared, followed by the globals it assigns.
+fn check(text: &str, expected: &str) {
+    check_with(text, LuaVersion::default(), expected)
+}
+
+fn check_with(text: &str, version: LuaVersion, expected: &str) {
+    let (mut db, file_id) = TestDB::with_single_file(text);
+    db.set_lua_version(version);
+    let def = WithBodyId::ModuleId(file_id);
+    let body = db.body(def);
+    let infer = db.infer(def);
+
+    let mut actual = String::new();
+    for (name_id, name) in body.names.iter() {
+        let ty = infer.type_of_name(name_id);
+        actual += &format!("{}: {}\n", name, ty.display(&infer));
+    }
+    let mut globals: Vec<_> = body
+        .exprs
+        .iter()
+        .filter_map(|(_, expr)| match expr {
+            hir::expr::Expr::Name(name) => Some(name.clone()),
+            _ => None,
+        })
+        .filter(|name| *infer.type_of_global(name) != crate::Ty::Unknown)
+        .collect();
+    globals.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    globals.dedup();
+    for name in globals {
+        actual += &format!("global {}: {}\n", name, infer.type_of_global(&name).display(&infer));
+    }
+    assert_eq_text!(expected.trim_start(), &actual);
+}
+
+#[test]
+fn infer_literals() {
+    check(
+        r#"
+local a, b, c, d, e, f, g = nil, true, 1, 1.5, "s", {}
+"#,
+        r#"
+a: nil
+b: boolean
+c: integer
+d: float
+e: string
+f: {}
+g: nil
+"#,
+    );
+}
+
+#[test]
+fn infer_numbers_before_integers() {
+    check_with(
+        r#"
+local a, b = 1, 1.5
+local c = a // 2
+"#,
+        LuaVersion::Lua51,
+        r#"
+a: number
+b: number
+c: number
+"#,
+    );
+}
+
+#[test]
+fn infer_operators() {
+    check(
+        r#"
+local t = { 1, 2 }
+for i = 1, 10 do
+    local a, b, c, d = i / 2, i // 2, i .. "", #t
+    local e, f, g = i + 0.5, -i, i < 2
+end
+"#,
+        r#"
+t: { [integer]: integer }
+i: integer
+a: float
+b: integer
+c: string
+d: integer
+e: float
+f: integer
+g: boolean
+"#,
+    );
+}
+
+#[test]
+fn infer_multiple_returns() {
+    check(
+        r#"
+local function f()
+    if cond then
+        return 1
+    end
+    return 2, "two"
+end
+local x, y, z = f()
+local w = (f())
+"#,
+        r#"
+f: fun(): integer, string | nil
+x: integer
+y: string | nil
+z: nil
+w: integer
+"#,
+    );
+}
+
+#[test]
+fn infer_unions_across_branches() {
+    check(
+        r#"
+local x
+if cond then
+    x = 1
+else
+    x = "one"
+end
+local y = x and 1.5 or false
+local z = x or 2
+"#,
+        r#"
+x: integer | string | nil
+y: boolean | float
+z: integer | string
+"#,
+    );
+}
+
+#[test]
+fn infer_table_shapes() {
+    check(
+        r#"
+local t = { a = 1, "x" }
+t.b = "s"
+t["c"] = true
+t[2] = "y"
+function t.f(n) return n end
+function t:m() return self.a end
+local r = t:m()
+local s = t.f(1)
+"#,
+        r#"
+t: { a: integer, b: string, c: boolean, f: function, m: function, [integer]: string }
+n: unknown
+self: { a: integer, b: string, c: boolean, f: function, m: function, [integer]: string }
+r: integer
+s: unknown
+"#,
+    );
+}
+
+#[test]
+fn infer_values_used_before_assignment() {
+    check(
+        r#"
+local helper
+local function run() return helper() end
+helper = function() return 42 end
+local x = run()
+
+local function fact(n)
+    if n <= 1 then return 1 end
+    return n * fact(n - 1)
+end
+"#,
+        r#"
+helper: fun(): integer | nil
+run: fun(): integer
+x: integer
+fact: fun(n): number
+n: unknown
+"#,
+    );
+}
+
+#[test]
+fn infer_globals() {
+    check(
+        r#"
+count = 0
+function inc(step, ...)
+    count = count + step
+    return count
+end
+local v = unknown_global
+"#,
+        r#"
+step: unknown
+v: unknown
+global count: number
+global inc: fun(step, ...): number
+"#,
+    );
+}
+
+#[test]
+fn infer_unknown_values() {
+    check(
+        r#"
+local m = 1
+m = print
+local r = require("mod")
+local w = io.write("s")
+local g = 1
+g = SOME_GLOBAL
+"#,
+        r#"
+m: unknown
+r: unknown
+w: unknown
+g: unknown
+"#,
+    );
+}
+
+#[test]
+fn infer_declared_types() {
+    check(
+        r#"
+---@param a integer
+---@param b? string
+---@return string
+local function f(a, b)
+    return a
+end
+local r = f(1)
+
+---@type integer[], table<string, boolean>
+local list, set = {}, {}
+local item, flag = list[1], set.x
+
+---@type fun(n: number): boolean
+local pred = nil
+local ok = pred(1)
+"#,
+        r#"
+f: fun(a: integer, b: string | nil): string
+a: integer
+b: string | nil
+r: string
+list: integer[]
+set: table<string, boolean>
+item: integer
+flag: boolean
+pred: fun(n: number): boolean
+ok: boolean
+"#,
+    );
+}
+
+#[test]
+fn infer_results_of_bare_function_type() {
+    check(
+        r#"
+---@type function
+local g = load("x")
+local a, b = g()
+"#,
+        r#"
+g: function
+a: unknown
+b: unknown
+"#,
+    );
+}
+
+#[test]
+fn infer_classes_and_aliases() {
+    check(
+        r#"
+---@alias Id integer | string
+
+---@class Point
+---@field x number
+---@field id Id
+local Point = {}
+
+function Point.new() return Point end
+function Point:norm() return self.x end
+
+local p = Point.new()
+local x, id, norm = p.x, p.id, p:norm()
+
+---@generic T
+---@param value T
+---@return T
+local function identity(value) return value end
+"#,
+        r#"
+Point: Point
+self: Point
+p: Point
+x: number
+id: integer | string
+norm: number
+identity: fun(value): unknown
+value: unknown
+"#,
+    );
+}
+
+/// Checks the diagnostics of the file, with the text of the expressions they
+/// are reported on.
+fn check_diagnostics(text: &str, expected: &str) {
+    let (db, file_id) = TestDB::with_single_file(text);
+    let def = WithBodyId::ModuleId(file_id);
+    let (_, source_map) = db.body_with_source_map(def);
+    let infer = db.infer(def);
+    let root = db.parse(file_id).syntax_node();
+    let text_of = |expr| {
+        let src = source_map.expr_syntax(expr).unwrap();
+        src.value.to_node(&root).syntax().text().to_string()
+    };
+
+    let mut actual = String::new();
+    for diagnostic in infer.diagnostics() {
+        actual += &match diagnostic {
+            InferenceDiagnostic::NotCallable { call, ty } => {
+                format!("not callable {}: {}\n", text_of(*call), ty.display(&infer))
+            }
+            InferenceDiagnostic::NotIndexable { base, ty } => {
+                format!("not indexable {}: {}\n", text_of(*base), ty.display(&infer))
+            }
+            InferenceDiagnostic::ArgTypeMismatch { arg, expected, actual } => format!(
+                "arg {}: expected {}, found {}\n",
+                text_of(*arg),
+                expected.display(&infer),
+                actual.display(&infer)
+            ),
+            InferenceDiagnostic::ReturnTypeMismatch { expr, expected, actual } => format!(
+                "return {}: expected {}, found {}\n",
+                text_of(*expr),
+                expected.display(&infer),
+                actual.display(&infer)
+            ),
+            InferenceDiagnostic::UndeclaredField { target, class, field } => {
+                format!("undeclared field {}: {}.{}\n", text_of(*target), class, field)
+            }
+        };
+    }
+    assert_eq_text!(expected.trim_start(), &actual);
+}
+
+#[test]
+fn diagnose_calls_and_indexing() {
+    check_diagnostics(
+        r#"
+local n, s, t = 1, "s", {}
+local maybe = nil
+if n then maybe = function() end end
+n()
+s:upper()
+t()
+maybe()
+local x = n.field
+local y = s.len
+local cb
+cb.x = 1
+print(unknown.field)
+"#,
+        r#"
+not callable n(): integer
+not indexable n: integer
+not indexable cb: nil
+"#,
+    );
+}
+
+#[test]
+fn no_diagnostics_for_unknown_values() {
+    check_diagnostics(
+        r#"
+local m = 1
+m = print
+m()
+local g = 1
+g = SOME_GLOBAL
+g.x = 1
+local r = 1
+r = require("mod")
+r.f()
+---@type function
+local h = load("x")
+local _, rest = h()
+rest()
+"#,
+        "",
+    );
+}
+
+#[test]
+fn diagnose_declared_types() {
+    check_diagnostics(
+        r#"
+---@param a integer
+---@param b? string
+---@return string
+local function f(a, b)
+    if a then return b or "" end
+    return a
+end
+f(1, "s")
+f("1", 2)
+f(1.5 * 2, nil)
+
+---@type fun(n: number): boolean
+local pred = nil
+pred("s")
+
+---@class Shape
+---@field name string
+
+---@class Point : Shape
+---@field x number
+local Point = { y = 0 }
+Point.z = 0
+function Point:move() self.w = 1 end
+
+---@param p Point
+---@param s Shape
+local function g(p, s)
+    p.x, p.y, p.z, p.w, p.name = 1, 2, 3, 4, "p"
+    p.v = 5
+    s.x = 1
+end
+---@type Shape
+local shape = {}
+g(shape, Point)
+"#,
+        r#"
+return a: expected string, found integer
+arg "1": expected integer, found string
+arg 2: expected string | nil, found integer
+arg 1.5 * 2: expected integer, found float
+arg "s": expected number, found string
+undeclared field p.v: Point.v
+undeclared field s.x: Shape.x
+arg shape: expected Point, found Shape
+"#,
+    );
+}
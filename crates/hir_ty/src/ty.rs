@@ -0,0 +1,239 @@
+use std::fmt;
+
+use hir::{expr::ExprId, Name};
+
+use crate::InferenceResult;
+
+/// The type of a Lua value.
+///
+/// Functions and tables are identified by the expression that creates them,
+/// their signature and shape are stored in the `InferenceResult` of the body.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Ty {
+    /// Nothing is known about the value, such as for parameters. It absorbs
+    /// every other type in unions.
+    Unknown,
+    Nil,
+    Boolean,
+    Integer,
+    Float,
+    /// A number that is either an integer or a float, and every number before
+    /// Lua 5.3.
+    Number,
+    String,
+    /// The function created by the `Expr::Function` with this id.
+    Function(ExprId),
+    /// The table created by the `Expr::Table` constructor with this id.
+    Table(ExprId),
+    /// A function of a declared type, such as `fun(a: string): integer`.
+    Signature(Box<FnSig>),
+    /// A table of a declared type with values of the first type at integer
+    /// keys, `T[]`.
+    Array(Box<Ty>),
+    /// A table of a declared type with keys and values of these types,
+    /// `table<K, V>`.
+    Map(Box<Ty>, Box<Ty>),
+    /// An instance of the class declared by `---@class`.
+    Class(Name),
+    /// One of several types, with at least two members which are neither
+    /// unions nor `Unknown`.
+    Union(Vec<Ty>),
+}
+
+impl Ty {
+    /// The type of a value that is of either type.
+    pub fn union(self, other: Ty) -> Ty {
+        if self == other {
+            return self;
+        }
+        if self == Ty::Unknown || other == Ty::Unknown {
+            return Ty::Unknown;
+        }
+
+        let mut members: Vec<Ty> = Vec::new();
+        for ty in self.members().iter().chain(other.members()) {
+            if !members.contains(ty) {
+                members.push(ty.clone());
+            }
+        }
+        let is_number = |it: &Ty| matches!(it, Ty::Integer | Ty::Float | Ty::Number);
+        let numbers = members.iter().filter(|it| is_number(it)).count();
+        if numbers > 1 {
+            members.retain(|it| !is_number(it));
+            members.push(Ty::Number);
+        }
+        members.sort_by_key(|it| it.sort_key());
+
+        if members.len() == 1 {
+            return members.pop().unwrap();
+        }
+        Ty::Union(members)
+    }
+
+    /// The types the value can be of, which is only the type itself unless it
+    /// is a union.
+    pub fn members(&self) -> &[Ty] {
+        match self {
+            Ty::Union(members) => members,
+            _ => std::slice::from_ref(self),
+        }
+    }
+
+    /// Whether the value can be `nil` or `false`.
+    pub fn is_falsy(&self) -> bool {
+        self.members().iter().any(|it| matches!(it, Ty::Unknown | Ty::Nil | Ty::Boolean))
+    }
+
+    pub fn display<'a>(&'a self, infer: &'a InferenceResult) -> TyDisplay<'a> {
+        TyDisplay { ty: self, infer, depth: 0 }
+    }
+
+    fn sort_key(&self) -> (u8, u32) {
+        match self {
+            Ty::Unknown | Ty::Union(_) => (0, 0),
+            Ty::Boolean => (1, 0),
+            Ty::Integer => (2, 0),
+            Ty::Float => (3, 0),
+            Ty::Number => (4, 0),
+            Ty::String => (5, 0),
+            Ty::Function(it) => (6, it.into_raw().into()),
+            Ty::Signature(_) => (6, u32::MAX),
+            Ty::Table(it) => (7, it.into_raw().into()),
+            Ty::Array(_) | Ty::Map(..) => (7, u32::MAX),
+            Ty::Class(_) => (8, 0),
+            Ty::Nil => (9, 0),
+        }
+    }
+}
+
+/// The signature of a function expression or of a declared function type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FnSig {
+    /// The parameters, starting with the implicit `self` of methods.
+    pub params: Vec<(Name, Ty)>,
+    pub vararg: bool,
+    /// The type of each value returned, `nil` for values only some of the
+    /// `return`s have.
+    pub returns: Vec<Ty>,
+}
+
+/// The fields known to be assigned to a table, by its constructor or by
+/// assignments to its fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableShape {
+    /// The named fields in the order they are first assigned.
+    pub fields: Vec<(Name, Ty)>,
+    /// The values of the array part, such as those of `{ 1, 2, 3 }`.
+    pub items: Option<Ty>,
+}
+
+impl FnSig {
+    /// The signature of `function`, which can take and return anything.
+    pub fn any() -> FnSig {
+        FnSig { params: Vec::new(), vararg: true, returns: vec![Ty::Unknown] }
+    }
+}
+
+impl TableShape {
+    pub fn field(&self, name: &Name) -> Option<&Ty> {
+        self.fields.iter().find(|(it, _)| it == name).map(|(_, ty)| ty)
+    }
+}
+
+/// Formats a type like LuaCATS annotations do, as in
+/// `fun(a, b: integer): string` or `{ x: number, [integer]: string }`.
+///
+/// Only the top level type shows the fields and signatures of the tables and
+/// functions it contains, which are plain `table` and `function` below it.
+pub struct TyDisplay<'a> {
+    ty: &'a Ty,
+    infer: &'a InferenceResult,
+    depth: u32,
+}
+
+impl TyDisplay<'_> {
+    fn nested<'b>(&'b self, ty: &'b Ty) -> TyDisplay<'b> {
+        TyDisplay { ty, infer: self.infer, depth: self.depth + 1 }
+    }
+
+    fn fmt_sig(&self, sig: &FnSig, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("fun(")?;
+        for (idx, (name, ty)) in sig.params.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            match ty {
+                Ty::Unknown => write!(f, "{}", name)?,
+                ty => write!(f, "{}: {}", name, self.nested(ty))?,
+            }
+        }
+        if sig.vararg {
+            f.write_str(if sig.params.is_empty() { "..." } else { ", ..." })?;
+        }
+        f.write_str(")")?;
+        for (idx, ty) in sig.returns.iter().enumerate() {
+            f.write_str(if idx == 0 { ": " } else { ", " })?;
+            write!(f, "{}", self.nested(ty))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TyDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty {
+            Ty::Unknown => f.write_str("unknown"),
+            Ty::Nil => f.write_str("nil"),
+            Ty::Boolean => f.write_str("boolean"),
+            Ty::Integer => f.write_str("integer"),
+            Ty::Float => f.write_str("float"),
+            Ty::Number => f.write_str("number"),
+            Ty::String => f.write_str("string"),
+            Ty::Function(func) => match self.infer.fn_sig(*func) {
+                Some(sig) if self.depth == 0 => self.fmt_sig(sig, f),
+                _ => f.write_str("function"),
+            },
+            Ty::Signature(sig) if self.depth == 0 && **sig != FnSig::any() => self.fmt_sig(sig, f),
+            Ty::Signature(_) => f.write_str("function"),
+            Ty::Array(elem) => match **elem {
+                Ty::Union(_) => write!(f, "({})[]", self.nested(elem)),
+                _ => write!(f, "{}[]", self.nested(elem)),
+            },
+            Ty::Map(key, value) => match (&**key, &**value) {
+                (Ty::Unknown, Ty::Unknown) => f.write_str("table"),
+                _ => write!(f, "table<{}, {}>", self.nested(key), self.nested(value)),
+            },
+            Ty::Class(name) => write!(f, "{}", name),
+            Ty::Table(table) => match self.infer.table(*table) {
+                Some(shape) if self.depth == 0 => {
+                    let fields = shape.fields.iter().map(|(name, ty)| (name.to_string(), ty));
+                    let items = shape.items.iter().map(|ty| ("[integer]".to_string(), ty));
+                    let mut entries = fields.chain(items).peekable();
+                    if entries.peek().is_none() {
+                        return f.write_str("{}");
+                    }
+                    f.write_str("{ ")?;
+                    for (idx, (key, ty)) in entries.enumerate() {
+                        if idx > 0 {
+                            f.write_str(", ")?;
+                        }
+                        write!(f, "{}: {}", key, self.nested(ty))?;
+                    }
+                    f.write_str(" }")
+                }
+                _ => f.write_str("table"),
+            },
+            Ty::Union(members) => {
+                for (idx, ty) in members.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    // the members of a union are flat, so they are shown as if
+                    // they were at the same depth
+                    write!(f, "{}", TyDisplay { ty, infer: self.infer, depth: self.depth })?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
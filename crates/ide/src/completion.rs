@@ -0,0 +1,79 @@
+//! Completion of names, fields and keywords.
+//!
+//! The completions are computed from the file as it is on disk, so the text
+//! being typed is a (possibly broken) part of the syntax tree. The context
+//! module works out what is being completed from the tokens around the cursor.
+
+mod complete_field;
+mod complete_keyword;
+mod complete_scope;
+mod context;
+#[cfg(test)]
+mod tests;
+
+use hir::Semantics;
+use ide_db::{base_db::FilePosition, RootDatabase};
+use syntax::TextRange;
+
+use self::context::CompletionContext;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionItemKind,
+    /// The range of the text replaced by the completion, which is the part of
+    /// the identifier typed so far, or the keyword typed so far when the
+    /// completion starts with it.
+    pub source_range: TextRange,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionItemKind {
+    Local,
+    Param,
+    Function,
+    Global,
+    Field,
+    Method,
+    Keyword,
+}
+
+/// Collects the completion items of a request.
+#[derive(Debug, Default)]
+pub(crate) struct Completions {
+    items: Vec<CompletionItem>,
+}
+
+impl Completions {
+    fn add(
+        &mut self,
+        ctx: &CompletionContext,
+        label: impl Into<String>,
+        kind: CompletionItemKind,
+        detail: Option<String>,
+    ) {
+        let label = label.into();
+        if self.items.iter().any(|it| it.label == label) {
+            return;
+        }
+        let source_range = match &ctx.keyword_prefix {
+            Some(it) if label.starts_with(it.text()) => it.text_range(),
+            _ => ctx.source_range,
+        };
+        self.items.push(CompletionItem { label, kind, source_range, detail });
+    }
+}
+
+pub(crate) fn completions(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<CompletionItem>> {
+    let sema = Semantics::new(db);
+    let ctx = CompletionContext::new(db, &sema, position)?;
+    let mut acc = Completions::default();
+    complete_field::complete_field(&mut acc, &ctx);
+    complete_scope::complete_scope(&mut acc, &ctx);
+    complete_keyword::complete_keyword(&mut acc, &ctx);
+    Some(acc.items)
+}
@@ -0,0 +1,100 @@
+//! Completes the fields of the receiver of a `.` or `:`.
+//!
+//! The fields are those of the inferred type of the receiver, in this file
+//! and in the others that assign it, and those read from it elsewhere.
+
+use hir::{expr::Expr, AccessPath, WithBodyId};
+use hir_ty::{HirTyDatabase, Ty};
+use ide_db::{
+    defs::Definition,
+    types::{def_types, InferredType},
+};
+use syntax::ast::AstNode;
+
+use crate::completion::{CompletionContext, CompletionItemKind, Completions};
+
+pub(super) fn complete_field(acc: &mut Completions, ctx: &CompletionContext) {
+    let receiver = match &ctx.dot_receiver {
+        Some(it) => it,
+        None => return,
+    };
+    let is_method = receiver.is_method;
+
+    let mut types = Vec::new();
+    if let Some(expr) = receiver.expr {
+        let infer = ctx.db.infer(WithBodyId::ModuleId(ctx.file_id));
+        let ty = infer.type_of_expr(expr).clone();
+        types.push(InferredType { infer, ty });
+    }
+    if let Some(path) = &receiver.path {
+        types.extend(def_types(ctx.db, &Definition::from_path(path.clone())));
+    }
+    for InferredType { infer, ty } in &types {
+        for (field, ty) in infer.fields(ty) {
+            // the field of an unfinished `function M.` being typed
+            if field.is_missing() {
+                continue;
+            }
+            let is_function =
+                ty.members().iter().any(|it| matches!(it, Ty::Function(_) | Ty::Signature(_)));
+            let kind = match is_function {
+                true => CompletionItemKind::Method,
+                false if is_method => continue,
+                false => CompletionItemKind::Field,
+            };
+            acc.add(ctx, field.as_str(), kind, None);
+        }
+    }
+
+    let receiver = match &receiver.path {
+        Some(it) => it,
+        None => return,
+    };
+    let sema = ctx.sema;
+    let field_of_receiver = |path: &AccessPath| match path.fields.split_last() {
+        Some((field, prefix))
+            if !field.is_missing()
+                && path.root == receiver.root
+                && prefix == &receiver.fields[..] =>
+        {
+            Some(field.clone())
+        }
+        _ => None,
+    };
+
+    let files = Definition::from_path(receiver.clone()).search_scope(sema);
+    // fields that are only read still tell something about the receiver
+    for &file_id in &files {
+        let (body, source_map) = sema.file_body(file_id);
+        let root = sema.parse(file_id).syntax().clone();
+        for (id, expr) in body.exprs.iter() {
+            let (path, kind) = match expr {
+                Expr::Dot { .. } if !is_method => {
+                    (sema.access_path(file_id, id), CompletionItemKind::Field)
+                }
+                Expr::Call(call) => match &call.method_name {
+                    Some(name) => {
+                        let path = sema.access_path(file_id, call.callee).map(|mut it| {
+                            it.fields.push(name.clone());
+                            it
+                        });
+                        (path, CompletionItemKind::Method)
+                    }
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let field = match path.as_ref().and_then(field_of_receiver) {
+                Some(it) => it,
+                None => continue,
+            };
+            let at_cursor = file_id == ctx.file_id
+                && source_map.expr_syntax(id).is_some_and(|src| {
+                    ctx.is_at_cursor(src.value.to_node(&root).syntax().text_range())
+                });
+            if !at_cursor {
+                acc.add(ctx, field.as_str(), kind, None);
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Completes the keywords that are valid at the cursor.
+
+use crate::completion::{CompletionContext, CompletionItemKind, Completions};
+
+const STMT_KEYWORDS: &[&str] =
+    &["local", "function", "if", "while", "for", "repeat", "return", "break", "goto", "do"];
+const EXPR_KEYWORDS: &[&str] = &["nil", "true", "false", "function", "not"];
+
+pub(super) fn complete_keyword(acc: &mut Completions, ctx: &CompletionContext) {
+    if ctx.dot_receiver.is_some() || ctx.is_new_name {
+        return;
+    }
+    let mut add = |keywords: &[&str]| {
+        for &keyword in keywords {
+            acc.add(ctx, keyword, CompletionItemKind::Keyword, None);
+        }
+    };
+    if let Some(keyword) = ctx.missing_keyword {
+        // `if a th`, the condition may also go on
+        add(&[keyword, "and", "or"]);
+        return;
+    }
+    if ctx.is_stmt_start {
+        add(STMT_KEYWORDS);
+        add(ctx.block_end_keywords);
+    } else {
+        add(EXPR_KEYWORDS);
+    }
+}
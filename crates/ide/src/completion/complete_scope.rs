@@ -0,0 +1,59 @@
+//! Completes the locals and upvalues visible at the cursor, and the globals
+//! assigned anywhere in the workspace.
+
+use hir::{
+    body::scope::{BindingKind, Resolution},
+    expr::Expr,
+    PathRoot,
+};
+use ide_db::assignments::file_assignments;
+use rustc_hash::FxHashSet;
+
+use crate::completion::{CompletionContext, CompletionItemKind, Completions};
+
+pub(super) fn complete_scope(acc: &mut Completions, ctx: &CompletionContext) {
+    if ctx.dot_receiver.is_some() || ctx.is_new_name || ctx.missing_keyword.is_some() {
+        return;
+    }
+    let sema = ctx.sema;
+
+    if let Some(scope) = ctx.scope {
+        let scopes = sema.file_scopes(ctx.file_id);
+        let mut seen = FxHashSet::default();
+        for entry in scopes.scope_chain(Some(scope)).flat_map(|it| scopes.entries(it).iter().rev())
+        {
+            if entry.name().is_missing() || !seen.insert(entry.name().clone()) {
+                continue;
+            }
+            let kind = match entry.kind() {
+                BindingKind::Param => CompletionItemKind::Param,
+                BindingKind::LocalFunction => CompletionItemKind::Function,
+                BindingKind::Local | BindingKind::ForVar => CompletionItemKind::Local,
+            };
+            let detail = match scopes.resolve_name_in_scope(scope, entry.name()) {
+                Some(Resolution::Upvalue { .. }) => Some("upvalue".to_string()),
+                _ => None,
+            };
+            acc.add(ctx, entry.name().as_str(), kind, detail);
+        }
+    }
+
+    for &file_id in sema.db.files().iter() {
+        let (body, _) = sema.file_body(file_id);
+        for assignment in file_assignments(sema, file_id) {
+            let name = match &assignment.path.root {
+                PathRoot::Global(name)
+                    if assignment.path.fields.is_empty() && !name.is_missing() =>
+                {
+                    name
+                }
+                _ => continue,
+            };
+            let kind = match body.exprs[assignment.value] {
+                Expr::Function { .. } => CompletionItemKind::Function,
+                _ => CompletionItemKind::Global,
+            };
+            acc.add(ctx, name.as_str(), kind, None);
+        }
+    }
+}
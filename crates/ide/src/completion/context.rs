@@ -0,0 +1,213 @@
+//! Works out what is being completed from the tokens around the cursor.
+
+use hir::{body::scope::ScopeId, expr::ExprId, AccessPath, InFile, Semantics};
+use ide_db::{
+    base_db::{FileId, FilePosition},
+    RootDatabase,
+};
+use syntax::{
+    ast::{self, AstNode},
+    match_ast, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize, T,
+};
+
+/// The expression before a `.` or `:`, whose fields are being completed.
+#[derive(Debug)]
+pub(super) struct DotReceiver {
+    pub(super) expr: Option<ExprId>,
+    /// `None` when the receiver is not a name or a field access.
+    pub(super) path: Option<AccessPath>,
+    /// Set for `a:b`, where only methods make sense.
+    pub(super) is_method: bool,
+}
+
+pub(crate) struct CompletionContext<'a> {
+    pub(super) db: &'a RootDatabase,
+    pub(super) sema: &'a Semantics<'a>,
+    pub(super) file_id: FileId,
+    pub(super) offset: TextSize,
+    pub(super) source_range: TextRange,
+    /// The keyword right before the cursor, which may be the start of a
+    /// name, such as the `in` of `inner`.
+    pub(super) keyword_prefix: Option<SyntaxToken>,
+    pub(super) dot_receiver: Option<DotReceiver>,
+    /// The scope locals are looked up in.
+    pub(super) scope: Option<ScopeId>,
+    /// Set when a new name is being declared, such as in `local a`, where
+    /// nothing should be completed.
+    pub(super) is_new_name: bool,
+    /// Set when the cursor is where a statement starts.
+    pub(super) is_stmt_start: bool,
+    /// The keyword the statement around the cursor is missing, such as the
+    /// `then` of `if a`.
+    pub(super) missing_keyword: Option<&'static str>,
+    /// The keywords that can close the block around the cursor.
+    pub(super) block_end_keywords: &'static [&'static str],
+}
+
+impl<'a> CompletionContext<'a> {
+    pub(super) fn new(
+        db: &'a RootDatabase,
+        sema: &'a Semantics<'a>,
+        position: FilePosition,
+    ) -> Option<CompletionContext<'a>> {
+        let FilePosition { file_id, offset } = position;
+        let file = sema.parse(file_id);
+        let token = file.syntax().token_at_offset(offset).left_biased()?;
+
+        let ident = Some(token.clone()).filter(|it| it.kind() == T![ident]);
+        let source_range = ident.as_ref().map_or(TextRange::empty(offset), |it| it.text_range());
+        let keyword_prefix = Some(token.clone())
+            .filter(|it| it.kind().is_keyword() && it.text_range().end() == offset);
+        let prev = match &ident {
+            Some(ident) => prev_non_trivia_token(ident.prev_token()),
+            None => prev_non_trivia_token(Some(token.clone())),
+        };
+
+        let mut ctx = CompletionContext {
+            db,
+            sema,
+            file_id,
+            offset,
+            source_range,
+            keyword_prefix,
+            dot_receiver: None,
+            scope: sema.scope_at_offset(file_id, offset),
+            is_new_name: false,
+            is_stmt_start: false,
+            missing_keyword: None,
+            block_end_keywords: &[],
+        };
+
+        if let Some(prev) = prev.as_ref().filter(|it| matches!(it.kind(), T![.] | T![:])) {
+            let expr = ctx.receiver_expr(prev);
+            ctx.dot_receiver = Some(DotReceiver {
+                expr,
+                path: expr.and_then(|it| sema.access_path(file_id, it)),
+                is_method: prev.kind() == T![:],
+            });
+            return Some(ctx);
+        }
+
+        // the node that contains the cursor, and the statement whose `then` or
+        // `do` may be missing
+        let (node, keyword_owner) = match &ident {
+            Some(ident) => {
+                let parent = ident.parent();
+                ctx.is_new_name = ast::Name::can_cast(parent.kind());
+                let stmt = parent.ancestors().find(|it| {
+                    ast::ExprStmt::can_cast(it.kind()) || ast::AssignStmt::can_cast(it.kind())
+                });
+                ctx.is_stmt_start =
+                    stmt.as_ref().and_then(|it| it.first_token()).as_ref() == Some(ident);
+                // `if a th` is parsed as an `if` whose block starts with `th`
+                let owner = stmt
+                    .as_ref()
+                    .and_then(|it| it.parent())
+                    .filter(|it| ast::Block::can_cast(it.kind()))
+                    .and_then(|it| it.parent());
+                (stmt.unwrap_or(parent), owner.filter(|_| ctx.is_stmt_start))
+            }
+            None => {
+                ctx.is_new_name = prev.as_ref().is_some_and(|it| {
+                    matches!(it.kind(), T![local] | T![function] | T![for] | T![goto] | T![::])
+                });
+                let parent = match ast::Block::before_trivia(&token) {
+                    Some(block) => block.syntax().clone(),
+                    None => token.parent(),
+                };
+                ctx.is_stmt_start = (token.kind().is_trivia()
+                    && matches!(parent.kind(), SyntaxKind::Block | SyntaxKind::SourceFile))
+                    || prev.as_ref().is_none_or(is_stmt_terminator);
+                (parent.clone(), Some(parent))
+            }
+        };
+        ctx.missing_keyword = keyword_owner.and_then(|it| missing_keyword(it, offset));
+        if ctx.is_stmt_start {
+            ctx.block_end_keywords = block_end_keywords(&node);
+        }
+        Some(ctx)
+    }
+
+    /// The expression before the `.` or `:` token `dot`.
+    fn receiver_expr(&self, dot: &SyntaxToken) -> Option<ExprId> {
+        let parent = dot.parent();
+        let receiver = match_ast! {
+            match parent {
+                ast::DotExpr(it) => it.expr()?,
+                ast::MethodCallExpr(it) => it.fun()?,
+                ast::IndexPath(it) => it
+                    .name_refs()
+                    .take_while(|name_ref| name_ref.syntax().text_range().end() <= dot.text_range().start())
+                    .last()?
+                    .into(),
+                ast::FunctionMethod(it) => it.index_path()?.name_refs().last()?.into(),
+                _ => return None,
+            }
+        };
+        let (_, source_map) = self.sema.file_body(self.file_id);
+        source_map.node_expr(InFile::new(self.file_id, &receiver))
+    }
+
+    /// Whether `range` contains the cursor, used to skip the name being
+    /// typed when collecting candidates.
+    pub(super) fn is_at_cursor(&self, range: TextRange) -> bool {
+        range.contains_inclusive(self.offset)
+    }
+}
+
+fn prev_non_trivia_token(mut token: Option<SyntaxToken>) -> Option<SyntaxToken> {
+    while let Some(it) = &token {
+        if !it.kind().is_trivia() {
+            break;
+        }
+        token = it.prev_token();
+    }
+    token
+}
+
+/// Whether a statement can start right after `token`.
+fn is_stmt_terminator(token: &SyntaxToken) -> bool {
+    match token.kind() {
+        T![then] | T![do] | T![else] | T![repeat] | T![end] | T![;] => true,
+        T![')'] => ast::ParamList::can_cast(token.parent().kind()),
+        _ => false,
+    }
+}
+
+/// The `then` or `do` that `node` is missing before `offset`.
+fn missing_keyword(node: SyntaxNode, offset: TextSize) -> Option<&'static str> {
+    let has_cond_before =
+        |cond: Option<ast::Expr>| cond.is_some_and(|it| it.syntax().text_range().end() <= offset);
+    match_ast! {
+        match node {
+            ast::IfStmt(it) => {
+                (it.then_token().is_none() && has_cond_before(it.cond())).then_some("then")
+            },
+            ast::ElseIfBranch(it) => {
+                (it.then_token().is_none() && has_cond_before(it.cond())).then_some("then")
+            },
+            ast::WhileStmt(it) => {
+                (it.do_token().is_none() && has_cond_before(it.cond())).then_some("do")
+            },
+            ast::ForStmt(it) => (it.do_token().is_none() && it.content().is_some()).then_some("do"),
+            _ => None,
+        }
+    }
+}
+
+fn block_end_keywords(node: &SyntaxNode) -> &'static [&'static str] {
+    let block = match node.ancestors().find_map(ast::Block::cast) {
+        Some(it) => it,
+        None => return &[],
+    };
+    let parent = match block.syntax().parent() {
+        Some(it) => it,
+        None => return &[],
+    };
+    match parent.kind() {
+        SyntaxKind::IfStmt | SyntaxKind::ElseIfBranch => &["else", "elseif", "end"],
+        SyntaxKind::RepeatUntilStmt => &["until"],
+        SyntaxKind::SourceFile => &[],
+        _ => &["end"],
+    }
+}
@@ -0,0 +1,402 @@
+use syntax::{TextRange, TextSize};
+
+use crate::{fixture, CompletionItemKind};
+
+/// Renders the completions at the cursor one per line, as the kind followed
+/// by the label and the detail, sorted so that kinds are grouped together.
+fn completion_list(ra_fixture: &str) -> String {
+    let (analysis, position) = fixture::position(ra_fixture);
+    let items = analysis.completions(position).unwrap().unwrap_or_default();
+    let mut lines: Vec<_> = items
+        .into_iter()
+        .map(|it| {
+            let kind = match it.kind {
+                CompletionItemKind::Local => "lc",
+                CompletionItemKind::Param => "pa",
+                CompletionItemKind::Function => "fn",
+                CompletionItemKind::Global => "gl",
+                CompletionItemKind::Field => "fd",
+                CompletionItemKind::Method => "me",
+                CompletionItemKind::Keyword => "kw",
+            };
+            match it.detail {
+                Some(detail) => format!("{} {} {}\n", kind, it.label, detail),
+                None => format!("{} {}\n", kind, it.label),
+            }
+        })
+        .collect();
+    lines.sort();
+    lines.concat()
+}
+
+fn trim_lines(text: &str) -> String {
+    text.lines().map(str::trim).filter(|it| !it.is_empty()).map(|it| format!("{}\n", it)).collect()
+}
+
+fn check(ra_fixture: &str, expect: &str) {
+    assert_eq!(completion_list(ra_fixture), trim_lines(expect));
+}
+
+/// Like `check`, but leaves out the keywords.
+fn check_names(ra_fixture: &str, expect: &str) {
+    let actual: String = completion_list(ra_fixture)
+        .lines()
+        .filter(|it| !it.starts_with("kw "))
+        .map(|it| format!("{}\n", it))
+        .collect();
+    assert_eq!(actual, trim_lines(expect));
+}
+
+#[test]
+fn completes_locals_in_scope() {
+    check_names(
+        r#"
+local alpha = 1
+do
+    local beta = 2
+end
+local gamma = a$0
+"#,
+        r#"
+        lc alpha
+        "#,
+    );
+}
+
+#[test]
+fn completes_params_and_upvalues() {
+    check_names(
+        r#"
+local count = 0
+local function step(by)
+    local function inner()
+        c$0
+    end
+end
+"#,
+        r#"
+        fn inner upvalue
+        fn step upvalue
+        lc count upvalue
+        pa by upvalue
+        "#,
+    );
+}
+
+#[test]
+fn completes_shadowed_local_once() {
+    check_names(
+        r#"
+local x = 1
+for x = 1, 10 do
+    print(x$0)
+end
+"#,
+        r#"
+        lc x
+        "#,
+    );
+}
+
+#[test]
+fn completes_globals_of_every_file() {
+    check_names(
+        r#"
+//- /main.lua
+config = {}
+function setup() end
+print(c$0)
+//- /other.lua
+VERSION = "1.0"
+"#,
+        r#"
+        fn setup
+        gl VERSION
+        gl config
+        "#,
+    );
+}
+
+#[test]
+fn completes_statement_keywords() {
+    check(
+        r#"
+local a = 1
+$0
+"#,
+        r#"
+        kw break
+        kw do
+        kw for
+        kw function
+        kw goto
+        kw if
+        kw local
+        kw repeat
+        kw return
+        kw while
+        lc a
+        "#,
+    );
+}
+
+#[test]
+fn completes_block_end_keywords() {
+    check(
+        r#"
+local a = true
+if a then
+    e$0
+end
+"#,
+        r#"
+        kw break
+        kw do
+        kw else
+        kw elseif
+        kw end
+        kw for
+        kw function
+        kw goto
+        kw if
+        kw local
+        kw repeat
+        kw return
+        kw while
+        lc a
+        "#,
+    );
+}
+
+#[test]
+fn completes_on_blank_line_after_nested_stmt() {
+    check(
+        r#"
+local outer = 1
+for i = 1, 10 do
+    local inner = i
+    $0
+end
+"#,
+        r#"
+        kw break
+        kw do
+        kw end
+        kw for
+        kw function
+        kw goto
+        kw if
+        kw local
+        kw repeat
+        kw return
+        kw while
+        lc i
+        lc inner
+        lc outer
+        "#,
+    );
+}
+
+#[test]
+fn completes_expressions_on_blank_line_after_unfinished_stmt() {
+    check(
+        r#"
+local a = 1
+if a then
+    local x = a +
+    $0
+end
+"#,
+        r#"
+        kw false
+        kw function
+        kw nil
+        kw not
+        kw true
+        lc a
+        "#,
+    );
+}
+
+#[test]
+fn completes_until_in_repeat() {
+    let list = completion_list(
+        r#"
+repeat
+    u$0
+until true
+"#,
+    );
+    assert!(list.contains("kw until\n"));
+    assert!(!list.contains("kw end\n"));
+}
+
+#[test]
+fn replaces_keyword_typed_as_start_of_name() {
+    let (analysis, position) = fixture::position(
+        r#"
+local inner = 1
+local x = in$0
+"#,
+    );
+    let items = analysis.completions(position).unwrap().unwrap();
+    let range_of = |label: &str| items.iter().find(|it| it.label == label).unwrap().source_range;
+    let keyword = TextRange::at(position.offset - TextSize::of("in"), TextSize::of("in"));
+    assert_eq!(range_of("inner"), keyword);
+    assert_eq!(range_of("nil"), TextRange::empty(position.offset));
+}
+
+#[test]
+fn completes_expression_keywords() {
+    check(
+        r#"
+local a = n$0
+"#,
+        r#"
+        kw false
+        kw function
+        kw nil
+        kw not
+        kw true
+        "#,
+    );
+}
+
+#[test]
+fn completes_then_after_if_condition() {
+    check(
+        r#"
+local ready = true
+if ready th$0
+"#,
+        r#"
+        kw and
+        kw or
+        kw then
+        "#,
+    );
+}
+
+#[test]
+fn completes_do_after_loop_header() {
+    check(
+        r#"
+local i = 0
+while i < 10 $0
+"#,
+        r#"
+        kw and
+        kw do
+        kw or
+        "#,
+    );
+}
+
+#[test]
+fn no_completions_for_new_names() {
+    check(
+        r#"
+local a = 1
+local b$0
+"#,
+        "",
+    );
+}
+
+#[test]
+fn completes_fields_of_table_constructor_and_assignments() {
+    check(
+        r#"
+local point = { x = 1, y = 2 }
+point.label = "origin"
+function point.norm() end
+print(point.$0)
+"#,
+        r#"
+        fd label
+        fd x
+        fd y
+        me norm
+        "#,
+    );
+}
+
+#[test]
+fn completes_nested_and_read_fields() {
+    check(
+        r#"
+local t = { inner = { a = 1 } }
+print(t.inner.b)
+print(t.inner.$0)
+"#,
+        r#"
+        fd a
+        fd b
+        "#,
+    );
+}
+
+#[test]
+fn completes_fields_of_globals_across_files() {
+    check(
+        r#"
+//- /main.lua
+M.name = "m"
+print(M.n$0)
+//- /other.lua
+M = { version = 1 }
+function M.init() end
+"#,
+        r#"
+        fd name
+        fd version
+        me init
+        "#,
+    );
+}
+
+#[test]
+fn completes_fields_of_inferred_types() {
+    check(
+        r#"
+local function new() return { alpha = 1, step = function() end } end
+local obj = new()
+obj.$0
+"#,
+        r#"
+        fd alpha
+        me step
+        "#,
+    );
+}
+
+#[test]
+fn completes_only_methods_after_colon() {
+    check(
+        r#"
+local obj = { size = 0 }
+function obj:grow() end
+obj:reset()
+obj:$0
+"#,
+        r#"
+        me grow
+        me reset
+        "#,
+    );
+}
+
+#[test]
+fn completes_fields_in_function_definition_name() {
+    check(
+        r#"
+local M = { helper = 1 }
+function M.run() end
+function M.$0
+"#,
+        r#"
+        fd helper
+        me run
+        "#,
+    );
+}
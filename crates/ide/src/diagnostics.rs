@@ -1,44 +1,325 @@
-use hir::Semantics;
-use ide_db::{base_db::SourceDatabase, RootDatabase};
-use syntax::TextRange;
+use hir::{item_tree::Attrib, Semantics, WithBodyId};
+use hir_ty::{HirTyDatabase, InferenceDiagnostic};
+use ide_db::{
+    base_db::SourceDatabase,
+    defs::Definition,
+    search::{FileReference, ReferenceAccess},
+    RootDatabase,
+};
+use rustc_hash::FxHashSet;
+use syntax::{ast, ast::AstNode, TextRange};
 
 use crate::FileId;
 
 #[derive(Debug)]
 pub struct Diagnostic {
-    // pub name: Option<String>,
+    pub code: DiagnosticCode,
     pub message: String,
     pub range: TextRange,
     pub severity: Severity,
 }
 
+/// The kind of a diagnostic, such as `not-callable`, by which it can be
+/// disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiagnosticCode(pub &'static str);
+
+impl DiagnosticCode {
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+/// Which diagnostics are reported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticsConfig {
+    /// The codes of the diagnostics that are not reported.
+    pub disabled: FxHashSet<String>,
+}
+
 impl Diagnostic {
-    fn error(range: TextRange, message: String) -> Self {
-        Self { message, range, severity: Severity::Error }
+    fn error(code: &'static str, range: TextRange, message: String) -> Self {
+        Self { code: DiagnosticCode(code), message, range, severity: Severity::Error }
     }
 
-    fn hint(range: TextRange, message: String) -> Self {
-        Self { message, range, severity: Severity::WeakWarning }
+    fn warning(code: &'static str, range: TextRange, message: String) -> Self {
+        Self { code: DiagnosticCode(code), message, range, severity: Severity::Warning }
     }
 }
 
-pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic> {
+pub(crate) fn diagnostics(
+    db: &RootDatabase,
+    config: &DiagnosticsConfig,
+    file_id: FileId,
+) -> Vec<Diagnostic> {
     let sema = Semantics::new(db);
     let parse = db.parse(file_id);
     let mut res = Vec::new();
     // [#34344] Only take first 128 errors to prevent slowing down editor/ide, the number 128 is chosen arbitrarily.
-    res.extend(
-        parse
-            .errors()
-            .iter()
-            .take(128)
-            .map(|err| Diagnostic::error(err.range(), format!("Syntax Error: {}", err))),
-    );
+    res.extend(parse.errors().iter().take(128).map(|err| {
+        Diagnostic::error("syntax-error", err.range(), format!("Syntax Error: {}", err))
+    }));
+    read_only_assignments(&sema, file_id, &mut res);
+    type_errors(db, &sema, file_id, &mut res);
+    res.retain(|it| !config.disabled.contains(it.code.as_str()));
     res
 }
 
+/// Assignments to locals declared as `<const>` or `<close>`.
+fn read_only_assignments(sema: &Semantics, file_id: FileId, acc: &mut Vec<Diagnostic>) {
+    let item_tree = sema.db.file_item_tree(file_id);
+    let ast_id_map = sema.db.ast_id_map(file_id);
+    let root = sema.parse(file_id).syntax().clone();
+
+    for assign in item_tree.local_assigns() {
+        let node = ast_id_map.get(assign.ast_id).to_node(&root);
+        let names = node.multi_name().into_iter().flat_map(|it| it.names());
+        for (name, attrib) in names.zip(&assign.attribs) {
+            let attrib = match attrib {
+                Some(Attrib::Const) => "const",
+                Some(Attrib::Close) => "close",
+                None => continue,
+            };
+            let local = match sema.resolve_local(file_id, &name) {
+                Some(it) => it,
+                None => continue,
+            };
+            let decl_range = name.syntax().text_range();
+            for FileReference { range, access, .. } in Definition::Local(local).find_usages(sema) {
+                if access == ReferenceAccess::Write && range != decl_range {
+                    acc.push(Diagnostic::error(
+                        "read-only-assignment",
+                        range,
+                        format!("Cannot assign to `{}`, it is a `<{}>` local", name.text(), attrib),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// The uses of values that their inferred or declared types do not allow.
+fn type_errors(db: &RootDatabase, sema: &Semantics, file_id: FileId, acc: &mut Vec<Diagnostic>) {
+    let infer = db.infer(WithBodyId::ModuleId(file_id));
+    let (_, source_map) = sema.file_body(file_id);
+    let root = sema.parse(file_id).syntax().clone();
+    let node = |expr| Some(source_map.expr_syntax(expr)?.value.to_node(&root));
+
+    for diagnostic in infer.diagnostics() {
+        let diagnostic = match diagnostic {
+            InferenceDiagnostic::NotCallable { call, ty } => {
+                let range = match node(*call) {
+                    Some(ast::Expr::CallExpr(call)) => {
+                        call.fun().map(|it| it.syntax().text_range())
+                    }
+                    Some(ast::Expr::MethodCallExpr(call)) => {
+                        call.method_name().map(|it| it.syntax().text_range())
+                    }
+                    _ => None,
+                };
+                let range = match range {
+                    Some(it) => it,
+                    None => continue,
+                };
+                Diagnostic::warning(
+                    "not-callable",
+                    range,
+                    format!("Cannot call a value of type `{}`", ty.display(&infer)),
+                )
+            }
+            InferenceDiagnostic::NotIndexable { base, ty } => {
+                let range = match node(*base) {
+                    Some(it) => it.syntax().text_range(),
+                    None => continue,
+                };
+                Diagnostic::warning(
+                    "not-indexable",
+                    range,
+                    format!("Cannot index a value of type `{}`", ty.display(&infer)),
+                )
+            }
+            InferenceDiagnostic::ArgTypeMismatch { arg, expected, actual } => {
+                let range = match node(*arg) {
+                    Some(it) => it.syntax().text_range(),
+                    None => continue,
+                };
+                Diagnostic::warning(
+                    "arg-type-mismatch",
+                    range,
+                    format!(
+                        "Expected an argument of type `{}`, found `{}`",
+                        expected.display(&infer),
+                        actual.display(&infer)
+                    ),
+                )
+            }
+            InferenceDiagnostic::ReturnTypeMismatch { expr, expected, actual } => {
+                let range = match node(*expr) {
+                    Some(it) => it.syntax().text_range(),
+                    None => continue,
+                };
+                Diagnostic::warning(
+                    "return-type-mismatch",
+                    range,
+                    format!(
+                        "Expected a return value of type `{}`, found `{}`",
+                        expected.display(&infer),
+                        actual.display(&infer)
+                    ),
+                )
+            }
+            InferenceDiagnostic::UndeclaredField { target, class, field } => {
+                let range = match node(*target) {
+                    Some(ast::Expr::DotExpr(dot)) => match dot.ident_token() {
+                        Some(it) => it.text_range(),
+                        None => continue,
+                    },
+                    Some(it) => it.syntax().text_range(),
+                    None => continue,
+                };
+                Diagnostic::warning(
+                    "undeclared-field",
+                    range,
+                    format!("Field `{}` is not declared on class `{}`", field, class),
+                )
+            }
+        };
+        acc.push(diagnostic);
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Severity {
     Error,
+    Warning,
     WeakWarning,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use syntax::LuaVersion;
+
+    use crate::{fixture, AnalysisHost, Change, DiagnosticsConfig, FileId};
+
+    fn check(ra_fixture: &str, expect: &[&str]) {
+        check_with_config(&DiagnosticsConfig::default(), ra_fixture, expect)
+    }
+
+    fn check_with_config(config: &DiagnosticsConfig, ra_fixture: &str, expect: &[&str]) {
+        let (analysis, file_id) = fixture::file(ra_fixture);
+        let actual: Vec<_> = analysis
+            .diagnostics(config, file_id)
+            .unwrap()
+            .into_iter()
+            .map(|it| format!("{:?} {}", it.range, it.message))
+            .collect();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn assign_to_const_local() {
+        check(
+            r#"
+local x <const>, y = 1, 2
+x = 3
+y = 4
+local function f()
+    x, y = y, x
+end
+"#,
+            &[
+                "26..27 Cannot assign to `x`, it is a `<const>` local",
+                "61..62 Cannot assign to `x`, it is a `<const>` local",
+            ],
+        );
+    }
+
+    #[test]
+    fn multiple_close_locals() {
+        check(
+            r#"
+local a <close>, b <close> = f(), g()
+"#,
+            &["19..26 Syntax Error: Only one variable of a declaration can be `<close>`"],
+        );
+    }
+
+    #[test]
+    fn type_errors() {
+        check(
+            r#"
+local n = 1
+n()
+local s = n.x
+
+---@return string
+local function f() return n end
+---@param x integer
+local function g(x) end
+g("1")
+local t = { x = 1 }
+t:x()
+"#,
+            &[
+                "12..13 Cannot call a value of type `integer`",
+                "26..27 Cannot index a value of type `integer`",
+                "75..76 Expected a return value of type `string`, found `integer`",
+                "127..130 Expected an argument of type `integer`, found `string`",
+                "154..155 Cannot call a value of type `integer`",
+            ],
+        );
+    }
+
+    #[test]
+    fn undeclared_class_field() {
+        check(
+            r#"
+---@class Point
+---@field x number
+local Point = {}
+function Point:move() self.y = 0 end
+
+---@type Point
+local p = {}
+p.x, p.y = 1, 2
+p.z = 3
+"#,
+            &["136..137 Field `z` is not declared on class `Point`"],
+        );
+    }
+
+    #[test]
+    fn lua_version_of_file() {
+        let mut host = AnalysisHost::new();
+        let mut change = Change::new();
+        for idx in 0..2 {
+            change.change_file(FileId(idx), Some(Arc::new("return 1 // 2\n".to_string())));
+        }
+        change.set_file_lua_version(FileId(1), Some(LuaVersion::Lua51));
+        host.apply_change(change);
+        let analysis = host.analysis();
+        let messages = |file_id| {
+            let diagnostics = analysis.diagnostics(&DiagnosticsConfig::default(), file_id);
+            diagnostics.unwrap().into_iter().map(|it| it.message).collect::<Vec<_>>()
+        };
+        assert!(messages(FileId(0)).is_empty());
+        assert_eq!(messages(FileId(1)), ["Syntax Error: `//` is not supported in Lua 5.1"]);
+    }
+
+    #[test]
+    fn disabled_diagnostics() {
+        let mut config = DiagnosticsConfig::default();
+        config.disabled.insert("not-callable".to_string());
+        check_with_config(
+            &config,
+            r#"
+local n = 1
+n()
+n.x = 2
+"#,
+            &["16..17 Cannot index a value of type `integer`"],
+        );
+    }
+}
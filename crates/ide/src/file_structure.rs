@@ -0,0 +1,108 @@
+use ide_db::{
+    base_db::FileId,
+    symbol_index::{SymbolKind, SymbolsDatabase},
+    RootDatabase,
+};
+use syntax::TextRange;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructureNode {
+    /// The index of the node this one is nested in.
+    pub parent: Option<usize>,
+    pub label: String,
+    pub navigation_range: TextRange,
+    pub node_range: TextRange,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+}
+
+// Feature: File Structure
+//
+// Lists the functions and locals declared in a file, the ones declared inside
+// of a function are nested under it.
+pub(crate) fn file_structure(db: &RootDatabase, file_id: FileId) -> Vec<StructureNode> {
+    let mut res: Vec<StructureNode> = Vec::new();
+    // the indices of the nodes that contain the current one
+    let mut stack: Vec<usize> = Vec::new();
+    for symbol in db.file_symbols(file_id).iter() {
+        while let Some(&parent) = stack.last() {
+            let range = res[parent].node_range;
+            if range != symbol.full_range && range.contains_range(symbol.full_range) {
+                break;
+            }
+            stack.pop();
+        }
+        stack.push(res.len());
+        res.push(StructureNode {
+            parent: stack.iter().rev().nth(1).copied(),
+            label: symbol.qualified_name(),
+            navigation_range: symbol.focus_range,
+            node_range: symbol.full_range,
+            kind: symbol.kind,
+            detail: symbol.detail.clone(),
+        });
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture;
+
+    /// Renders the structure one node per line, indented by depth.
+    fn check(ra_fixture: &str, expect: &str) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let nodes = analysis.file_structure(position.file_id).unwrap();
+        let mut actual = String::new();
+        for node in &nodes {
+            let mut depth = 0;
+            let mut parent = node.parent;
+            while let Some(idx) = parent {
+                depth += 1;
+                parent = nodes[idx].parent;
+            }
+            let detail = node.detail.as_deref().unwrap_or_default();
+            actual.push_str(&format!(
+                "{}{:?} {}{} {:?}\n",
+                "  ".repeat(depth),
+                node.kind,
+                node.label,
+                detail,
+                node.navigation_range
+            ));
+        }
+        assert_eq!(actual.trim(), expect.trim());
+    }
+
+    #[test]
+    fn nested_structure() {
+        check(
+            r#"
+$0local M, N = {}, {}
+
+local function helper(a, ...)
+    local tmp = a
+    local function inner() end
+end
+
+function M.run()
+    if true then
+        local flag = true
+    end
+end
+
+function M.obj:method(x) end
+"#,
+            r#"
+Local M 6..7
+Local N 9..10
+Function helper(a, ...) 36..42
+  Local tmp 61..64
+  Function inner() 88..93
+Function M.run() 116..119
+  Local flag 153..157
+Method M.obj:method(x) 193..199
+"#,
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Utilities for creating `Analysis` instances for tests.
+
+use std::sync::Arc;
+
+use ide_db::base_db::{FilePosition, FileRange};
+use test_utils::{
+    extract_annotations, extract_offset, extract_range, parse_fixture, CURSOR_MARKER,
+};
+
+use crate::{Analysis, AnalysisHost, Change, FileId};
+
+/// Creates an analysis from a fixture of one or more files, the files get
+/// ids in the order they appear in. Returns the position of the `$0` marker,
+/// if there is one.
+fn with_files(ra_fixture: &str) -> (Analysis, Option<FilePosition>, Vec<(FileId, String)>) {
+    let mut host = AnalysisHost::new();
+    let mut change = Change::new();
+    let mut position = None;
+    let mut files = Vec::new();
+    for (idx, file) in parse_fixture(ra_fixture).into_iter().enumerate() {
+        let file_id = FileId(idx as u32);
+        let text = if file.text.contains(CURSOR_MARKER) {
+            let (offset, text) = extract_offset(&file.text);
+            position = Some(FilePosition { file_id, offset });
+            text
+        } else {
+            file.text
+        };
+        change.change_file(file_id, Some(Arc::new(text.clone())));
+        files.push((file_id, text));
+    }
+    host.apply_change(change);
+    (host.analysis(), position, files)
+}
+
+/// Creates an analysis from a fixture with a single file.
+pub(crate) fn file(ra_fixture: &str) -> (Analysis, FileId) {
+    let (analysis, _, files) = with_files(ra_fixture);
+    (analysis, files[0].0)
+}
+
+/// Creates an analysis from a fixture with a `$0` marker.
+pub(crate) fn position(ra_fixture: &str) -> (Analysis, FilePosition) {
+    let (analysis, position, _) = with_files(ra_fixture);
+    (analysis, position.expect("fixture should contain a cursor marker"))
+}
+
+/// Creates an analysis from a fixture with a single file and a range between
+/// two `$0` markers.
+pub(crate) fn range(ra_fixture: &str) -> (Analysis, FileRange) {
+    let mut host = AnalysisHost::new();
+    let mut change = Change::new();
+    let file_id = FileId(0);
+    let (range, text) = extract_range(&parse_fixture(ra_fixture)[0].text);
+    change.change_file(file_id, Some(Arc::new(text)));
+    host.apply_change(change);
+    (host.analysis(), FileRange { file_id, range })
+}
+
+/// Creates an analysis from a fixture with a `$0` marker and returns the
+/// ranges annotated with `--^^^` comments along with their text, sorted by
+/// file and position.
+pub(crate) fn annotations(ra_fixture: &str) -> (Analysis, FilePosition, Vec<(FileRange, String)>) {
+    let (analysis, position, files) = with_files(ra_fixture);
+    let annotations = files
+        .iter()
+        .flat_map(|(file_id, text)| {
+            extract_annotations(text)
+                .into_iter()
+                .map(move |(range, text)| (FileRange { file_id: *file_id, range }, text))
+        })
+        .collect();
+    (analysis, position.expect("fixture should contain a cursor marker"), annotations)
+}
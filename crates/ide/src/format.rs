@@ -0,0 +1,1094 @@
+use ide_db::{
+    base_db::{FileId, FileRange, SourceDatabase},
+    RootDatabase,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::{
+    ast::{self, AstNode},
+    Direction, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize, N, T,
+};
+use text_edit::{Indel, TextEdit};
+
+/// How a file is formatted, usually read from a `luafmt.toml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatConfig {
+    /// The number of columns of an indentation level.
+    pub indent_width: u32,
+    /// Whether to indent with tabs instead of spaces.
+    pub use_tabs: bool,
+    /// The column after which table constructors and argument lists are split
+    /// into one item per line.
+    pub max_width: u32,
+    pub quote_style: QuoteStyle,
+    pub table_separator: TableSeparator,
+    pub trailing_separator: TrailingSeparator,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        FormatConfig {
+            indent_width: 4,
+            use_tabs: false,
+            max_width: 120,
+            quote_style: QuoteStyle::Double,
+            table_separator: TableSeparator::Comma,
+            trailing_separator: TrailingSeparator::Multiline,
+        }
+    }
+}
+
+/// The quotes of short strings. Strings keep their quotes when their contents
+/// contain the preferred one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteStyle {
+    Double,
+    Single,
+    Preserve,
+}
+
+/// The separator between the fields of a table constructor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableSeparator {
+    Comma,
+    Semicolon,
+    Preserve,
+}
+
+/// When the last field of a table constructor is followed by a separator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingSeparator {
+    Always,
+    Never,
+    /// Only when the fields are on their own lines.
+    Multiline,
+}
+
+// Feature: Format
+//
+// Normalizes the indentation and spacing of a file, lays out table
+// constructors and long argument lists one item per line and unifies string
+// quotes and table separators. Comments are kept where they are. Files with
+// syntax errors are left alone.
+pub(crate) fn format(
+    db: &RootDatabase,
+    file_id: FileId,
+    config: &FormatConfig,
+) -> Option<TextEdit> {
+    let indels = format_indels(db, file_id, config)?;
+    let mut builder = TextEdit::builder();
+    indels.into_iter().for_each(|it| builder.replace(it.delete, it.insert));
+    Some(builder.finish())
+}
+
+/// Formats the whole file but only keeps the edits that touch `frange`.
+pub(crate) fn format_range(
+    db: &RootDatabase,
+    frange: FileRange,
+    config: &FormatConfig,
+) -> Option<TextEdit> {
+    let range = frange.range;
+    let indels = format_indels(db, frange.file_id, config)?;
+    let mut builder = TextEdit::builder();
+    indels
+        .into_iter()
+        .filter(|it| match range.intersect(it.delete) {
+            Some(common) => !common.is_empty() || it.delete.is_empty(),
+            None => false,
+        })
+        .for_each(|it| builder.replace(it.delete, it.insert));
+    Some(builder.finish())
+}
+
+fn format_indels(db: &RootDatabase, file_id: FileId, config: &FormatConfig) -> Option<Vec<Indel>> {
+    let parse = db.parse(file_id);
+    if !parse.errors().is_empty() {
+        return None;
+    }
+    let root = parse.tree().syntax().clone();
+    Some(Formatter::new(config).run(&root))
+}
+
+/// What the lines inside a pair of brackets or a block are indented by.
+#[derive(Debug)]
+struct Context {
+    kind: ContextKind,
+    /// The indentation of the lines inside.
+    inner: u32,
+    /// The indentation of the line of the opening token, which a closing
+    /// bracket at the start of a line gets.
+    outer: u32,
+    /// The table or argument list of brackets.
+    node: Option<SyntaxNode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContextKind {
+    Block,
+    Bracket { broken: bool },
+}
+
+struct Formatter<'a> {
+    config: &'a FormatConfig,
+    /// The non whitespace tokens of the file.
+    tokens: Vec<SyntaxToken>,
+    /// Tables and argument lists with one item per line.
+    broken: FxHashSet<SyntaxNode>,
+    /// The operators of long binary expressions that lines are broken after.
+    broken_ops: FxHashSet<SyntaxToken>,
+    /// Tokens that are removed, like trailing separators.
+    dropped: FxHashSet<SyntaxToken>,
+    /// Text inserted after a token, like a trailing separator.
+    appended: FxHashMap<SyntaxToken, String>,
+    /// The indentation of the lines of the keywords of the enclosing blocks.
+    keywords: Vec<u32>,
+    contexts: Vec<Context>,
+    line_indent: u32,
+    column: u32,
+    indels: Vec<Indel>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(config: &'a FormatConfig) -> Formatter<'a> {
+        Formatter {
+            config,
+            tokens: Vec::new(),
+            broken: FxHashSet::default(),
+            broken_ops: FxHashSet::default(),
+            dropped: FxHashSet::default(),
+            appended: FxHashMap::default(),
+            keywords: Vec::new(),
+            contexts: Vec::new(),
+            line_indent: 0,
+            column: 0,
+            indels: Vec::new(),
+        }
+    }
+
+    fn run(mut self, root: &SyntaxNode) -> Vec<Indel> {
+        self.tokens = root
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() != T![whitespace])
+            .collect();
+        let text = root.text().to_string();
+
+        let mut prev: Option<SyntaxToken> = None;
+        let mut prev_code: Option<SyntaxToken> = None;
+        for idx in 0..self.tokens.len() {
+            let token = self.tokens[idx].clone();
+            if self.dropped.contains(&token) {
+                continue;
+            }
+
+            let closer_indent = self.close(&token);
+            let gap_start = prev.as_ref().map_or(0.into(), |it| it.text_range().end());
+            let gap = TextRange::new(gap_start, token.text_range().start());
+            let original = &text[gap];
+            let newlines = match &prev {
+                Some(prev) => self.newlines(prev, prev_code.as_ref(), idx, original),
+                None => 0,
+            };
+            let whitespace = if newlines > 0 {
+                let indent = match closer_indent {
+                    Some(it) => it,
+                    None => self.indent(&token, prev_code.as_ref()),
+                };
+                self.line_indent = indent;
+                self.column = indent * self.config.indent_width;
+                "\n".repeat(newlines) + &self.indent_text(indent)
+            } else if prev.as_ref().is_some_and(|prev| space_between(prev, &token, original)) {
+                self.column += 1;
+                " ".to_string()
+            } else {
+                String::new()
+            };
+            self.replace(gap, original, whitespace);
+
+            self.open_bracket(&token);
+            self.break_infix(&token);
+            let new_text = self.token_text(&token);
+            self.column = match new_text.rfind('\n') {
+                Some(idx) => width(&new_text[idx + 1..]),
+                None => self.column + width(&new_text),
+            };
+            self.replace(token.text_range(), token.text(), new_text);
+            self.open(&token);
+
+            if token.kind() != T![comment] {
+                prev_code = Some(token.clone());
+            }
+            prev = Some(token);
+        }
+
+        if let Some(prev) = prev {
+            let gap = TextRange::new(prev.text_range().end(), TextSize::of(text.as_str()));
+            self.replace(gap, &text[gap], "\n".to_string());
+        }
+        self.indels
+    }
+
+    fn replace(&mut self, range: TextRange, original: &str, new_text: String) {
+        if original != new_text {
+            self.indels.push(Indel::replace(range, new_text));
+        }
+    }
+
+    fn indent_text(&self, level: u32) -> String {
+        if self.config.use_tabs {
+            "\t".repeat(level as usize)
+        } else {
+            " ".repeat((level * self.config.indent_width) as usize)
+        }
+    }
+
+    /// Leaves the block or brackets that `token` closes, returning the
+    /// indentation it gets at the start of a line.
+    fn close(&mut self, token: &SyntaxToken) -> Option<u32> {
+        match token.kind() {
+            T![end] | T![until] if block_owner(token).is_some() => {
+                self.pop_context(ContextKind::Block);
+                self.keywords.pop()
+            }
+            T![else] | T![elseif] => {
+                self.pop_context(ContextKind::Block);
+                self.keywords.last().copied()
+            }
+            T![')'] | T!['}'] | T![']'] => {
+                let idx = self
+                    .contexts
+                    .iter()
+                    .rposition(|it| matches!(it.kind, ContextKind::Bracket { .. }))?;
+                let context = self.contexts.drain(idx..).next()?;
+                Some(context.outer)
+            }
+            _ => None,
+        }
+    }
+
+    fn pop_context(&mut self, kind: ContextKind) {
+        if let Some(idx) = self.contexts.iter().rposition(|it| it.kind == kind) {
+            self.contexts.truncate(idx);
+        }
+    }
+
+    /// Decides the layout of a table or argument list before its opening
+    /// bracket is written.
+    fn open_bracket(&mut self, token: &SyntaxToken) {
+        let node = token.parent();
+        match (token.kind(), node.kind()) {
+            (T!['{'], N![TableExpr]) => {
+                let broken =
+                    node.text().contains_char('\n') || self.too_wide(&node).unwrap_or(true);
+                if broken {
+                    self.broken.insert(node.clone());
+                }
+                self.trailing_separator(&node, broken);
+            }
+            (T!['('], N![ArgList]) => {
+                let is_empty = self.next_token(token).is_none_or(|it| it.kind() == T![')']);
+                let after_paren = token
+                    .next_token()
+                    .is_some_and(|it| it.kind() == T![whitespace] && it.text().contains('\n'));
+                if !is_empty && (after_paren || self.too_wide(&node).unwrap_or(false)) {
+                    self.broken.insert(node);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Breaks the binary expression that starts at `token` after its operators
+    /// of the lowest precedence when it goes past the maximum width. The parts
+    /// between them are checked again when they start their own lines.
+    fn break_infix(&mut self, token: &SyntaxToken) {
+        let expr = token
+            .parent()
+            .ancestors()
+            .take_while(|it| it.text_range().start() == token.text_range().start())
+            .filter_map(ast::InfixExpr::cast)
+            .last();
+        let expr = match expr {
+            Some(it) if self.too_wide(it.syntax()).unwrap_or(false) => it,
+            _ => return,
+        };
+        let precedence = match expr.op_kind() {
+            Some(it) => it.infix_precedence(),
+            None => return,
+        };
+        let mut exprs = vec![expr];
+        while let Some(expr) = exprs.pop() {
+            if let Some(op) = expr.op_token() {
+                self.broken_ops.insert(op);
+            }
+            let (lhs, rhs) = expr.sub_exprs();
+            for sub_expr in lhs.into_iter().chain(rhs) {
+                match sub_expr {
+                    ast::Expr::InfixExpr(it)
+                        if it.op_kind().map(|op| op.infix_precedence()) == Some(precedence) =>
+                    {
+                        exprs.push(it)
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Whether `node` goes past the maximum width when written on the current
+    /// line, `None` when it can't be written on one line.
+    fn too_wide(&self, node: &SyntaxNode) -> Option<bool> {
+        // breaking it would stop a one line function or block from being one
+        if node.ancestors().skip(1).any(|it| is_block_owner(it.kind()) && is_inline(&it)) {
+            return Some(false);
+        }
+        self.flat_width(node).map(|it| self.column + it > self.config.max_width)
+    }
+
+    /// Adds or removes the separator after the last field of a table.
+    fn trailing_separator(&mut self, table: &SyntaxNode, broken: bool) {
+        let last_field = match table.children().filter(|it| is_field(it.kind())).last() {
+            Some(it) => it,
+            None => return,
+        };
+        let trailing = last_field
+            .siblings(Direction::Next)
+            .skip(1)
+            .find(|it| it.kind() == N![TableSep])
+            .and_then(|it| it.first_token());
+        let wanted = match self.config.trailing_separator {
+            TrailingSeparator::Always => true,
+            TrailingSeparator::Never => false,
+            TrailingSeparator::Multiline => broken,
+        };
+        match trailing {
+            Some(sep) if !wanted => {
+                self.dropped.insert(sep);
+            }
+            None if wanted => {
+                if let Some(last) = last_field.last_token() {
+                    let sep = match self.config.table_separator {
+                        TableSeparator::Comma => ",",
+                        TableSeparator::Semicolon => ";",
+                        TableSeparator::Preserve => table
+                            .children()
+                            .find(|it| it.kind() == N![TableSep])
+                            .and_then(|it| it.first_token())
+                            .map_or(",", |it| if it.kind() == T![;] { ";" } else { "," }),
+                    };
+                    self.appended.insert(last, sep.to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Enters the block or brackets that `token` opens.
+    fn open(&mut self, token: &SyntaxToken) {
+        let line_indent = self.line_indent;
+        if block_owner_keyword(token) {
+            self.keywords.push(line_indent);
+        }
+        if block_opener(token).is_some() {
+            let inner = self.keywords.last().map_or(0, |it| it + 1);
+            self.contexts.push(Context {
+                kind: ContextKind::Block,
+                inner,
+                outer: inner.saturating_sub(1),
+                node: None,
+            });
+        }
+        if matches!(token.kind(), T!['('] | T!['{'] | T!['[']) {
+            let node = token.parent();
+            let broken = self.broken.contains(&node);
+            self.contexts.push(Context {
+                kind: ContextKind::Bracket { broken },
+                inner: line_indent + broken as u32,
+                outer: line_indent,
+                node: Some(node),
+            });
+        }
+    }
+
+    /// The indentation of `token` when it starts a line.
+    fn indent(&self, token: &SyntaxToken, prev_code: Option<&SyntaxToken>) -> u32 {
+        let context = match self.contexts.last() {
+            Some(it) => it,
+            None => return if self.is_item_start(token, prev_code, None) { 0 } else { 1 },
+        };
+        let continuation = !self.is_item_start(token, prev_code, Some(context));
+        context.inner + continuation as u32
+    }
+
+    /// Whether `token` starts a statement or an item of a broken list, which
+    /// are aligned with each other instead of indented as a continuation.
+    fn is_item_start(
+        &self,
+        token: &SyntaxToken,
+        prev_code: Option<&SyntaxToken>,
+        context: Option<&Context>,
+    ) -> bool {
+        if matches!(token.kind(), T![comment] | T![shebang]) || starts_stmt(token) {
+            return true;
+        }
+        let prev = match prev_code {
+            Some(it) => it,
+            None => return true,
+        };
+        match context {
+            Some(Context {
+                kind: ContextKind::Bracket { broken: true }, node: Some(node), ..
+            }) => {
+                matches!(prev.kind(), T!['('] | T!['{'] | T!['['])
+                    || (matches!(prev.kind(), T![,] | T![;])
+                        && item_list(prev).as_ref() == Some(node))
+            }
+            Some(Context { kind: ContextKind::Block, .. }) => block_opener(prev).is_some(),
+            _ => false,
+        }
+    }
+
+    /// The number of line breaks before the token at `idx`.
+    fn newlines(
+        &self,
+        prev: &SyntaxToken,
+        prev_code: Option<&SyntaxToken>,
+        idx: usize,
+        original: &str,
+    ) -> usize {
+        let token = &self.tokens[idx];
+        let mut newlines = original.matches('\n').count().min(2);
+        if is_line_comment(prev) || prev.kind() == T![shebang] {
+            newlines = newlines.max(1);
+        }
+        // a comment at the end of a line stays there
+        if token.kind() == T![comment] && newlines == 0 {
+            return 0;
+        }
+
+        let next_code = self.tokens[idx..].iter().find(|it| it.kind() != T![comment]);
+        if let (Some(prev), Some(next)) = (prev_code, next_code) {
+            if self.breaks_between(prev, next) {
+                newlines = newlines.max(1);
+            }
+            // no blank lines at the start or end of a block or brackets
+            if block_opener(prev).is_some()
+                || block_closer(next)
+                || matches!(prev.kind(), T!['('] | T!['{'] | T!['['])
+                || matches!(next.kind(), T![')'] | T!['}'] | T![']'])
+            {
+                newlines = newlines.min(1);
+            }
+        }
+        newlines
+    }
+
+    /// Whether the layout puts `next` on a new line after `prev`.
+    fn breaks_between(&self, prev: &SyntaxToken, next: &SyntaxToken) -> bool {
+        if starts_stmt(next) && !starts_first_stmt(next) {
+            return true;
+        }
+        if let Some(owner) = block_opener(prev) {
+            if !is_inline(&owner) {
+                return true;
+            }
+        }
+        if block_closer(next) && block_owner(next).is_some_and(|it| !is_inline(&it)) {
+            return true;
+        }
+        let opens_broken = |token: &SyntaxToken| {
+            matches!(token.kind(), T!['{'] | T!['(']) && self.broken.contains(&token.parent())
+        };
+        let closes_broken = |token: &SyntaxToken| {
+            matches!(token.kind(), T!['}'] | T![')']) && self.broken.contains(&token.parent())
+        };
+        let separates_broken = |token: &SyntaxToken| {
+            matches!(token.kind(), T![,] | T![;])
+                && item_list(token).is_some_and(|it| self.broken.contains(&it))
+        };
+        opens_broken(prev)
+            || closes_broken(next)
+            || separates_broken(prev)
+            || self.broken_ops.contains(prev)
+    }
+
+    /// The text of `token` once formatted.
+    fn token_text(&self, token: &SyntaxToken) -> String {
+        let mut text = match token.kind() {
+            T![str] => requote(token.text(), self.config.quote_style),
+            T![,] | T![;] if token.parent().kind() == N![TableSep] => {
+                match self.config.table_separator {
+                    TableSeparator::Comma => ",".to_string(),
+                    TableSeparator::Semicolon => ";".to_string(),
+                    TableSeparator::Preserve => token.text().to_string(),
+                }
+            }
+            _ => token.text().to_string(),
+        };
+        if let Some(appended) = self.appended.get(token) {
+            text.push_str(appended);
+        }
+        text
+    }
+
+    /// The width of `node` written on a single line, `None` when it can't be.
+    fn flat_width(&self, node: &SyntaxNode) -> Option<u32> {
+        if node.text().contains_char('\n') {
+            return None;
+        }
+        if node.descendants().any(|it| is_block_owner(it.kind()) && !is_inline(&it)) {
+            return None;
+        }
+        let mut total = 0;
+        let mut prev: Option<SyntaxToken> = None;
+        let tokens = node
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() != T![whitespace]);
+        for token in tokens {
+            if self.config.trailing_separator != TrailingSeparator::Always
+                && is_trailing_separator(&token)
+            {
+                continue;
+            }
+            if let Some(prev) = &prev {
+                let original =
+                    if prev.text_range().end() == token.text_range().start() { "" } else { " " };
+                total += space_between(prev, &token, original) as u32;
+            }
+            total += width(&self.token_text(&token));
+            prev = Some(token);
+        }
+        Some(total)
+    }
+
+    fn next_token(&self, token: &SyntaxToken) -> Option<SyntaxToken> {
+        let mut next = token.next_token();
+        while let Some(token) = &next {
+            if !token.kind().is_trivia() {
+                break;
+            }
+            next = token.next_token();
+        }
+        next
+    }
+}
+
+/// Whether there is a space between `prev` and `next` when they are on the
+/// same line.
+fn space_between(prev: &SyntaxToken, next: &SyntaxToken, original: &str) -> bool {
+    let prev_parent = prev.parent();
+    let next_parent = next.parent();
+    match (prev.kind(), next.kind()) {
+        (T!['{'], T!['}']) => false,
+        (T!['{'], _) | (_, T!['}']) => true,
+        // `t[ [[long string]] ]` would start a long string
+        (T!['['], _) if next.text().starts_with('[') || next.text().starts_with('=') => true,
+        (T![str], T![']']) if prev.text().ends_with(']') => true,
+        (T!['('] | T!['['], _) | (_, T![')'] | T![']'] | T![,] | T![;]) => false,
+        (T![.], _) | (_, T![.]) => false,
+        (T![:], _) | (_, T![:]) => false,
+        (T![::], T![ident]) | (T![ident], T![::]) => false,
+        (_, T!['(']) if matches!(next_parent.kind(), N![ArgList] | N![ParamList]) => false,
+        (_, T!['[']) if next_parent.kind() == N![Index] => {
+            next_parent.parent().is_none_or(|it| it.kind() != N![IndexExpr])
+        }
+        (T![<], _) | (_, T![>]) if next_parent.kind() == N![Attrib] => false,
+        // `- -x` would start a comment
+        (T![-], _) if prev_parent.kind() == N![PrefixExpr] => next.text().starts_with('-'),
+        (T![#] | T![~], _) if prev_parent.kind() == N![PrefixExpr] => false,
+        // calls with a string or table keep the space, if any
+        _ if is_call_arg(next) => !original.is_empty(),
+        _ => true,
+    }
+}
+
+/// Whether `token` starts a string or table argument, as in `f "x"`.
+fn is_call_arg(token: &SyntaxToken) -> bool {
+    let parent = token.parent();
+    match token.kind() {
+        T![str] => parent.kind() == N![CallArgs],
+        T!['{'] => parent.parent().is_some_and(|it| it.kind() == N![CallArgs]),
+        _ => false,
+    }
+}
+
+/// Switches the quotes of a short string, if it doesn't contain the
+/// preferred quote.
+fn requote(text: &str, style: QuoteStyle) -> String {
+    let (quote, other) = match style {
+        QuoteStyle::Double => ('"', '\''),
+        QuoteStyle::Single => ('\'', '"'),
+        QuoteStyle::Preserve => return text.to_string(),
+    };
+    if !text.starts_with(other) || text.len() < 2 || !text.ends_with(other) {
+        return text.to_string();
+    }
+
+    let mut res = String::with_capacity(text.len());
+    res.push(quote);
+    let mut chars = text[1..text.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                // the other quote doesn't need to be escaped anymore
+                Some(c) if c == other => res.push(c),
+                Some(c) => {
+                    res.push('\\');
+                    res.push(c);
+                }
+                None => res.push('\\'),
+            },
+            c if c == quote => return text.to_string(),
+            c => res.push(c),
+        }
+    }
+    res.push(quote);
+    res
+}
+
+fn width(text: &str) -> u32 {
+    text.chars().count() as u32
+}
+
+fn is_line_comment(token: &SyntaxToken) -> bool {
+    if token.kind() != T![comment] {
+        return false;
+    }
+    let rest = token.text().trim_start_matches("--");
+    !(rest.starts_with('[') && rest[1..].trim_start_matches('=').starts_with('['))
+}
+
+fn is_stmt(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        N![AssignStmt]
+            | N![LocalAssignStmt]
+            | N![LocalFunctionDefStmt]
+            | N![FunctionDefStmt]
+            | N![ForStmt]
+            | N![IfStmt]
+            | N![ReturnStmt]
+            | N![WhileStmt]
+            | N![ExprStmt]
+            | N![BreakStmt]
+            | N![RepeatUntilStmt]
+            | N![LabelStmt]
+            | N![GotoStmt]
+            | N![DoStmt]
+    )
+}
+
+fn is_field(kind: SyntaxKind) -> bool {
+    matches!(kind, N![KeyValue] | N![PositionalValue])
+}
+
+/// The statement that `token` is the first token of.
+fn stmt_of(token: &SyntaxToken) -> Option<SyntaxNode> {
+    token
+        .parent()
+        .ancestors()
+        .take_while(|it| it.text_range().start() == token.text_range().start())
+        .find(|it| is_stmt(it.kind()))
+}
+
+fn starts_stmt(token: &SyntaxToken) -> bool {
+    stmt_of(token).is_some()
+}
+
+fn starts_first_stmt(token: &SyntaxToken) -> bool {
+    stmt_of(token)
+        .is_some_and(|stmt| stmt.siblings(Direction::Prev).skip(1).all(|it| !is_stmt(it.kind())))
+}
+
+/// The table or argument list that a separator separates the items of.
+fn item_list(token: &SyntaxToken) -> Option<SyntaxNode> {
+    let parent = token.parent();
+    match parent.kind() {
+        N![TableSep] => parent.parent(),
+        N![MultivalExpr] => parent.parent().filter(|it| it.kind() == N![ArgList]),
+        _ => None,
+    }
+}
+
+fn is_trailing_separator(token: &SyntaxToken) -> bool {
+    let sep = token.parent();
+    sep.kind() == N![TableSep]
+        && sep.siblings(Direction::Next).skip(1).all(|it| !is_field(it.kind()))
+}
+
+fn is_block_owner(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        N![IfStmt]
+            | N![WhileStmt]
+            | N![ForStmt]
+            | N![DoStmt]
+            | N![RepeatUntilStmt]
+            | N![FunctionExpr]
+            | N![FunctionDefStmt]
+            | N![LocalFunctionDefStmt]
+    )
+}
+
+/// The statement or function that the block around `token` belongs to.
+fn block_owner(token: &SyntaxToken) -> Option<SyntaxNode> {
+    token.parent().ancestors().find(|it| is_block_owner(it.kind()))
+}
+
+/// Whether `token` is the keyword that the line of a block's closing `end` or
+/// `until` lines up with.
+fn block_owner_keyword(token: &SyntaxToken) -> bool {
+    let parent = token.parent();
+    match token.kind() {
+        T![if] | T![while] | T![for] | T![repeat] | T![function] => is_block_owner(parent.kind()),
+        T![do] => parent.kind() == N![DoStmt],
+        _ => false,
+    }
+}
+
+/// The owner of the block that starts after `token`.
+fn block_opener(token: &SyntaxToken) -> Option<SyntaxNode> {
+    let parent = token.parent();
+    match token.kind() {
+        T![then] | T![else] | T![do] | T![repeat] => block_owner(token),
+        T![')'] if parent.kind() == N![ParamList] => parent.parent(),
+        _ => None,
+    }
+}
+
+fn block_closer(token: &SyntaxToken) -> bool {
+    matches!(token.kind(), T![end] | T![else] | T![elseif] | T![until])
+        && block_owner(token).is_some()
+}
+
+/// Whether a block statement or function stays on one line, which it does
+/// when it's written on one line and none of its blocks have more than one
+/// statement.
+fn is_inline(owner: &SyntaxNode) -> bool {
+    !owner.text().contains_char('\n')
+        && owned_blocks(owner)
+            .iter()
+            .all(|it| it.children().filter(|it| is_stmt(it.kind())).count() <= 1)
+}
+
+fn owned_blocks(owner: &SyntaxNode) -> Vec<SyntaxNode> {
+    owner
+        .children()
+        .flat_map(|child| match child.kind() {
+            N![Block] => vec![child],
+            N![ElseIfBranch] | N![ElseBranch] => owned_blocks(&child),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::assert_eq_text;
+
+    use crate::fixture;
+
+    use super::*;
+
+    fn check_with(config: FormatConfig, before: &str, after: &str) {
+        let (analysis, file_id) = fixture::file(before);
+        let edit = analysis.format(file_id, &config).unwrap().expect("file should be formatted");
+        let mut actual = analysis.file_text(file_id).unwrap().to_string();
+        edit.apply(&mut actual);
+        assert_eq_text!(after, &actual);
+
+        // formatting twice doesn't change anything
+        let (analysis, file_id) = fixture::file(&actual);
+        let edit = analysis.format(file_id, &config).unwrap().unwrap();
+        assert!(edit.is_empty(), "formatting isn't idempotent: {:?}", edit);
+    }
+
+    fn check(before: &str, after: &str) {
+        check_with(FormatConfig::default(), before, after)
+    }
+
+    #[test]
+    fn format_indentation() {
+        check(
+            r#"
+local function f(a,b)
+if a then
+return b
+  elseif b then
+        while true do
+  print( a )
+      end
+else
+ repeat a = a - 1 until a<0
+end
+end
+"#,
+            r#"local function f(a, b)
+    if a then
+        return b
+    elseif b then
+        while true do
+            print(a)
+        end
+    else
+        repeat a = a - 1 until a < 0
+    end
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn format_spacing() {
+        check(
+            r#"
+local x<const> ,y=-a.b:c( 1 , 2 )..'s' , # t
+t [ 1 ]= not x  and y or- -z
+::top:: goto top
+print "hi" print{ }
+local s = t[ [[x]] ]
+"#,
+            r#"local x <const>, y = -a.b:c(1, 2) .. "s", #t
+t[1] = not x and y or - -z
+::top::
+goto top
+print "hi"
+print{}
+local s = t[ [[x]] ]
+"#,
+        );
+    }
+
+    #[test]
+    fn format_keeps_comments() {
+        check(
+            r#"
+-- header
+
+local t = { -- trailing
+    a = 1, --[[ inline ]] b = 2,
+  -- on its own line
+}
+if t then -- why
+    -- body
+  print(t)
+    -- before end
+end
+"#,
+            r#"-- header
+
+local t = { -- trailing
+    a = 1, --[[ inline ]]
+    b = 2,
+    -- on its own line
+}
+if t then -- why
+    -- body
+    print(t)
+    -- before end
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn format_blank_lines() {
+        check(
+            r#"
+
+
+local a = 1
+
+
+
+local b = 2
+function f()
+
+    return a
+
+end
+"#,
+            r#"local a = 1
+
+local b = 2
+function f()
+    return a
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn format_inline_functions() {
+        check(
+            r#"
+local t = { value = function(x) return x * 2 end }
+call(function() a() b() end)
+foo(function()
+return 1
+end, 2)
+"#,
+            r#"local t = { value = function(x) return x * 2 end }
+call(function()
+    a()
+    b()
+end)
+foo(function()
+    return 1
+end, 2)
+"#,
+        );
+    }
+
+    #[test]
+    fn format_table_layout() {
+        check(
+            r#"
+local a = { 1, 2; 3, }
+local b = {
+  x = 1; y = { 2,
+  3 } }
+local c = {}
+"#,
+            r#"local a = { 1, 2, 3 }
+local b = {
+    x = 1,
+    y = {
+        2,
+        3,
+    },
+}
+local c = {}
+"#,
+        );
+    }
+
+    #[test]
+    fn format_table_separators() {
+        let config = FormatConfig {
+            table_separator: TableSeparator::Semicolon,
+            trailing_separator: TrailingSeparator::Always,
+            ..FormatConfig::default()
+        };
+        check_with(config, "local t = { 1, 2 }\n", "local t = { 1; 2; }\n");
+
+        let config = FormatConfig {
+            trailing_separator: TrailingSeparator::Never,
+            ..FormatConfig::default()
+        };
+        check_with(config, "local t = {\n1,\n2,\n}\n", "local t = {\n    1,\n    2\n}\n");
+    }
+
+    #[test]
+    fn format_long_lines() {
+        let config = FormatConfig { max_width: 30, ..FormatConfig::default() };
+        check_with(
+            config,
+            r#"
+local t = { first = 1, second = 2, third = 3 }
+print(first_argument, second_argument)
+print(a, b)
+"#,
+            r#"local t = {
+    first = 1,
+    second = 2,
+    third = 3,
+}
+print(
+    first_argument,
+    second_argument
+)
+print(a, b)
+"#,
+        );
+    }
+
+    #[test]
+    fn format_long_expressions() {
+        let config = FormatConfig { max_width: 30, ..FormatConfig::default() };
+        check_with(
+            config,
+            r#"
+local s = "first part" .. name .. "last part"
+local n = first_value * 2 + second_value * 3
+local b = a + b * c
+if some_condition and (x or y) or other then
+f()
+end
+"#,
+            r#"local s = "first part" ..
+    name ..
+    "last part"
+local n = first_value * 2 +
+    second_value * 3
+local b = a + b * c
+if some_condition and (x or y) or
+    other then
+    f()
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn format_continuation_lines() {
+        check(
+            r#"
+local x = a +
+b
+foo(a,
+b)
+local y = obj
+:method()
+"#,
+            r#"local x = a +
+    b
+foo(a,
+    b)
+local y = obj
+    :method()
+"#,
+        );
+    }
+
+    #[test]
+    fn format_quotes() {
+        check(
+            r#"
+local a, b, c, d = 'x', 'say "hi"', 'it\'s', [['raw']]
+"#,
+            r#"local a, b, c, d = "x", 'say "hi"', "it's", [['raw']]
+"#,
+        );
+
+        let config = FormatConfig { quote_style: QuoteStyle::Single, ..FormatConfig::default() };
+        check_with(config, "local a = \"x\"\n", "local a = 'x'\n");
+    }
+
+    #[test]
+    fn format_with_tabs() {
+        let config = FormatConfig { use_tabs: true, ..FormatConfig::default() };
+        check_with(config, "do\nx()\nend\n", "do\n\tx()\nend\n");
+    }
+
+    #[test]
+    fn format_skips_syntax_errors() {
+        let (analysis, file_id) = fixture::file("local = 1\n");
+        assert!(analysis.format(file_id, &FormatConfig::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn format_range_only_edits_range() {
+        let (analysis, frange) = fixture::range(
+            r#"
+local a   =   1
+$0local b   =   2$0
+local c   =   3
+"#,
+        );
+        let edit = analysis.format_range(frange, &FormatConfig::default()).unwrap().unwrap();
+        let mut actual = analysis.file_text(frange.file_id).unwrap().to_string();
+        edit.apply(&mut actual);
+        assert_eq_text!("local a   =   1\nlocal b = 2\nlocal c   =   3\n", &actual);
+    }
+}
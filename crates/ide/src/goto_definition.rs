@@ -0,0 +1,255 @@
+use hir::Semantics;
+use ide_db::{base_db::FilePosition, defs, RootDatabase};
+use syntax::{ast::AstNode, T};
+
+use crate::NavigationTarget;
+
+// Feature: Go to Definition
+//
+// Navigates to the declaration of the identifier under the cursor. Globals
+// and table fields navigate to every place they are assigned to in the
+// workspace.
+pub(crate) fn goto_definition(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let token = file.syntax().token_at_offset(position.offset).find(|it| it.kind() == T![ident])?;
+    let def = defs::classify(&sema, position.file_id, &token)?;
+    Some(NavigationTarget::from_definition(&sema, &def))
+}
+
+#[cfg(test)]
+mod tests {
+    use ide_db::base_db::FileRange;
+
+    use crate::fixture;
+
+    /// Checks that going to the definition of the `$0` position leads to the
+    /// ranges marked with `^^^` (the focus range) in the fixture.
+    fn check(ra_fixture: &str) {
+        let (analysis, position, annotations) = fixture::annotations(ra_fixture);
+        let expected: Vec<FileRange> = annotations.into_iter().map(|(range, _)| range).collect();
+        let navs = analysis.goto_definition(position).unwrap().expect("no definition found");
+        let mut actual: Vec<FileRange> = navs
+            .into_iter()
+            .map(|nav| FileRange { file_id: nav.file_id, range: nav.focus_range })
+            .collect();
+        actual.sort_by_key(|it| (it.file_id, it.range.start()));
+        assert_eq!(actual, expected);
+    }
+
+    fn check_unresolved(ra_fixture: &str) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let navs = analysis.goto_definition(position).unwrap().unwrap_or_default();
+        assert!(navs.is_empty(), "unexpected definition: {:?}", navs);
+    }
+
+    #[test]
+    fn goto_local() {
+        check(
+            r#"
+local a = 1
+    --^
+print(a$0)
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_shadowing_local() {
+        check(
+            r#"
+local a = 1
+local a = a
+    --^
+print(a$0)
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_param() {
+        check(
+            r#"
+local function f(x, y)
+                  --^
+    print(x + y$0)
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_upvalue() {
+        check(
+            r#"
+local count = 0
+    --^^^^^
+local function inc()
+    count$0 = count + 1
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_local_function() {
+        check(
+            r#"
+local function helper() end
+             --^^^^^^
+helper$0()
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_recursive_local_function() {
+        check(
+            r#"
+local function fact(n)
+             --^^^^
+    print(n * fact$0(n - 1))
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_for_variable() {
+        check(
+            r#"
+for i = 1, 10 do
+  --^
+    print(i$0)
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_method() {
+        check(
+            r#"
+local M = {}
+function M.new() end
+function M:greet() end
+         --^^^^^
+M:greet$0()
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_nested_field() {
+        check(
+            r#"
+local a = {}
+a.b = {}
+function a.b.c() end
+           --^
+a.b.c$0()
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_field_path_prefix() {
+        check(
+            r#"
+local a = {}
+a.b = {}
+--^
+function a.b$0.c() end
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_table_constructor_field() {
+        check(
+            r#"
+local config = { debug = false }
+               --^^^^^
+config.debug = true
+     --^^^^^
+print(config.debug$0)
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_label() {
+        check(
+            r#"
+while true do
+    ::continue::
+    --^^^^^^^^
+    goto continue$0
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_label_does_not_cross_functions() {
+        check_unresolved(
+            r#"
+::top::
+local function f()
+    goto top$0
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_global_in_other_file() {
+        check(
+            r#"
+//- /main.lua
+config$0.debug = true
+//- /config.lua
+  config = {}
+--^^^^^^
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_global_function_in_other_files() {
+        check(
+            r#"
+//- /main.lua
+greet$0()
+//- /a.lua
+function greet() end
+       --^^^^^
+//- /b.lua
+  greet = nil
+--^^^^^
+"#,
+        );
+    }
+
+    #[test]
+    fn local_does_not_leak_into_other_files() {
+        check(
+            r#"
+//- /main.lua
+local helper = 1
+    --^^^^^^
+print(helper$0)
+//- /other.lua
+local helper = 2
+"#,
+        );
+    }
+
+    #[test]
+    fn no_definition_for_keyword() {
+        check_unresolved("lo$0cal a = 1");
+    }
+}
@@ -0,0 +1,119 @@
+use hir::Semantics;
+use ide_db::{base_db::FilePosition, search::ReferenceAccess, RootDatabase};
+use syntax::TextRange;
+
+use crate::references::find_def;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightedRange {
+    pub range: TextRange,
+    pub access: ReferenceAccess,
+}
+
+// Feature: Highlight Related
+//
+// Highlights every read and write of the identifier under the cursor in the
+// current file.
+pub(crate) fn highlight_related(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<HighlightedRange>> {
+    let sema = Semantics::new(db);
+    let def = find_def(&sema, position)?;
+    let res = def
+        .find_usages(&sema)
+        .into_iter()
+        .filter(|it| it.file_id == position.file_id)
+        .map(|it| HighlightedRange { range: it.range, access: it.access })
+        .collect();
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use ide_db::search::ReferenceAccess;
+
+    use crate::fixture;
+
+    /// Checks the highlights against the `--^^^ read` and `--^^^ write`
+    /// annotations of the fixture.
+    fn check(ra_fixture: &str) {
+        let (analysis, position, annotations) = fixture::annotations(ra_fixture);
+        let highlights = analysis.highlight_related(position).unwrap().unwrap_or_default();
+        let actual: Vec<_> = highlights
+            .into_iter()
+            .map(|it| {
+                let access = match it.access {
+                    ReferenceAccess::Read => "read",
+                    ReferenceAccess::Write => "write",
+                };
+                (it.range, access.to_string())
+            })
+            .collect();
+        let expected: Vec<_> =
+            annotations.into_iter().map(|(range, access)| (range.range, access)).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn highlight_local() {
+        check(
+            r#"
+local total = 0
+    --^^^^^ write
+for i = 1, 10 do
+    total = total + i
+  --^^^^^ write
+          --^^^^^ read
+end
+print(total$0)
+    --^^^^^ read
+"#,
+        );
+    }
+
+    #[test]
+    fn highlight_field() {
+        check(
+            r#"
+local t = {}
+t.size = 0
+--^^^^ write
+function t.grow()
+    t.size$0 = t.size + 1
+    --^^^^ write
+             --^^^^ read
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn highlight_label() {
+        check(
+            r#"
+while true do
+    ::retry::
+    --^^^^^ write
+    goto retry$0
+       --^^^^^ read
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn highlights_in_current_file_only() {
+        let (analysis, position) = fixture::position(
+            r#"
+//- /main.lua
+value$0 = 1
+//- /other.lua
+value = 2
+"#,
+        );
+        let highlights = analysis.highlight_related(position).unwrap().unwrap();
+        assert_eq!(highlights.len(), 1);
+        assert_eq!(highlights[0].access, ReferenceAccess::Write);
+    }
+}
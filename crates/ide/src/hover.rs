@@ -0,0 +1,504 @@
+use hir::{AccessPath, InFile, Local, PathRoot, Semantics};
+use ide_db::{
+    base_db::FilePosition,
+    defs::{self, Definition},
+    docs::doc_comment,
+    search::ReferenceAccess,
+    types::{def_types, InferredType},
+    RootDatabase,
+};
+use syntax::{
+    ast::{self, AstNode},
+    doc::{self, DocAstNode, DocTag},
+    match_ast, NodeOrToken, SyntaxNode, TextRange, T,
+};
+
+/// The markdown shown when hovering a name, and the range it applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoverResult {
+    pub range: TextRange,
+    pub markup: String,
+}
+
+// Feature: Hover
+//
+// Shows the declaration of the name under the cursor, the comments directly
+// above it and its type, either declared by a `---@type` or `---@param`
+// annotation or inferred from the values assigned to it.
+pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<HoverResult> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let token = file.syntax().token_at_offset(position.offset).find(|it| it.kind() == T![ident])?;
+    let def = defs::classify(&sema, position.file_id, &token)?;
+
+    let (declaration, docs) = match &def {
+        Definition::Local(local) => hover_local(db, &sema, &def, local),
+        Definition::Label(label) => {
+            let src = label.source(db)?;
+            (format!("::{}::", label.name(db)), doc_comment(src.value.syntax()))
+        }
+        Definition::Path(path) => hover_path(db, &sema, &def, path),
+    };
+
+    let mut markup = format!("```lua\n{}\n```", declaration);
+    if let Some(docs) = docs {
+        markup.push_str("\n\n---\n\n");
+        markup.push_str(&docs);
+    }
+    Some(HoverResult { range: token.text_range(), markup })
+}
+
+fn hover_local(
+    db: &RootDatabase,
+    sema: &Semantics,
+    def: &Definition,
+    local: &Local,
+) -> (String, Option<String>) {
+    let name = local.name(sema.db);
+    let src = match local.source(sema.db) {
+        Some(it) => it,
+        // the implicit `self` of a method
+        None => return (format!("(parameter) {}", name), None),
+    };
+    let decl = src.value.syntax().ancestors().find(|it| {
+        ast::ParamList::can_cast(it.kind())
+            || ast::ForStmt::can_cast(it.kind())
+            || ast::LocalFunctionDefStmt::can_cast(it.kind())
+            || ast::LocalAssignStmt::can_cast(it.kind())
+    });
+    let decl = match decl {
+        Some(it) => it,
+        None => return (format!("local {}", name), None),
+    };
+    let mut param_docs = None;
+    let declaration = match_ast! {
+        match decl {
+            ast::ParamList(it) => {
+                let param = param_annotation(&it, name.as_str());
+                let ty = param.as_ref().and_then(|param| {
+                    let ty = param.ty()?;
+                    Some(match ty {
+                        _ if !param.is_optional() => ty.syntax().to_string(),
+                        doc::DocType::Union(_) => format!("({})?", ty.syntax()),
+                        _ => format!("{}?", ty.syntax()),
+                    })
+                });
+                param_docs = param.and_then(|it| it.description()).map(|it| it.text());
+                with_type(format!("(parameter) {}", name), ty)
+            },
+            ast::ForStmt(it) => match it.content() {
+                Some(ast::ForContent::NumericFor(_)) => format!("(for variable) {}: number", name),
+                _ => format!("(for variable) {}", name),
+            },
+            ast::LocalFunctionDefStmt(it) => {
+                let ast_id = sema.db.ast_id_map(src.file_id).ast_id(&it);
+                let item_tree = sema.db.file_item_tree(src.file_id);
+                let func = item_tree.local_functions().find(|func| func.ast_id == ast_id);
+                match func {
+                    Some(func) => format!("local function {}{}", func.name, func.params),
+                    None => format!("local function {}", name),
+                }
+            },
+            ast::LocalAssignStmt(it) => {
+                let idx = it
+                    .multi_name()
+                    .and_then(|names| names.names().position(|it| it == src.value));
+                let ty = idx.and_then(|idx| declared_type(it.syntax(), idx));
+                with_type(format!("local {}", name), ty.or_else(|| value_type(db, def)))
+            },
+            _ => with_type(format!("local {}", name), value_type(db, def)),
+        }
+    };
+    let docs = if ast::ParamList::can_cast(decl.kind()) { param_docs } else { doc_comment(&decl) };
+    (declaration, docs)
+}
+
+/// The `---@param` annotation of the parameter `name` of the function with
+/// these parameters.
+fn param_annotation(params: &ast::ParamList, name: &str) -> Option<doc::ParamTag> {
+    let stmt = params.syntax().ancestors().find(|it| ast::Stmt::can_cast(it.kind()))?;
+    doc::doc_block(&stmt)?.tree().tags().find_map(|tag| match tag {
+        DocTag::Param(it) if it.name_token()?.text() == name => Some(it),
+        _ => None,
+    })
+}
+
+/// The type declared by `---@type` for the `idx`th name of a statement.
+fn declared_type(stmt: &SyntaxNode, idx: usize) -> Option<String> {
+    let tags = doc::doc_block(stmt)?.tree().tags();
+    let mut types = tags.filter_map(|tag| match tag {
+        DocTag::Type(it) => Some(it.types()),
+        _ => None,
+    });
+    Some(types.next()?.nth(idx)?.syntax().to_string())
+}
+
+fn hover_path(
+    db: &RootDatabase,
+    sema: &Semantics,
+    def: &Definition,
+    path: &AccessPath,
+) -> (String, Option<String>) {
+    // the statements or table fields that assign to the path
+    let writes: Vec<InFile<SyntaxNode>> = def
+        .find_usages(sema)
+        .into_iter()
+        .filter(|it| it.access == ReferenceAccess::Write)
+        .filter_map(|it| {
+            let root = sema.parse(it.file_id).syntax().clone();
+            let node = match root.covering_element(it.range) {
+                NodeOrToken::Node(it) => it,
+                NodeOrToken::Token(it) => it.parent(),
+            };
+            let decl = node
+                .ancestors()
+                .find(|it| ast::Stmt::can_cast(it.kind()) || ast::KeyValue::can_cast(it.kind()))?;
+            Some(InFile::new(it.file_id, decl))
+        })
+        .collect();
+
+    let function = writes.iter().find_map(|src| {
+        let stmt = ast::FunctionDefStmt::cast(src.value.clone())?;
+        let ast_id = sema.db.ast_id_map(src.file_id).ast_id(&stmt);
+        let item_tree = sema.db.file_item_tree(src.file_id);
+        let func = item_tree.functions().find(|func| func.ast_id == ast_id)?;
+        let mut name = String::new();
+        for segment in func.path.segments() {
+            name.push_str(segment.as_str());
+            name.push('.');
+        }
+        if func.is_method {
+            name.pop();
+            name.push(':');
+        }
+        Some(format!("function {}{}{}", name, func.name, func.params))
+    });
+    let declaration = function.unwrap_or_else(|| {
+        let kind = if path.fields.is_empty() { "global" } else { "field" };
+        with_type(format!("({}) {}", kind, path_to_string(sema, path)), value_type(db, def))
+    });
+    let docs = writes.iter().find_map(|src| doc_comment(&src.value));
+    (declaration, docs)
+}
+
+fn with_type(declaration: String, ty: Option<String>) -> String {
+    match ty {
+        Some(ty) => format!("{}: {}", declaration, ty),
+        None => declaration,
+    }
+}
+
+fn path_to_string(sema: &Semantics, path: &AccessPath) -> String {
+    let mut res = match &path.root {
+        PathRoot::Local(local) => local.name(sema.db).to_string(),
+        PathRoot::Global(name) => name.to_string(),
+    };
+    for field in &path.fields {
+        res.push('.');
+        res.push_str(field.as_str());
+    }
+    res
+}
+
+/// The inferred type of the definition, such as `number | nil`, joined
+/// across the files that assign it.
+fn value_type(db: &RootDatabase, def: &Definition) -> Option<String> {
+    let mut types: Vec<String> = Vec::new();
+    for InferredType { infer, ty } in def_types(db, def) {
+        let ty = ty.display(&infer).to_string();
+        if !types.contains(&ty) {
+            types.push(ty);
+        }
+    }
+    if types.is_empty() {
+        None
+    } else {
+        Some(types.join(" | "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture;
+
+    fn check(ra_fixture: &str, expect: &str) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let hover = analysis.hover(position).unwrap().expect("no hover");
+        assert_eq!(hover.markup.trim(), expect.trim());
+    }
+
+    fn check_none(ra_fixture: &str) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        assert_eq!(analysis.hover(position).unwrap(), None);
+    }
+
+    #[test]
+    fn hover_local_with_type_and_docs() {
+        check(
+            r#"
+-- The number of items seen so far.
+local count = 0
+count = count + 1
+print(count$0)
+"#,
+            r#"
+```lua
+local count: integer
+```
+
+---
+
+The number of items seen so far.
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_local_function_signature() {
+        check(
+            r#"
+--- Adds things up.
+--- Returns the sum.
+local function sum(a, b, ...)
+end
+sum$0(1, 2)
+"#,
+            r#"
+```lua
+local function sum(a, b, ...)
+```
+
+---
+
+Adds things up.
+Returns the sum.
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_nested_local_function() {
+        check(
+            r#"
+local function outer()
+    local function inner()
+    end
+    inner$0()
+end
+"#,
+            r#"
+```lua
+local function inner()
+```
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_parameter() {
+        check(
+            r#"
+local function f(first, second)
+    print(second$0)
+end
+"#,
+            r#"
+```lua
+(parameter) second
+```
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_declared_types() {
+        check(
+            r#"
+---Scales a value.
+---@param factor? number # how much to scale by
+local function scale(value, factor)
+    return value * factor$0
+end
+"#,
+            r#"
+```lua
+(parameter) factor: number?
+```
+
+---
+
+how much to scale by
+"#,
+        );
+        check(
+            r#"
+---The names seen so far.
+---@type string[], integer
+local seen, count = {}, 0
+print(count$0)
+"#,
+            r#"
+```lua
+local count: integer
+```
+
+---
+
+The names seen so far.
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_method_definition() {
+        check(
+            r#"
+local M = {}
+--[[ Resets the counter. ]]
+function M.counter:reset(to)
+end
+M.counter:reset$0(0)
+"#,
+            r#"
+```lua
+function M.counter:reset(to)
+```
+
+---
+
+Resets the counter.
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_global_with_mixed_types() {
+        check(
+            r#"
+//- /main.lua
+-- Whether debugging is enabled.
+DEBUG = false
+print(DEBUG$0)
+//- /other.lua
+DEBUG = nil
+"#,
+            r#"
+```lua
+(global) DEBUG: boolean | nil
+```
+
+---
+
+Whether debugging is enabled.
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_table_field() {
+        check(
+            r#"
+local config = {
+    -- In seconds.
+    timeout = 30,
+}
+print(config.timeout$0)
+"#,
+            r#"
+```lua
+(field) config.timeout: integer
+```
+
+---
+
+In seconds.
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_inferred_types() {
+        check(
+            r#"
+local function f(a)
+    local n$0 = a + 1
+end
+"#,
+            r#"
+```lua
+local n: number
+```
+"#,
+        );
+        check(
+            r#"
+local function new() return { alpha = 1 } end
+local obj = new()
+print(obj$0)
+"#,
+            r#"
+```lua
+local obj: { alpha: integer }
+```
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_for_variable() {
+        check(
+            r#"
+for i = 1, 10 do
+    print(i$0)
+end
+"#,
+            r#"
+```lua
+(for variable) i: number
+```
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_skips_comment_before_blank_line() {
+        check(
+            r#"
+-- Section header.
+
+local x = "x"
+print(x$0)
+"#,
+            r#"
+```lua
+local x: string
+```
+"#,
+        );
+    }
+
+    #[test]
+    fn hover_skips_comment_at_end_of_previous_line() {
+        check(
+            r#"
+local a = 1 -- note about a
+local b = "b"
+print(b$0)
+"#,
+            r#"
+```lua
+local b: string
+```
+"#,
+        );
+    }
+
+    #[test]
+    fn no_hover_on_keyword() {
+        check_none(
+            r#"
+loc$0al x = 1
+"#,
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use hir::{InFile, Semantics, WithBodyId};
+use hir_ty::{HirTyDatabase, Ty};
+use ide_db::{base_db::FileId, RootDatabase};
+use syntax::{
+    ast::{self, AstNode, LiteralKind},
+    doc::{self, DocTag},
+    match_ast, SyntaxNode, TextRange,
+};
+
+use crate::signature_help::called_function;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlayHintsConfig {
+    pub parameter_hints: bool,
+    pub type_hints: bool,
+    pub return_index_hints: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InlayKind {
+    /// The name of the parameter a literal argument is passed to, shown
+    /// before the argument.
+    ParameterHint,
+    /// The inferred type of a local, shown after its name.
+    TypeHint,
+    /// The position in the values returned by a call of the name it is
+    /// assigned to, shown before the name.
+    ReturnIndexHint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlayHint {
+    /// The range of the argument or name the hint is about.
+    pub range: TextRange,
+    pub kind: InlayKind,
+    pub label: String,
+}
+
+// Feature: Inlay Hints
+//
+// Shows the parameter names of literal arguments, the inferred types of
+// locals whose values don't make them obvious, and which of the values
+// returned by a call each name of a multiple assignment gets.
+pub(crate) fn inlay_hints(
+    db: &RootDatabase,
+    config: &InlayHintsConfig,
+    file_id: FileId,
+    range: TextRange,
+) -> Vec<InlayHint> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(file_id);
+    let mut res = Vec::new();
+    for node in file.syntax().descendants() {
+        if node.text_range().intersect(range).is_none() {
+            continue;
+        }
+        match_ast! {
+            match node {
+                ast::CallExpr(it) => {
+                    if config.parameter_hints {
+                        parameter_hints(&sema, file_id, &it.into(), &mut res);
+                    }
+                },
+                ast::MethodCallExpr(it) => {
+                    if config.parameter_hints {
+                        parameter_hints(&sema, file_id, &it.into(), &mut res);
+                    }
+                },
+                ast::LocalAssignStmt(it) => {
+                    let names: Vec<_> = it.multi_name().into_iter().flat_map(|it| it.names()).collect();
+                    let exprs: Vec<_> = it.multival_expr().into_iter().flat_map(|it| it.exprs()).collect();
+                    if config.type_hints {
+                        type_hints(db, &sema, file_id, &it, &names, &exprs, &mut res);
+                    }
+                    if config.return_index_hints {
+                        let ranges = names.iter().map(|it| it.syntax().text_range());
+                        return_index_hints(ranges.collect(), &exprs, &mut res);
+                    }
+                },
+                ast::AssignStmt(it) => {
+                    if config.return_index_hints {
+                        let targets = it.lhs().into_iter().flat_map(|it| it.exprs());
+                        let exprs: Vec<_> = it.rhs().into_iter().flat_map(|it| it.exprs()).collect();
+                        let ranges = targets.map(|it| it.syntax().text_range());
+                        return_index_hints(ranges.collect(), &exprs, &mut res);
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+    res.retain(|it| it.range.intersect(range).is_some());
+    res.sort_by_key(|it| it.range.start());
+    res
+}
+
+/// The names of the parameters that literal arguments of a call are passed
+/// to.
+fn parameter_hints(sema: &Semantics, file_id: FileId, call: &ast::Expr, acc: &mut Vec<InlayHint>) {
+    let call_args = match call {
+        ast::Expr::CallExpr(it) => it.call_args(),
+        ast::Expr::MethodCallExpr(it) => it.call_args(),
+        _ => None,
+    };
+    let args = match call_args.and_then(|it| it.arg_list()).and_then(|it| it.multival_expr()) {
+        Some(it) => it,
+        None => return,
+    };
+    // resolving the callee is costly, and without literals there is no hint
+    if !args.exprs().any(|arg| is_literal(&arg)) {
+        return;
+    }
+    let func = match called_function(sema, file_id, call) {
+        Some(it) => it,
+        None => return,
+    };
+    for (arg, param) in args.exprs().zip(&func.params) {
+        // the remaining arguments are all passed to the varargs
+        if param.name == "..." {
+            break;
+        }
+        if is_literal(&arg) {
+            acc.push(InlayHint {
+                range: arg.syntax().text_range(),
+                kind: InlayKind::ParameterHint,
+                label: param.name.clone(),
+            });
+        }
+    }
+}
+
+fn is_literal(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Literal(it) => !matches!(it.kind(), LiteralKind::Vararg),
+        _ => false,
+    }
+}
+
+/// The inferred types of the names of a `local` statement, unless their
+/// values or a `---@type` annotation already spell them out.
+fn type_hints(
+    db: &RootDatabase,
+    sema: &Semantics,
+    file_id: FileId,
+    stmt: &ast::LocalAssignStmt,
+    names: &[ast::Name],
+    exprs: &[ast::Expr],
+    acc: &mut Vec<InlayHint>,
+) {
+    if has_type_annotation(stmt.syntax()) {
+        return;
+    }
+    let infer = db.infer(WithBodyId::ModuleId(file_id));
+    let (_, source_map) = sema.file_body(file_id);
+    for (idx, name) in names.iter().enumerate() {
+        // the names without a value of their own get the extra values
+        // returned by the last call
+        let value = match exprs.get(idx) {
+            Some(it) => it,
+            None => match exprs.last() {
+                Some(it @ (ast::Expr::CallExpr(_) | ast::Expr::MethodCallExpr(_))) => it,
+                _ => continue,
+            },
+        };
+        let is_obvious = matches!(
+            value,
+            ast::Expr::Literal(_) | ast::Expr::TableExpr(_) | ast::Expr::FunctionExpr(_)
+        );
+        if is_obvious {
+            continue;
+        }
+        let name_id = match source_map.node_name(InFile::new(file_id, name)) {
+            Some(it) => it,
+            None => continue,
+        };
+        let ty = infer.type_of_name(name_id);
+        if *ty == Ty::Unknown {
+            continue;
+        }
+        acc.push(InlayHint {
+            range: name.syntax().text_range(),
+            kind: InlayKind::TypeHint,
+            label: ty.display(&infer).to_string(),
+        });
+    }
+}
+
+fn has_type_annotation(stmt: &SyntaxNode) -> bool {
+    doc::doc_block(stmt)
+        .is_some_and(|block| block.tree().tags().any(|it| matches!(it, DocTag::Type(_))))
+}
+
+/// The positions in the values returned by the last expression of the
+/// targets that get them, when there are at least two.
+fn return_index_hints(targets: Vec<TextRange>, exprs: &[ast::Expr], acc: &mut Vec<InlayHint>) {
+    match exprs.last() {
+        Some(ast::Expr::CallExpr(_) | ast::Expr::MethodCallExpr(_)) => (),
+        _ => return,
+    }
+    let first = exprs.len() - 1;
+    if targets.len() < first + 2 {
+        return;
+    }
+    for (idx, range) in targets[first..].iter().enumerate() {
+        acc.push(InlayHint {
+            range: *range,
+            kind: InlayKind::ReturnIndexHint,
+            label: format!("#{}", idx + 1),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::assert_eq_text;
+
+    use crate::{fixture, InlayHintsConfig};
+
+    const ALL: InlayHintsConfig =
+        InlayHintsConfig { parameter_hints: true, type_hints: true, return_index_hints: true };
+
+    /// Checks the hints of the file, one per line with the text they are
+    /// about.
+    fn check_with_config(config: InlayHintsConfig, ra_fixture: &str, expect: &str) {
+        let (analysis, file_id) = fixture::file(ra_fixture);
+        let text = analysis.file_text(file_id).unwrap();
+        let range = syntax::TextRange::up_to(syntax::TextSize::of(text.as_str()));
+        let hints = analysis.inlay_hints(&config, file_id, range).unwrap();
+        let actual: String = hints
+            .iter()
+            .map(|it| format!("{} {:?} {}\n", &text[it.range], it.kind, it.label))
+            .collect();
+        assert_eq_text!(expect.trim_start(), &actual);
+    }
+
+    fn check(ra_fixture: &str, expect: &str) {
+        check_with_config(ALL, ra_fixture, expect);
+    }
+
+    #[test]
+    fn parameter_hints_for_literals() {
+        check(
+            r#"
+local function f(a, b, c) end
+local x = 1
+f(x, "s", nil)
+f(1, x + 1)
+"#,
+            r#"
+"s" ParameterHint b
+nil ParameterHint c
+1 ParameterHint a
+"#,
+        );
+    }
+
+    #[test]
+    fn parameter_hints_for_methods_and_varargs() {
+        check(
+            r#"
+local Point = {}
+function Point:move(dx, dy) end
+Point:move(1, 2)
+Point.move(Point, 1)
+local function log(fmt, ...) end
+log("%s", 1, 2)
+"#,
+            r#"
+1 ParameterHint dx
+2 ParameterHint dy
+1 ParameterHint dx
+"%s" ParameterHint fmt
+"#,
+        );
+    }
+
+    #[test]
+    fn type_hints_when_not_obvious() {
+        check(
+            r#"
+local n = 1
+local t = {}
+local m = n + 1
+---@type number
+local k = n
+local function f() return "s" end
+local s = f()
+local u = unknown
+"#,
+            r#"
+m TypeHint integer
+s TypeHint string
+"#,
+        );
+    }
+
+    #[test]
+    fn return_index_hints() {
+        check(
+            r#"
+---@return integer
+---@return string
+local function f() end
+local a, b = f()
+local c, d, e = 1, f()
+local g = f()
+local h, i = 1, 2
+h, i = f()
+"#,
+            r#"
+a TypeHint integer
+a ReturnIndexHint #1
+b TypeHint string
+b ReturnIndexHint #2
+d TypeHint integer
+d ReturnIndexHint #1
+e TypeHint string
+e ReturnIndexHint #2
+g TypeHint integer
+h ReturnIndexHint #1
+i ReturnIndexHint #2
+"#,
+        );
+    }
+
+    #[test]
+    fn disabled_hints() {
+        check_with_config(
+            InlayHintsConfig {
+                parameter_hints: false,
+                type_hints: false,
+                return_index_hints: true,
+            },
+            r#"
+local function f(a) return a, a end
+local x, y = f(1)
+"#,
+            r#"
+x ReturnIndexHint #1
+y ReturnIndexHint #2
+"#,
+        );
+    }
+
+    #[test]
+    fn hints_in_range() {
+        let (analysis, frange) = fixture::range(
+            r#"
+local function f(a) end
+f(1)
+$0f(2)$0
+f(3)
+"#,
+        );
+        let hints = analysis.inlay_hints(&ALL, frange.file_id, frange.range).unwrap();
+        let ranges: Vec<_> = hints.iter().map(|it| it.range).collect();
+        let arg = frange.range.start() + syntax::TextSize::from(2);
+        assert_eq!(ranges, vec![syntax::TextRange::at(arg, syntax::TextSize::from(1))]);
+    }
+}
@@ -1,19 +1,49 @@
+mod completion;
 mod diagnostics;
+mod file_structure;
+#[cfg(test)]
+mod fixture;
+mod format;
+mod goto_definition;
+mod highlight_related;
+mod hover;
+mod inlay_hints;
+mod navigation_target;
+mod references;
+mod rename;
+mod signature_help;
+mod syntax_highlighting;
 
 use std::sync::Arc;
 
-pub use diagnostics::Severity;
+pub use completion::{CompletionItem, CompletionItemKind};
+pub use diagnostics::{DiagnosticCode, DiagnosticsConfig, Severity};
+pub use file_structure::StructureNode;
+pub use format::{FormatConfig, QuoteStyle, TableSeparator, TrailingSeparator};
+pub use highlight_related::HighlightedRange;
+pub use hover::HoverResult;
 pub use ide_db::{
-    base_db::{Change, FileId},
+    base_db::{Change, FileId, FilePosition, FileRange},
     line_index::{LineCol, LineColUtf16, LineIndex},
+    search::{FileReference, ReferenceAccess},
+    source_change::SourceChange,
+    symbol_index::{FileSymbol, Query, SymbolKind},
     LineIndexDatabase,
 };
+pub use inlay_hints::{InlayHint, InlayHintsConfig, InlayKind};
+pub use navigation_target::NavigationTarget;
+pub use references::ReferenceSearchResult;
+pub use rename::{RenameError, RenameResult};
+pub use signature_help::{SignatureHelp, SignatureParam};
+pub use syntax_highlighting::{Highlight, HlMod, HlMods, HlRange, HlTag};
+pub use text_edit::{Indel, TextEdit};
 
 use ide_db::{
-    base_db::{salsa, Canceled, CheckCanceled},
-    RootDatabase,
+    base_db::{salsa, Canceled, CheckCanceled, SourceDatabase},
+    symbol_index, RootDatabase,
 };
 use salsa::{ParallelDatabase, Snapshot};
+use syntax::TextRange;
 
 use diagnostics::Diagnostic;
 
@@ -54,11 +84,115 @@ impl Analysis {
         self.db.catch_canceled(f)
     }
 
+    /// Returns the text of a file.
+    pub fn file_text(&self, file_id: FileId) -> Cancelable<Arc<String>> {
+        self.with_db(|db| db.file_text(file_id))
+    }
+
     pub fn file_line_index(&self, file_id: FileId) -> Cancelable<Arc<LineIndex>> {
         self.with_db(|db| db.line_index(file_id))
     }
 
-    pub fn diagnostics(&self, file_id: FileId) -> Cancelable<Vec<Diagnostic>> {
-        self.with_db(|db| diagnostics::diagnostics(db, file_id))
+    pub fn diagnostics(
+        &self,
+        config: &DiagnosticsConfig,
+        file_id: FileId,
+    ) -> Cancelable<Vec<Diagnostic>> {
+        self.with_db(|db| diagnostics::diagnostics(db, config, file_id))
+    }
+
+    /// Returns the functions and locals of the file, nested in the functions
+    /// they are declared in.
+    pub fn file_structure(&self, file_id: FileId) -> Cancelable<Vec<StructureNode>> {
+        self.with_db(|db| file_structure::file_structure(db, file_id))
+    }
+
+    /// Fuzzy searches the functions and top-level locals of every file.
+    pub fn symbol_search(&self, query: Query) -> Cancelable<Vec<FileSymbol>> {
+        self.with_db(|db| symbol_index::world_symbols(db, query))
+    }
+
+    /// Returns the declarations of the identifier at `position`.
+    pub fn goto_definition(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| goto_definition::goto_definition(db, position))
+    }
+
+    /// Finds every read and write of the identifier at `position`.
+    pub fn find_all_refs(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<ReferenceSearchResult>> {
+        self.with_db(|db| references::find_all_refs(db, position))
+    }
+
+    /// Finds the reads and writes of the identifier at `position` in its file.
+    pub fn highlight_related(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<Vec<HighlightedRange>>> {
+        self.with_db(|db| highlight_related::highlight_related(db, position))
+    }
+
+    /// Returns the declaration, docs and type of the name at `position`.
+    pub fn hover(&self, position: FilePosition) -> Cancelable<Option<HoverResult>> {
+        self.with_db(|db| hover::hover(db, position))
+    }
+
+    /// Returns the signature of the function called by the arguments around
+    /// `position`.
+    pub fn signature_help(&self, position: FilePosition) -> Cancelable<Option<SignatureHelp>> {
+        self.with_db(|db| signature_help::signature_help(db, position))
+    }
+
+    /// Returns the parameter, type and return index hints of the file that
+    /// are in `range`.
+    pub fn inlay_hints(
+        &self,
+        config: &InlayHintsConfig,
+        file_id: FileId,
+        range: TextRange,
+    ) -> Cancelable<Vec<InlayHint>> {
+        self.with_db(|db| inlay_hints::inlay_hints(db, config, file_id, range))
+    }
+
+    /// Returns the range of the identifier that `rename` would rename.
+    pub fn prepare_rename(&self, position: FilePosition) -> Cancelable<RenameResult<TextRange>> {
+        self.with_db(|db| rename::prepare_rename(db, position))
+    }
+
+    /// Computes the completions at `position`.
+    pub fn completions(&self, position: FilePosition) -> Cancelable<Option<Vec<CompletionItem>>> {
+        self.with_db(|db| completion::completions(db, position))
+    }
+
+    /// Computes the semantic highlighting of the file.
+    pub fn highlight(&self, file_id: FileId) -> Cancelable<Vec<HlRange>> {
+        self.with_db(|db| syntax_highlighting::highlight(db, file_id))
+    }
+
+    /// Computes the edit that formats the file, `None` when it has syntax errors.
+    pub fn format(&self, file_id: FileId, config: &FormatConfig) -> Cancelable<Option<TextEdit>> {
+        self.with_db(|db| format::format(db, file_id, config))
+    }
+
+    /// Like `format`, but only keeps the edits in `frange`.
+    pub fn format_range(
+        &self,
+        frange: FileRange,
+        config: &FormatConfig,
+    ) -> Cancelable<Option<TextEdit>> {
+        self.with_db(|db| format::format_range(db, frange, config))
+    }
+
+    /// Renames the identifier at `position` and all of its references.
+    pub fn rename(
+        &self,
+        position: FilePosition,
+        new_name: &str,
+    ) -> Cancelable<RenameResult<SourceChange>> {
+        self.with_db(|db| rename::rename(db, position, new_name))
     }
 }
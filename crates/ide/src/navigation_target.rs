@@ -0,0 +1,92 @@
+use hir::Semantics;
+use ide_db::{
+    base_db::FileId,
+    defs::Definition,
+    search::{FileReference, ReferenceAccess},
+};
+use syntax::{
+    ast::{self, AstNode},
+    NodeOrToken, SyntaxNode, TextRange,
+};
+
+/// Something the client can jump to, such as the declaration of a local.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavigationTarget {
+    pub file_id: FileId,
+    /// The range of the whole declaration, such as a `local` statement.
+    pub full_range: TextRange,
+    /// The range of the name being declared, where the cursor should be
+    /// placed.
+    pub focus_range: TextRange,
+    pub name: String,
+}
+
+impl NavigationTarget {
+    /// Every place the definition is declared at. Globals and fields have no
+    /// single declaration, so all of the places they are assigned to are used.
+    pub(crate) fn from_definition(sema: &Semantics, def: &Definition) -> Vec<NavigationTarget> {
+        let name = def.name(sema.db).to_string();
+        match def {
+            Definition::Local(local) => match local.source(sema.db) {
+                Some(src) => {
+                    let range = src.value.syntax().text_range();
+                    vec![NavigationTarget::from_syntax(
+                        src.file_id,
+                        src.value.syntax(),
+                        range,
+                        name,
+                    )]
+                }
+                None => Vec::new(),
+            },
+            Definition::Label(label) => label
+                .source(sema.db)
+                .and_then(|src| {
+                    let name_node = src.value.name()?;
+                    let nav = NavigationTarget {
+                        file_id: src.file_id,
+                        full_range: src.value.syntax().text_range(),
+                        focus_range: name_node.syntax().text_range(),
+                        name,
+                    };
+                    Some(nav)
+                })
+                .into_iter()
+                .collect(),
+            Definition::Path(_) => def
+                .find_usages(sema)
+                .into_iter()
+                .filter(|it| it.access == ReferenceAccess::Write)
+                .map(|FileReference { file_id, range, .. }| {
+                    let root = sema.parse(file_id).syntax().clone();
+                    let node = match root.covering_element(range) {
+                        NodeOrToken::Node(it) => it,
+                        NodeOrToken::Token(it) => it.parent(),
+                    };
+                    NavigationTarget::from_syntax(file_id, &node, range, name.clone())
+                })
+                .collect(),
+        }
+    }
+
+    /// A target that spans the statement containing `node`, parameters only
+    /// span themselves.
+    fn from_syntax(
+        file_id: FileId,
+        node: &SyntaxNode,
+        focus_range: TextRange,
+        name: String,
+    ) -> NavigationTarget {
+        let full_range = node
+            .ancestors()
+            .find_map(|it| {
+                if ast::ParamList::can_cast(it.kind()) {
+                    Some(focus_range)
+                } else {
+                    ast::Stmt::cast(it).map(|it| it.syntax().text_range())
+                }
+            })
+            .unwrap_or(focus_range);
+        NavigationTarget { file_id, full_range, focus_range, name }
+    }
+}
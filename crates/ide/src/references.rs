@@ -0,0 +1,204 @@
+use hir::Semantics;
+use ide_db::{
+    base_db::FilePosition,
+    defs::{self, Definition},
+    search::FileReference,
+    RootDatabase,
+};
+use syntax::{ast::AstNode, T};
+
+use crate::NavigationTarget;
+
+#[derive(Debug, Clone)]
+pub struct ReferenceSearchResult {
+    /// The declaration of a local or a label, globals and fields do not have
+    /// one.
+    pub declaration: Option<NavigationTarget>,
+    /// Every read and write, including the declaration.
+    pub references: Vec<FileReference>,
+}
+
+// Feature: Find All References
+//
+// Shows every read and write of the identifier under the cursor. Globals and
+// table fields are searched for in every file of the workspace.
+pub(crate) fn find_all_refs(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<ReferenceSearchResult> {
+    let sema = Semantics::new(db);
+    let def = find_def(&sema, position)?;
+    let declaration = match def {
+        Definition::Local(_) | Definition::Label(_) => {
+            NavigationTarget::from_definition(&sema, &def).into_iter().next()
+        }
+        Definition::Path(_) => None,
+    };
+    Some(ReferenceSearchResult { declaration, references: def.find_usages(&sema) })
+}
+
+pub(crate) fn find_def(sema: &Semantics, position: FilePosition) -> Option<Definition> {
+    let file = sema.parse(position.file_id);
+    let token = file.syntax().token_at_offset(position.offset).find(|it| it.kind() == T![ident])?;
+    defs::classify(sema, position.file_id, &token)
+}
+
+#[cfg(test)]
+mod tests {
+    use ide_db::search::ReferenceAccess;
+
+    use crate::fixture;
+
+    fn check(ra_fixture: &str, expect: &str) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let refs = analysis.find_all_refs(position).unwrap().expect("no definition found");
+        let mut actual = String::new();
+        if let Some(decl) = refs.declaration {
+            actual += &format!("declaration {:?} {:?}\n", decl.file_id, decl.focus_range);
+        }
+        for it in refs.references {
+            let access = match it.access {
+                ReferenceAccess::Read => "read",
+                ReferenceAccess::Write => "write",
+            };
+            actual += &format!("{:?} {:?} {}\n", it.file_id, it.range, access);
+        }
+        assert_eq!(actual.trim(), expect.trim());
+    }
+
+    #[test]
+    fn local_reads_and_writes() {
+        check(
+            r#"
+local x$0 = 1
+x = x + 1
+print(x)
+"#,
+            r#"
+declaration FileId(0) 6..7
+FileId(0) 6..7 write
+FileId(0) 12..13 write
+FileId(0) 16..17 read
+FileId(0) 28..29 read
+"#,
+        );
+    }
+
+    #[test]
+    fn upvalue_in_closure() {
+        check(
+            r#"
+local n = 0
+local function inc() n$0 = n + 1 end
+"#,
+            r#"
+declaration FileId(0) 6..7
+FileId(0) 6..7 write
+FileId(0) 33..34 write
+FileId(0) 37..38 read
+"#,
+        );
+    }
+
+    #[test]
+    fn shadowed_local_is_separate() {
+        check(
+            r#"
+local a = 1
+local a$0 = a
+print(a)
+"#,
+            r#"
+declaration FileId(0) 18..19
+FileId(0) 18..19 write
+FileId(0) 30..31 read
+"#,
+        );
+    }
+
+    #[test]
+    fn param() {
+        check(
+            r#"
+local function f(p$0) print(p) end
+"#,
+            r#"
+declaration FileId(0) 17..18
+FileId(0) 17..18 write
+FileId(0) 26..27 read
+"#,
+        );
+    }
+
+    #[test]
+    fn label() {
+        check(
+            r#"
+while true do
+    ::next$0::
+    goto next
+end
+"#,
+            r#"
+declaration FileId(0) 20..24
+FileId(0) 20..24 write
+FileId(0) 36..40 read
+"#,
+        );
+    }
+
+    #[test]
+    fn global_across_files() {
+        check(
+            r#"
+//- /main.lua
+counter$0 = 0
+//- /other.lua
+counter = counter + 1
+"#,
+            r#"
+FileId(0) 0..7 write
+FileId(1) 0..7 write
+FileId(1) 10..17 read
+"#,
+        );
+    }
+
+    #[test]
+    fn table_field_across_files() {
+        check(
+            r#"
+//- /main.lua
+config = {}
+config.debug$0 = false
+function config:debug() end
+//- /other.lua
+if config.debug then config:debug() end
+"#,
+            r#"
+FileId(0) 19..24 write
+FileId(0) 49..54 write
+FileId(1) 10..15 read
+FileId(1) 28..33 read
+"#,
+        );
+    }
+
+    #[test]
+    fn field_of_local_stays_in_file() {
+        check(
+            r#"
+//- /main.lua
+local t = {}
+t.x$0 = 1
+print(t.x)
+//- /other.lua
+t.x = 2
+"#,
+            r#"
+FileId(0) 15..16 write
+FileId(0) 29..30 read
+"#,
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! Renaming of locals, labels, globals and table fields.
+//!
+//! A rename is refused when it would change what any name refers to, such as
+//! when an inner `local` with the new name would capture the renamed uses.
+
+use std::fmt;
+
+use hir::{expr::Expr, AccessPath, AsName, Label, Local, Name, PathRoot, Semantics, WithBodyId};
+use ide_db::{
+    base_db::{FilePosition, SourceDatabase},
+    defs::Definition,
+    source_change::SourceChange,
+    RootDatabase,
+};
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxNode, TextRange, T,
+};
+use text_edit::TextEdit;
+
+use crate::references::find_def;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameError(pub String);
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for RenameError {}
+
+pub type RenameResult<T> = Result<T, RenameError>;
+
+macro_rules! bail {
+    ($($tokens:tt)*) => {
+        return Err(RenameError(format!($($tokens)*)))
+    };
+}
+
+/// Returns the range of the identifier that would be renamed.
+pub(crate) fn prepare_rename(db: &RootDatabase, position: FilePosition) -> RenameResult<TextRange> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let token = file
+        .syntax()
+        .token_at_offset(position.offset)
+        .find(|it| it.kind() == T![ident])
+        .ok_or_else(|| RenameError("No identifier at the cursor".to_string()))?;
+    if find_def(&sema, position).is_none() {
+        bail!("No references found at the cursor");
+    }
+    Ok(token.text_range())
+}
+
+// Feature: Rename
+//
+// Renames the identifier under the cursor along with all of its references in
+// the workspace.
+pub(crate) fn rename(
+    db: &RootDatabase,
+    position: FilePosition,
+    new_name: &str,
+) -> RenameResult<SourceChange> {
+    let sema = Semantics::new(db);
+    let version = db.file_lua_version(position.file_id);
+    if syntax::is_keyword(new_name, version) {
+        bail!("Invalid name `{}`: it is a reserved word", new_name);
+    }
+    if !syntax::is_identifier(new_name, version) {
+        bail!("Invalid name `{}`: not an identifier", new_name);
+    }
+    let def =
+        find_def(&sema, position).ok_or_else(|| RenameError("No references found".to_string()))?;
+    let new_name = Name::resolve(new_name);
+    if def.name(db) == new_name {
+        return Ok(SourceChange::default());
+    }
+
+    match &def {
+        Definition::Local(local) => check_local(&sema, *local, &new_name)?,
+        Definition::Label(label) => check_label(&sema, *label, &new_name)?,
+        Definition::Path(path) => check_path(&sema, path, &new_name)?,
+    }
+
+    let mut change = SourceChange::default();
+    for (file_id, refs) in group_by_file(def.find_usages(&sema)) {
+        // a global can also be used in files of another version
+        let version = db.file_lua_version(file_id);
+        if syntax::is_keyword(new_name.as_str(), version) {
+            bail!("Invalid name `{}`: it is a reserved word in {}", new_name, version);
+        }
+        let mut builder = TextEdit::builder();
+        for range in refs {
+            builder.replace(range, new_name.to_string());
+        }
+        change.insert_source_edit(file_id, builder.finish());
+    }
+    Ok(change)
+}
+
+fn group_by_file(
+    refs: Vec<ide_db::search::FileReference>,
+) -> Vec<(ide_db::base_db::FileId, Vec<TextRange>)> {
+    let mut res: Vec<(_, Vec<_>)> = Vec::new();
+    for it in refs {
+        match res.last_mut() {
+            Some((file_id, ranges)) if *file_id == it.file_id => ranges.push(it.range),
+            _ => res.push((it.file_id, vec![it.range])),
+        }
+    }
+    res
+}
+
+/// Checks that every name of the file still refers to the same binding once
+/// `local` is renamed.
+fn check_local(sema: &Semantics, local: Local, new_name: &Name) -> RenameResult<()> {
+    let (body, _) = sema.file_body(local.file_id);
+    let scopes = sema.file_scopes(local.file_id);
+    for (expr, data) in body.exprs.iter() {
+        let name = match data {
+            Expr::Name(it) => it,
+            _ => continue,
+        };
+        let scope = match scopes.scope_for(expr) {
+            Some(it) => it,
+            None => continue,
+        };
+        let before = scopes.resolve_name_in_scope(scope, name).and_then(|it| it.name_id());
+        let name = if before == Some(local.name) { new_name } else { name };
+        if name != new_name {
+            continue;
+        }
+        let after = scopes
+            .resolve_name_in_scope_renamed(scope, name, local.name, new_name)
+            .and_then(|it| it.name_id());
+        if before != after {
+            if before == Some(local.name) {
+                bail!("Renaming to `{}` would make its uses refer to another binding", new_name);
+            }
+            bail!("Renaming to `{}` would capture other uses of `{}`", new_name, new_name);
+        }
+    }
+    Ok(())
+}
+
+/// Labels must be unique within a function, so no other label of the
+/// function may have the new name.
+fn check_label(sema: &Semantics, label: Label, new_name: &Name) -> RenameResult<()> {
+    let src = match label.source(sema.db) {
+        Some(it) => it,
+        None => return Ok(()),
+    };
+    let function = src
+        .value
+        .syntax()
+        .ancestors()
+        .find(is_function)
+        .unwrap_or_else(|| sema.parse(src.file_id).syntax().clone());
+    let mut preorder = function.preorder();
+    while let Some(event) = preorder.next() {
+        let node = match event {
+            syntax::WalkEvent::Enter(it) => it,
+            syntax::WalkEvent::Leave(_) => continue,
+        };
+        if node != function && is_function(&node) {
+            preorder.skip_subtree();
+            continue;
+        }
+        let other = match ast::LabelStmt::cast(node).and_then(|it| it.name()) {
+            Some(it) => it,
+            None => continue,
+        };
+        if other.as_name() == *new_name {
+            bail!("A label named `{}` already exists in this function", new_name);
+        }
+    }
+    Ok(())
+}
+
+fn is_function(node: &SyntaxNode) -> bool {
+    ast::FunctionExpr::can_cast(node.kind())
+        || ast::FunctionDefStmt::can_cast(node.kind())
+        || ast::LocalFunctionDefStmt::can_cast(node.kind())
+}
+
+fn check_path(sema: &Semantics, path: &AccessPath, new_name: &Name) -> RenameResult<()> {
+    let mut renamed = path.clone();
+    match renamed.fields.last_mut() {
+        Some(field) => *field = new_name.clone(),
+        None => renamed.root = PathRoot::Global(new_name.clone()),
+    }
+    if !Definition::Path(renamed).find_usages(sema).is_empty() {
+        bail!("`{}` is already defined", new_name);
+    }
+    if !path.fields.is_empty() {
+        return Ok(());
+    }
+
+    // a renamed global must not be captured by a local of the new name
+    for file_id in Definition::Path(path.clone()).search_scope(sema) {
+        let body = sema.db.body(WithBodyId::ModuleId(file_id));
+        let scopes = sema.file_scopes(file_id);
+        for (expr, _) in body.exprs.iter() {
+            if AccessPath::of_expr(file_id, &body, &scopes, expr).as_ref() != Some(path) {
+                continue;
+            }
+            let scope = match scopes.scope_for(expr) {
+                Some(it) => it,
+                None => continue,
+            };
+            if scopes.resolve_name_in_scope(scope, new_name).is_some() {
+                bail!("Renaming to `{}` would make uses of the global refer to a local", new_name);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ide_db::base_db::FilePosition;
+    use syntax::{LuaVersion, TextSize};
+    use test_utils::assert_eq_text;
+
+    use crate::{fixture, AnalysisHost, Change, FileId};
+
+    /// Renames the identifier at `$0` and checks the text of the first file.
+    fn check(new_name: &str, ra_fixture_before: &str, ra_fixture_after: &str) {
+        let (analysis, position) = fixture::position(ra_fixture_before);
+        let change = analysis.rename(position, new_name).unwrap().unwrap_or_else(|err| {
+            panic!("rename failed: {}", err);
+        });
+        let mut text = analysis.file_text(position.file_id).unwrap().to_string();
+        if let Some(edit) = change.source_file_edits.get(&position.file_id) {
+            edit.apply(&mut text);
+        }
+        assert_eq_text!(ra_fixture_after.trim_start(), &*text);
+    }
+
+    fn check_error(new_name: &str, ra_fixture: &str, expected: &str) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        match analysis.rename(position, new_name).unwrap() {
+            Ok(change) => panic!("rename should fail, got {:?}", change),
+            Err(err) => assert_eq!(err.to_string(), expected),
+        }
+    }
+
+    #[test]
+    fn rename_local() {
+        check(
+            "count",
+            r#"
+local n$0 = 0
+n = n + 1
+print(n)
+"#,
+            r#"
+local count = 0
+count = count + 1
+print(count)
+"#,
+        );
+    }
+
+    #[test]
+    fn rename_param_keeps_shadowed_outer() {
+        check(
+            "value",
+            r#"
+local x = 1
+local function f(x$0)
+    print(x)
+end
+print(x)
+"#,
+            r#"
+local x = 1
+local function f(value)
+    print(value)
+end
+print(x)
+"#,
+        );
+    }
+
+    #[test]
+    fn rename_label() {
+        check(
+            "continue",
+            r#"
+while true do
+    goto skip$0
+    ::skip::
+end
+"#,
+            r#"
+while true do
+    goto continue
+    ::continue::
+end
+"#,
+        );
+    }
+
+    #[test]
+    fn rename_method() {
+        check(
+            "greet",
+            r#"
+local M = {}
+function M:hello$0() end
+M:hello()
+"#,
+            r#"
+local M = {}
+function M:greet() end
+M:greet()
+"#,
+        );
+    }
+
+    #[test]
+    fn rename_global_across_files() {
+        let (analysis, position) = fixture::position(
+            r#"
+//- /main.lua
+settings$0 = {}
+//- /other.lua
+print(settings)
+"#,
+        );
+        let change = analysis.rename(position, "config").unwrap().unwrap();
+        let mut files: Vec<_> = change.source_file_edits.keys().copied().collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        let mut text = analysis.file_text(files[1]).unwrap().to_string();
+        change.source_file_edits[&files[1]].apply(&mut text);
+        assert_eq!(text, "print(config)\n");
+    }
+
+    #[test]
+    fn refuse_keyword() {
+        check_error("end", "local a$0 = 1", "Invalid name `end`: it is a reserved word");
+    }
+
+    #[test]
+    fn goto_is_a_name_in_lua_51() {
+        check_error("goto", "local a$0 = 1", "Invalid name `goto`: it is a reserved word");
+
+        let mut host = AnalysisHost::new();
+        let mut change = Change::new();
+        change.set_lua_version(LuaVersion::Lua51);
+        change.change_file(FileId(0), Some(Arc::new("local a = 1\nprint(a)\n".to_string())));
+        host.apply_change(change);
+        let position = FilePosition { file_id: FileId(0), offset: TextSize::from(6) };
+        let change = host.analysis().rename(position, "goto").unwrap().unwrap();
+        let mut text = "local a = 1\nprint(a)\n".to_string();
+        change.source_file_edits.get(&FileId(0)).unwrap().apply(&mut text);
+        assert_eq_text!("local goto = 1\nprint(goto)\n", &*text);
+    }
+
+    #[test]
+    fn refuse_invalid_identifier() {
+        check_error("1a", "local a$0 = 1", "Invalid name `1a`: not an identifier");
+    }
+
+    #[test]
+    fn refuse_capture_by_inner_local() {
+        check_error(
+            "b",
+            r#"
+local a$0 = 1
+while true do
+    local b = 2
+    print(a)
+end
+"#,
+            "Renaming to `b` would make its uses refer to another binding",
+        );
+    }
+
+    #[test]
+    fn refuse_capturing_other_uses() {
+        check_error(
+            "b",
+            r#"
+local b = 1
+while true do
+    local a$0 = 2
+    print(b)
+end
+"#,
+            "Renaming to `b` would capture other uses of `b`",
+        );
+    }
+
+    #[test]
+    fn refuse_capturing_global() {
+        check_error(
+            "print",
+            r#"
+local log$0 = 1
+print(log)
+"#,
+            "Renaming to `print` would capture other uses of `print`",
+        );
+    }
+
+    #[test]
+    fn allow_shadowing_without_uses() {
+        check(
+            "b",
+            r#"
+local b = 1
+local a$0 = 2
+print(a)
+"#,
+            r#"
+local b = 1
+local b = 2
+print(b)
+"#,
+        );
+    }
+
+    #[test]
+    fn refuse_duplicate_label() {
+        check_error(
+            "top",
+            r#"
+::top::
+while true do
+    ::next$0::
+end
+"#,
+            "A label named `top` already exists in this function",
+        );
+    }
+
+    #[test]
+    fn refuse_existing_field() {
+        check_error(
+            "b",
+            r#"
+local t = {}
+t.a$0 = 1
+t.b = 2
+"#,
+            "`b` is already defined",
+        );
+    }
+
+    #[test]
+    fn refuse_global_captured_by_local() {
+        check_error(
+            "x",
+            r#"
+local x = 1
+value$0 = x
+"#,
+            "Renaming to `x` would make uses of the global refer to a local",
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use hir::{item_tree::IndexPath, Name, Semantics};
+use ide_db::{
+    base_db::{FileId, FilePosition},
+    defs::{self, Definition},
+    docs::doc_comment,
+    search::ReferenceAccess,
+    RootDatabase,
+};
+use syntax::{
+    ast::{self, AstNode},
+    doc::{self, DocAstNode, DocTag},
+    NodeOrToken, SyntaxNode, TextRange, TextSize, T,
+};
+
+/// The signature of the function called by the arguments around the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureHelp {
+    /// The declaration of the function, such as
+    /// `local function f(a: integer, ...)`.
+    pub signature: String,
+    pub doc: Option<String>,
+    pub parameters: Vec<SignatureParam>,
+    /// The index in `parameters` of the parameter of the argument the cursor
+    /// is in.
+    pub active_parameter: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureParam {
+    /// The range of the parameter in the signature.
+    pub range: TextRange,
+    pub doc: Option<String>,
+}
+
+// Feature: Signature Help
+//
+// Shows the parameters of the function being called while its arguments are
+// typed, with the types and descriptions of their `---@param` annotations,
+// and highlights the parameter of the argument at the cursor.
+pub(crate) fn signature_help(db: &RootDatabase, position: FilePosition) -> Option<SignatureHelp> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let offset = position.offset;
+    let token = file.syntax().token_at_offset(offset).left_biased()?;
+    // the innermost argument list the cursor is inside of the parentheses of
+    let arg_list = token.ancestors().filter_map(ast::ArgList::cast).find(|it| {
+        let after_l_paren = it.l_paren_token().is_some_and(|it| it.text_range().end() <= offset);
+        let before_r_paren = it.r_paren_token().is_none_or(|it| offset <= it.text_range().start());
+        after_l_paren && before_r_paren
+    })?;
+    let call = arg_list.syntax().ancestors().skip(1).find_map(ast::Expr::cast)?;
+    let func = called_function(&sema, position.file_id, &call)?;
+
+    let mut signature = func.keyword.to_string();
+    if let Some(path) = &func.path {
+        for segment in path.segments() {
+            signature.push_str(segment.as_str());
+            signature.push('.');
+        }
+        if func.is_method {
+            signature.pop();
+            signature.push(':');
+        }
+    }
+    signature.push_str(func.name.as_str());
+    signature.push('(');
+    let mut parameters = Vec::new();
+    for (idx, param) in func.params.iter().enumerate() {
+        if idx > 0 {
+            signature.push_str(", ");
+        }
+        let start = TextSize::of(&signature);
+        signature.push_str(&param.name);
+        if param.is_optional {
+            signature.push('?');
+        }
+        if let Some(ty) = &param.ty {
+            signature.push_str(": ");
+            signature.push_str(ty);
+        }
+        let range = TextRange::new(start, TextSize::of(&signature));
+        parameters.push(SignatureParam { range, doc: param.doc.clone() });
+    }
+    signature.push(')');
+
+    let arg_idx = arg_list.multival_expr().map_or(0, |args| {
+        args.syntax()
+            .children_with_tokens()
+            .filter_map(NodeOrToken::into_token)
+            .filter(|it| it.kind() == T![,] && it.text_range().end() <= offset)
+            .count()
+    });
+    let params = &func.params;
+    let active_parameter = if arg_idx < params.len() {
+        Some(arg_idx)
+    } else if params.last().is_some_and(|it| it.name == "...") {
+        // every argument after the others is one of the varargs
+        Some(params.len() - 1)
+    } else {
+        None
+    };
+    Some(SignatureHelp { signature, doc: func.doc, parameters, active_parameter })
+}
+
+/// The function called by a call or method call expression, with the
+/// parameters its arguments are passed to.
+pub(crate) fn called_function(
+    sema: &Semantics,
+    file_id: FileId,
+    call: &ast::Expr,
+) -> Option<Function> {
+    let (name_token, is_method_call) = match call {
+        ast::Expr::CallExpr(call) => match call.fun()? {
+            ast::Expr::NameRef(it) => (it.syntax().first_token()?, false),
+            ast::Expr::DotExpr(it) => (it.ident_token()?, false),
+            _ => return None,
+        },
+        ast::Expr::MethodCallExpr(call) => (call.method_name()?.syntax().first_token()?, true),
+        _ => return None,
+    };
+    let def = defs::classify(sema, file_id, &name_token)?;
+    let mut func = function_of(sema, &def)?;
+
+    // `a.b(a)` passes the `self` of `function a:b()`, and `a:b()` passes the
+    // first parameter of `function a.b(self)` implicitly
+    if func.is_method && !is_method_call {
+        let param = Param { name: "self".to_string(), is_optional: false, ty: None, doc: None };
+        func.params.insert(0, param);
+        func.is_method = false;
+    } else if !func.is_method && is_method_call && func.path.is_some() {
+        if func.params.first().is_some_and(|it| it.name != "...") {
+            func.params.remove(0);
+        }
+        func.is_method = true;
+    }
+    Some(func)
+}
+
+/// A function declared by a statement.
+pub(crate) struct Function {
+    keyword: &'static str,
+    /// The tables of `function a.b:c()`, `None` for local functions.
+    path: Option<IndexPath>,
+    name: Name,
+    is_method: bool,
+    pub(crate) params: Vec<Param>,
+    doc: Option<String>,
+}
+
+pub(crate) struct Param {
+    /// The name of the parameter, which is `...` for varargs.
+    pub(crate) name: String,
+    is_optional: bool,
+    ty: Option<String>,
+    doc: Option<String>,
+}
+
+/// The function declared by the statement that defines `def`.
+fn function_of(sema: &Semantics, def: &Definition) -> Option<Function> {
+    match def {
+        Definition::Local(local) => {
+            let src = local.source(sema.db)?;
+            let stmt = src.value.syntax().parent().and_then(ast::LocalFunctionDefStmt::cast)?;
+            let ast_id = sema.db.ast_id_map(src.file_id).ast_id(&stmt);
+            let item_tree = sema.db.file_item_tree(src.file_id);
+            let func = item_tree.local_functions().find(|func| func.ast_id == ast_id)?;
+            Some(Function {
+                keyword: "local function ",
+                path: None,
+                name: func.name.clone(),
+                is_method: false,
+                params: annotated_params(stmt.syntax(), &func.params),
+                doc: doc_comment(stmt.syntax()),
+            })
+        }
+        Definition::Path(_) => def
+            .find_usages(sema)
+            .into_iter()
+            .filter(|it| it.access == ReferenceAccess::Write)
+            .find_map(|it| {
+                let root = sema.parse(it.file_id).syntax().clone();
+                let stmt = function_def_at(&root, it.range)?;
+                let ast_id = sema.db.ast_id_map(it.file_id).ast_id(&stmt);
+                let item_tree = sema.db.file_item_tree(it.file_id);
+                let func = item_tree.functions().find(|func| func.ast_id == ast_id)?;
+                Some(Function {
+                    keyword: "function ",
+                    path: Some(func.path.clone()),
+                    name: func.name.clone(),
+                    is_method: func.is_method,
+                    params: annotated_params(stmt.syntax(), &func.params),
+                    doc: doc_comment(stmt.syntax()),
+                })
+            }),
+        Definition::Label(_) => None,
+    }
+}
+
+/// The `function` statement whose name is at `range`.
+fn function_def_at(root: &SyntaxNode, range: TextRange) -> Option<ast::FunctionDefStmt> {
+    let node = match root.covering_element(range) {
+        NodeOrToken::Node(it) => it,
+        NodeOrToken::Token(it) => it.parent(),
+    };
+    node.ancestors().find(|it| ast::Stmt::can_cast(it.kind())).and_then(ast::FunctionDefStmt::cast)
+}
+
+/// The parameters of a function with the types and descriptions of their
+/// `---@param` annotations.
+fn annotated_params(stmt: &SyntaxNode, params: &hir::expr::ParamList) -> Vec<Param> {
+    let tags: Vec<doc::ParamTag> = doc::doc_block(stmt)
+        .map(|block| {
+            block
+                .tree()
+                .tags()
+                .filter_map(|tag| match tag {
+                    DocTag::Param(it) => Some(it),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let names = params.names.iter().map(|it| it.as_str());
+    names
+        .chain(params.vararg.then_some("..."))
+        .map(|name| {
+            let tag = tags.iter().find(|it| it.name_token().is_some_and(|it| it.text() == name));
+            Param {
+                name: name.to_string(),
+                is_optional: tag.is_some_and(|it| it.is_optional()),
+                ty: tag.and_then(|it| it.ty()).map(|it| it.syntax().to_string()),
+                doc: tag.and_then(|it| it.description()).map(|it| it.text()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::assert_eq_text;
+
+    use crate::fixture;
+
+    /// Checks the signature with the active parameter in `<>`, followed by
+    /// the docs of the function and of its parameters.
+    fn check(ra_fixture: &str, expect: &str) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let help = analysis.signature_help(position).unwrap().expect("no signature help");
+        let mut actual = help.signature.clone();
+        if let Some(param) = help.active_parameter.map(|it| help.parameters[it].range) {
+            actual.insert(param.end().into(), '>');
+            actual.insert(param.start().into(), '<');
+        }
+        actual.push('\n');
+        if let Some(doc) = &help.doc {
+            actual += &format!("{}\n", doc);
+        }
+        for param in &help.parameters {
+            if let Some(doc) = &param.doc {
+                actual += &format!("{}: {}\n", &help.signature[param.range], doc);
+            }
+        }
+        assert_eq_text!(expect.trim_start(), &actual);
+    }
+
+    fn check_none(ra_fixture: &str) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let help = analysis.signature_help(position).unwrap();
+        assert!(help.is_none(), "unexpected signature help: {:?}", help);
+    }
+
+    #[test]
+    fn local_function_with_annotations() {
+        check(
+            r#"
+---Clamps a number.
+---@param n number
+---@param lo? number # the lower bound
+local function clamp(n, lo, hi) end
+clamp(1, $0)
+"#,
+            r#"
+local function clamp(n: number, <lo?: number>, hi)
+Clamps a number.
+lo?: number: the lower bound
+"#,
+        );
+    }
+
+    #[test]
+    fn first_argument() {
+        check(
+            r#"
+local function f(a, b) end
+f($0)
+"#,
+            r#"
+local function f(<a>, b)
+"#,
+        );
+    }
+
+    #[test]
+    fn varargs() {
+        check(
+            r#"
+---@param ... string
+function log(fmt, ...) end
+log("%s %s", "a", $0)
+"#,
+            r#"
+function log(fmt, <...: string>)
+"#,
+        );
+    }
+
+    #[test]
+    fn too_many_arguments() {
+        check(
+            r#"
+local function f(a) end
+f(1, $0)
+"#,
+            r#"
+local function f(a)
+"#,
+        );
+    }
+
+    #[test]
+    fn nested_calls() {
+        check(
+            r#"
+local function f(a, b) end
+local function g(x) end
+f(g(1), g($0))
+"#,
+            r#"
+local function g(<x>)
+"#,
+        );
+        check(
+            r#"
+local function f(a, b) end
+local function g(x) end
+f(g(1), $0)
+"#,
+            r#"
+local function f(a, <b>)
+"#,
+        );
+    }
+
+    #[test]
+    fn method_calls() {
+        check(
+            r#"
+local Point = {}
+function Point:move(dx, dy) end
+Point:move(1, $0)
+"#,
+            r#"
+function Point:move(dx, <dy>)
+"#,
+        );
+        check(
+            r#"
+local Point = {}
+function Point:move(dx, dy) end
+Point.move(Point, $0)
+"#,
+            r#"
+function Point.move(self, <dx>, dy)
+"#,
+        );
+        check(
+            r#"
+local Point = {}
+function Point.move(self, dx, dy) end
+Point:move($0)
+"#,
+            r#"
+function Point:move(<dx>, dy)
+"#,
+        );
+    }
+
+    #[test]
+    fn no_signature_outside_of_arguments() {
+        check_none(
+            r#"
+local function f(a) end
+f(1)$0
+"#,
+        );
+        check_none(
+            r#"
+local f = 1
+f($0)
+"#,
+        );
+    }
+}
@@ -0,0 +1,453 @@
+use hir::{
+    body::{
+        scope::{BindingKind, ExprScopes, Resolution},
+        Body, BodySourceMap,
+    },
+    expr::{Expr, ExprId},
+    stmt::Stmt,
+    InFile, Local, Semantics,
+};
+use ide_db::{
+    base_db::{FileId, SourceDatabase},
+    RootDatabase,
+};
+use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, AstNode},
+    LuaVersion, SyntaxToken, TextRange, T,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HlRange {
+    pub range: TextRange,
+    pub highlight: Highlight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Highlight {
+    pub tag: HlTag,
+    pub mods: HlMods,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HlTag {
+    /// A local of the function it is used in.
+    Local,
+    Parameter,
+    /// A local of an enclosing function.
+    Upvalue,
+    Global,
+    /// A global of the standard library of the Lua version, such as `print`
+    /// or `string`.
+    Builtin,
+    Field,
+    /// The name of a method call `a:b()` or definition `function a:b()`.
+    Method,
+    Label,
+    Keyword,
+    Comment,
+    String,
+    Number,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HlMod {
+    /// The name that declares a local, label, field or function.
+    Declaration,
+    /// A local that is assigned to after its declaration.
+    Mutated,
+    /// A `<const>` local.
+    Const,
+}
+
+/// A set of `HlMod`s.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct HlMods(u32);
+
+impl HlMod {
+    pub const ALL: &'static [HlMod] = &[HlMod::Declaration, HlMod::Mutated, HlMod::Const];
+
+    fn mask(self) -> u32 {
+        1 << (self as u32)
+    }
+}
+
+impl HlMods {
+    pub fn contains(self, m: HlMod) -> bool {
+        self.0 & m.mask() != 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = HlMod> {
+        HlMod::ALL.iter().copied().filter(move |it| self.contains(*it))
+    }
+}
+
+impl std::ops::BitOr<HlMod> for Highlight {
+    type Output = Highlight;
+
+    fn bitor(mut self, rhs: HlMod) -> Highlight {
+        self.mods.0 |= rhs.mask();
+        self
+    }
+}
+
+impl From<HlTag> for Highlight {
+    fn from(tag: HlTag) -> Highlight {
+        Highlight { tag, mods: HlMods::default() }
+    }
+}
+
+// Feature: Semantic Syntax Highlighting
+//
+// Highlights names by what they resolve to, which tells the locals of a
+// function apart from its upvalues and from globals, along with keywords,
+// comments, strings and numbers.
+pub(crate) fn highlight(db: &RootDatabase, file_id: FileId) -> Vec<HlRange> {
+    let sema = Semantics::new(db);
+    let (body, source_map) = sema.file_body(file_id);
+    let scopes = sema.file_scopes(file_id);
+    let file = sema.parse(file_id);
+    let hl = Highlighter {
+        sema: &sema,
+        file_id,
+        body: &body,
+        source_map: &source_map,
+        scopes: &scopes,
+        version: db.file_lua_version(file_id),
+        mutated: mutated_locals(file_id, &body, &scopes),
+        consts: const_locals(&file, file_id, &source_map),
+    };
+
+    let mut res = Vec::new();
+    for token in file.syntax().descendants_with_tokens().filter_map(|it| it.into_token()) {
+        let highlight = match token.kind() {
+            T![comment] | T![shebang] => HlTag::Comment.into(),
+            T![str] => HlTag::String.into(),
+            T![number] => HlTag::Number.into(),
+            kind if kind.is_keyword() => HlTag::Keyword.into(),
+            T![ident] => match hl.name(&token) {
+                Some(it) => it,
+                None => continue,
+            },
+            _ => continue,
+        };
+        res.push(HlRange { range: token.text_range(), highlight });
+    }
+    res
+}
+
+struct Highlighter<'a, 'db> {
+    sema: &'a Semantics<'db>,
+    file_id: FileId,
+    body: &'a Body,
+    source_map: &'a BodySourceMap,
+    scopes: &'a ExprScopes,
+    version: LuaVersion,
+    mutated: FxHashSet<Local>,
+    consts: FxHashSet<Local>,
+}
+
+impl Highlighter<'_, '_> {
+    fn name(&self, token: &SyntaxToken) -> Option<Highlight> {
+        let parent = token.parent();
+        if ast::DotExpr::can_cast(parent.kind()) {
+            return Some(HlTag::Field.into());
+        }
+        if ast::Attrib::can_cast(parent.kind()) {
+            return Some(HlTag::Keyword.into());
+        }
+        if let Some(name) = ast::Name::cast(parent.clone()) {
+            return self.name_decl(&name);
+        }
+        let name_ref = ast::NameRef::cast(parent)?;
+        let parent = name_ref.syntax().parent()?;
+        if ast::GotoStmt::can_cast(parent.kind()) {
+            return Some(HlTag::Label.into());
+        }
+        if let Some(call) = ast::MethodCallExpr::cast(parent) {
+            if call.method_name().as_ref() == Some(&name_ref) {
+                return Some(HlTag::Method.into());
+            }
+        }
+        let expr = self.source_map.node_expr(InFile::new(self.file_id, &name_ref.into()))?;
+        self.expr(expr)
+    }
+
+    fn name_decl(&self, name: &ast::Name) -> Option<Highlight> {
+        let parent = name.syntax().parent()?;
+        if ast::LabelStmt::can_cast(parent.kind()) {
+            return Some(Highlight::from(HlTag::Label) | HlMod::Declaration);
+        }
+        if ast::KeyValue::can_cast(parent.kind()) {
+            return Some(Highlight::from(HlTag::Field) | HlMod::Declaration);
+        }
+        if ast::FunctionMethod::can_cast(parent.kind()) {
+            return Some(Highlight::from(HlTag::Method) | HlMod::Declaration);
+        }
+        if ast::FunctionStatic::can_cast(parent.kind()) {
+            return Some(Highlight::from(HlTag::Field) | HlMod::Declaration);
+        }
+        if let Some(local) = self.sema.resolve_local(self.file_id, name) {
+            // parameters are in the `MultiName` of the `ParamList`
+            let is_param = parent.parent().is_some_and(|it| ast::ParamList::can_cast(it.kind()));
+            let tag = if is_param { HlTag::Parameter } else { HlTag::Local };
+            return Some(self.local(tag, local) | HlMod::Declaration);
+        }
+
+        // the name of `function f()`, which assigns to `f`
+        let stmt = ast::FunctionDefStmt::cast(parent)?;
+        let stmt = self.source_map.node_stmt(InFile::new(self.file_id, &stmt.into()))?;
+        match &self.body.stmts[stmt] {
+            Stmt::FunctionDef { target, .. } => match self.expr(*target)? {
+                it if it.tag == HlTag::Global => Some(it | HlMod::Declaration),
+                it => Some(it),
+            },
+            _ => None,
+        }
+    }
+
+    fn expr(&self, expr: ExprId) -> Option<Highlight> {
+        if let Expr::Dot { .. } = self.body.exprs[expr] {
+            return Some(HlTag::Field.into());
+        }
+        let hl = match self.scopes.resolve_expr(self.body, expr)? {
+            Resolution::Local { name, kind } => {
+                let tag = if kind == BindingKind::Param { HlTag::Parameter } else { HlTag::Local };
+                self.local(tag, Local { file_id: self.file_id, name })
+            }
+            Resolution::Upvalue { name, .. } => {
+                self.local(HlTag::Upvalue, Local { file_id: self.file_id, name })
+            }
+            Resolution::Global(name) if is_builtin(name.as_str(), self.version) => {
+                HlTag::Builtin.into()
+            }
+            Resolution::Global(_) => HlTag::Global.into(),
+        };
+        Some(hl)
+    }
+
+    fn local(&self, tag: HlTag, local: Local) -> Highlight {
+        let mut hl = Highlight::from(tag);
+        if self.mutated.contains(&local) {
+            hl = hl | HlMod::Mutated;
+        }
+        if self.consts.contains(&local) {
+            hl = hl | HlMod::Const;
+        }
+        hl
+    }
+}
+
+/// The locals that are assigned to, including by `function f()`.
+fn mutated_locals(file_id: FileId, body: &Body, scopes: &ExprScopes) -> FxHashSet<Local> {
+    let mut res = FxHashSet::default();
+    for (_, stmt) in body.stmts.iter() {
+        let targets = match stmt {
+            Stmt::Assign { lhs, .. } => lhs.as_slice(),
+            Stmt::FunctionDef { target, .. } => std::slice::from_ref(target),
+            _ => continue,
+        };
+        let names = targets
+            .iter()
+            .filter_map(|&it| scopes.resolve_expr(body, it))
+            .filter_map(|it| it.name_id())
+            .map(|name| Local { file_id, name });
+        res.extend(names);
+    }
+    res
+}
+
+fn const_locals(
+    file: &ast::SourceFile,
+    file_id: FileId,
+    source_map: &BodySourceMap,
+) -> FxHashSet<Local> {
+    file.syntax()
+        .descendants()
+        .filter_map(ast::MultiName::cast)
+        .flat_map(|it| it.names_with_attribs())
+        .filter(|(_, attrib)| {
+            attrib.as_ref().and_then(|it| it.kind()) == Some(ast::AttribKind::Const)
+        })
+        .filter_map(|(name, _)| source_map.node_name(InFile::new(file_id, &name)))
+        .map(|name| Local { file_id, name })
+        .collect()
+}
+
+/// Whether `name` is a global of the standard library of `version`.
+fn is_builtin(name: &str, version: LuaVersion) -> bool {
+    use LuaVersion::*;
+
+    match name {
+        "_G" | "_VERSION" | "assert" | "collectgarbage" | "dofile" | "error" | "getmetatable"
+        | "ipairs" | "load" | "loadfile" | "next" | "pairs" | "pcall" | "print" | "rawequal"
+        | "rawget" | "rawset" | "require" | "select" | "setmetatable" | "tonumber" | "tostring"
+        | "type" | "xpcall" | "coroutine" | "debug" | "io" | "math" | "os" | "package"
+        | "string" | "table" => true,
+        "getfenv" | "setfenv" | "loadstring" | "module" | "unpack" => {
+            matches!(version, Lua51 | LuaJit)
+        }
+        "rawlen" => matches!(version, Lua52 | Lua53 | Lua54),
+        "bit32" => version == Lua52,
+        "utf8" => matches!(version, Lua53 | Lua54),
+        "warn" => version == Lua54,
+        "bit" | "jit" => version == LuaJit,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::assert_eq_text;
+
+    use crate::fixture;
+
+    use super::*;
+
+    /// Checks the highlights of the names of the fixture, one per line as in
+    /// `x local declaration`.
+    fn check(ra_fixture: &str, expected: &str) {
+        let (analysis, file_id) = fixture::file(ra_fixture);
+        let text = analysis.file_text(file_id).unwrap();
+        let actual: String = analysis
+            .highlight(file_id)
+            .unwrap()
+            .into_iter()
+            .filter(|it| {
+                !matches!(
+                    it.highlight.tag,
+                    HlTag::Keyword | HlTag::Comment | HlTag::String | HlTag::Number
+                )
+            })
+            .map(|it| {
+                let mut line = format!("{} {:?}", &text[it.range], it.highlight.tag);
+                for m in it.highlight.mods.iter() {
+                    line.push_str(&format!(" {:?}", m));
+                }
+                line.to_lowercase() + "\n"
+            })
+            .collect();
+        assert_eq_text!(expected.trim_start(), &actual);
+    }
+
+    #[test]
+    fn highlight_locals_and_upvalues() {
+        check(
+            r#"
+local count = 0
+local function inc(step)
+    count = count + step
+    return count
+end
+"#,
+            r#"
+count local declaration mutated
+inc local declaration
+step parameter declaration
+count upvalue mutated
+count upvalue mutated
+step parameter
+count upvalue mutated
+"#,
+        );
+    }
+
+    #[test]
+    fn highlight_globals_and_builtins() {
+        check(
+            r#"
+config = {}
+print(config, string.format("%d", 1), unknown)
+"#,
+            r#"
+config global
+print builtin
+config global
+string builtin
+format field
+unknown global
+"#,
+        );
+    }
+
+    #[test]
+    fn builtins_depend_on_version() {
+        assert!(is_builtin("unpack", LuaVersion::Lua51));
+        assert!(!is_builtin("unpack", LuaVersion::Lua54));
+        assert!(is_builtin("utf8", LuaVersion::Lua54));
+        assert!(is_builtin("jit", LuaVersion::LuaJit));
+    }
+
+    #[test]
+    fn highlight_fields_and_methods() {
+        check(
+            r#"
+local M = { name = "m" }
+function M.new() end
+function M:greet() self:log(self.name) end
+M:greet()
+"#,
+            r#"
+m local declaration
+name field declaration
+m local
+new field declaration
+m local
+greet method declaration
+self parameter
+log method
+self parameter
+name field
+m local
+greet method
+"#,
+        );
+    }
+
+    #[test]
+    fn highlight_function_definitions() {
+        check(
+            r#"
+local f
+function f() end
+function g() end
+"#,
+            r#"
+f local declaration mutated
+f local mutated
+g global declaration
+"#,
+        );
+    }
+
+    #[test]
+    fn highlight_const_locals() {
+        check(
+            r#"
+local limit <const>, other = 10, 1
+print(limit + other)
+"#,
+            r#"
+limit local declaration const
+other local declaration
+print builtin
+limit local const
+other local
+"#,
+        );
+    }
+
+    #[test]
+    fn highlight_labels() {
+        check(
+            r#"
+::top::
+goto top
+"#,
+            r#"
+top label declaration
+top label
+"#,
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Collects the values assigned to locals, globals and table fields.
+//!
+//! Lua has no declarations for globals and fields, so what is known about
+//! them comes from the places they are assigned to, including the fields of
+//! table constructors such as `t = { a = 1 }`.
+
+use base_db::FileId;
+use hir::{
+    body::Body,
+    expr::{Expr, ExprId, TableField},
+    stmt::Stmt,
+    AccessPath, Local, PathRoot, Semantics,
+};
+
+/// A value assigned to an access path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    pub file_id: FileId,
+    pub path: AccessPath,
+    pub value: ExprId,
+}
+
+/// Every assignment of a known value in the file, in the order of the body.
+pub fn file_assignments(sema: &Semantics, file_id: FileId) -> Vec<Assignment> {
+    let (body, _) = sema.file_body(file_id);
+    let scopes = sema.file_scopes(file_id);
+    let mut res = Vec::new();
+    let mut push = |path: AccessPath, value: ExprId| {
+        collect_table_fields(&body, file_id, &path, value, &mut res);
+        res.push(Assignment { file_id, path, value });
+    };
+    for (_, stmt) in body.stmts.iter() {
+        match stmt {
+            Stmt::Local { names, exprs } => {
+                for (&name, &value) in names.iter().zip(exprs) {
+                    let local = Local { file_id, name };
+                    push(AccessPath { root: PathRoot::Local(local), fields: Vec::new() }, value);
+                }
+            }
+            Stmt::LocalFunction { name, func } => {
+                let local = Local { file_id, name: *name };
+                push(AccessPath { root: PathRoot::Local(local), fields: Vec::new() }, *func);
+            }
+            Stmt::FunctionDef { target, func, .. } => {
+                if let Some(path) = AccessPath::of_expr(file_id, &body, &scopes, *target) {
+                    push(path, *func);
+                }
+            }
+            Stmt::Assign { lhs, rhs } => {
+                for (&target, &value) in lhs.iter().zip(rhs) {
+                    if let Some(path) = AccessPath::of_expr(file_id, &body, &scopes, target) {
+                        push(path, value);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    res
+}
+
+fn collect_table_fields(
+    body: &Body,
+    file_id: FileId,
+    path: &AccessPath,
+    value: ExprId,
+    acc: &mut Vec<Assignment>,
+) {
+    let table = match &body.exprs[value] {
+        Expr::Table(it) => it,
+        _ => return,
+    };
+    for field in &table.fields {
+        if let TableField::Named { name, value } = field {
+            let mut path = path.clone();
+            path.fields.push(name.clone());
+            collect_table_fields(body, file_id, &path, *value, acc);
+            acc.push(Assignment { file_id, path, value: *value });
+        }
+    }
+}
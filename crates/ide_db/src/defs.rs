@@ -0,0 +1,101 @@
+//! `Definition` is what a name in the source refers to, and `classify` finds it
+//! for the token under the cursor.
+
+use base_db::FileId;
+use hir::{AccessPath, AsName, InFile, Label, Local, PathRoot, Semantics};
+use syntax::{
+    ast::{self, AstNode},
+    match_ast, SyntaxToken, T,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Definition {
+    Local(Local),
+    Label(Label),
+    /// A global or a field of a table, such as `a.b.c`, which has no single
+    /// declaration but is assigned to wherever it is written.
+    Path(AccessPath),
+}
+
+impl Definition {
+    pub fn from_path(path: AccessPath) -> Definition {
+        match path.root {
+            PathRoot::Local(local) if path.fields.is_empty() => Definition::Local(local),
+            _ => Definition::Path(path),
+        }
+    }
+
+    pub fn name(&self, db: &dyn hir::HirDatabase) -> hir::Name {
+        match self {
+            Definition::Local(it) => it.name(db),
+            Definition::Label(it) => it.name(db),
+            Definition::Path(it) => it.name(db),
+        }
+    }
+}
+
+/// Finds what an identifier token refers to.
+pub fn classify(sema: &Semantics, file_id: FileId, token: &SyntaxToken) -> Option<Definition> {
+    if token.kind() != T![ident] {
+        return None;
+    }
+    let parent = token.parent();
+    if let Some(dot) = ast::DotExpr::cast(parent.clone()) {
+        return classify_expr(sema, file_id, &dot.into());
+    }
+    match_ast! {
+        match parent {
+            ast::Name(name) => classify_name(sema, file_id, &name),
+            ast::NameRef(name_ref) => classify_name_ref(sema, file_id, &name_ref),
+            _ => None,
+        }
+    }
+}
+
+fn classify_name(sema: &Semantics, file_id: FileId, name: &ast::Name) -> Option<Definition> {
+    let parent = name.syntax().parent()?;
+    if let Some(label) = ast::LabelStmt::cast(parent.clone()) {
+        return sema.resolve_label(file_id, &label).map(Definition::Label);
+    }
+    if let Some(local) = sema.resolve_local(file_id, name) {
+        return Some(Definition::Local(local));
+    }
+    // the name of `function a.b:c()` or of `function a()` is the last part of its target
+    let stmt = parent.ancestors().find_map(ast::FunctionDefStmt::cast)?;
+    let (body, source_map) = sema.file_body(file_id);
+    let stmt = source_map.node_stmt(InFile::new(file_id, &stmt.into()))?;
+    match &body.stmts[stmt] {
+        hir::stmt::Stmt::FunctionDef { target, .. } => {
+            sema.access_path(file_id, *target).map(Definition::from_path)
+        }
+        _ => None,
+    }
+}
+
+fn classify_name_ref(
+    sema: &Semantics,
+    file_id: FileId,
+    name_ref: &ast::NameRef,
+) -> Option<Definition> {
+    let parent = name_ref.syntax().parent()?;
+    if let Some(goto) = ast::GotoStmt::cast(parent.clone()) {
+        return sema.resolve_goto(file_id, &goto).map(Definition::Label);
+    }
+    if let Some(call) = ast::MethodCallExpr::cast(parent) {
+        if call.method_name().as_ref() == Some(name_ref) {
+            let receiver = call.fun()?;
+            let (_, source_map) = sema.file_body(file_id);
+            let receiver = source_map.node_expr(InFile::new(file_id, &receiver))?;
+            let mut path = sema.access_path(file_id, receiver)?;
+            path.fields.push(name_ref.as_name());
+            return Some(Definition::Path(path));
+        }
+    }
+    classify_expr(sema, file_id, &name_ref.clone().into())
+}
+
+fn classify_expr(sema: &Semantics, file_id: FileId, expr: &ast::Expr) -> Option<Definition> {
+    let (_, source_map) = sema.file_body(file_id);
+    let expr = source_map.node_expr(InFile::new(file_id, expr))?;
+    sema.access_path(file_id, expr).map(Definition::from_path)
+}
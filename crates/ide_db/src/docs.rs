@@ -0,0 +1,61 @@
+//! Doc comments are the block of comments directly above a definition, with
+//! no blank line in between.
+//!
+//! The LuaCATS annotations among them, the `---@tag` and `---|` lines, are
+//! parsed by `syntax::doc` and are not part of the text.
+
+use syntax::{NodeOrToken, SyntaxNode, SyntaxToken, T};
+
+/// The text of the comments directly above `node`, without the comment
+/// markers and annotations, or `None` if there are none.
+pub fn doc_comment(node: &SyntaxNode) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut sibling = node.prev_sibling_or_token();
+    while let Some(NodeOrToken::Token(token)) = sibling {
+        match token.kind() {
+            // a comment at the end of the previous line is about that line
+            T![comment] if !starts_line(&token) => break,
+            T![comment] if is_annotation(token.text()) => (),
+            T![comment] => lines.push(comment_text(token.text())),
+            T![whitespace] if token.text().matches('\n').count() < 2 => (),
+            _ => break,
+        }
+        sibling = token.prev_sibling_or_token();
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    lines.reverse();
+    Some(lines.join("\n"))
+}
+
+fn starts_line(token: &SyntaxToken) -> bool {
+    match token.prev_sibling_or_token() {
+        Some(NodeOrToken::Token(it)) => it.kind() == T![whitespace] && it.text().contains('\n'),
+        Some(NodeOrToken::Node(_)) => false,
+        None => true,
+    }
+}
+
+fn is_annotation(comment: &str) -> bool {
+    comment.strip_prefix("---").is_some_and(|it| it.starts_with(['@', '|']))
+}
+
+fn comment_text(comment: &str) -> String {
+    let text = comment.trim_start_matches('-');
+    match long_bracket_level(text) {
+        Some(level) => {
+            let close = format!("]{}]", "=".repeat(level));
+            let text = &text[level + 2..];
+            text.strip_suffix(close.as_str()).unwrap_or(text).trim().to_string()
+        }
+        None => text.strip_prefix(' ').unwrap_or(text).trim_end().to_string(),
+    }
+}
+
+/// The number of `=` of the `[==[` opening a long comment.
+fn long_bracket_level(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('[')?;
+    let level = rest.chars().take_while(|&c| c == '=').count();
+    rest[level..].starts_with('[').then_some(level)
+}
@@ -1,4 +1,11 @@
+pub mod assignments;
+pub mod defs;
+pub mod docs;
 pub mod line_index;
+pub mod search;
+pub mod source_change;
+pub mod symbol_index;
+pub mod types;
 
 use std::{fmt, sync::Arc};
 
@@ -13,11 +20,13 @@ use line_index::LineIndex;
 
 #[salsa::database(
     LineIndexDatabaseStorage,
+    symbol_index::SymbolsDatabaseStorage,
     base_db::SourceDatabaseStorage,
     hir::InternDatabaseStorage,
     hir::AstDatabaseStorage,
     hir::HirDatabaseStorage,
-    hir::DefDatabaseStorage
+    hir::DefDatabaseStorage,
+    hir_ty::HirTyDatabaseStorage
 )]
 pub struct RootDatabase {
     storage: salsa::Storage<RootDatabase>,
@@ -88,7 +97,11 @@ impl salsa::ParallelDatabase for RootDatabase {
 
 impl RootDatabase {
     pub fn new() -> RootDatabase {
-        RootDatabase { storage: salsa::Storage::default() }
+        let mut db = RootDatabase { storage: salsa::Storage::default() };
+        db.set_files(Default::default());
+        db.set_lua_version(Default::default());
+        db.set_lua_version_overrides(Default::default());
+        db
     }
 }
 
@@ -0,0 +1,217 @@
+//! Finds every occurrence of a `Definition`.
+//!
+//! Locals and labels can only be used in the file that declares them, while
+//! globals and their fields are looked up in every file of the workspace.
+
+use base_db::FileId;
+use hir::{expr::Expr, stmt::Stmt, Label, PathRoot, Semantics};
+use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, AstNode},
+    TextRange,
+};
+
+use crate::{assignments::file_assignments, defs::Definition};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceAccess {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReference {
+    pub file_id: FileId,
+    pub range: TextRange,
+    pub access: ReferenceAccess,
+}
+
+impl Definition {
+    /// The files in which the definition can be used.
+    pub fn search_scope(&self, sema: &Semantics) -> Vec<FileId> {
+        match self {
+            Definition::Local(it) => vec![it.file_id],
+            Definition::Label(it) => vec![it.file_id],
+            Definition::Path(it) => match &it.root {
+                PathRoot::Local(local) => vec![local.file_id],
+                PathRoot::Global(_) => sema.db.files().to_vec(),
+            },
+        }
+    }
+
+    /// Finds every read and write of the definition, declarations are writes.
+    /// The references are sorted by file and then by position.
+    pub fn find_usages(&self, sema: &Semantics) -> Vec<FileReference> {
+        let mut res = Vec::new();
+        for file_id in self.search_scope(sema) {
+            let start = res.len();
+            FileSearch::new(sema, file_id, self).run(&mut res);
+            res[start..].sort_by_key(|it: &FileReference| it.range.start());
+        }
+        res
+    }
+}
+
+struct FileSearch<'a, 'db> {
+    sema: &'a Semantics<'db>,
+    file_id: FileId,
+    def: &'a Definition,
+    root: syntax::SyntaxNode,
+}
+
+impl<'a, 'db> FileSearch<'a, 'db> {
+    fn new(sema: &'a Semantics<'db>, file_id: FileId, def: &'a Definition) -> Self {
+        let root = sema.parse(file_id).syntax().clone();
+        FileSearch { sema, file_id, def, root }
+    }
+
+    fn run(&self, acc: &mut Vec<FileReference>) {
+        let (body, source_map) = self.sema.file_body(self.file_id);
+
+        if let Definition::Local(local) = self.def {
+            if let Some(src) = local.source(self.sema.db) {
+                self.push(acc, src.value.syntax().text_range(), ReferenceAccess::Write);
+            }
+        }
+
+        let mut writes = FxHashSet::default();
+        for (_, stmt) in body.stmts.iter() {
+            if let Stmt::Assign { lhs, .. } = stmt {
+                writes.extend(lhs.iter().copied());
+            }
+        }
+
+        for (id, expr) in body.exprs.iter() {
+            let src = match source_map.expr_syntax(id) {
+                Some(it) => it,
+                None => continue,
+            };
+            match expr {
+                Expr::Name(_) | Expr::Dot { .. } => {
+                    let path = match self.sema.access_path(self.file_id, id) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    if Definition::from_path(path) != *self.def {
+                        continue;
+                    }
+                    let node = src.value.to_node(&self.root);
+                    let range = match &node {
+                        ast::Expr::DotExpr(dot) => dot.ident_token().map(|it| it.text_range()),
+                        _ => Some(node.syntax().text_range()),
+                    };
+                    let access = if writes.contains(&id) {
+                        ReferenceAccess::Write
+                    } else {
+                        ReferenceAccess::Read
+                    };
+                    if let Some(range) = range {
+                        self.push(acc, range, access);
+                    }
+                }
+                Expr::Call(call) => {
+                    let method_name = match &call.method_name {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    let mut path = match self.sema.access_path(self.file_id, call.callee) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    path.fields.push(method_name.clone());
+                    if Definition::Path(path) != *self.def {
+                        continue;
+                    }
+                    let name_ref = match src.value.to_node(&self.root) {
+                        ast::Expr::MethodCallExpr(it) => it.method_name(),
+                        _ => None,
+                    };
+                    if let Some(name_ref) = name_ref {
+                        self.push(acc, name_ref.syntax().text_range(), ReferenceAccess::Read);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        // fields of table constructors, as in `t = { a = 1 }`
+        for assignment in file_assignments(self.sema, self.file_id) {
+            if Definition::from_path(assignment.path) != *self.def {
+                continue;
+            }
+            let key = source_map
+                .expr_syntax(assignment.value)
+                .and_then(|src| src.value.to_node(&self.root).syntax().parent())
+                .and_then(ast::KeyValue::cast)
+                .and_then(|it| it.key());
+            if let Some(ast::TableKey::Name(name)) = key {
+                self.push(acc, name.syntax().text_range(), ReferenceAccess::Write);
+            }
+        }
+
+        for (id, stmt) in body.stmts.iter() {
+            let src = match source_map.stmt_syntax(id) {
+                Some(it) => it,
+                None => continue,
+            };
+            match stmt {
+                // only the last name of a function definition target has no syntax of its own
+                Stmt::FunctionDef { target, .. } if source_map.expr_syntax(*target).is_none() => {
+                    let path = match self.sema.access_path(self.file_id, *target) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    if Definition::from_path(path) != *self.def {
+                        continue;
+                    }
+                    let name = match src.value.to_node(&self.root) {
+                        ast::Stmt::FunctionDefStmt(it) => function_def_name(&it),
+                        _ => None,
+                    };
+                    if let Some(name) = name {
+                        self.push(acc, name.syntax().text_range(), ReferenceAccess::Write);
+                    }
+                }
+                Stmt::Label { label } => {
+                    if *self.def
+                        != Definition::Label(Label { file_id: self.file_id, label: *label })
+                    {
+                        continue;
+                    }
+                    if let ast::Stmt::LabelStmt(it) = src.value.to_node(&self.root) {
+                        if let Some(name) = it.name() {
+                            self.push(acc, name.syntax().text_range(), ReferenceAccess::Write);
+                        }
+                    }
+                }
+                Stmt::Goto { .. } => {
+                    let goto = match src.value.to_node(&self.root) {
+                        ast::Stmt::GotoStmt(it) => it,
+                        _ => continue,
+                    };
+                    let label = self.sema.resolve_goto(self.file_id, &goto);
+                    if label.map(Definition::Label).as_ref() != Some(self.def) {
+                        continue;
+                    }
+                    if let Some(name_ref) = goto.name_ref() {
+                        self.push(acc, name_ref.syntax().text_range(), ReferenceAccess::Read);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn push(&self, acc: &mut Vec<FileReference>, range: TextRange, access: ReferenceAccess) {
+        acc.push(FileReference { file_id: self.file_id, range, access });
+    }
+}
+
+/// The name that is being defined by `function a.b:c()`, here `c`.
+pub fn function_def_name(stmt: &ast::FunctionDefStmt) -> Option<ast::Name> {
+    match stmt.function_def_content()? {
+        ast::FunctionDefContent::Name(it) => Some(it),
+        ast::FunctionDefContent::FunctionStatic(it) => it.name(),
+        ast::FunctionDefContent::FunctionMethod(it) => it.name(),
+    }
+}
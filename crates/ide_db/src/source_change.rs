@@ -0,0 +1,38 @@
+//! A `SourceChange` is a set of edits to the files of the workspace, such as
+//! the result of a rename.
+
+use std::collections::hash_map::Entry;
+
+use base_db::FileId;
+use rustc_hash::FxHashMap;
+use text_edit::TextEdit;
+
+#[derive(Default, Debug, Clone)]
+pub struct SourceChange {
+    pub source_file_edits: FxHashMap<FileId, TextEdit>,
+}
+
+impl SourceChange {
+    pub fn from_text_edit(file_id: FileId, edit: TextEdit) -> SourceChange {
+        let mut change = SourceChange::default();
+        change.insert_source_edit(file_id, edit);
+        change
+    }
+
+    /// Adds an edit of `file_id`, merging it with the previous edits of the
+    /// file. The edits must not overlap.
+    pub fn insert_source_edit(&mut self, file_id: FileId, edit: TextEdit) {
+        match self.source_file_edits.entry(file_id) {
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().union(edit).expect("overlapping edits for the same file");
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(edit);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.source_file_edits.is_empty()
+    }
+}
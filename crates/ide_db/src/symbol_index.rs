@@ -0,0 +1,222 @@
+//! The functions and locals declared in each file, as collected by the
+//! `ItemTree`, and the fuzzy search over them used by `workspace/symbol`.
+//!
+//! The symbols of each file are memoized by salsa, and a search goes through
+//! the symbols of every file of the database, which includes the files of the
+//! `workspace_library` roots.
+
+use std::sync::Arc;
+
+use base_db::{salsa, CheckCanceled, FileId};
+use hir::HirDatabase;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxNode, TextRange,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Local,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileSymbol {
+    pub file_id: FileId,
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The range of the whole declaration.
+    pub full_range: TextRange,
+    /// The range of the declared name.
+    pub focus_range: TextRange,
+    /// The table a function is stored in, such as `a.b` for
+    /// `function a.b:c()`.
+    pub container_name: Option<String>,
+    /// The parameters of functions, such as `(a, b, ...)`.
+    pub detail: Option<String>,
+    /// Whether the symbol is a `local` declared inside of a function, which
+    /// can't be seen from the rest of the workspace.
+    pub is_nested: bool,
+}
+
+impl FileSymbol {
+    /// The name with its container, such as `a.b:c`.
+    pub fn qualified_name(&self) -> String {
+        match &self.container_name {
+            Some(container) if self.kind == SymbolKind::Method => {
+                format!("{}:{}", container, self.name)
+            }
+            Some(container) => format!("{}.{}", container, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[salsa::query_group(SymbolsDatabaseStorage)]
+pub trait SymbolsDatabase: HirDatabase + CheckCanceled {
+    fn file_symbols(&self, file_id: FileId) -> Arc<Vec<FileSymbol>>;
+}
+
+fn file_symbols(db: &dyn SymbolsDatabase, file_id: FileId) -> Arc<Vec<FileSymbol>> {
+    let item_tree = db.file_item_tree(file_id);
+    let ast_id_map = db.ast_id_map(file_id);
+    let root = db.parse(file_id).syntax_node();
+    let mut res = Vec::new();
+
+    for func in item_tree.local_functions() {
+        let node = ast_id_map.get(func.ast_id).to_node(&root);
+        let name = match node.name() {
+            Some(it) => it,
+            None => continue,
+        };
+        res.push(FileSymbol {
+            file_id,
+            name: func.name.to_string(),
+            kind: SymbolKind::Function,
+            full_range: node.syntax().text_range(),
+            focus_range: name.syntax().text_range(),
+            container_name: None,
+            detail: Some(func.params.to_string()),
+            is_nested: is_nested(node.syntax()),
+        });
+    }
+
+    for func in item_tree.functions() {
+        let node = ast_id_map.get(func.ast_id).to_node(&root);
+        let name = match crate::search::function_def_name(&node) {
+            Some(it) => it,
+            None => continue,
+        };
+        let segments: Vec<_> = func.path.segments().iter().map(|it| it.as_str()).collect();
+        res.push(FileSymbol {
+            file_id,
+            name: func.name.to_string(),
+            kind: if func.is_method { SymbolKind::Method } else { SymbolKind::Function },
+            full_range: node.syntax().text_range(),
+            focus_range: name.syntax().text_range(),
+            container_name: (!segments.is_empty()).then(|| segments.join(".")),
+            detail: Some(func.params.to_string()),
+            is_nested: false,
+        });
+    }
+
+    for assign in item_tree.local_assigns() {
+        let node = ast_id_map.get(assign.ast_id).to_node(&root);
+        let names = node.multi_name().into_iter().flat_map(|it| it.names());
+        for (name, ast_name) in assign.multi_name.iter().zip(names) {
+            res.push(FileSymbol {
+                file_id,
+                name: name.to_string(),
+                kind: SymbolKind::Local,
+                full_range: node.syntax().text_range(),
+                focus_range: ast_name.syntax().text_range(),
+                container_name: None,
+                detail: None,
+                is_nested: is_nested(node.syntax()),
+            });
+        }
+    }
+
+    res.sort_by_key(|it| (it.full_range.start(), it.focus_range.start()));
+    Arc::new(res)
+}
+
+fn is_nested(node: &SyntaxNode) -> bool {
+    node.ancestors().skip(1).any(|it| {
+        ast::FunctionExpr::can_cast(it.kind())
+            || ast::FunctionDefStmt::can_cast(it.kind())
+            || ast::LocalFunctionDefStmt::can_cast(it.kind())
+    })
+}
+
+/// A fuzzy search for symbols, the characters of the query have to appear in
+/// the name in order, ignoring case.
+#[derive(Debug, Clone)]
+pub struct Query {
+    query: String,
+    limit: usize,
+}
+
+impl Query {
+    pub fn new(query: String) -> Query {
+        Query { query: query.to_lowercase(), limit: usize::MAX }
+    }
+
+    pub fn limit(&mut self, limit: usize) {
+        self.limit = limit
+    }
+
+    /// How well `name` matches, lower is better.
+    fn score(&self, name: &str) -> Option<u8> {
+        let name = name.to_lowercase();
+        let score = if name == self.query {
+            0
+        } else if name.starts_with(&self.query) {
+            1
+        } else if name.contains(&self.query) {
+            2
+        } else {
+            let mut chars = name.chars();
+            if !self.query.chars().all(|c| chars.any(|it| it == c)) {
+                return None;
+            }
+            3
+        };
+        Some(score)
+    }
+}
+
+/// Searches the symbols of every file, leaving out the locals of functions.
+/// The best matches come first.
+pub fn world_symbols(db: &dyn SymbolsDatabase, query: Query) -> Vec<FileSymbol> {
+    let mut res = Vec::new();
+    for &file_id in db.files().iter() {
+        db.check_canceled();
+        for symbol in db.file_symbols(file_id).iter() {
+            if symbol.is_nested {
+                continue;
+            }
+            if let Some(score) = query.score(&symbol.name) {
+                res.push((score, symbol.clone()));
+            }
+        }
+    }
+    res.sort_by(|(score, a), (other_score, b)| {
+        score.cmp(other_score).then_with(|| a.name.cmp(&b.name))
+    });
+    res.into_iter().map(|(_, symbol)| symbol).take(query.limit).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base_db::{Change, FileId};
+
+    use super::{world_symbols, Query};
+    use crate::RootDatabase;
+
+    fn search(files: &[&str], query: &str) -> Vec<String> {
+        let mut db = RootDatabase::new();
+        let mut change = Change::new();
+        for (idx, text) in files.iter().enumerate() {
+            change.change_file(FileId(idx as u32), Some(Arc::new(text.to_string())));
+        }
+        db.apply_change(change);
+        world_symbols(&db, Query::new(query.to_string()))
+            .into_iter()
+            .map(|it| format!("{} {}", it.file_id.0, it.qualified_name()))
+            .collect()
+    }
+
+    #[test]
+    fn searches_every_file() {
+        let files = [
+            "local function parse_args() local parsed = 1 end\nlocal PARSER = {}\n",
+            "function util.pretty_print(x) end\nfunction util.parse() end\n",
+        ];
+        assert_eq!(search(&files, "parse"), ["1 util.parse", "0 PARSER", "0 parse_args"]);
+        assert_eq!(search(&files, "pprint"), ["1 util.pretty_print"]);
+    }
+}
@@ -0,0 +1,51 @@
+//! The inferred types of locals, globals and table fields.
+//!
+//! Every file is inferred on its own, so a global has a type in each of the
+//! files that assign it.
+
+use std::sync::Arc;
+
+use hir::{PathRoot, Semantics, WithBodyId};
+use hir_ty::{HirTyDatabase, InferenceResult, Ty};
+
+use crate::{defs::Definition, RootDatabase};
+
+/// The type of a definition in one file, with the inference result it is
+/// displayed with.
+#[derive(Debug, Clone)]
+pub struct InferredType {
+    pub infer: Arc<InferenceResult>,
+    pub ty: Ty,
+}
+
+/// The known types of the definition in the files it can be used in.
+pub fn def_types(db: &RootDatabase, def: &Definition) -> Vec<InferredType> {
+    let path = match def {
+        Definition::Local(local) => {
+            let infer = db.infer(WithBodyId::ModuleId(local.file_id));
+            let ty = infer.type_of_name(local.name).clone();
+            return known(InferredType { infer, ty }).into_iter().collect();
+        }
+        Definition::Path(it) => it,
+        Definition::Label(_) => return Vec::new(),
+    };
+    let sema = Semantics::new(db);
+    def.search_scope(&sema)
+        .into_iter()
+        .filter_map(|file_id| {
+            let infer = db.infer(WithBodyId::ModuleId(file_id));
+            let mut ty = match &path.root {
+                PathRoot::Local(local) => infer.type_of_name(local.name).clone(),
+                PathRoot::Global(name) => infer.type_of_global(name).clone(),
+            };
+            for field in &path.fields {
+                ty = infer.field_ty(&ty, field);
+            }
+            known(InferredType { infer, ty })
+        })
+        .collect()
+}
+
+fn known(ty: InferredType) -> Option<InferredType> {
+    (ty.ty != Ty::Unknown).then_some(ty)
+}
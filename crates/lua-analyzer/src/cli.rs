@@ -0,0 +1,100 @@
+//! Commands that run without a client.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use ide::{AnalysisHost, Change, FileId};
+use syntax::LuaVersion;
+
+use crate::format_config;
+
+/// Formats the Lua files in `paths`, and every Lua file under them if they
+/// are directories. With `check`, files are only listed when they aren't
+/// formatted and fail the command instead of being written. The files are
+/// parsed as `lua_version`, or else as the version of their `luafmt.toml`.
+pub fn format(paths: &[PathBuf], check: bool, lua_version: Option<LuaVersion>) -> Result<()> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect_lua_files(path, &mut files)?;
+    }
+
+    let mut host = AnalysisHost::new();
+    let mut change = Change::new();
+    if let Some(version) = lua_version {
+        change.set_lua_version(version);
+    }
+    for (idx, path) in files.iter().enumerate() {
+        let file_id = FileId(idx as u32);
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        change.change_file(file_id, Some(Arc::new(text)));
+        if lua_version.is_none() {
+            let version = format_config::lua_version(&fs::canonicalize(path)?)?;
+            change.set_file_lua_version(file_id, version);
+        }
+    }
+    host.apply_change(change);
+    let analysis = host.analysis();
+
+    let mut failed = 0;
+    for (idx, path) in files.iter().enumerate() {
+        let file_id = FileId(idx as u32);
+        let abs_path = fs::canonicalize(path)?;
+        let config = format_config::load(&abs_path, None)?;
+        let edit = match analysis.format(file_id, &config)? {
+            Some(it) => it,
+            None => {
+                eprintln!("{}: not formatted because of syntax errors", path.display());
+                failed += 1;
+                continue;
+            }
+        };
+        if edit.is_empty() {
+            continue;
+        }
+        if check {
+            println!("{}", path.display());
+            failed += 1;
+            continue;
+        }
+        let mut text = analysis.file_text(file_id)?.to_string();
+        edit.apply(&mut text);
+        fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    if failed > 0 {
+        if check {
+            bail!("{} of {} files are not formatted", failed, files.len());
+        }
+        bail!("{} of {} files could not be formatted", failed, files.len());
+    }
+    Ok(())
+}
+
+fn collect_lua_files(path: &Path, acc: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        acc.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .map(|entry| entry.map(|it| it.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for entry in entries {
+        let is_hidden =
+            entry.file_name().and_then(|it| it.to_str()).is_some_and(|it| it.starts_with('.'));
+        if is_hidden {
+            continue;
+        }
+        if entry.is_dir() || entry.extension().is_some_and(|it| it == "lua") {
+            collect_lua_files(&entry, acc)?;
+        }
+    }
+    Ok(())
+}
@@ -1,12 +1,36 @@
 use std::iter;
 
+use globset::Glob;
+use ide::{DiagnosticsConfig, InlayHintsConfig};
 use lsp_types::ClientCapabilities;
-use serde::de::DeserializeOwned;
-use stdx::paths::AbsPathBuf;
+use rustc_hash::FxHashSet;
+use serde::{de::DeserializeOwned, Deserialize};
+use stdx::paths::{AbsPath, AbsPathBuf};
+use syntax::LuaVersion;
 
 config_data! {
     struct ConfigData {
+        /// The version of Lua the files are written in: `5.1`, `5.2`, `5.3`,
+        /// `5.4` or `LuaJIT`.
+        runtime_version: LuaVersionDef = "\"5.4\"",
+        /// The versions of Lua of the files under a directory or matching a
+        /// glob, relative to the workspace root, such as
+        /// `[{ "path": "legacy/**/*.lua", "version": "5.1" }]`. The first one
+        /// that contains a file is used instead of `runtime.version`.
+        runtime_fileVersions: Vec<FileLuaVersionDef> = "[]",
         workspace_library: Vec<String> = "[]",
+        /// The codes of the diagnostics that are not reported, such as
+        /// `not-callable` or `undeclared-field`.
+        diagnostics_disabled: FxHashSet<String> = "[]",
+        /// Whether to show the names of the parameters that literal
+        /// arguments are passed to.
+        inlayHints_parameterHints: bool = "true",
+        /// Whether to show the inferred types of locals whose values don't
+        /// make them obvious.
+        inlayHints_typeHints: bool = "true",
+        /// Whether to show which of the values returned by a call each name
+        /// of a multiple assignment gets.
+        inlayHints_returnIndexHints: bool = "true",
     }
 }
 
@@ -39,6 +63,79 @@ impl Config {
     pub fn library(&self) -> &[String] {
         &self.data.workspace_library
     }
+
+    pub fn diagnostics(&self) -> DiagnosticsConfig {
+        DiagnosticsConfig { disabled: self.data.diagnostics_disabled.clone() }
+    }
+
+    pub fn inlay_hints(&self) -> InlayHintsConfig {
+        InlayHintsConfig {
+            parameter_hints: self.data.inlayHints_parameterHints,
+            type_hints: self.data.inlayHints_typeHints,
+            return_index_hints: self.data.inlayHints_returnIndexHints,
+        }
+    }
+
+    pub fn lua_version(&self) -> LuaVersion {
+        self.data.runtime_version.to_lua_version()
+    }
+
+    /// The version of Lua of the file at `path` when it has one of its own in
+    /// `runtime.fileVersions`.
+    pub fn file_lua_version(&self, path: &AbsPath) -> Option<LuaVersion> {
+        let matches = |pattern: &str| {
+            let pattern = self.root_path.join(pattern);
+            path.starts_with(&pattern)
+                || Glob::new(&pattern.to_string_lossy())
+                    .is_ok_and(|it| it.compile_matcher().is_match(path))
+        };
+        let def = self.data.runtime_fileVersions.iter().find(|it| matches(&it.path))?;
+        Some(def.version.to_lua_version())
+    }
+
+    pub fn hierarchical_symbols(&self) -> bool {
+        (|| -> Option<bool> {
+            self.caps
+                .text_document
+                .as_ref()?
+                .document_symbol
+                .as_ref()?
+                .hierarchical_document_symbol_support
+        })()
+        .unwrap_or(false)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+enum LuaVersionDef {
+    #[serde(rename = "5.1", alias = "Lua 5.1")]
+    Lua51,
+    #[serde(rename = "5.2", alias = "Lua 5.2")]
+    Lua52,
+    #[serde(rename = "5.3", alias = "Lua 5.3")]
+    Lua53,
+    #[serde(rename = "5.4", alias = "Lua 5.4")]
+    Lua54,
+    #[serde(rename = "LuaJIT", alias = "luajit")]
+    LuaJit,
+}
+
+impl LuaVersionDef {
+    fn to_lua_version(self) -> LuaVersion {
+        match self {
+            LuaVersionDef::Lua51 => LuaVersion::Lua51,
+            LuaVersionDef::Lua52 => LuaVersion::Lua52,
+            LuaVersionDef::Lua53 => LuaVersion::Lua53,
+            LuaVersionDef::Lua54 => LuaVersion::Lua54,
+            LuaVersionDef::LuaJit => LuaVersion::LuaJit,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct FileLuaVersionDef {
+    path: String,
+    version: LuaVersionDef,
 }
 
 macro_rules! _config_data {
@@ -6,6 +6,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use crate::{
     global_state::{GlobalState, GlobalStateSnapshot},
     main_loop::Task,
+    LspError,
 };
 
 pub struct RequestDispatcher<'a> {
@@ -152,10 +153,13 @@ where
 {
     match result {
         Ok(resp) => lsp_server::Response::new_ok(id, &resp),
-        Err(e) => lsp_server::Response::new_err(
-            id,
-            lsp_server::ErrorCode::InternalError as i32,
-            e.to_string(),
-        ),
+        Err(e) => match e.downcast::<LspError>() {
+            Ok(lsp_error) => lsp_server::Response::new_err(id, lsp_error.code, lsp_error.message),
+            Err(e) => lsp_server::Response::new_err(
+                id,
+                lsp_server::ErrorCode::InternalError as i32,
+                e.to_string(),
+            ),
+        },
     }
 }
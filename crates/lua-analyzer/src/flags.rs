@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use syntax::LuaVersion;
+
+xflags::xflags! {
+    src "./src/flags.rs"
+
+    /// A language server for Lua
+    cmd lua-analyzer {
+        /// Run the language server over stdio
+        default cmd lsp-server {
+            optional -h, --help
+        }
+
+        /// Format Lua files in place, the ones in the current directory by default
+        cmd format
+            /// The files or directories to format
+            repeated paths: PathBuf
+        {
+            /// List the unformatted files instead, failing if there are any
+            optional --check
+            /// The version of Lua of the files, overriding the one of `luafmt.toml`
+            optional --lua-version version: LuaVersion
+        }
+    }
+}
+// generated start
+// The following code is generated by `xflags` macro.
+// Run `env UPDATE_XFLAGS=1 cargo build` to regenerate.
+#[derive(Debug)]
+pub struct LuaAnalyzer {
+    pub subcommand: LuaAnalyzerCmd,
+}
+
+#[derive(Debug)]
+pub enum LuaAnalyzerCmd {
+    LspServer(LspServer),
+    Format(Format),
+}
+
+#[derive(Debug)]
+pub struct LspServer {
+    pub help: bool,
+}
+
+#[derive(Debug)]
+pub struct Format {
+    pub paths: Vec<PathBuf>,
+
+    pub check: bool,
+    pub lua_version: Option<LuaVersion>,
+}
+
+impl LuaAnalyzer {
+    pub const HELP: &'static str = Self::HELP_;
+
+    pub fn from_env() -> xflags::Result<Self> {
+        Self::from_env_()
+    }
+
+    pub fn from_vec(args: Vec<std::ffi::OsString>) -> xflags::Result<Self> {
+        Self::from_vec_(args)
+    }
+}
+// generated end
@@ -0,0 +1,183 @@
+//! Finds the formatting options of a file in the closest `luafmt.toml`, as in
+//!
+//! ```toml
+//! indent_width = 2
+//! use_tabs = false
+//! max_width = 100
+//! quote_style = "single"           # "double", "single" or "preserve"
+//! table_separator = "comma"        # "comma", "semicolon" or "preserve"
+//! trailing_separator = "multiline" # "always", "never" or "multiline"
+//! lua_version = "5.4"              # "5.1", "5.2", "5.3", "5.4" or "LuaJIT"
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use ide::{FormatConfig, QuoteStyle, TableSeparator, TrailingSeparator};
+use serde::Deserialize;
+use syntax::LuaVersion;
+
+const FILE_NAMES: &[&str] = &["luafmt.toml", ".luafmt.toml"];
+
+/// Returns the options of the file at `path`. The client's options are used
+/// for what the config file doesn't set.
+pub(crate) fn load(
+    path: &Path,
+    client: Option<&lsp_types::FormattingOptions>,
+) -> Result<FormatConfig> {
+    let mut config = FormatConfig::default();
+    if let Some(client) = client {
+        config.indent_width = client.tab_size;
+        config.use_tabs = !client.insert_spaces;
+    }
+
+    if let Some((_, data)) = read_config_file(path)? {
+        data.apply(&mut config);
+    }
+    Ok(config)
+}
+
+/// Returns the version of Lua that the config file of the file at `path`
+/// sets, if any.
+pub(crate) fn lua_version(path: &Path) -> Result<Option<LuaVersion>> {
+    let (config_file, version) = match read_config_file(path)? {
+        Some((config_file, FormatConfigData { lua_version: Some(it), .. })) => (config_file, it),
+        _ => return Ok(None),
+    };
+    let version = version
+        .parse()
+        .map_err(|err| anyhow!("Failed to parse {}: {}", config_file.display(), err))?;
+    Ok(Some(version))
+}
+
+fn read_config_file(path: &Path) -> Result<Option<(PathBuf, FormatConfigData)>> {
+    let config_file = match find_config_file(path) {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let text = fs::read_to_string(&config_file)
+        .with_context(|| format!("Failed to read {}", config_file.display()))?;
+    let data = toml::from_str(&text)
+        .with_context(|| format!("Failed to parse {}", config_file.display()))?;
+    Ok(Some((config_file, data)))
+}
+
+fn find_config_file(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .flat_map(|dir| FILE_NAMES.iter().map(move |name| dir.join(name)))
+        .find(|it| it.is_file())
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FormatConfigData {
+    indent_width: Option<u32>,
+    use_tabs: Option<bool>,
+    max_width: Option<u32>,
+    quote_style: Option<QuoteStyleDef>,
+    table_separator: Option<TableSeparatorDef>,
+    trailing_separator: Option<TrailingSeparatorDef>,
+    lua_version: Option<String>,
+}
+
+impl FormatConfigData {
+    fn apply(self, config: &mut FormatConfig) {
+        if let Some(it) = self.indent_width {
+            config.indent_width = it;
+        }
+        if let Some(it) = self.use_tabs {
+            config.use_tabs = it;
+        }
+        if let Some(it) = self.max_width {
+            config.max_width = it;
+        }
+        if let Some(it) = self.quote_style {
+            config.quote_style = match it {
+                QuoteStyleDef::Double => QuoteStyle::Double,
+                QuoteStyleDef::Single => QuoteStyle::Single,
+                QuoteStyleDef::Preserve => QuoteStyle::Preserve,
+            };
+        }
+        if let Some(it) = self.table_separator {
+            config.table_separator = match it {
+                TableSeparatorDef::Comma => TableSeparator::Comma,
+                TableSeparatorDef::Semicolon => TableSeparator::Semicolon,
+                TableSeparatorDef::Preserve => TableSeparator::Preserve,
+            };
+        }
+        if let Some(it) = self.trailing_separator {
+            config.trailing_separator = match it {
+                TrailingSeparatorDef::Always => TrailingSeparator::Always,
+                TrailingSeparatorDef::Never => TrailingSeparator::Never,
+                TrailingSeparatorDef::Multiline => TrailingSeparator::Multiline,
+            };
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum QuoteStyleDef {
+    Double,
+    Single,
+    Preserve,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum TableSeparatorDef {
+    Comma,
+    Semicolon,
+    Preserve,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum TrailingSeparatorDef {
+    Always,
+    Never,
+    Multiline,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_file_overrides_client() {
+        let data: FormatConfigData =
+            toml::from_str("indent_width = 2\nquote_style = \"single\"").unwrap();
+        let mut config =
+            FormatConfig { indent_width: 8, use_tabs: true, ..FormatConfig::default() };
+        data.apply(&mut config);
+        assert_eq!(
+            config,
+            FormatConfig {
+                indent_width: 2,
+                use_tabs: true,
+                quote_style: QuoteStyle::Single,
+                ..FormatConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn lua_version_of_config_file() {
+        let dir = std::env::temp_dir().join("luafmt-lua-version");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("luafmt.toml"), "lua_version = \"5.1\"").unwrap();
+        let version = lua_version(&dir.join("init.lua")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(version, Some(LuaVersion::Lua51));
+    }
+
+    #[test]
+    fn unknown_options_are_errors() {
+        assert!(toml::from_str::<FormatConfigData>("indent = 2").is_err());
+        assert!(toml::from_str::<FormatConfigData>("quote_style = \"backtick\"").is_err());
+    }
+}
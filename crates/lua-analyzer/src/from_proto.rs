@@ -2,10 +2,12 @@ use std::convert::TryFrom;
 
 use anyhow::{anyhow, Result};
 
-use ide::{LineColUtf16, LineIndex};
+use ide::{FilePosition, LineColUtf16, LineIndex};
 use stdx::paths::AbsPathBuf;
 use syntax::{TextRange, TextSize};
 
+use crate::global_state::GlobalStateSnapshot;
+
 pub(crate) fn abs_path(url: &lsp_types::Url) -> Result<AbsPathBuf> {
     let path = url.to_file_path().map_err(|()| anyhow!("url is not a file"))?;
     Ok(AbsPathBuf::try_from(path).unwrap())
@@ -24,3 +26,13 @@ pub(crate) fn text_range(line_index: &LineIndex, range: lsp_types::Range) -> Tex
     let end = offset(line_index, range.end);
     TextRange::new(start, end)
 }
+
+pub(crate) fn file_position(
+    snap: &GlobalStateSnapshot,
+    tdpp: lsp_types::TextDocumentPositionParams,
+) -> Result<FilePosition> {
+    let file_id = snap.url_to_file_id(&tdpp.text_document.uri)?;
+    let line_index = snap.file_line_index(file_id)?;
+    let offset = offset(&line_index, tdpp.position);
+    Ok(FilePosition { file_id, offset })
+}
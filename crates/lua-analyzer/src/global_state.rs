@@ -1,20 +1,21 @@
 use std::{sync::Arc, time::Instant};
 
+use anyhow::Result;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use ide::{Analysis, AnalysisHost, Cancelable, Change, FileId, LineIndex};
 use log::{error, info, trace};
 use lsp_server::{Notification, Request};
-use lsp_types::Url;
-use parking_lot::RwLock;
+use lsp_types::{SemanticTokens, Url};
+use parking_lot::{Mutex, RwLock};
 use rustc_hash::FxHashMap;
-use stdx::paths::AbsPathBuf;
+use stdx::paths::{AbsPath, AbsPathBuf};
 
 use crate::{
     config::Config,
     diagnostics::DiagnosticCollection,
     dispatch::{NotificationDispatcher, RequestDispatcher},
     document::DocumentData,
-    handlers,
+    from_proto, handlers,
     lsp_utils::is_canceled,
     main_loop::Task,
     thread_pool::TaskPool,
@@ -42,6 +43,8 @@ pub struct GlobalState {
     pub(crate) config: Arc<Config>,
     pub(crate) analysis_host: AnalysisHost,
     pub(crate) vfs_config_version: u32,
+    /// The last tokens sent for each file, which deltas are computed from.
+    pub(crate) semantic_tokens_cache: Arc<Mutex<FxHashMap<Url, SemanticTokens>>>,
 }
 
 impl GlobalState {
@@ -60,6 +63,11 @@ impl GlobalState {
             Handle { handle, receiver }
         };
 
+        let mut analysis_host = AnalysisHost::new();
+        let mut change = Change::new();
+        change.set_lua_version(config.lua_version());
+        analysis_host.apply_change(change);
+
         GlobalState {
             req_queue: ReqQueue::default(),
             sender,
@@ -68,10 +76,11 @@ impl GlobalState {
             loader,
             mem_docs: FxHashMap::default(),
             config: Arc::new(config),
-            analysis_host: AnalysisHost::new(),
+            analysis_host,
             diagnostics: Default::default(),
             shutdown_requested: false,
             vfs_config_version: 0,
+            semantic_tokens_cache: Arc::new(Default::default()),
         }
     }
 
@@ -85,6 +94,12 @@ impl GlobalState {
             }
 
             for file in changed_files {
+                if file.is_created_or_deleted() {
+                    let path = AbsPath::assert(vfs.file_path(file.file_id));
+                    let version =
+                        if file.exists() { self.config.file_lua_version(path) } else { None };
+                    change.set_file_lua_version(file.file_id, version);
+                }
                 let text = if file.exists() {
                     let bytes = vfs.file_contents(file.file_id).to_vec();
                     match String::from_utf8(bytes).ok() {
@@ -110,6 +125,7 @@ impl GlobalState {
             vfs: self.vfs.clone(),
             mem_docs: self.mem_docs.clone(),
             config: self.config.clone(),
+            semantic_tokens_cache: Arc::clone(&self.semantic_tokens_cache),
         }
     }
 
@@ -205,12 +221,23 @@ pub(crate) struct GlobalStateSnapshot {
     vfs: Arc<RwLock<vfs::Vfs>>,
     mem_docs: FxHashMap<AbsPathBuf, DocumentData>,
     pub(crate) config: Arc<Config>,
+    pub(crate) semantic_tokens_cache: Arc<Mutex<FxHashMap<Url, SemanticTokens>>>,
 }
 
 impl GlobalStateSnapshot {
     pub(crate) fn file_line_index(&self, file_id: FileId) -> Cancelable<Arc<LineIndex>> {
         Ok(self.analysis.file_line_index(file_id)?)
     }
+
+    pub(crate) fn url_to_file_id(&self, url: &Url) -> Result<FileId> {
+        let path = from_proto::abs_path(url)?;
+        let vfs = self.vfs.read();
+        vfs.file_id(&path).ok_or_else(|| anyhow::anyhow!("file not found: {}", path.display()))
+    }
+
+    pub(crate) fn file_id_to_url(&self, id: FileId) -> Url {
+        file_id_to_url(&self.vfs.read(), id)
+    }
 }
 
 pub(crate) fn file_id_to_url(vfs: &vfs::Vfs, id: FileId) -> Url {
@@ -1,26 +1,315 @@
 use anyhow::Result;
-use lsp_types::{CompletionItem, CompletionItemKind, CompletionResponse, Diagnostic};
+use ide::{Cancelable, FileRange, Query};
+use lsp_types::{CompletionResponse, Diagnostic, NumberOrString};
 use vfs::FileId;
 
-use crate::{global_state::GlobalStateSnapshot, to_proto};
+use crate::{
+    format_config, from_proto, global_state::GlobalStateSnapshot, lsp_ext, semantic_tokens,
+    to_proto,
+};
 
 pub(crate) fn handle_completion(
     snap: GlobalStateSnapshot,
     params: lsp_types::CompletionParams,
 ) -> Result<Option<lsp_types::CompletionResponse>> {
-    let items = vec![
-        completion_item("first".to_string()),
-        completion_item("second".to_string()),
-        completion_item("third".to_string()),
-        completion_item("hello world!".to_string()),
-    ];
+    let position = from_proto::file_position(&snap, params.text_document_position)?;
+    let items = match snap.analysis.completions(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let line_index = snap.file_line_index(position.file_id)?;
+    let items =
+        items.into_iter().map(|item| to_proto::completion_item(&line_index, item)).collect();
+    Ok(Some(CompletionResponse::Array(items)))
+}
+
+pub(crate) fn handle_goto_definition(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::GotoDefinitionParams,
+) -> Result<Option<lsp_types::GotoDefinitionResponse>> {
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+    let navs = match snap.analysis.goto_definition(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let locations = navs
+        .into_iter()
+        .map(|nav| to_proto::location_from_nav(&snap, nav))
+        .collect::<Cancelable<Vec<_>>>()?;
+    Ok(Some(locations.into()))
+}
+
+pub(crate) fn handle_references(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::ReferenceParams,
+) -> Result<Option<Vec<lsp_types::Location>>> {
+    let include_declaration = params.context.include_declaration;
+    let position = from_proto::file_position(&snap, params.text_document_position)?;
+    let refs = match snap.analysis.find_all_refs(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let declaration = refs.declaration.map(|nav| (nav.file_id, nav.focus_range));
+    let locations = refs
+        .references
+        .into_iter()
+        .filter(|it| include_declaration || Some((it.file_id, it.range)) != declaration)
+        .map(|it| {
+            let range = FileRange { file_id: it.file_id, range: it.range };
+            to_proto::location(&snap, range)
+        })
+        .collect::<Cancelable<Vec<_>>>()?;
+    Ok(Some(locations))
+}
+
+pub(crate) fn handle_document_highlight(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentHighlightParams,
+) -> Result<Option<Vec<lsp_types::DocumentHighlight>>> {
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+    let line_index = snap.file_line_index(position.file_id)?;
+    let highlights = match snap.analysis.highlight_related(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res = highlights
+        .into_iter()
+        .map(|it| lsp_types::DocumentHighlight {
+            range: to_proto::range(&line_index, it.range),
+            kind: Some(to_proto::document_highlight_kind(it.access)),
+        })
+        .collect();
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_hover(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::HoverParams,
+) -> Result<Option<lsp_types::Hover>> {
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+    let hover = match snap.analysis.hover(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let line_index = snap.file_line_index(position.file_id)?;
+    let res = lsp_types::Hover {
+        contents: lsp_types::HoverContents::Markup(lsp_types::MarkupContent {
+            kind: lsp_types::MarkupKind::Markdown,
+            value: hover.markup,
+        }),
+        range: Some(to_proto::range(&line_index, hover.range)),
+    };
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_signature_help(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::SignatureHelpParams,
+) -> Result<Option<lsp_types::SignatureHelp>> {
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+    let help = match snap.analysis.signature_help(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    Ok(Some(to_proto::signature_help(help)))
+}
+
+pub(crate) fn handle_inlay_hints(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::InlayHintParams,
+) -> Result<Option<Vec<lsp_ext::InlayHint>>> {
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let line_index = snap.file_line_index(file_id)?;
+    let range = from_proto::text_range(&line_index, params.range);
+    let hints = snap.analysis.inlay_hints(&snap.config.inlay_hints(), file_id, range)?;
+    Ok(Some(hints.into_iter().map(|it| to_proto::inlay_hint(&line_index, it)).collect()))
+}
+
+pub(crate) fn handle_document_symbol(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentSymbolParams,
+) -> Result<Option<lsp_types::DocumentSymbolResponse>> {
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let line_index = snap.file_line_index(file_id)?;
+
+    let mut parents: Vec<(lsp_types::DocumentSymbol, Option<usize>)> = Vec::new();
+    for node in snap.analysis.file_structure(file_id)? {
+        #[allow(deprecated)]
+        let doc_symbol = lsp_types::DocumentSymbol {
+            name: node.label,
+            detail: node.detail,
+            kind: to_proto::symbol_kind(node.kind),
+            tags: None,
+            deprecated: None,
+            range: to_proto::range(&line_index, node.node_range),
+            selection_range: to_proto::range(&line_index, node.navigation_range),
+            children: None,
+        };
+        parents.push((doc_symbol, node.parent));
+    }
 
-    let response = CompletionResponse::Array(items);
-    Ok(Some(response))
+    // parents always come before their children, so the children can be
+    // moved into their parents from the back
+    let mut document_symbols = Vec::new();
+    while let Some((node, parent)) = parents.pop() {
+        match parent {
+            None => document_symbols.push(node),
+            Some(i) => parents[i].0.children.get_or_insert_with(Vec::new).push(node),
+        }
+    }
+    reverse_symbols(&mut document_symbols);
+
+    let res = if snap.config.hierarchical_symbols() {
+        document_symbols.into()
+    } else {
+        let url = snap.file_id_to_url(file_id);
+        let mut symbol_information = Vec::new();
+        for symbol in document_symbols {
+            flatten_document_symbol(&symbol, None, &url, &mut symbol_information);
+        }
+        symbol_information.into()
+    };
+    Ok(Some(res))
+}
+
+fn reverse_symbols(symbols: &mut Vec<lsp_types::DocumentSymbol>) {
+    symbols.reverse();
+    for symbol in symbols {
+        if let Some(children) = &mut symbol.children {
+            reverse_symbols(children);
+        }
+    }
+}
+
+fn flatten_document_symbol(
+    symbol: &lsp_types::DocumentSymbol,
+    container_name: Option<String>,
+    url: &lsp_types::Url,
+    res: &mut Vec<lsp_types::SymbolInformation>,
+) {
+    #[allow(deprecated)]
+    res.push(lsp_types::SymbolInformation {
+        name: symbol.name.clone(),
+        kind: symbol.kind,
+        tags: None,
+        deprecated: None,
+        location: lsp_types::Location::new(url.clone(), symbol.range),
+        container_name,
+    });
+    for child in symbol.children.iter().flatten() {
+        flatten_document_symbol(child, Some(symbol.name.clone()), url, res);
+    }
+}
+
+pub(crate) fn handle_workspace_symbol(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::WorkspaceSymbolParams,
+) -> Result<Option<Vec<lsp_types::SymbolInformation>>> {
+    let mut query = Query::new(params.query);
+    query.limit(128);
+    let mut res = Vec::new();
+    for symbol in snap.analysis.symbol_search(query)? {
+        let range = FileRange { file_id: symbol.file_id, range: symbol.focus_range };
+        #[allow(deprecated)]
+        res.push(lsp_types::SymbolInformation {
+            name: symbol.name,
+            kind: to_proto::symbol_kind(symbol.kind),
+            tags: None,
+            deprecated: None,
+            location: to_proto::location(&snap, range)?,
+            container_name: symbol.container_name,
+        });
+    }
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_prepare_rename(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::TextDocumentPositionParams,
+) -> Result<Option<lsp_types::PrepareRenameResponse>> {
+    let position = from_proto::file_position(&snap, params)?;
+    let range = snap.analysis.prepare_rename(position)?.map_err(to_proto::rename_error)?;
+    let line_index = snap.file_line_index(position.file_id)?;
+    let range = to_proto::range(&line_index, range);
+    Ok(Some(lsp_types::PrepareRenameResponse::Range(range)))
 }
 
-fn completion_item(s: String) -> CompletionItem {
-    CompletionItem { label: s, kind: Some(CompletionItemKind::Function), ..Default::default() }
+pub(crate) fn handle_rename(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::RenameParams,
+) -> Result<Option<lsp_types::WorkspaceEdit>> {
+    let position = from_proto::file_position(&snap, params.text_document_position)?;
+    let change =
+        snap.analysis.rename(position, &params.new_name)?.map_err(to_proto::rename_error)?;
+    let workspace_edit = to_proto::workspace_edit(&snap, change)?;
+    Ok(Some(workspace_edit))
+}
+
+pub(crate) fn handle_formatting(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentFormattingParams,
+) -> Result<Option<Vec<lsp_types::TextEdit>>> {
+    let path = from_proto::abs_path(&params.text_document.uri)?;
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let config = format_config::load(&path, Some(&params.options))?;
+    let edit = match snap.analysis.format(file_id, &config)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let line_index = snap.file_line_index(file_id)?;
+    Ok(Some(to_proto::text_edit_vec(&line_index, edit)))
+}
+
+pub(crate) fn handle_range_formatting(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentRangeFormattingParams,
+) -> Result<Option<Vec<lsp_types::TextEdit>>> {
+    let path = from_proto::abs_path(&params.text_document.uri)?;
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let config = format_config::load(&path, Some(&params.options))?;
+    let line_index = snap.file_line_index(file_id)?;
+    let range = from_proto::text_range(&line_index, params.range);
+    let edit = match snap.analysis.format_range(FileRange { file_id, range }, &config)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    Ok(Some(to_proto::text_edit_vec(&line_index, edit)))
+}
+
+pub(crate) fn handle_semantic_tokens_full(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::SemanticTokensParams,
+) -> Result<Option<lsp_types::SemanticTokensResult>> {
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let text = snap.analysis.file_text(file_id)?;
+    let line_index = snap.file_line_index(file_id)?;
+    let highlights = snap.analysis.highlight(file_id)?;
+    let tokens = to_proto::semantic_tokens(&text, &line_index, highlights);
+
+    snap.semantic_tokens_cache.lock().insert(params.text_document.uri, tokens.clone());
+    Ok(Some(tokens.into()))
+}
+
+pub(crate) fn handle_semantic_tokens_full_delta(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::SemanticTokensDeltaParams,
+) -> Result<Option<lsp_types::SemanticTokensFullDeltaResult>> {
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let text = snap.analysis.file_text(file_id)?;
+    let line_index = snap.file_line_index(file_id)?;
+    let highlights = snap.analysis.highlight(file_id)?;
+    let tokens = to_proto::semantic_tokens(&text, &line_index, highlights);
+
+    let mut cache = snap.semantic_tokens_cache.lock();
+    let cached = cache.insert(params.text_document.uri, tokens.clone());
+    match cached {
+        Some(prev) if prev.result_id.as_ref() == Some(&params.previous_result_id) => {
+            let edits = semantic_tokens::diff_tokens(&prev.data, &tokens.data);
+            let delta = lsp_types::SemanticTokensDelta { result_id: tokens.result_id, edits };
+            Ok(Some(delta.into()))
+        }
+        _ => Ok(Some(tokens.into())),
+    }
 }
 
 pub(crate) fn publish_diagnostics(
@@ -31,12 +320,12 @@ pub(crate) fn publish_diagnostics(
 
     let diagnostics: Vec<Diagnostic> = snap
         .analysis
-        .diagnostics(file_id)?
+        .diagnostics(&snap.config.diagnostics(), file_id)?
         .into_iter()
         .map(|d| Diagnostic {
             range: to_proto::range(&line_index, d.range),
             severity: Some(to_proto::diagnostic_severity(d.severity)),
-            code: None,
+            code: Some(NumberOrString::String(d.code.as_str().to_string())),
             code_description: None,
             source: Some("lua-analyzer".to_string()),
             message: d.message,
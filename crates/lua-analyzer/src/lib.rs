@@ -1,17 +1,23 @@
 mod caps;
+pub mod cli;
 pub mod config;
 mod diagnostics;
 mod dispatch;
 mod document;
+mod format_config;
 mod from_proto;
 mod global_state;
 mod handlers;
+mod lsp_ext;
 mod lsp_utils;
 mod main_loop;
 mod reload;
+mod semantic_tokens;
 mod thread_pool;
 mod to_proto;
 
+use std::fmt;
+
 use anyhow::{anyhow, Result};
 use serde::de::DeserializeOwned;
 
@@ -23,3 +29,25 @@ pub fn from_json<T: DeserializeOwned>(what: &'static str, json: serde_json::Valu
         .map_err(|e| anyhow!("Failed to deserialize {}: {}; {}", what, e, json))?;
     Ok(res)
 }
+
+/// An error that is reported to the client as is, instead of as an internal
+/// error.
+#[derive(Debug)]
+struct LspError {
+    code: i32,
+    message: String,
+}
+
+impl LspError {
+    fn new(code: i32, message: String) -> LspError {
+        LspError { code, message }
+    }
+}
+
+impl fmt::Display for LspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Language Server request failed with {}. ({})", self.code, self.message)
+    }
+}
+
+impl std::error::Error for LspError {}
@@ -1,7 +1,12 @@
+// `from_vec` is generated but unused
+#[allow(dead_code)]
+mod flags;
+
 use std::{
     convert::TryFrom,
     env,
     fs::{self, File},
+    path::PathBuf,
     process,
 };
 
@@ -22,9 +27,23 @@ fn main() {
 }
 
 fn try_main() -> Result<()> {
-    setup_logging()?;
-
-    run_server()?;
+    use flags::{LuaAnalyzer, LuaAnalyzerCmd::*};
+
+    let flags = LuaAnalyzer::from_env()?;
+    match flags.subcommand {
+        LspServer(cmd) => {
+            if cmd.help {
+                eprintln!("{}", LuaAnalyzer::HELP);
+                return Ok(());
+            }
+            setup_logging()?;
+            run_server()?;
+        }
+        Format(cmd) => {
+            let paths = if cmd.paths.is_empty() { vec![PathBuf::from(".")] } else { cmd.paths };
+            lua_analyzer::cli::format(&paths, cmd.check, cmd.lua_version)?;
+        }
+    }
 
     Ok(())
 }
@@ -43,7 +62,9 @@ fn run_server() -> Result<()> {
 
     let initialize_result =
         lsp_types::InitializeResult { capabilities: server_capabilities, server_info: None };
-    let initialize_result = serde_json::to_value(initialize_result).unwrap();
+    let mut initialize_result = serde_json::to_value(initialize_result).unwrap();
+    // `lsp_types` predates inlay hints, so the capability is added to the JSON
+    initialize_result["capabilities"]["inlayHintProvider"] = serde_json::Value::Bool(true);
 
     connection.initialize_finish(initialize_id, initialize_result)?;
 
@@ -14,7 +14,7 @@ use crate::{
     document::DocumentData,
     from_proto,
     global_state::{file_id_to_url, GlobalState},
-    handlers,
+    handlers, lsp_ext,
     lsp_utils::{apply_document_changes, notification_is},
 };
 
@@ -205,6 +205,20 @@ impl GlobalState {
                 Ok(())
             })?
             .on::<Completion>(handlers::handle_completion)
+            .on::<GotoDefinition>(handlers::handle_goto_definition)
+            .on::<References>(handlers::handle_references)
+            .on::<DocumentHighlightRequest>(handlers::handle_document_highlight)
+            .on::<HoverRequest>(handlers::handle_hover)
+            .on::<SignatureHelpRequest>(handlers::handle_signature_help)
+            .on::<lsp_ext::InlayHintRequest>(handlers::handle_inlay_hints)
+            .on::<DocumentSymbolRequest>(handlers::handle_document_symbol)
+            .on::<WorkspaceSymbol>(handlers::handle_workspace_symbol)
+            .on::<PrepareRenameRequest>(handlers::handle_prepare_rename)
+            .on::<Rename>(handlers::handle_rename)
+            .on::<Formatting>(handlers::handle_formatting)
+            .on::<RangeFormatting>(handlers::handle_range_formatting)
+            .on::<SemanticTokensFullRequest>(handlers::handle_semantic_tokens_full)
+            .on::<SemanticTokensFullDeltaRequest>(handlers::handle_semantic_tokens_full_delta)
             .finish();
 
         Ok(())
@@ -269,6 +283,7 @@ impl GlobalState {
                         None => log::error!("orphan DidCloseTextDocument: {}", path.display()),
                     }
                 }
+                this.semantic_tokens_cache.lock().remove(&params.text_document.uri);
 
                 // Clear the diagnostics for the previously known version of the file.
                 // This prevents stale "cargo check" diagnostics if the file is
@@ -1,5 +1,6 @@
 use std::{mem, path::Path, sync::Arc};
 
+use ide::Change;
 use log::info;
 use stdx::paths::AbsPathBuf;
 
@@ -13,6 +14,18 @@ impl GlobalState {
             self.load_workspace();
         }
 
+        let mut change = Change::new();
+        if self.config.lua_version() != old_config.lua_version() {
+            change.set_lua_version(self.config.lua_version());
+        }
+        for (file_id, path) in self.vfs.read().iter() {
+            let version = self.config.file_lua_version(path);
+            if version != old_config.file_lua_version(path) {
+                change.set_file_lua_version(file_id, version);
+            }
+        }
+        self.analysis_host.apply_change(change);
+
         true
     }
 
@@ -0,0 +1,183 @@
+//! The legend of the semantic tokens and helpers to encode them.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use ide::{HlMod, HlTag};
+use lsp_types::{
+    Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensEdit, SemanticTokensLegend,
+};
+
+pub(crate) const UPVALUE: SemanticTokenType = SemanticTokenType::new("upvalue");
+pub(crate) const GLOBAL: SemanticTokenType = SemanticTokenType::new("global");
+pub(crate) const LABEL: SemanticTokenType = SemanticTokenType::new("label");
+pub(crate) const BUILTIN: SemanticTokenType = SemanticTokenType::new("builtin");
+
+pub(crate) const MUTABLE: SemanticTokenModifier = SemanticTokenModifier::new("mutable");
+
+/// The order of the types and modifiers defines their indices in the encoded
+/// tokens.
+const SUPPORTED_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::METHOD,
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+    UPVALUE,
+    GLOBAL,
+    LABEL,
+    BUILTIN,
+];
+
+const SUPPORTED_MODIFIERS: &[SemanticTokenModifier] =
+    &[SemanticTokenModifier::DECLARATION, MUTABLE, SemanticTokenModifier::READONLY];
+
+pub(crate) fn semantic_tokens_legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: SUPPORTED_TYPES.to_vec(),
+        token_modifiers: SUPPORTED_MODIFIERS.to_vec(),
+    }
+}
+
+pub(crate) fn token_type(tag: HlTag) -> SemanticTokenType {
+    match tag {
+        HlTag::Local => SemanticTokenType::VARIABLE,
+        HlTag::Parameter => SemanticTokenType::PARAMETER,
+        HlTag::Upvalue => UPVALUE,
+        HlTag::Global => GLOBAL,
+        HlTag::Builtin => BUILTIN,
+        HlTag::Field => SemanticTokenType::PROPERTY,
+        HlTag::Method => SemanticTokenType::METHOD,
+        HlTag::Label => LABEL,
+        HlTag::Keyword => SemanticTokenType::KEYWORD,
+        HlTag::Comment => SemanticTokenType::COMMENT,
+        HlTag::String => SemanticTokenType::STRING,
+        HlTag::Number => SemanticTokenType::NUMBER,
+    }
+}
+
+pub(crate) fn token_modifier(m: HlMod) -> SemanticTokenModifier {
+    match m {
+        HlMod::Declaration => SemanticTokenModifier::DECLARATION,
+        HlMod::Mutated => MUTABLE,
+        HlMod::Const => SemanticTokenModifier::READONLY,
+    }
+}
+
+pub(crate) fn type_index(ty: SemanticTokenType) -> u32 {
+    SUPPORTED_TYPES.iter().position(|it| *it == ty).unwrap() as u32
+}
+
+pub(crate) fn modifier_bitset(modifiers: impl Iterator<Item = SemanticTokenModifier>) -> u32 {
+    modifiers
+        .map(|m| SUPPORTED_MODIFIERS.iter().position(|it| *it == m).unwrap())
+        .fold(0, |acc, idx| acc | 1 << idx)
+}
+
+/// Tokenizes semantic tokens, which are sent relative to the previous one.
+pub(crate) struct SemanticTokensBuilder {
+    id: String,
+    prev_line: u32,
+    prev_char: u32,
+    data: Vec<SemanticToken>,
+}
+
+impl SemanticTokensBuilder {
+    pub(crate) fn new() -> SemanticTokensBuilder {
+        static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst).to_string();
+        SemanticTokensBuilder { id, prev_line: 0, prev_char: 0, data: Vec::new() }
+    }
+
+    /// Pushes a token, which must be on a single line and after the previous
+    /// one.
+    pub(crate) fn push(&mut self, range: Range, token_type: u32, token_modifiers_bitset: u32) {
+        let mut delta_line = range.start.line;
+        let mut delta_start = range.start.character;
+        if !self.data.is_empty() {
+            delta_line -= self.prev_line;
+            if delta_line == 0 {
+                delta_start -= self.prev_char;
+            }
+        }
+
+        self.data.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length: range.end.character - range.start.character,
+            token_type,
+            token_modifiers_bitset,
+        });
+        self.prev_line = range.start.line;
+        self.prev_char = range.start.character;
+    }
+
+    pub(crate) fn build(self) -> SemanticTokens {
+        SemanticTokens { result_id: Some(self.id), data: self.data }
+    }
+}
+
+/// Computes the edit that turns `old` into `new`, which replaces everything
+/// between their common prefix and suffix.
+pub(crate) fn diff_tokens(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(old, new)| old == new).count();
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = old.iter().rev().zip(new.iter().rev()).take_while(|(old, new)| old == new).count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    if old.is_empty() && new.is_empty() {
+        return Vec::new();
+    }
+    // the offsets are in units of the five integers a token is encoded as
+    vec![SemanticTokensEdit {
+        start: 5 * prefix as u32,
+        delete_count: 5 * old.len() as u32,
+        data: Some(new.to_vec()),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(delta_start: u32) -> SemanticToken {
+        SemanticToken {
+            delta_line: 0,
+            delta_start,
+            length: 1,
+            token_type: 0,
+            token_modifiers_bitset: 0,
+        }
+    }
+
+    #[test]
+    fn diff_replaces_the_middle() {
+        let old = [token(1), token(2), token(3), token(4)];
+        let new = [token(1), token(5), token(6), token(4)];
+        let edits = diff_tokens(&old, &new);
+        assert_eq!(
+            edits,
+            vec![SemanticTokensEdit {
+                start: 5,
+                delete_count: 10,
+                data: Some(vec![token(5), token(6)])
+            }]
+        );
+    }
+
+    #[test]
+    fn diff_inserts_and_deletes() {
+        let old = [token(1), token(2)];
+        let new = [token(1), token(3), token(2)];
+        let edits = diff_tokens(&old, &new);
+        assert_eq!(
+            edits,
+            vec![SemanticTokensEdit { start: 5, delete_count: 0, data: Some(vec![token(3)]) }]
+        );
+        assert_eq!(diff_tokens(&new, &old)[0].delete_count, 5);
+        assert!(diff_tokens(&old, &old).is_empty());
+    }
+}
@@ -1,9 +1,15 @@
 use std::path::{self, Path};
 
-use ide::{LineIndex, Severity};
+use ide::{
+    Cancelable, CompletionItem, CompletionItemKind, FileRange, HlRange, Indel, InlayHint,
+    InlayKind, LineIndex, NavigationTarget, ReferenceAccess, RenameError, Severity, SignatureHelp,
+    SourceChange, SymbolKind, TextEdit,
+};
 use itertools::Itertools;
 use syntax::{TextRange, TextSize};
 
+use crate::{global_state::GlobalStateSnapshot, lsp_ext, semantic_tokens, LspError};
+
 pub(crate) fn position(line_index: &LineIndex, offset: TextSize) -> lsp_types::Position {
     let line_col = line_index.line_col(offset);
     let line_col = line_index.to_utf16(line_col);
@@ -19,10 +25,186 @@ pub(crate) fn range(line_index: &LineIndex, range: TextRange) -> lsp_types::Rang
 pub(crate) fn diagnostic_severity(severity: Severity) -> lsp_types::DiagnosticSeverity {
     match severity {
         Severity::Error => lsp_types::DiagnosticSeverity::Error,
+        Severity::Warning => lsp_types::DiagnosticSeverity::Warning,
         Severity::WeakWarning => lsp_types::DiagnosticSeverity::Hint,
     }
 }
 
+pub(crate) fn document_highlight_kind(access: ReferenceAccess) -> lsp_types::DocumentHighlightKind {
+    match access {
+        ReferenceAccess::Read => lsp_types::DocumentHighlightKind::Read,
+        ReferenceAccess::Write => lsp_types::DocumentHighlightKind::Write,
+    }
+}
+
+pub(crate) fn symbol_kind(kind: SymbolKind) -> lsp_types::SymbolKind {
+    match kind {
+        SymbolKind::Function => lsp_types::SymbolKind::Function,
+        SymbolKind::Method => lsp_types::SymbolKind::Method,
+        SymbolKind::Local => lsp_types::SymbolKind::Variable,
+    }
+}
+
+pub(crate) fn completion_item_kind(kind: CompletionItemKind) -> lsp_types::CompletionItemKind {
+    match kind {
+        CompletionItemKind::Local | CompletionItemKind::Param | CompletionItemKind::Global => {
+            lsp_types::CompletionItemKind::Variable
+        }
+        CompletionItemKind::Function => lsp_types::CompletionItemKind::Function,
+        CompletionItemKind::Field => lsp_types::CompletionItemKind::Field,
+        CompletionItemKind::Method => lsp_types::CompletionItemKind::Method,
+        CompletionItemKind::Keyword => lsp_types::CompletionItemKind::Keyword,
+    }
+}
+
+pub(crate) fn completion_item(
+    line_index: &LineIndex,
+    item: CompletionItem,
+) -> lsp_types::CompletionItem {
+    let edit = lsp_types::TextEdit {
+        range: range(line_index, item.source_range),
+        new_text: item.label.clone(),
+    };
+    lsp_types::CompletionItem {
+        label: item.label,
+        kind: Some(completion_item_kind(item.kind)),
+        detail: item.detail,
+        text_edit: Some(edit.into()),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn text_edit(line_index: &LineIndex, indel: Indel) -> lsp_types::TextEdit {
+    let range = range(line_index, indel.delete);
+    lsp_types::TextEdit { range, new_text: indel.insert }
+}
+
+pub(crate) fn text_edit_vec(
+    line_index: &LineIndex,
+    text_edit: TextEdit,
+) -> Vec<lsp_types::TextEdit> {
+    text_edit.into_iter().map(|indel| self::text_edit(line_index, indel)).collect()
+}
+
+pub(crate) fn workspace_edit(
+    snap: &GlobalStateSnapshot,
+    source_change: SourceChange,
+) -> Cancelable<lsp_types::WorkspaceEdit> {
+    let mut changes = std::collections::HashMap::new();
+    for (file_id, edit) in source_change.source_file_edits {
+        let line_index = snap.file_line_index(file_id)?;
+        changes.insert(snap.file_id_to_url(file_id), text_edit_vec(&line_index, edit));
+    }
+    Ok(lsp_types::WorkspaceEdit { changes: Some(changes), ..Default::default() })
+}
+
+pub(crate) fn rename_error(err: RenameError) -> LspError {
+    LspError::new(lsp_server::ErrorCode::InvalidParams as i32, err.to_string())
+}
+
+pub(crate) fn location(
+    snap: &GlobalStateSnapshot,
+    frange: FileRange,
+) -> Cancelable<lsp_types::Location> {
+    let url = snap.file_id_to_url(frange.file_id);
+    let line_index = snap.file_line_index(frange.file_id)?;
+    let range = range(&line_index, frange.range);
+    Ok(lsp_types::Location::new(url, range))
+}
+
+pub(crate) fn location_from_nav(
+    snap: &GlobalStateSnapshot,
+    nav: NavigationTarget,
+) -> Cancelable<lsp_types::Location> {
+    location(snap, FileRange { file_id: nav.file_id, range: nav.focus_range })
+}
+
+pub(crate) fn inlay_hint(line_index: &LineIndex, hint: InlayHint) -> lsp_ext::InlayHint {
+    match hint.kind {
+        InlayKind::ParameterHint => lsp_ext::InlayHint {
+            position: position(line_index, hint.range.start()),
+            label: format!("{}:", hint.label),
+            kind: Some(lsp_ext::InlayHintKind::PARAMETER),
+            padding_left: false,
+            padding_right: true,
+        },
+        InlayKind::TypeHint => lsp_ext::InlayHint {
+            position: position(line_index, hint.range.end()),
+            label: format!(": {}", hint.label),
+            kind: Some(lsp_ext::InlayHintKind::TYPE),
+            padding_left: false,
+            padding_right: false,
+        },
+        InlayKind::ReturnIndexHint => lsp_ext::InlayHint {
+            position: position(line_index, hint.range.start()),
+            label: hint.label,
+            kind: None,
+            padding_left: false,
+            padding_right: true,
+        },
+    }
+}
+
+pub(crate) fn signature_help(help: SignatureHelp) -> lsp_types::SignatureHelp {
+    let markdown = |value| {
+        lsp_types::Documentation::MarkupContent(lsp_types::MarkupContent {
+            kind: lsp_types::MarkupKind::Markdown,
+            value,
+        })
+    };
+    // the offsets of parameters are in UTF-16 code units
+    let utf16_len = |text: &str| text.encode_utf16().count() as u32;
+    let signature = &help.signature;
+    let parameters = help
+        .parameters
+        .into_iter()
+        .map(|param| {
+            let start = utf16_len(&signature[..usize::from(param.range.start())]);
+            let len = utf16_len(&signature[param.range]);
+            lsp_types::ParameterInformation {
+                label: lsp_types::ParameterLabel::LabelOffsets([start, start + len]),
+                documentation: param.doc.map(markdown),
+            }
+        })
+        .collect();
+    let active_parameter = help.active_parameter.map(|it| it as u32);
+    let signature = lsp_types::SignatureInformation {
+        label: help.signature,
+        documentation: help.doc.map(markdown),
+        parameters: Some(parameters),
+        active_parameter,
+    };
+    lsp_types::SignatureHelp {
+        signatures: vec![signature],
+        active_signature: Some(0),
+        active_parameter,
+    }
+}
+
+pub(crate) fn semantic_tokens(
+    text: &str,
+    line_index: &LineIndex,
+    highlights: Vec<HlRange>,
+) -> lsp_types::SemanticTokens {
+    let mut builder = semantic_tokens::SemanticTokensBuilder::new();
+    for hl in highlights {
+        let ty = semantic_tokens::type_index(semantic_tokens::token_type(hl.highlight.tag));
+        let mods = semantic_tokens::modifier_bitset(
+            hl.highlight.mods.iter().map(semantic_tokens::token_modifier),
+        );
+        // long strings and comments can span lines, but tokens can't
+        let mut start = hl.range.start();
+        for line in text[hl.range].split_inclusive('\n') {
+            let len = TextSize::of(line.trim_end_matches(&['\n', '\r'][..]));
+            if len > TextSize::from(0) {
+                builder.push(range(line_index, TextRange::at(start, len)), ty, mods);
+            }
+            start += TextSize::of(line);
+        }
+    }
+    builder.build()
+}
+
 /// Returns a `Url` object from a given path, will lowercase drive letters if present.
 /// This will only happen when processing windows paths.
 ///
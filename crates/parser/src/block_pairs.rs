@@ -0,0 +1,148 @@
+//! Pairs the keywords that open a block with the `end` or `until` that closes
+//! it before parsing, so the parser knows which blocks are never closed.
+
+use crate::{SyntaxKind, TokenPos};
+
+#[derive(Debug)]
+struct Open {
+    idx: u32,
+    kind: SyntaxKind,
+    indent: u32,
+    /// A `while` or `for` waiting for its `do`.
+    wants_do: bool,
+}
+
+/// Returns the indices of the block openers that have no closer, sorted.
+///
+/// Code that pairs up is trusted as is. Only when something is left unclosed,
+/// an `end` that starts a line closes the innermost opener with the same
+/// indentation, and every opener between the two is the one missing its `end`.
+pub(crate) fn unclosed_openers(tokens: &[(SyntaxKind, TokenPos)]) -> Vec<u32> {
+    let (unclosed, _) = pair(tokens, false);
+    if unclosed.is_empty() {
+        return unclosed;
+    }
+    let (mut unclosed, _) = pair(tokens, true);
+    unclosed.sort_unstable();
+    unclosed
+}
+
+/// Whether every opener is closed and every closer closes something.
+pub(crate) fn is_balanced(kinds: impl Iterator<Item = SyntaxKind>) -> bool {
+    let tokens: Vec<_> = kinds.map(|kind| (kind, TokenPos::default())).collect();
+    let (unclosed, stray) = pair(&tokens, false);
+    unclosed.is_empty() && !stray
+}
+
+/// Returns the unclosed openers and whether a closer had nothing to close.
+fn pair(tokens: &[(SyntaxKind, TokenPos)], use_indent: bool) -> (Vec<u32>, bool) {
+    let mut stack: Vec<Open> = Vec::new();
+    let mut unclosed = Vec::new();
+    let mut stray = false;
+
+    for (idx, &(kind, pos)) in tokens.iter().enumerate() {
+        let open = |wants_do| Open { idx: idx as u32, kind, indent: pos.indent, wants_do };
+        match kind {
+            T![function] | T![if] | T![repeat] => stack.push(open(false)),
+            T![while] | T![for] => stack.push(open(true)),
+            T![do] => match stack.last_mut() {
+                Some(top) if top.wants_do => top.wants_do = false,
+                _ => stack.push(open(false)),
+            },
+            T![end] | T![until] => {
+                let closes = |o: &Open| (o.kind == T![repeat]) == (kind == T![until]);
+                let by_indent = if use_indent && pos.line_start {
+                    stack.iter().rposition(|o| closes(o) && o.indent == pos.indent)
+                } else {
+                    None
+                };
+                match by_indent.or_else(|| stack.iter().rposition(closes)) {
+                    Some(i) => {
+                        unclosed.extend(stack.drain(i + 1..).map(|o| o.idx));
+                        stack.pop();
+                    }
+                    None => stray = true,
+                }
+            }
+            _ => (),
+        }
+    }
+
+    unclosed.extend(stack.into_iter().map(|o| o.idx));
+    (unclosed, stray)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every line is `indent` spaces followed by space separated keywords.
+    fn check(text: &str, expected: &[&str]) {
+        let mut tokens = Vec::new();
+        let mut words = Vec::new();
+        for (line, text) in text.lines().enumerate() {
+            let indent = text.len() - text.trim_start().len();
+            for (i, word) in text.split_whitespace().enumerate() {
+                let kind = match word {
+                    "function" => T![function],
+                    "if" => T![if],
+                    "then" => T![then],
+                    "repeat" => T![repeat],
+                    "until" => T![until],
+                    "while" => T![while],
+                    "for" => T![for],
+                    "do" => T![do],
+                    "end" => T![end],
+                    _ => T![ident],
+                };
+                let pos = TokenPos { line: line as u32, indent: indent as u32, line_start: i == 0 };
+                tokens.push((kind, pos));
+                words.push(format!("{}@{}", word, line + 1));
+            }
+        }
+        let actual: Vec<_> =
+            unclosed_openers(&tokens).into_iter().map(|idx| words[idx as usize].as_str()).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn balanced_blocks() {
+        check("function\n  if then end\n  while do end\nend", &[]);
+        check("repeat\n  for do end\nuntil x", &[]);
+    }
+
+    #[test]
+    fn oddly_indented_balanced_blocks() {
+        check("function\n  if then x\nend end", &[]);
+    }
+
+    #[test]
+    fn unclosed_at_eof() {
+        check("function\n  if then\n    x\n  end", &["function@1"]);
+    }
+
+    #[test]
+    fn indentation_pairs_end() {
+        check("function\n  if then\n    x\n  y\nend", &["if@2"]);
+        check("function\n  while do\n    for do\n  end\nend", &["for@3"]);
+    }
+
+    #[test]
+    fn until_closes_repeat() {
+        check("repeat\n  if then\nuntil x", &["if@2"]);
+    }
+
+    #[test]
+    fn stray_end() {
+        check("if then\nend\nend", &[]);
+    }
+
+    #[test]
+    fn balanced() {
+        let kinds = |kinds: &[SyntaxKind]| is_balanced(kinds.iter().copied());
+        assert!(kinds(&[T![while], T![do], T![do], T![end], T![end]]));
+        assert!(kinds(&[T![repeat], T![function], T![end], T![until]]));
+        assert!(!kinds(&[T![if], T![end], T![end]]));
+        assert!(!kinds(&[T![repeat], T![end]]));
+    }
+}
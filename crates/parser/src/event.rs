@@ -5,7 +5,10 @@ use crate::{ParseError, SyntaxKind, TreeSink};
 /// TODO: add node error event
 #[derive(Debug)]
 pub enum Event {
-    Start { kind: SyntaxKind, forward_parent: Option<u32> },
+    Start {
+        kind: SyntaxKind,
+        forward_parent: Option<u32>,
+    },
 
     Finish,
 
@@ -16,6 +19,9 @@ pub enum Event {
     Token,
 
     Error(ParseError),
+
+    /// An error about the token that was just added
+    TokenError(ParseError),
 }
 
 impl Event {
@@ -59,6 +65,7 @@ pub(super) fn process<TS: TreeSink>(mut sink: TS, mut events: Vec<Event>) -> TS:
                 }
             }
             Event::Error(e) => sink.error(e),
+            Event::TokenError(e) => sink.token_error(e),
             Event::Finish => sink.finish_node(),
             Event::Token => sink.token(),
             Event::StartError => sink.start_error_node(),
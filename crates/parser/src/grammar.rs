@@ -4,12 +4,12 @@ mod statements;
 use accept::Acceptor;
 
 use crate::{
-    parser::{MarkerComplete, Parser},
+    parser::{MarkerComplete, Opener, Parser},
     SyntaxKind, TokenSet, TS,
 };
-use expressions::expr_single;
 pub use expressions::LuaOp;
-use statements::{stmt, STMT_RECOVERY};
+use expressions::{expr_single, table_expr};
+use statements::{stmt, stmt_without_semicolon, STMT_RECOVERY};
 
 const RECOVERY_SET: TokenSet = TS![function, do, ::];
 
@@ -56,6 +56,33 @@ fn multi_name_r(p: &mut Parser, recovery: TokenSet, vararg: bool) {
     m.complete(p, N![MultiName]);
 }
 
+/// The names of a `local` statement, each of which can have an attribute such
+/// as `<const>`.
+fn attrib_multi_name_r(p: &mut Parser, recovery: TokenSet) {
+    let recovery = recovery.union(TokenSet::new(&[T![,], T![<]]));
+
+    let m = p.start();
+    loop {
+        name_r(p, recovery);
+        if p.at(T![<]) {
+            attrib(p);
+        }
+        if !p.accept(T![,]) {
+            break;
+        }
+    }
+    m.complete(p, N![MultiName]);
+}
+
+fn attrib(p: &mut Parser) {
+    assert!(p.at(T![<]));
+    let m = p.start();
+    p.bump(T![<]);
+    p.expect(T![ident]);
+    p.expect(T![>]);
+    m.complete(p, N![Attrib]);
+}
+
 fn name_r(p: &mut Parser, recovery: TokenSet) -> Option<MarkerComplete> {
     if p.at(T![ident]) {
         Some(name_unchecked(p))
@@ -101,20 +128,32 @@ fn name(p: &mut Parser) {
     }
 }
 
-fn block_raw(p: &mut Parser, end: TokenSet) {
+const BLOCK_END: TokenSet = TS![eof, end, elseif, else, until];
+
+/// Parses statements until `end`. When `opener` is never closed, the block
+/// also ends at the first statement that is indented less than it.
+fn block_raw(p: &mut Parser, end: TokenSet, opener: Option<Opener>) {
     let m = p.start();
     while !p.at(end) {
+        if opener.is_some_and(|it| p.at_dedent(it)) {
+            break;
+        }
         stmt(p);
     }
     m.complete(p, N![Block]);
 }
 
 fn root_block(p: &mut Parser) {
-    block_raw(p, TS![eof])
+    block_raw(p, TS![eof], None)
 }
 
 pub(crate) fn block(p: &mut Parser) {
-    block_raw(p, TS![eof, end, elseif, else, until]);
+    block_raw(p, BLOCK_END, None);
+}
+
+/// The block of `opener`.
+fn block_in(p: &mut Parser, opener: Opener) {
+    block_raw(p, BLOCK_END, Some(opener));
 }
 
 const VARARG_ERROR_MSG: &str = "Nothing can be after a vararg";
@@ -144,6 +183,23 @@ fn param_list(p: &mut Parser) -> MarkerComplete {
 pub(crate) fn reparser(node: SyntaxKind) -> Option<fn(&mut Parser)> {
     Some(match node {
         N![Block] => block,
+        N![TableExpr] => |p| {
+            table_expr(p);
+        },
+        N![LocalAssignStmt]
+        | N![LocalFunctionDefStmt]
+        | N![FunctionDefStmt]
+        | N![AssignStmt]
+        | N![ExprStmt]
+        | N![IfStmt]
+        | N![WhileStmt]
+        | N![ForStmt]
+        | N![RepeatUntilStmt]
+        | N![DoStmt]
+        | N![ReturnStmt]
+        | N![BreakStmt]
+        | N![GotoStmt]
+        | N![LabelStmt] => stmt_without_semicolon,
         _ => return None,
     })
 }
@@ -1,13 +1,13 @@
-use accept::{Any, source::Source, Acceptor};
+use accept::{source::Source, Acceptor, Any};
 use binding_powers::{precedences, Operator, LOWEST, NOT_AN_OP, NOT_AN_OP_INFIX, NOT_AN_OP_PREFIX};
 
-use super::{block, name_ref, name_unchecked, param_list};
+use super::{block_in, name_ref, name_unchecked, param_list, statements::STMT_RECOVERY};
 use crate::{
     parser::{MarkerComplete, Parser},
     SyntaxKind, TokenSet, TS,
 };
 
-pub(super) const EXPR_RECOVERY_SET: TokenSet = TS![local];
+pub(super) const EXPR_RECOVERY_SET: TokenSet = STMT_RECOVERY.union(TS![then]);
 
 precedences! {
     pub enum LuaOp {
@@ -20,53 +20,84 @@ precedences! {
         #[Infix, Left]
         Lt,
 
-        #[Infix, Left]
+        #[Infix, Left, Same]
         Gt,
 
-        #[Infix, Left]
+        #[Infix, Left, Same]
         LtEq,
 
-        #[Infix, Left]
+        #[Infix, Left, Same]
         GtEq,
 
-        #[Infix, Left]
+        #[Infix, Left, Same]
         NotEq,
 
-        #[Infix, Left]
+        #[Infix, Left, Same]
         Eq,
 
+        #[Infix, Left]
+        BitOr,
+
+        #[Infix, Left]
+        BitXor,
+
+        #[Infix, Left]
+        BitAnd,
+
+        #[Infix, Left]
+        Shl,
+
+        #[Infix, Left, Same]
+        Shr,
+
         #[Infix, Right]
         Concat,
 
         #[Infix, Left]
         Plus,
 
-        #[Infix, Left]
+        #[Infix, Left, Same]
         Minus,
 
         #[Infix, Left]
         Mul,
 
-        #[Infix, Left]
+        #[Infix, Left, Same]
         Div,
 
-        #[Infix, Left]
+        #[Infix, Left, Same]
+        FloorDiv,
+
+        #[Infix, Left, Same]
         Mod,
 
         #[Prefix]
         Not,
 
-        #[Prefix]
+        #[Prefix, Same]
         Len,
 
-        #[Prefix]
+        #[Prefix, Same]
         Minus,
 
+        // the unary `~` is the bitwise not
+        #[Prefix, Same]
+        BitXor,
+
         #[Infix, Right]
         Power,
     }
 }
 
+impl LuaOp {
+    /// How tightly the operator binds as a binary one, the operators with a
+    /// higher precedence are applied first.
+    pub fn infix_precedence(&self) -> u8 {
+        let (left, right) = self.infix_power();
+        left.min(right)
+    }
+}
+
 impl From<SyntaxKind> for Option<LuaOp> {
     fn from(kind: SyntaxKind) -> Option<LuaOp> {
         use LuaOp::*;
@@ -88,6 +119,12 @@ impl From<SyntaxKind> for Option<LuaOp> {
             T![*] => Mul,
             T![/] => Div,
             T![%] => Mod,
+            T![/ /] => FloorDiv,
+            T![&] => BitAnd,
+            T![|] => BitOr,
+            T![~] => BitXor,
+            T![<<] => Shl,
+            T![>>] => Shr,
             T![^] => Power,
             _ => return None,
         })
@@ -146,7 +183,7 @@ fn expr_bp(p: &mut Parser, min_bp: u8) -> Option<MarkerComplete> {
     Some(lhs)
 }
 
-const LHS_FIRST: TokenSet = TS![not, #, -].union(ATOM_EXPR_FIRST);
+const LHS_FIRST: TokenSet = TS![not, #, -, ~].union(ATOM_EXPR_FIRST);
 
 fn lhs(p: &mut Parser) -> Option<MarkerComplete> {
     let ((), r_bp) = prefix_binding_power(p.current());
@@ -241,7 +278,7 @@ fn table_sep(p: &mut Parser) -> MarkerComplete {
 
 const TABLE_RECOVERY: TokenSet = TS!['}', '['].union(TABLE_SEP);
 
-fn table_expr(p: &mut Parser) -> MarkerComplete {
+pub(super) fn table_expr(p: &mut Parser) -> MarkerComplete {
     let m = p.start();
 
     p.bump(T!['{']);
@@ -273,59 +310,39 @@ fn table_correct(p: &mut Parser) {
     p.err_until("Failed to find comma or closing bracket", TABLE_RECOVERY);
 }
 
-fn table_content(p: &mut Parser) -> MarkerComplete {
+fn table_content(p: &mut Parser) -> Option<MarkerComplete> {
     let m = p.start();
     match p.current() {
-        T![ident] => {
-            if p.nth(1) == T![=] {
-                let m = p.start();
-
-                {
-                    let m = p.start();
-                    name_unchecked(p);
-                    m.complete(p, N![TableKey]);
-                }
-
-                p.bump(T![=]);
-                expr_single(p);
-                m.complete(p, N![KeyValue]);
-            } else {
-                let m = p.start();
-                expr_single(p);
-                m.complete(p, N![PositionalValue]);
-            }
+        T![ident] if p.nth(1) == T![=] => {
+            name_unchecked(p);
+            p.bump(T![=]);
+            expr_single(p);
+            Some(m.complete(p, N![KeyValue]))
         }
         T!['['] => {
-            let m = p.start();
-
-            {
-                let m = p.start();
-                index(p);
-                m.complete(p, N![TableKey]);
-            }
-
+            index(p);
             p.expect(T![=]);
             expr_single(p);
-            m.complete(p, N![KeyValue]);
+            Some(m.complete(p, N![KeyValue]))
         }
         _ if p.at(EXPR_FIRST) => {
-            let m = p.start();
             expr_single(p);
-            m.complete(p, N![PositionalValue]);
+            Some(m.complete(p, N![PositionalValue]))
         }
         _ => {
+            m.abandon(p);
             p.error("Expected an expression");
+            None
         }
-    };
-    m.complete(p, N![TableContent])
+    }
 }
 
 fn function_expr(p: &mut Parser) -> MarkerComplete {
     let m = p.start();
-    p.bump(T![function]);
+    let opener = p.bump_opener(T![function]);
     param_list(p);
-    block(p);
-    p.expect(T![end]);
+    block_in(p, opener);
+    p.expect_closer(opener, T![end]);
     m.complete(p, N![FunctionExpr])
 }
 
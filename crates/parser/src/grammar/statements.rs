@@ -1,17 +1,29 @@
-use accept::{source::Source, Acceptor};
+use accept::{source::Source, Acceptor, Any};
 
 use super::{
-    block, expr_single,
+    attrib_multi_name_r, block_in, expr_single,
     expressions::{expr, EXPR_FIRST, LITERAL_FIRST},
     multi_name_r, name, name_r, name_ref, name_ref_r, param_list,
 };
-use crate::parser::{MarkerComplete, Parser};
+use crate::parser::{MarkerComplete, Opener, Parser};
 use crate::SyntaxKind::*;
 use crate::{ParseError, TokenSet, TS};
 
-pub(super) const STMT_RECOVERY: TokenSet = TS![function, do, ::];
+/// The keywords a statement can start with, or that end a block. Recovery
+/// stops at them so that a block is never torn from its `end`.
+pub(super) const STMT_RECOVERY: TokenSet = TS![
+    local, function, if, while, for, repeat, return, do, goto, ::, break, end, until, else, elseif
+];
 
 pub(super) fn stmt(p: &mut Parser) {
+    stmt_without_semicolon(p);
+
+    // Optional semicolon at the end of each statement
+    p.accept(T![;]);
+}
+
+/// A statement without the `;` after it, which belongs to the block instead.
+pub(super) fn stmt_without_semicolon(p: &mut Parser) {
     match p.current() {
         T![if] => {
             if_stmt(p);
@@ -57,61 +69,69 @@ pub(super) fn stmt(p: &mut Parser) {
             p.err_and_bump("Semicolons can only be used after statements");
         }
         _ if p.at(LITERAL_FIRST) => {
-            p.err_and_bump("A literal cannot be the start of a statement");
+            stmt_recover(p, "A literal cannot be the start of a statement");
         }
         T![end] => {
             let e = p.start_error();
             p.bump(T![end]);
             e.complete(p, ParseError::msg("Unmatched end token"));
         }
-        _ => p.err_and_bump("Expected a statement"),
+        _ => stmt_recover(p, "Expected a statement"),
     };
+}
 
-    // Optional semicolon at the end of each statement
-    p.accept(T![;]);
+/// Wraps everything up to the next statement in an error. The next statement
+/// starts at a keyword or at the next line.
+fn stmt_recover(p: &mut Parser, message: &str) {
+    let m = p.start_error();
+    p.bump(Any);
+    while !p.at(STMT_RECOVERY) && !p.at(T![eof]) && !p.at_line_start() {
+        p.bump(Any);
+    }
+    m.complete(p, ParseError::msg(message));
 }
 
 fn if_stmt(p: &mut Parser) -> MarkerComplete {
     let m = p.start();
-    p.bump(T![if]);
+    let opener = p.bump_opener(T![if]);
     expr_single(p);
-    p.expect(T![then]);
-    block(p);
+    p.expect_after(opener, T![then]);
+    block_in(p, opener);
     match p.current() {
         T![elseif] => {
-            elseif_branch(p);
+            elseif_branch(p, opener);
         }
         T![else] => {
-            else_branch(p);
+            else_branch(p, opener);
         }
         _ => (),
     }
-    p.expect(T![end]);
+    p.expect_closer(opener, T![end]);
     m.complete(p, IfStmt)
 }
 
-fn elseif_branch(p: &mut Parser) -> MarkerComplete {
+fn elseif_branch(p: &mut Parser, opener: Opener) -> MarkerComplete {
     let m = p.start();
-    p.bump(T![elseif]);
+    let elseif = p.bump_opener(T![elseif]);
     expr_single(p);
-    p.expect(T![then]);
-    block(p);
+    p.expect_after(elseif, T![then]);
+    block_in(p, opener);
     match p.current() {
         T![elseif] => {
-            elseif_branch(p);
+            elseif_branch(p, opener);
         }
         T![else] => {
-            else_branch(p);
+            else_branch(p, opener);
         }
         _ => (),
     };
     m.complete(p, ElseIfBranch)
 }
 
-fn else_branch(p: &mut Parser) -> MarkerComplete {
+fn else_branch(p: &mut Parser, opener: Opener) -> MarkerComplete {
     let m = p.start();
     p.bump(T![else]);
-    block(p);
+    block_in(p, opener);
     m.complete(p, ElseBranch)
 }
 
@@ -139,26 +159,25 @@ fn label_delim(p: &mut Parser) -> MarkerComplete {
 
 fn while_stmt(p: &mut Parser) -> MarkerComplete {
     let m = p.start();
-    p.bump(T![while]);
+    let opener = p.bump_opener(T![while]);
     expr_single(p);
-    p.expect(T![do]);
-    block(p);
-    p.expect(T![end]);
+    p.expect_after(opener, T![do]);
+    block_in(p, opener);
+    p.expect_closer(opener, T![end]);
     m.complete(p, WhileStmt)
 }
 
 fn for_stmt(p: &mut Parser) -> MarkerComplete {
     let m = p.start();
-    p.bump(T![for]);
+    let opener = p.bump_opener(T![for]);
     for_content(p);
-    p.expect(T![do]);
-    block(p);
-    p.expect(T![end]);
+    p.expect_after(opener, T![do]);
+    block_in(p, opener);
+    p.expect_closer(opener, T![end]);
     m.complete(p, ForStmt)
 }
 
-fn for_content(p: &mut Parser) -> MarkerComplete {
-    let m = p.start();
+fn for_content(p: &mut Parser) {
     if p.at(T![ident]) {
         if p.nth(1) == T![=] {
             numeric_for(p);
@@ -168,7 +187,6 @@ fn for_content(p: &mut Parser) -> MarkerComplete {
     } else {
         p.error("Expected an identifier");
     }
-    m.complete(p, ForContent)
 }
 
 fn generic_for(p: &mut Parser) -> MarkerComplete {
@@ -191,10 +209,11 @@ fn numeric_for(p: &mut Parser) -> MarkerComplete {
 
 fn repeat_until_stmt(p: &mut Parser) -> MarkerComplete {
     let m = p.start();
-    p.bump(T![repeat]);
-    block(p);
-    p.expect(T![until]);
-    expr_single(p);
+    let opener = p.bump_opener(T![repeat]);
+    block_in(p, opener);
+    if p.expect_closer(opener, T![until]) {
+        expr_single(p);
+    }
     m.complete(p, RepeatUntilStmt)
 }
 
@@ -227,29 +246,28 @@ fn local_function_def_stmt(p: &mut Parser) -> MarkerComplete {
 
     let m = p.start();
     p.bump(T![local]);
-    p.expect(T![function]);
+    let opener = p.bump_opener(T![function]);
     name_r(p, RECOVERY);
     param_list(p);
-    block(p);
-    p.expect(T![end]);
+    block_in(p, opener);
+    p.expect_closer(opener, T![end]);
     m.complete(p, LocalFunctionDefStmt)
 }
 
 fn function_def_stmt(p: &mut Parser) -> MarkerComplete {
     let m = p.start();
 
-    p.expect(T![function]);
+    let opener = p.bump_opener(T![function]);
     function_def_content(p);
     param_list(p);
-    block(p);
-    p.expect(T![end]);
+    block_in(p, opener);
+    p.expect_closer(opener, T![end]);
     m.complete(p, FunctionDefStmt)
 }
 
-fn function_def_content(p: &mut Parser) -> MarkerComplete {
+fn function_def_content(p: &mut Parser) {
     const RECOVERY: TokenSet = TS!['('].union(STMT_RECOVERY);
 
-    let m = p.start();
     if p.at(T![ident]) && p.nth(1) == T!['('] {
         name_r(p, TS!['('].union(RECOVERY));
     } else {
@@ -263,7 +281,6 @@ fn function_def_content(p: &mut Parser) -> MarkerComplete {
             m.complete(p, FunctionStatic);
         }
     }
-    m.complete(p, FunctionDefContent)
 }
 
 fn index_path(p: &mut Parser) -> MarkerComplete {
@@ -296,9 +313,9 @@ fn index_path(p: &mut Parser) -> MarkerComplete {
 
 fn do_stmt(p: &mut Parser) -> MarkerComplete {
     let m = p.start();
-    p.bump(T![do]);
-    block(p);
-    p.expect(T![end]);
+    let opener = p.bump_opener(T![do]);
+    block_in(p, opener);
+    p.expect_closer(opener, T![end]);
     m.complete(p, DoStmt)
 }
 
@@ -314,7 +331,7 @@ fn return_stmt(p: &mut Parser) -> MarkerComplete {
 fn local_assign_stmt(p: &mut Parser) -> MarkerComplete {
     let m = p.start();
     p.bump(T![local]);
-    multi_name_r(p, STMT_RECOVERY, false);
+    attrib_multi_name_r(p, STMT_RECOVERY);
     if p.accept(T![=]) {
         expr(p);
     }
@@ -1,6 +1,7 @@
 mod parser;
 #[macro_use]
 mod syntax_kind;
+mod block_pairs;
 mod event;
 mod grammar;
 mod parse_error;
@@ -45,6 +46,18 @@ impl Token {
     }
 }
 
+/// Where a token is in the source, which is used to pair blocks by their
+/// indentation when recovering from a missing `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TokenPos {
+    /// The zero based line the token starts on.
+    pub line: u32,
+    /// The width of the leading whitespace of that line.
+    pub indent: u32,
+    /// Whether the token is the first one of its line.
+    pub line_start: bool,
+}
+
 /// `Token` abstracts the cursor of `TokenSource` operates on.
 pub trait TokenSource {
     fn current(&self) -> Token;
@@ -52,6 +65,9 @@ pub trait TokenSource {
     /// Lookahead n token
     fn lookahead_nth(&self, n: usize) -> Token;
 
+    /// The position of the nth token ahead
+    fn lookahead_nth_pos(&self, n: usize) -> TokenPos;
+
     /// bump cursor to next token
     fn bump(&mut self) -> Option<Token>;
 
@@ -79,6 +95,9 @@ pub trait TreeSink {
 
     fn error(&mut self, error: ParseError);
 
+    /// Adds an error that covers the last token added.
+    fn token_error(&mut self, error: ParseError);
+
     fn finish(self) -> Self::FinishResult;
 }
 
@@ -105,9 +124,14 @@ where
     parse_from_tokens(token_source, tree_sink, grammar::root)
 }
 
-/// A parsing function for a block.
-/// Blocks can be from functions, do stmts, loops, etc.
-/// Anything that has an end.
+/// Whether every `function`, `if`, `while`, `for`, `do` and `repeat` in
+/// `kinds` is closed, and every `end` and `until` closes one of them.
+pub fn is_balanced(kinds: impl Iterator<Item = SyntaxKind>) -> bool {
+    block_pairs::is_balanced(kinds)
+}
+
+/// A parsing function for a node that can be reparsed on its own. These are
+/// blocks, statements and table constructors.
 pub struct Reparser(fn(&mut Parser));
 
 impl Reparser {
@@ -115,14 +139,25 @@ impl Reparser {
         grammar::reparser(node).map(Reparser)
     }
 
-    pub fn parse<TS>(self, token_source: &mut dyn TokenSource, tree_sink: TS) -> TS::FinishResult
+    /// Parses the node from the tokens of `token_source`, the last `lookahead`
+    /// of which come after the node. Returns `None` when the node doesn't end
+    /// right before them.
+    pub fn parse<TS>(
+        self,
+        token_source: &mut dyn TokenSource,
+        tree_sink: TS,
+        lookahead: usize,
+    ) -> Option<TS::FinishResult>
     where
         TS: TreeSink,
     {
         let Reparser(r) = self;
         let mut p = Parser::new(token_source);
         r(&mut p);
+        if p.remaining() != lookahead {
+            return None;
+        }
         let events = p.finish();
-        event::process(tree_sink, events)
+        Some(event::process(tree_sink, events))
     }
 }
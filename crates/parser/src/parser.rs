@@ -1,15 +1,30 @@
 use std::{fmt, marker::PhantomData};
 
-use accept::{Acceptor, source::Source, Any};
+use accept::{source::Source, Acceptor, Any};
 use drop_bomb::DropBomb;
 
-use crate::{assert_matches, Event, ParseError, SyntaxKind, Token, TokenSet, TokenSource, T, TS};
+use crate::{
+    assert_matches, block_pairs::unclosed_openers, Event, ParseError, SyntaxKind, Token, TokenPos,
+    TokenSet, TokenSource, T, TS,
+};
 
 const RECOVERY: TokenSet = TS![end];
 
 pub struct Parser<'a> {
     token_source: &'a mut dyn TokenSource,
     events: Vec<Event>,
+    /// The index of the current token.
+    token_idx: u32,
+    /// The tokens that open a block which is never closed, sorted.
+    unclosed: Vec<u32>,
+}
+
+/// A keyword that starts a block, such as `if` or `function`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Opener {
+    idx: u32,
+    kind: SyntaxKind,
+    pos: TokenPos,
 }
 
 impl Source for Parser<'_> {
@@ -17,6 +32,9 @@ impl Source for Parser<'_> {
 
     fn advance(&mut self) -> Option<SyntaxKind> {
         self.push_event(Event::Token);
+        if self.current() != T![eof] {
+            self.token_idx += 1;
+        }
         self.token_source.bump().map(|Token { kind, .. }| kind)
     }
 
@@ -32,13 +50,32 @@ impl Source for Parser<'_> {
 
 impl<'a> Parser<'a> {
     pub fn new(token_source: &mut dyn TokenSource) -> Parser {
-        Parser { token_source, events: Vec::new() }
+        let mut tokens = Vec::new();
+        loop {
+            let Token { kind, .. } = token_source.lookahead_nth(tokens.len());
+            if kind == T![eof] {
+                break;
+            }
+            tokens.push((kind, token_source.lookahead_nth_pos(tokens.len())));
+        }
+        let unclosed = unclosed_openers(&tokens);
+
+        Parser { token_source, events: Vec::new(), token_idx: 0, unclosed }
     }
 
     pub(crate) fn finish(self) -> Vec<Event> {
         self.events
     }
 
+    /// The number of tokens left before eof.
+    pub(crate) fn remaining(&self) -> usize {
+        let mut n = 0;
+        while self.token_source.lookahead_nth(n).kind != T![eof] {
+            n += 1;
+        }
+        n
+    }
+
     fn events_len(&self) -> u32 {
         self.events.len() as u32
     }
@@ -106,6 +143,62 @@ impl<'a> Parser<'a> {
         m.complete(self, ParseError::Message(message.to_string()));
     }
 
+    /// Consumes the keyword that starts a block. When the block is never closed
+    /// the error is reported here, so that it points at the keyword.
+    pub(crate) fn bump_opener(&mut self, kind: SyntaxKind) -> Opener {
+        let opener = Opener { idx: self.token_idx, kind, pos: self.current_pos() };
+        self.bump(kind);
+        if self.is_unclosed(opener) {
+            let closer = if kind == T![repeat] { T![until] } else { T![end] };
+            let message = missing_msg(opener, closer);
+            self.push_event(Event::TokenError(ParseError::Message(message)));
+        }
+        opener
+    }
+
+    fn current_pos(&self) -> TokenPos {
+        self.token_source.lookahead_nth_pos(0)
+    }
+
+    /// Whether the current token is the first one of its line.
+    pub(crate) fn at_line_start(&self) -> bool {
+        self.current_pos().line_start
+    }
+
+    pub(crate) fn is_unclosed(&self, opener: Opener) -> bool {
+        self.unclosed.binary_search(&opener.idx).is_ok()
+    }
+
+    /// Whether the current statement is outside of the block of an unclosed
+    /// `opener`, going by its indentation.
+    pub(crate) fn at_dedent(&self, opener: Opener) -> bool {
+        let pos = self.current_pos();
+        self.is_unclosed(opener)
+            && pos.line_start
+            && pos.line > opener.pos.line
+            && pos.indent <= opener.pos.indent
+    }
+
+    /// Consumes the `end` or `until` of `opener`. Nothing is consumed for an
+    /// unclosed block since `bump_opener` already reported it and the closer
+    /// here belongs to another block.
+    pub(crate) fn expect_closer(&mut self, opener: Opener, closer: SyntaxKind) -> bool {
+        if self.is_unclosed(opener) {
+            return false;
+        }
+        self.expect(closer)
+    }
+
+    /// Consumes the `then` or `do` that follows the header of `opener`.
+    pub(crate) fn expect_after(&mut self, opener: Opener, kind: SyntaxKind) -> bool {
+        if self.at(kind) {
+            self.bump(kind);
+            return true;
+        }
+        self.error(missing_msg(opener, kind));
+        false
+    }
+
     /// Checks if the current token is `kind`.
     pub(crate) fn expect_at(&mut self, kind: SyntaxKind) -> bool {
         let current = self.current();
@@ -136,6 +229,31 @@ impl<'a> Parser<'a> {
     }
 }
 
+fn missing_msg(opener: Opener, missing: SyntaxKind) -> String {
+    format!(
+        "`{}` at line {} is missing `{}`",
+        keyword_text(opener.kind),
+        opener.pos.line + 1,
+        keyword_text(missing)
+    )
+}
+
+fn keyword_text(kind: SyntaxKind) -> &'static str {
+    match kind {
+        T![function] => "function",
+        T![if] => "if",
+        T![elseif] => "elseif",
+        T![then] => "then",
+        T![while] => "while",
+        T![for] => "for",
+        T![do] => "do",
+        T![repeat] => "repeat",
+        T![until] => "until",
+        T![end] => "end",
+        _ => unreachable!("{:?} is not a block keyword", kind),
+    }
+}
+
 pub(crate) trait MarkerType {}
 
 pub(crate) enum ErrorMarker {}
@@ -14,6 +14,7 @@ pub enum SyntaxKind {
     Bang,
     Hash,
     Modulo,
+    Amp,
     LParen,
     RParen,
     Asterisk,
@@ -24,20 +25,25 @@ pub enum SyntaxKind {
     DoubleDot,
     TripleDot,
     Slash,
+    DoubleSlash,
     Colon,
     DoubleColon,
     Semicolon,
     Lt,
+    Shl,
     LtEq,
     Eq,
     EqEq,
     Gt,
     GtEq,
+    Shr,
     LBracket,
     RBracket,
     Caret,
     LBrace,
+    Pipe,
     RBrace,
+    Tilde,
     NotEq,
     FunctionKw,
     LocalKw,
@@ -71,6 +77,7 @@ pub enum SyntaxKind {
     Name,
     NameRef,
     MultiName,
+    Attrib,
     SourceFile,
     Block,
     AssignStmt,
@@ -86,8 +93,8 @@ pub enum SyntaxKind {
     RepeatUntilStmt,
     LabelStmt,
     GotoStmt,
-    LabelDelim,
     DoStmt,
+    LabelDelim,
     ParamList,
     FunctionMethod,
     FunctionStatic,
@@ -106,10 +113,10 @@ pub enum SyntaxKind {
     FunctionExpr,
     CallExpr,
     MethodCallExpr,
+    ParenExpr,
     CallArgs,
     ArgList,
     TableSep,
-    ParenExpr,
     KeyValue,
     PositionalValue,
     Index,
@@ -163,6 +170,7 @@ impl SyntaxKind {
             self,
             Bang | Hash
                 | Modulo
+                | Amp
                 | LParen
                 | RParen
                 | Asterisk
@@ -173,20 +181,25 @@ impl SyntaxKind {
                 | DoubleDot
                 | TripleDot
                 | Slash
+                | DoubleSlash
                 | Colon
                 | DoubleColon
                 | Semicolon
                 | Lt
+                | Shl
                 | LtEq
                 | Eq
                 | EqEq
                 | Gt
                 | GtEq
+                | Shr
                 | LBracket
                 | RBracket
                 | Caret
                 | LBrace
+                | Pipe
                 | RBrace
+                | Tilde
                 | NotEq
         )
     }
@@ -224,7 +237,7 @@ impl SyntaxKind {
 }
 #[doc = r" A helper macro to get the token"]
 #[macro_export]
-macro_rules ! T { [!] => { $ crate :: SyntaxKind :: Bang } ; [#] => { $ crate :: SyntaxKind :: Hash } ; [%] => { $ crate :: SyntaxKind :: Modulo } ; ['('] => { $ crate :: SyntaxKind :: LParen } ; [')'] => { $ crate :: SyntaxKind :: RParen } ; [*] => { $ crate :: SyntaxKind :: Asterisk } ; [+] => { $ crate :: SyntaxKind :: Plus } ; [,] => { $ crate :: SyntaxKind :: Comma } ; [-] => { $ crate :: SyntaxKind :: Minus } ; [.] => { $ crate :: SyntaxKind :: Dot } ; [..] => { $ crate :: SyntaxKind :: DoubleDot } ; [...] => { $ crate :: SyntaxKind :: TripleDot } ; [/] => { $ crate :: SyntaxKind :: Slash } ; [:] => { $ crate :: SyntaxKind :: Colon } ; [::] => { $ crate :: SyntaxKind :: DoubleColon } ; [;] => { $ crate :: SyntaxKind :: Semicolon } ; [<] => { $ crate :: SyntaxKind :: Lt } ; [<=] => { $ crate :: SyntaxKind :: LtEq } ; [=] => { $ crate :: SyntaxKind :: Eq } ; [==] => { $ crate :: SyntaxKind :: EqEq } ; [>] => { $ crate :: SyntaxKind :: Gt } ; [>=] => { $ crate :: SyntaxKind :: GtEq } ; ['['] => { $ crate :: SyntaxKind :: LBracket } ; [']'] => { $ crate :: SyntaxKind :: RBracket } ; [^] => { $ crate :: SyntaxKind :: Caret } ; ['{'] => { $ crate :: SyntaxKind :: LBrace } ; ['}'] => { $ crate :: SyntaxKind :: RBrace } ; [~=] => { $ crate :: SyntaxKind :: NotEq } ; [function] => { $ crate :: SyntaxKind :: FunctionKw } ; [local] => { $ crate :: SyntaxKind :: LocalKw } ; [true] => { $ crate :: SyntaxKind :: TrueKw } ; [false] => { $ crate :: SyntaxKind :: FalseKw } ; [if] => { $ crate :: SyntaxKind :: IfKw } ; [then] => { $ crate :: SyntaxKind :: ThenKw } ; [else] => { $ crate :: SyntaxKind :: ElseKw } ; [elseif] => { $ crate :: SyntaxKind :: ElseifKw } ; [while] => { $ crate :: SyntaxKind :: WhileKw } ; [for] => { $ crate :: SyntaxKind :: ForKw } ; [in] => { $ crate :: SyntaxKind :: InKw } ; [break] => { $ crate :: SyntaxKind :: BreakKw } ; [do] => { $ crate :: SyntaxKind :: DoKw } ; [goto] => { $ crate :: SyntaxKind :: GotoKw } ; [and] => { $ crate :: SyntaxKind :: AndKw } ; [or] => { $ crate :: SyntaxKind :: OrKw } ; [not] => { $ crate :: SyntaxKind :: NotKw } ; [return] => { $ crate :: SyntaxKind :: ReturnKw } ; [end] => { $ crate :: SyntaxKind :: EndKw } ; [repeat] => { $ crate :: SyntaxKind :: RepeatKw } ; [until] => { $ crate :: SyntaxKind :: UntilKw } ; [nil] => { $ crate :: SyntaxKind :: NilKw } ; [number] => { $ crate :: SyntaxKind :: Number } ; [str] => { $ crate :: SyntaxKind :: Str } ; [true] => { $ crate :: SyntaxKind :: True } ; [false] => { $ crate :: SyntaxKind :: False } ; [error] => { $ crate :: SyntaxKind :: Error } ; [ident] => { $ crate :: SyntaxKind :: Ident } ; [shebang] => { $ crate :: SyntaxKind :: Shebang } ; [comment] => { $ crate :: SyntaxKind :: Comment } ; [whitespace] => { $ crate :: SyntaxKind :: Whitespace } ; [__] => { $ crate :: SyntaxKind :: Tombstone } ; [eof] => { $ crate :: SyntaxKind :: Eof } ; [unknown] => { $ crate :: SyntaxKind :: Unknown } ; }
+macro_rules ! T { [!] => { $ crate :: SyntaxKind :: Bang } ; [#] => { $ crate :: SyntaxKind :: Hash } ; [%] => { $ crate :: SyntaxKind :: Modulo } ; [&] => { $ crate :: SyntaxKind :: Amp } ; ['('] => { $ crate :: SyntaxKind :: LParen } ; [')'] => { $ crate :: SyntaxKind :: RParen } ; [*] => { $ crate :: SyntaxKind :: Asterisk } ; [+] => { $ crate :: SyntaxKind :: Plus } ; [,] => { $ crate :: SyntaxKind :: Comma } ; [-] => { $ crate :: SyntaxKind :: Minus } ; [.] => { $ crate :: SyntaxKind :: Dot } ; [..] => { $ crate :: SyntaxKind :: DoubleDot } ; [...] => { $ crate :: SyntaxKind :: TripleDot } ; [/] => { $ crate :: SyntaxKind :: Slash } ; [/ /] => { $ crate :: SyntaxKind :: DoubleSlash } ; [:] => { $ crate :: SyntaxKind :: Colon } ; [::] => { $ crate :: SyntaxKind :: DoubleColon } ; [;] => { $ crate :: SyntaxKind :: Semicolon } ; [<] => { $ crate :: SyntaxKind :: Lt } ; [<<] => { $ crate :: SyntaxKind :: Shl } ; [<=] => { $ crate :: SyntaxKind :: LtEq } ; [=] => { $ crate :: SyntaxKind :: Eq } ; [==] => { $ crate :: SyntaxKind :: EqEq } ; [>] => { $ crate :: SyntaxKind :: Gt } ; [>=] => { $ crate :: SyntaxKind :: GtEq } ; [>>] => { $ crate :: SyntaxKind :: Shr } ; ['['] => { $ crate :: SyntaxKind :: LBracket } ; [']'] => { $ crate :: SyntaxKind :: RBracket } ; [^] => { $ crate :: SyntaxKind :: Caret } ; ['{'] => { $ crate :: SyntaxKind :: LBrace } ; [|] => { $ crate :: SyntaxKind :: Pipe } ; ['}'] => { $ crate :: SyntaxKind :: RBrace } ; [~] => { $ crate :: SyntaxKind :: Tilde } ; [~=] => { $ crate :: SyntaxKind :: NotEq } ; [function] => { $ crate :: SyntaxKind :: FunctionKw } ; [local] => { $ crate :: SyntaxKind :: LocalKw } ; [true] => { $ crate :: SyntaxKind :: TrueKw } ; [false] => { $ crate :: SyntaxKind :: FalseKw } ; [if] => { $ crate :: SyntaxKind :: IfKw } ; [then] => { $ crate :: SyntaxKind :: ThenKw } ; [else] => { $ crate :: SyntaxKind :: ElseKw } ; [elseif] => { $ crate :: SyntaxKind :: ElseifKw } ; [while] => { $ crate :: SyntaxKind :: WhileKw } ; [for] => { $ crate :: SyntaxKind :: ForKw } ; [in] => { $ crate :: SyntaxKind :: InKw } ; [break] => { $ crate :: SyntaxKind :: BreakKw } ; [do] => { $ crate :: SyntaxKind :: DoKw } ; [goto] => { $ crate :: SyntaxKind :: GotoKw } ; [and] => { $ crate :: SyntaxKind :: AndKw } ; [or] => { $ crate :: SyntaxKind :: OrKw } ; [not] => { $ crate :: SyntaxKind :: NotKw } ; [return] => { $ crate :: SyntaxKind :: ReturnKw } ; [end] => { $ crate :: SyntaxKind :: EndKw } ; [repeat] => { $ crate :: SyntaxKind :: RepeatKw } ; [until] => { $ crate :: SyntaxKind :: UntilKw } ; [nil] => { $ crate :: SyntaxKind :: NilKw } ; [number] => { $ crate :: SyntaxKind :: Number } ; [str] => { $ crate :: SyntaxKind :: Str } ; [true] => { $ crate :: SyntaxKind :: True } ; [false] => { $ crate :: SyntaxKind :: False } ; [error] => { $ crate :: SyntaxKind :: Error } ; [ident] => { $ crate :: SyntaxKind :: Ident } ; [shebang] => { $ crate :: SyntaxKind :: Shebang } ; [comment] => { $ crate :: SyntaxKind :: Comment } ; [whitespace] => { $ crate :: SyntaxKind :: Whitespace } ; [__] => { $ crate :: SyntaxKind :: Tombstone } ; [eof] => { $ crate :: SyntaxKind :: Eof } ; [unknown] => { $ crate :: SyntaxKind :: Unknown } ; }
 #[doc = r" A helper macro to get the node"]
 #[macro_export]
-macro_rules ! N { [Name] => { $ crate :: SyntaxKind :: Name } ; [NameRef] => { $ crate :: SyntaxKind :: NameRef } ; [MultiName] => { $ crate :: SyntaxKind :: MultiName } ; [SourceFile] => { $ crate :: SyntaxKind :: SourceFile } ; [Block] => { $ crate :: SyntaxKind :: Block } ; [AssignStmt] => { $ crate :: SyntaxKind :: AssignStmt } ; [LocalAssignStmt] => { $ crate :: SyntaxKind :: LocalAssignStmt } ; [LocalFunctionDefStmt] => { $ crate :: SyntaxKind :: LocalFunctionDefStmt } ; [FunctionDefStmt] => { $ crate :: SyntaxKind :: FunctionDefStmt } ; [ForStmt] => { $ crate :: SyntaxKind :: ForStmt } ; [IfStmt] => { $ crate :: SyntaxKind :: IfStmt } ; [ReturnStmt] => { $ crate :: SyntaxKind :: ReturnStmt } ; [WhileStmt] => { $ crate :: SyntaxKind :: WhileStmt } ; [ExprStmt] => { $ crate :: SyntaxKind :: ExprStmt } ; [BreakStmt] => { $ crate :: SyntaxKind :: BreakStmt } ; [RepeatUntilStmt] => { $ crate :: SyntaxKind :: RepeatUntilStmt } ; [LabelStmt] => { $ crate :: SyntaxKind :: LabelStmt } ; [GotoStmt] => { $ crate :: SyntaxKind :: GotoStmt } ; [LabelDelim] => { $ crate :: SyntaxKind :: LabelDelim } ; [DoStmt] => { $ crate :: SyntaxKind :: DoStmt } ; [ParamList] => { $ crate :: SyntaxKind :: ParamList } ; [FunctionMethod] => { $ crate :: SyntaxKind :: FunctionMethod } ; [FunctionStatic] => { $ crate :: SyntaxKind :: FunctionStatic } ; [IndexPath] => { $ crate :: SyntaxKind :: IndexPath } ; [MultivalExpr] => { $ crate :: SyntaxKind :: MultivalExpr } ; [ElseBranch] => { $ crate :: SyntaxKind :: ElseBranch } ; [ElseIfBranch] => { $ crate :: SyntaxKind :: ElseIfBranch } ; [NumericFor] => { $ crate :: SyntaxKind :: NumericFor } ; [GenericFor] => { $ crate :: SyntaxKind :: GenericFor } ; [Literal] => { $ crate :: SyntaxKind :: Literal } ; [TableExpr] => { $ crate :: SyntaxKind :: TableExpr } ; [InfixExpr] => { $ crate :: SyntaxKind :: InfixExpr } ; [PrefixExpr] => { $ crate :: SyntaxKind :: PrefixExpr } ; [IndexExpr] => { $ crate :: SyntaxKind :: IndexExpr } ; [DotExpr] => { $ crate :: SyntaxKind :: DotExpr } ; [FunctionExpr] => { $ crate :: SyntaxKind :: FunctionExpr } ; [CallExpr] => { $ crate :: SyntaxKind :: CallExpr } ; [MethodCallExpr] => { $ crate :: SyntaxKind :: MethodCallExpr } ; [CallArgs] => { $ crate :: SyntaxKind :: CallArgs } ; [ArgList] => { $ crate :: SyntaxKind :: ArgList } ; [TableSep] => { $ crate :: SyntaxKind :: TableSep } ; [ParenExpr] => { $ crate :: SyntaxKind :: ParenExpr } ; [KeyValue] => { $ crate :: SyntaxKind :: KeyValue } ; [PositionalValue] => { $ crate :: SyntaxKind :: PositionalValue } ; [Index] => { $ crate :: SyntaxKind :: Index } ; [IdentKey] => { $ crate :: SyntaxKind :: IdentKey } ; [Stmt] => { $ crate :: SyntaxKind :: Stmt } ; [StmtItem] => { $ crate :: SyntaxKind :: StmtItem } ; [FunctionDefContent] => { $ crate :: SyntaxKind :: FunctionDefContent } ; [Expr] => { $ crate :: SyntaxKind :: Expr } ; [ForContent] => { $ crate :: SyntaxKind :: ForContent } ; [TableContent] => { $ crate :: SyntaxKind :: TableContent } ; [TableKey] => { $ crate :: SyntaxKind :: TableKey } ; }
+macro_rules ! N { [Name] => { $ crate :: SyntaxKind :: Name } ; [NameRef] => { $ crate :: SyntaxKind :: NameRef } ; [MultiName] => { $ crate :: SyntaxKind :: MultiName } ; [Attrib] => { $ crate :: SyntaxKind :: Attrib } ; [SourceFile] => { $ crate :: SyntaxKind :: SourceFile } ; [Block] => { $ crate :: SyntaxKind :: Block } ; [AssignStmt] => { $ crate :: SyntaxKind :: AssignStmt } ; [LocalAssignStmt] => { $ crate :: SyntaxKind :: LocalAssignStmt } ; [LocalFunctionDefStmt] => { $ crate :: SyntaxKind :: LocalFunctionDefStmt } ; [FunctionDefStmt] => { $ crate :: SyntaxKind :: FunctionDefStmt } ; [ForStmt] => { $ crate :: SyntaxKind :: ForStmt } ; [IfStmt] => { $ crate :: SyntaxKind :: IfStmt } ; [ReturnStmt] => { $ crate :: SyntaxKind :: ReturnStmt } ; [WhileStmt] => { $ crate :: SyntaxKind :: WhileStmt } ; [ExprStmt] => { $ crate :: SyntaxKind :: ExprStmt } ; [BreakStmt] => { $ crate :: SyntaxKind :: BreakStmt } ; [RepeatUntilStmt] => { $ crate :: SyntaxKind :: RepeatUntilStmt } ; [LabelStmt] => { $ crate :: SyntaxKind :: LabelStmt } ; [GotoStmt] => { $ crate :: SyntaxKind :: GotoStmt } ; [DoStmt] => { $ crate :: SyntaxKind :: DoStmt } ; [LabelDelim] => { $ crate :: SyntaxKind :: LabelDelim } ; [ParamList] => { $ crate :: SyntaxKind :: ParamList } ; [FunctionMethod] => { $ crate :: SyntaxKind :: FunctionMethod } ; [FunctionStatic] => { $ crate :: SyntaxKind :: FunctionStatic } ; [IndexPath] => { $ crate :: SyntaxKind :: IndexPath } ; [MultivalExpr] => { $ crate :: SyntaxKind :: MultivalExpr } ; [ElseBranch] => { $ crate :: SyntaxKind :: ElseBranch } ; [ElseIfBranch] => { $ crate :: SyntaxKind :: ElseIfBranch } ; [NumericFor] => { $ crate :: SyntaxKind :: NumericFor } ; [GenericFor] => { $ crate :: SyntaxKind :: GenericFor } ; [Literal] => { $ crate :: SyntaxKind :: Literal } ; [TableExpr] => { $ crate :: SyntaxKind :: TableExpr } ; [InfixExpr] => { $ crate :: SyntaxKind :: InfixExpr } ; [PrefixExpr] => { $ crate :: SyntaxKind :: PrefixExpr } ; [IndexExpr] => { $ crate :: SyntaxKind :: IndexExpr } ; [DotExpr] => { $ crate :: SyntaxKind :: DotExpr } ; [FunctionExpr] => { $ crate :: SyntaxKind :: FunctionExpr } ; [CallExpr] => { $ crate :: SyntaxKind :: CallExpr } ; [MethodCallExpr] => { $ crate :: SyntaxKind :: MethodCallExpr } ; [ParenExpr] => { $ crate :: SyntaxKind :: ParenExpr } ; [CallArgs] => { $ crate :: SyntaxKind :: CallArgs } ; [ArgList] => { $ crate :: SyntaxKind :: ArgList } ; [TableSep] => { $ crate :: SyntaxKind :: TableSep } ; [KeyValue] => { $ crate :: SyntaxKind :: KeyValue } ; [PositionalValue] => { $ crate :: SyntaxKind :: PositionalValue } ; [Index] => { $ crate :: SyntaxKind :: Index } ; [IdentKey] => { $ crate :: SyntaxKind :: IdentKey } ; [Stmt] => { $ crate :: SyntaxKind :: Stmt } ; [StmtItem] => { $ crate :: SyntaxKind :: StmtItem } ; [FunctionDefContent] => { $ crate :: SyntaxKind :: FunctionDefContent } ; [Expr] => { $ crate :: SyntaxKind :: Expr } ; [ForContent] => { $ crate :: SyntaxKind :: ForContent } ; [TableContent] => { $ crate :: SyntaxKind :: TableContent } ; [TableKey] => { $ crate :: SyntaxKind :: TableKey } ; }
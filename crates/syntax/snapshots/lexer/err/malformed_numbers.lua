@@ -0,0 +1 @@
+0x 1e 0x1p 3abc 1.5ll
@@ -0,0 +1,3 @@
+a & b | c ~ d
+~x << 2 >> y
+7 // 2 % 3
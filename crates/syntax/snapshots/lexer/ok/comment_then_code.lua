@@ -0,0 +1,3 @@
+local a = --[[ a ]] 1
+local b = --[==[ ]] ]==] 2
+--[=x line comment
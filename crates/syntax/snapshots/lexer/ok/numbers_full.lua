@@ -0,0 +1,2 @@
+0X1F 0x.8 0x1.8p3 0xA.8P-1 0x1p+4
+.5 3. 1e+5 2E-3 0xe+1
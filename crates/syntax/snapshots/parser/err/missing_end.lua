@@ -0,0 +1,15 @@
+local function f(a)
+    if a then
+        print(a)
+    print("done")
+end
+
+while true do
+    local function g()
+        return 1
+end
+
+for i = 1, 10 do
+    repeat
+        i = i + 1
+end
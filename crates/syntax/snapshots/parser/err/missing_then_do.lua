@@ -0,0 +1,11 @@
+if a
+    print(a)
+elseif b
+    print(b)
+end
+while a
+    print(a)
+end
+for i = 1, 2
+    print(i)
+end
@@ -0,0 +1,6 @@
+local a = 1
+5 + 3 * a
+print(a)
+local b = if a then
+    print(b)
+end
@@ -0,0 +1,5 @@
+local a = x | y ~ z & w
+local b = 1 << 2 >> n .. s
+local c = ~x + ~~y
+local d = a // b * c % 4
+local e = a < b == c
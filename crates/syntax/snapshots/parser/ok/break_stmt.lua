@@ -1,3 +1,3 @@
 break
-return 1234
+do return 1234 end
 break
@@ -0,0 +1,2 @@
+local x <const>, y <close> = 5, nil
+local a, b <const> = 1
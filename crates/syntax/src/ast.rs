@@ -3,9 +3,11 @@ mod generated;
 mod grammar_ext;
 mod node_ext;
 mod stmt_ext;
+pub(crate) mod token_ext;
 
 pub use self::generated::{nodes::*, tokens::*};
-pub use expr_ext::LiteralKind;
+pub use expr_ext::{LiteralKind, NumberError, NumberValue};
+pub use node_ext::AttribKind;
 
 use rowan::TextRange;
 
@@ -40,6 +40,34 @@ impl ast::InfixExpr {
     }
 }
 
+impl ast::PrefixExpr {
+    pub fn op_details(&self) -> Option<(SyntaxToken, LuaOp)> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find_map(|c| <Option<LuaOp>>::from(c.kind()).map(|op| (c, op)))
+    }
+
+    pub fn op_kind(&self) -> Option<LuaOp> {
+        self.op_details().map(|t| t.1)
+    }
+
+    pub fn op_token(&self) -> Option<SyntaxToken> {
+        self.op_details().map(|t| t.0)
+    }
+}
+
+impl ast::MethodCallExpr {
+    /// The name of the method being called. Unlike `name_ref`, this is never
+    /// the receiver, as in `a:b()`.
+    pub fn method_name(&self) -> Option<ast::NameRef> {
+        self.syntax()
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != T![:])
+            .find_map(|it| it.into_node().and_then(ast::NameRef::cast))
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum LiteralKind {
     Str(ast::Str),
@@ -78,3 +106,126 @@ impl ast::Literal {
         }
     }
 }
+
+/// The value of a number literal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberValue {
+    Int(i64),
+    Float(f64),
+    /// LuaJIT's imaginary numbers, as in `2i`.
+    Imaginary(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberError {
+    Malformed,
+    /// An integer with an `LL` or `ULL` suffix that doesn't fit in 64 bits.
+    Overflow,
+}
+
+impl ast::Number {
+    /// Decimal integers that don't fit in an `i64` become floats and hex
+    /// integers wrap around, like in Lua 5.3.
+    pub fn value(&self) -> Result<NumberValue, NumberError> {
+        parse_number(self.text())
+    }
+}
+
+impl ast::Literal {
+    /// `None` when the literal isn't a number.
+    pub fn number_value(&self) -> Option<Result<NumberValue, NumberError>> {
+        match self.kind() {
+            LiteralKind::Number(it) => Some(it.value()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Suffix {
+    Ll,
+    Ull,
+    Imaginary,
+}
+
+fn parse_number(text: &str) -> Result<NumberValue, NumberError> {
+    let text = text.to_ascii_lowercase();
+    let (text, suffix) = if let Some(it) = text.strip_suffix("ull") {
+        (it, Some(Suffix::Ull))
+    } else if let Some(it) = text.strip_suffix("ll") {
+        (it, Some(Suffix::Ll))
+    } else if let Some(it) = text.strip_suffix('i') {
+        (it, Some(Suffix::Imaginary))
+    } else {
+        (text.as_str(), None)
+    };
+
+    let value = match text.strip_prefix("0x") {
+        Some(hex) => parse_hex(hex, suffix)?,
+        None => parse_decimal(text, suffix)?,
+    };
+    match (value, suffix) {
+        (NumberValue::Float(_), Some(Suffix::Ll | Suffix::Ull)) => Err(NumberError::Malformed),
+        (NumberValue::Int(it), Some(Suffix::Imaginary)) => Ok(NumberValue::Imaginary(it as f64)),
+        (NumberValue::Float(it), Some(Suffix::Imaginary)) => Ok(NumberValue::Imaginary(it)),
+        (value, _) => Ok(value),
+    }
+}
+
+fn parse_decimal(text: &str, suffix: Option<Suffix>) -> Result<NumberValue, NumberError> {
+    if !text.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return Err(NumberError::Malformed);
+    }
+    if text.contains(['.', 'e']) {
+        return text.parse().map(NumberValue::Float).map_err(|_| NumberError::Malformed);
+    }
+    if !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(NumberError::Malformed);
+    }
+    let value = match suffix {
+        Some(Suffix::Ull) => text.parse::<u64>().map(|it| it as i64),
+        _ => text.parse::<i64>(),
+    };
+    match (value, suffix) {
+        (Ok(it), _) => Ok(NumberValue::Int(it)),
+        (Err(_), Some(Suffix::Ll | Suffix::Ull)) => Err(NumberError::Overflow),
+        (Err(_), _) => text.parse().map(NumberValue::Float).map_err(|_| NumberError::Malformed),
+    }
+}
+
+fn parse_hex(text: &str, suffix: Option<Suffix>) -> Result<NumberValue, NumberError> {
+    let (mantissa, exponent) = match text.split_once('p') {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (text, None),
+    };
+    let (int, frac) = match mantissa.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (mantissa, None),
+    };
+    let digits = || int.chars().chain(frac.unwrap_or_default().chars());
+    if int.len() + frac.map_or(0, str::len) == 0 || !digits().all(|c| c.is_ascii_hexdigit()) {
+        return Err(NumberError::Malformed);
+    }
+
+    if frac.is_none() && exponent.is_none() {
+        let mut value = 0u64;
+        let mut overflowed = false;
+        for digit in digits().filter_map(|c| c.to_digit(16)) {
+            overflowed |= value >> 60 != 0;
+            value = value.wrapping_mul(16).wrapping_add(digit as u64);
+        }
+        if overflowed && matches!(suffix, Some(Suffix::Ll | Suffix::Ull)) {
+            return Err(NumberError::Overflow);
+        }
+        return Ok(NumberValue::Int(value as i64));
+    }
+
+    let exponent: i32 = match exponent {
+        Some(it) => it.parse().map_err(|_| NumberError::Malformed)?,
+        None => 0,
+    };
+    let mantissa =
+        digits().filter_map(|c| c.to_digit(16)).fold(0f64, |acc, it| acc * 16.0 + it as f64);
+    let shift = exponent.saturating_sub(4 * frac.map_or(0, str::len) as i32);
+    Ok(NumberValue::Float(mantissa * 2f64.powi(shift)))
+}
@@ -31,6 +31,24 @@ impl MultiName {
     pub fn names(&self) -> AstChildren<Name> {
         support::children(&self.syntax)
     }
+    pub fn attribs(&self) -> AstChildren<Attrib> {
+        support::children(&self.syntax)
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Attrib {
+    pub(crate) syntax: SyntaxNode,
+}
+impl Attrib {
+    pub fn lt_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![<])
+    }
+    pub fn ident_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![ident])
+    }
+    pub fn gt_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![>])
+    }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SourceFile {
@@ -178,7 +196,7 @@ impl ReturnStmt {
     pub fn return_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![return])
     }
-    pub fn expr(&self) -> Option<Expr> {
+    pub fn multival_expr(&self) -> Option<MultivalExpr> {
         support::child(&self.syntax)
     }
 }
@@ -267,15 +285,6 @@ impl GotoStmt {
     }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct LabelDelim {
-    pub(crate) syntax: SyntaxNode,
-}
-impl LabelDelim {
-    pub fn double_colon_token(&self) -> Option<SyntaxToken> {
-        support::token(&self.syntax, T![::])
-    }
-}
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct DoStmt {
     pub(crate) syntax: SyntaxNode,
 }
@@ -291,6 +300,15 @@ impl DoStmt {
     }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LabelDelim {
+    pub(crate) syntax: SyntaxNode,
+}
+impl LabelDelim {
+    pub fn double_colon_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![::])
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ParamList {
     pub(crate) syntax: SyntaxNode,
 }
@@ -418,7 +436,7 @@ impl GenericFor {
     pub fn in_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![in])
     }
-    pub fn expr(&self) -> Option<MultivalExpr> {
+    pub fn expr(&self) -> Option<Expr> {
         support::child(&self.syntax)
     }
 }
@@ -448,8 +466,8 @@ impl TableExpr {
     pub fn l_curly_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T!['{'])
     }
-    pub fn table_contents(&self) -> Option<TableContent> {
-        support::child(&self.syntax)
+    pub fn table_contents(&self) -> AstChildren<TableContent> {
+        support::children(&self.syntax)
     }
     pub fn r_curly_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T!['}'])
@@ -545,6 +563,21 @@ impl MethodCallExpr {
     }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParenExpr {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ParenExpr {
+    pub fn l_paren_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T!['('])
+    }
+    pub fn expr(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+    pub fn r_paren_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![')'])
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CallArgs {
     pub(crate) syntax: SyntaxNode,
 }
@@ -567,11 +600,8 @@ impl ArgList {
     pub fn l_paren_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T!['('])
     }
-    pub fn args(&self) -> AstChildren<Expr> {
-        support::children(&self.syntax)
-    }
-    pub fn triple_dot_token(&self) -> Option<SyntaxToken> {
-        support::token(&self.syntax, T![...])
+    pub fn multival_expr(&self) -> Option<MultivalExpr> {
+        support::child(&self.syntax)
     }
     pub fn r_paren_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![')'])
@@ -587,21 +617,6 @@ impl TableSep {
     }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct ParenExpr {
-    pub(crate) syntax: SyntaxNode,
-}
-impl ParenExpr {
-    pub fn l_paren_token(&self) -> Option<SyntaxToken> {
-        support::token(&self.syntax, T!['('])
-    }
-    pub fn expr(&self) -> Option<Expr> {
-        support::child(&self.syntax)
-    }
-    pub fn r_paren_token(&self) -> Option<SyntaxToken> {
-        support::token(&self.syntax, T![')'])
-    }
-}
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct KeyValue {
     pub(crate) syntax: SyntaxNode,
 }
@@ -662,6 +677,7 @@ pub enum Stmt {
     RepeatUntilStmt(RepeatUntilStmt),
     LabelStmt(LabelStmt),
     GotoStmt(GotoStmt),
+    DoStmt(DoStmt),
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum StmtItem {
@@ -686,6 +702,7 @@ pub enum Expr {
     FunctionExpr(FunctionExpr),
     CallExpr(CallExpr),
     MethodCallExpr(MethodCallExpr),
+    ParenExpr(ParenExpr),
     NameRef(NameRef),
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -748,6 +765,21 @@ impl AstNode for MultiName {
         &self.syntax
     }
 }
+impl AstNode for Attrib {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        kind == SyntaxKind::Attrib
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
 impl AstNode for SourceFile {
     fn can_cast(kind: SyntaxKind) -> bool {
         kind == SyntaxKind::SourceFile
@@ -973,9 +1005,9 @@ impl AstNode for GotoStmt {
         &self.syntax
     }
 }
-impl AstNode for LabelDelim {
+impl AstNode for DoStmt {
     fn can_cast(kind: SyntaxKind) -> bool {
-        kind == SyntaxKind::LabelDelim
+        kind == SyntaxKind::DoStmt
     }
     fn cast(syntax: SyntaxNode) -> Option<Self> {
         if Self::can_cast(syntax.kind()) {
@@ -988,9 +1020,9 @@ impl AstNode for LabelDelim {
         &self.syntax
     }
 }
-impl AstNode for DoStmt {
+impl AstNode for LabelDelim {
     fn can_cast(kind: SyntaxKind) -> bool {
-        kind == SyntaxKind::DoStmt
+        kind == SyntaxKind::LabelDelim
     }
     fn cast(syntax: SyntaxNode) -> Option<Self> {
         if Self::can_cast(syntax.kind()) {
@@ -1273,9 +1305,9 @@ impl AstNode for MethodCallExpr {
         &self.syntax
     }
 }
-impl AstNode for CallArgs {
+impl AstNode for ParenExpr {
     fn can_cast(kind: SyntaxKind) -> bool {
-        kind == SyntaxKind::CallArgs
+        kind == SyntaxKind::ParenExpr
     }
     fn cast(syntax: SyntaxNode) -> Option<Self> {
         if Self::can_cast(syntax.kind()) {
@@ -1288,9 +1320,9 @@ impl AstNode for CallArgs {
         &self.syntax
     }
 }
-impl AstNode for ArgList {
+impl AstNode for CallArgs {
     fn can_cast(kind: SyntaxKind) -> bool {
-        kind == SyntaxKind::ArgList
+        kind == SyntaxKind::CallArgs
     }
     fn cast(syntax: SyntaxNode) -> Option<Self> {
         if Self::can_cast(syntax.kind()) {
@@ -1303,9 +1335,9 @@ impl AstNode for ArgList {
         &self.syntax
     }
 }
-impl AstNode for TableSep {
+impl AstNode for ArgList {
     fn can_cast(kind: SyntaxKind) -> bool {
-        kind == SyntaxKind::TableSep
+        kind == SyntaxKind::ArgList
     }
     fn cast(syntax: SyntaxNode) -> Option<Self> {
         if Self::can_cast(syntax.kind()) {
@@ -1318,9 +1350,9 @@ impl AstNode for TableSep {
         &self.syntax
     }
 }
-impl AstNode for ParenExpr {
+impl AstNode for TableSep {
     fn can_cast(kind: SyntaxKind) -> bool {
-        kind == SyntaxKind::ParenExpr
+        kind == SyntaxKind::TableSep
     }
     fn cast(syntax: SyntaxNode) -> Option<Self> {
         if Self::can_cast(syntax.kind()) {
@@ -1463,6 +1495,11 @@ impl From<GotoStmt> for Stmt {
         Stmt::GotoStmt(node)
     }
 }
+impl From<DoStmt> for Stmt {
+    fn from(node: DoStmt) -> Stmt {
+        Stmt::DoStmt(node)
+    }
+}
 impl AstNode for Stmt {
     fn can_cast(kind: SyntaxKind) -> bool {
         match kind {
@@ -1479,7 +1516,8 @@ impl AstNode for Stmt {
             | SyntaxKind::BreakStmt
             | SyntaxKind::RepeatUntilStmt
             | SyntaxKind::LabelStmt
-            | SyntaxKind::GotoStmt => true,
+            | SyntaxKind::GotoStmt
+            | SyntaxKind::DoStmt => true,
             _ => false,
         }
     }
@@ -1501,6 +1539,7 @@ impl AstNode for Stmt {
             SyntaxKind::RepeatUntilStmt => Stmt::RepeatUntilStmt(RepeatUntilStmt { syntax }),
             SyntaxKind::LabelStmt => Stmt::LabelStmt(LabelStmt { syntax }),
             SyntaxKind::GotoStmt => Stmt::GotoStmt(GotoStmt { syntax }),
+            SyntaxKind::DoStmt => Stmt::DoStmt(DoStmt { syntax }),
             _ => return None,
         };
         Some(res)
@@ -1521,6 +1560,7 @@ impl AstNode for Stmt {
             Stmt::RepeatUntilStmt(it) => &it.syntax,
             Stmt::LabelStmt(it) => &it.syntax,
             Stmt::GotoStmt(it) => &it.syntax,
+            Stmt::DoStmt(it) => &it.syntax,
         }
     }
 }
@@ -1655,6 +1695,11 @@ impl From<MethodCallExpr> for Expr {
         Expr::MethodCallExpr(node)
     }
 }
+impl From<ParenExpr> for Expr {
+    fn from(node: ParenExpr) -> Expr {
+        Expr::ParenExpr(node)
+    }
+}
 impl From<NameRef> for Expr {
     fn from(node: NameRef) -> Expr {
         Expr::NameRef(node)
@@ -1672,6 +1717,7 @@ impl AstNode for Expr {
             | SyntaxKind::FunctionExpr
             | SyntaxKind::CallExpr
             | SyntaxKind::MethodCallExpr
+            | SyntaxKind::ParenExpr
             | SyntaxKind::NameRef => true,
             _ => false,
         }
@@ -1687,6 +1733,7 @@ impl AstNode for Expr {
             SyntaxKind::FunctionExpr => Expr::FunctionExpr(FunctionExpr { syntax }),
             SyntaxKind::CallExpr => Expr::CallExpr(CallExpr { syntax }),
             SyntaxKind::MethodCallExpr => Expr::MethodCallExpr(MethodCallExpr { syntax }),
+            SyntaxKind::ParenExpr => Expr::ParenExpr(ParenExpr { syntax }),
             SyntaxKind::NameRef => Expr::NameRef(NameRef { syntax }),
             _ => return None,
         };
@@ -1703,6 +1750,7 @@ impl AstNode for Expr {
             Expr::FunctionExpr(it) => &it.syntax,
             Expr::CallExpr(it) => &it.syntax,
             Expr::MethodCallExpr(it) => &it.syntax,
+            Expr::ParenExpr(it) => &it.syntax,
             Expr::NameRef(it) => &it.syntax,
         }
     }
@@ -1855,6 +1903,11 @@ impl std::fmt::Display for MultiName {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
+impl std::fmt::Display for Attrib {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
 impl std::fmt::Display for SourceFile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
@@ -1930,12 +1983,12 @@ impl std::fmt::Display for GotoStmt {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
-impl std::fmt::Display for LabelDelim {
+impl std::fmt::Display for DoStmt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
-impl std::fmt::Display for DoStmt {
+impl std::fmt::Display for LabelDelim {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
     }
@@ -2030,22 +2083,22 @@ impl std::fmt::Display for MethodCallExpr {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
-impl std::fmt::Display for CallArgs {
+impl std::fmt::Display for ParenExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
-impl std::fmt::Display for ArgList {
+impl std::fmt::Display for CallArgs {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
-impl std::fmt::Display for TableSep {
+impl std::fmt::Display for ArgList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
-impl std::fmt::Display for ParenExpr {
+impl std::fmt::Display for TableSep {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
     }
@@ -1,4 +1,4 @@
-use crate::{ast, SyntaxNode, TokenText};
+use crate::{ast, NodeOrToken, SyntaxKind, SyntaxNode, SyntaxToken, TokenText, T};
 
 use super::AstNode;
 
@@ -14,6 +14,85 @@ impl ast::NameRef {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttribKind {
+    Const,
+    Close,
+}
+
+impl ast::Attrib {
+    /// `None` when the attribute is neither `const` nor `close`.
+    pub fn kind(&self) -> Option<AttribKind> {
+        match self.ident_token()?.text() {
+            "const" => Some(AttribKind::Const),
+            "close" => Some(AttribKind::Close),
+            _ => None,
+        }
+    }
+}
+
+impl ast::MultiName {
+    /// Every name with the attribute that follows it, as in `local a <const>, b`.
+    pub fn names_with_attribs(&self) -> Vec<(ast::Name, Option<ast::Attrib>)> {
+        let mut res: Vec<(ast::Name, Option<ast::Attrib>)> = Vec::new();
+        for node in self.syntax().children() {
+            if let Some(name) = ast::Name::cast(node.clone()) {
+                res.push((name, None));
+            } else if let Some(attrib) = ast::Attrib::cast(node) {
+                if let Some((_, it)) = res.last_mut() {
+                    *it = Some(attrib);
+                }
+            }
+        }
+        res
+    }
+}
+
+impl ast::Block {
+    /// The block that the trivia `token` comes after, such as a blank line
+    /// between the last statement of a block and its `end`, which belongs to
+    /// the parent of the block. `None` when the last statement is unfinished,
+    /// as in `local x = a +`, since the trivia is then still part of it.
+    pub fn before_trivia(token: &SyntaxToken) -> Option<ast::Block> {
+        if !token.kind().is_trivia() {
+            return None;
+        }
+        let mut prev = token.prev_sibling_or_token();
+        while let Some(NodeOrToken::Token(it)) = &prev {
+            if !it.kind().is_trivia() {
+                return None;
+            }
+            prev = it.prev_sibling_or_token();
+        }
+        let block = prev?.into_node().and_then(ast::Block::cast)?;
+        // `last_token` stops at an empty node, such as the values of `local x =`
+        let last = block.syntax().descendants_with_tokens().filter_map(|it| it.into_token()).last();
+        last.is_none_or(|it| can_end_stmt(it.kind())).then_some(block)
+    }
+}
+
+/// Whether a statement can end with a token of `kind`, unlike with the `+` of
+/// `a +`.
+fn can_end_stmt(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        T![ident]
+            | T![number]
+            | T![str]
+            | T![true]
+            | T![false]
+            | T![nil]
+            | T![...]
+            | T![')']
+            | T![']']
+            | T!['}']
+            | T![end]
+            | T![break]
+            | T![return]
+            | T![::]
+    )
+}
+
 fn text_of_first_token(node: &SyntaxNode) -> TokenText {
     let first_token =
         node.green().children().next().and_then(|it| it.into_token()).unwrap().to_owned();
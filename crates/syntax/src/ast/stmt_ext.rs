@@ -11,6 +11,7 @@ impl ast::Stmt {
                 | ast::Stmt::WhileStmt(_)
                 | ast::Stmt::FunctionDefStmt(_)
                 | ast::Stmt::RepeatUntilStmt(_)
+                | ast::Stmt::DoStmt(_)
         )
     }
 }
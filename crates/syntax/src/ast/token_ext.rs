@@ -0,0 +1,179 @@
+use std::{borrow::Cow, str::Chars};
+
+use rowan::TextSize;
+
+use crate::ast::{self, AstToken};
+
+impl ast::Str {
+    /// The string at runtime, with every escape decoded. Long bracket strings
+    /// are kept as written except for their leading newline. Invalid escapes
+    /// are kept as written and bytes that aren't valid UTF-8, as in `"\xff"`,
+    /// are replaced.
+    pub fn value(&self) -> Cow<'_, str> {
+        let text = self.text();
+        let inner = match unquote(text) {
+            Some((_, it)) => it,
+            // unclosed, the lexer already reported it
+            None => &text[1..],
+        };
+
+        if text.starts_with('[') {
+            let skip = match inner.as_bytes() {
+                [b'\r', b'\n', ..] | [b'\n', b'\r', ..] => 2,
+                [b'\r' | b'\n', ..] => 1,
+                _ => 0,
+            };
+            return Cow::Borrowed(&inner[skip..]);
+        }
+        if !inner.contains('\\') {
+            return Cow::Borrowed(inner);
+        }
+
+        let mut buf = Vec::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                continue;
+            }
+            let escape = chars.as_str();
+            if decode_escape(&mut chars, &mut buf).is_err() {
+                buf.push(b'\\');
+                buf.extend_from_slice(&escape.as_bytes()[..escape.len() - chars.as_str().len()]);
+            }
+        }
+        match String::from_utf8(buf) {
+            Ok(it) => Cow::Owned(it),
+            Err(e) => Cow::Owned(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+        }
+    }
+}
+
+/// Unquotes a lua string, returning the offset of the contents. `None` when the
+/// string is a quoted one that was never closed.
+pub(crate) fn unquote(mut text: &str) -> Option<(TextSize, &str)> {
+    let delimit = text.chars().next().unwrap();
+
+    if delimit == '\'' || delimit == '\"' {
+        if !text.ends_with(delimit) || text.len() == 1 {
+            return None;
+        }
+        Some((1.into(), &text[1..text.len() - 1]))
+    } else if delimit == '[' {
+        // unquote front
+        text = &text[1..];
+        let equals = text.bytes().take_while(|c| *c == b'=').count();
+        text = &text[equals..];
+        text = text.strip_prefix('[').unwrap_or(text);
+
+        // unquote back
+        if let Some(rest) = text.strip_suffix(']') {
+            let back_equals = rest.bytes().rev().take_while(|c| *c == b'=').count();
+            text = &rest[..rest.len() - back_equals];
+            text = text.strip_suffix(']').unwrap_or(text);
+        };
+
+        let offset = TextSize::from(2 + equals as u32);
+        Some((offset, text))
+    } else {
+        unreachable!();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EscapeError;
+
+/// Decodes the escape after a `\` into `buf`. `chars` is left after the
+/// escape, or after the character that made it invalid.
+pub(crate) fn decode_escape(chars: &mut Chars<'_>, buf: &mut Vec<u8>) -> Result<(), EscapeError> {
+    let peek = |chars: &Chars<'_>| chars.clone().next();
+
+    let byte = match chars.next().ok_or(EscapeError)? {
+        'a' => 0x07,
+        'b' => 0x08,
+        'f' => 0x0c,
+        'n' => b'\n',
+        'r' => b'\r',
+        't' => b'\t',
+        'v' => 0x0b,
+        c @ ('\\' | '"' | '\'' | '[' | ']') => c as u8,
+        // an escaped newline, which can be `\r\n` or `\n\r` too
+        c @ ('\n' | '\r') => {
+            if matches!(peek(chars), Some(next) if next != c && matches!(next, '\n' | '\r')) {
+                chars.next();
+            }
+            b'\n'
+        }
+        'z' => {
+            while peek(chars).is_some_and(|c| c.is_ascii_whitespace()) {
+                chars.next();
+            }
+            return Ok(());
+        }
+        'x' => {
+            let mut value = 0;
+            for _ in 0..2 {
+                let digit = peek(chars).and_then(|c| c.to_digit(16)).ok_or(EscapeError)?;
+                chars.next();
+                value = value * 16 + digit;
+            }
+            value as u8
+        }
+        'u' => {
+            if peek(chars) != Some('{') {
+                return Err(EscapeError);
+            }
+            chars.next();
+            let mut value: u32 = 0;
+            let mut digits = 0;
+            while let Some(digit) = peek(chars).and_then(|c| c.to_digit(16)) {
+                chars.next();
+                digits += 1;
+                value = value.checked_mul(16).map(|it| it + digit).ok_or(EscapeError)?;
+            }
+            if digits == 0 || peek(chars) != Some('}') || value > 0x7FFF_FFFF {
+                return Err(EscapeError);
+            }
+            chars.next();
+            push_utf8(buf, value);
+            return Ok(());
+        }
+        c @ '0'..='9' => {
+            let mut value = c.to_digit(10).unwrap();
+            for _ in 0..2 {
+                match peek(chars).and_then(|c| c.to_digit(10)) {
+                    Some(digit) => {
+                        chars.next();
+                        value = value * 10 + digit;
+                    }
+                    None => break,
+                }
+            }
+            if value > 0xff {
+                return Err(EscapeError);
+            }
+            value as u8
+        }
+        _ => return Err(EscapeError),
+    };
+    buf.push(byte);
+    Ok(())
+}
+
+/// Encodes like Lua does, which allows code points up to `0x7FFFFFFF` with the
+/// original six byte UTF-8.
+fn push_utf8(buf: &mut Vec<u8>, mut value: u32) {
+    if value < 0x80 {
+        buf.push(value as u8);
+        return;
+    }
+    let mut bytes = Vec::with_capacity(6);
+    let mut first_byte_max = 0x3f;
+    while value > first_byte_max {
+        bytes.push(0x80 | (value & 0x3f) as u8);
+        value >>= 6;
+        first_byte_max >>= 1;
+    }
+    bytes.push(((!first_byte_max << 1) | value) as u8);
+    buf.extend(bytes.iter().rev());
+}
@@ -0,0 +1,233 @@
+//! LuaCATS annotations, the `---@tag` lines of the comments above a
+//! statement, such as
+//!
+//! ```lua
+//! ---Adds two numbers.
+//! ---@param a integer
+//! ---@param b? integer defaults to 1
+//! ---@return integer
+//! local function add(a, b) end
+//! ```
+//!
+//! They are a small language of their own, so the comments are lexed and
+//! parsed again into a separate lossless tree, rooted at a `DocBlock`, whose
+//! offsets are relative to the start of the comments.
+
+mod lexer;
+mod nodes;
+mod parser;
+
+pub use nodes::*;
+
+use std::sync::Arc;
+
+use rowan::{GreenNode, Language, TextRange, TextSize};
+
+use crate::{ast::AstNode, NodeOrToken, SyntaxError, SyntaxNode, SyntaxToken, T};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum DocKind {
+    // tokens
+    /// The dashes starting a line, `---`.
+    CommentMarker,
+    Whitespace,
+    Newline,
+    At,
+    Ident,
+    Number,
+    String,
+    Colon,
+    Comma,
+    Pipe,
+    Question,
+    Hash,
+    Dots,
+    LParen,
+    RParen,
+    LBrack,
+    RBrack,
+    LCurly,
+    RCurly,
+    LAngle,
+    RAngle,
+    /// Text with no meaning in annotations, such as descriptions.
+    Text,
+
+    // nodes
+    DocBlock,
+    Description,
+    ParamTag,
+    ReturnTag,
+    ReturnItem,
+    ClassTag,
+    FieldTag,
+    TypeTag,
+    AliasTag,
+    AliasVariant,
+    GenericTag,
+    GenericParam,
+    OtherTag,
+    NameType,
+    GenericArgs,
+    ArrayType,
+    OptionalType,
+    UnionType,
+    FunType,
+    FunParam,
+    TableType,
+    TableField,
+    LiteralType,
+    ParenType,
+    Error,
+}
+
+impl DocKind {
+    pub fn is_trivia(self) -> bool {
+        matches!(self, DocKind::Whitespace | DocKind::Newline | DocKind::CommentMarker)
+    }
+}
+
+impl From<DocKind> for rowan::SyntaxKind {
+    fn from(kind: DocKind) -> rowan::SyntaxKind {
+        rowan::SyntaxKind(kind as u16)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DocLanguage {}
+
+impl Language for DocLanguage {
+    type Kind = DocKind;
+
+    fn kind_from_raw(raw: rowan::SyntaxKind) -> DocKind {
+        assert!(raw.0 <= DocKind::Error as u16);
+        unsafe { std::mem::transmute::<u16, DocKind>(raw.0) }
+    }
+
+    fn kind_to_raw(kind: DocKind) -> rowan::SyntaxKind {
+        kind.into()
+    }
+}
+
+pub type DocNode = rowan::SyntaxNode<DocLanguage>;
+pub type DocToken = rowan::SyntaxToken<DocLanguage>;
+pub type DocElement = rowan::SyntaxElement<DocLanguage>;
+
+/// The tree of a block of annotations, which remembers where the comments it
+/// was parsed from start in the file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DocParse {
+    green: GreenNode,
+    errors: Arc<Vec<SyntaxError>>,
+    offset: TextSize,
+}
+
+impl DocParse {
+    /// Parses the text of consecutive `---` comments, which start at `offset`
+    /// in the file.
+    pub fn parse(text: &str, offset: TextSize) -> DocParse {
+        let tokens = lexer::tokenize(text);
+        let (green, errors) = parser::parse(text, &tokens);
+        let errors =
+            errors.into_iter().map(|(msg, range)| SyntaxError::new(msg, range + offset)).collect();
+        DocParse { green, errors: Arc::new(errors), offset }
+    }
+
+    pub fn syntax_node(&self) -> DocNode {
+        DocNode::new_root(self.green.clone())
+    }
+
+    pub fn tree(&self) -> DocBlock {
+        DocBlock::cast(self.syntax_node()).unwrap()
+    }
+
+    /// The errors of the annotations, with ranges in the file.
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+
+    /// The offset in the file of the start of the comments.
+    pub fn offset(&self) -> TextSize {
+        self.offset
+    }
+
+    /// The range in the file of a range of the tree.
+    pub fn file_range(&self, range: TextRange) -> TextRange {
+        range + self.offset
+    }
+
+    pub fn debug_dump(&self) -> String {
+        let mut s = format!("{:#?}", self.syntax_node());
+        for error in self.errors.iter() {
+            s.push_str(&format!("error {:?}: {}\n", error.range(), error));
+        }
+        s
+    }
+}
+
+/// The annotations in the `---` comments directly above `node`, with no blank
+/// line in between.
+pub fn doc_block(node: &SyntaxNode) -> Option<DocParse> {
+    let mut first = None;
+    let mut last = None;
+    let mut sibling = node.prev_sibling_or_token();
+    while let Some(NodeOrToken::Token(token)) = sibling {
+        match token.kind() {
+            T![comment] if is_doc_comment(&token) => {
+                last.get_or_insert_with(|| token.clone());
+                first = Some(token.clone());
+            }
+            T![whitespace] if token.text().matches('\n').count() < 2 => (),
+            _ => break,
+        }
+        sibling = token.prev_sibling_or_token();
+    }
+    let range = first?.text_range().cover(last?.text_range());
+    Some(parse_range(node, range))
+}
+
+/// Every block of consecutive `---` comments of a file, including those that
+/// are not above a statement, such as those declaring classes and aliases.
+pub fn doc_blocks(file: &crate::ast::SourceFile) -> Vec<DocParse> {
+    let root = file.syntax();
+    let mut blocks = Vec::new();
+    let mut current: Option<TextRange> = None;
+    for element in root.descendants_with_tokens() {
+        let token = match element {
+            NodeOrToken::Token(token) => token,
+            NodeOrToken::Node(_) => continue,
+        };
+        match token.kind() {
+            T![comment] if is_doc_comment(&token) => {
+                let range = token.text_range();
+                current = Some(current.map_or(range, |it| it.cover(range)));
+            }
+            T![whitespace] if token.text().matches('\n').count() < 2 => (),
+            _ => {
+                if let Some(range) = current.take() {
+                    blocks.push(parse_range(root, range));
+                }
+            }
+        }
+    }
+    if let Some(range) = current {
+        blocks.push(parse_range(root, range));
+    }
+    blocks
+}
+
+/// Whether a comment is a `---` line comment, which are the only ones holding
+/// annotations.
+fn is_doc_comment(token: &SyntaxToken) -> bool {
+    let text = token.text();
+    text.starts_with("---") && !text.trim_start_matches('-').starts_with('[')
+}
+
+fn parse_range(node: &SyntaxNode, range: TextRange) -> DocParse {
+    let text = node.ancestors().last().unwrap().text().slice(range).to_string();
+    DocParse::parse(&text, range.start())
+}
+
+#[cfg(test)]
+mod tests;
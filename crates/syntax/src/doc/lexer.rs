@@ -0,0 +1,125 @@
+//! Splits the text of annotation comments into tokens.
+//!
+//! Every line starts with its comment marker. Only the rest of the lines
+//! starting with `@` or `|` are annotations, other lines are plain text.
+
+use rowan::TextSize;
+
+use super::DocKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Token {
+    pub(super) kind: DocKind,
+    pub(super) len: TextSize,
+}
+
+pub(super) fn tokenize(text: &str) -> Vec<Token> {
+    let mut lexer = Lexer { rest: text, tokens: Vec::new() };
+    while !lexer.rest.is_empty() {
+        lexer.line();
+    }
+    lexer.tokens
+}
+
+struct Lexer<'a> {
+    rest: &'a str,
+    tokens: Vec<Token>,
+}
+
+impl Lexer<'_> {
+    fn line(&mut self) {
+        self.eat_while(DocKind::Whitespace, is_whitespace);
+        self.eat_while(DocKind::CommentMarker, |c| c == '-');
+        if self.rest.starts_with('@') || self.rest.starts_with('|') {
+            while !self.rest.is_empty() && !self.newline() {
+                self.annotation_token();
+            }
+        } else {
+            self.eat_while(DocKind::Whitespace, is_whitespace);
+            self.eat_while(DocKind::Text, |c| c != '\n' && c != '\r');
+            if !self.newline() {
+                // a lone `\r`
+                self.eat_len(DocKind::Text, self.rest.chars().next().map_or(0, char::len_utf8));
+            }
+        }
+    }
+
+    /// Eats the newline at the start of the text, if any.
+    fn newline(&mut self) -> bool {
+        let len = if self.rest.starts_with("\r\n") {
+            2
+        } else if self.rest.starts_with('\n') {
+            1
+        } else {
+            return false;
+        };
+        self.eat_len(DocKind::Newline, len);
+        true
+    }
+
+    fn annotation_token(&mut self) {
+        let c = self.rest.chars().next().unwrap();
+        let kind = match c {
+            _ if is_whitespace(c) => return self.eat_while(DocKind::Whitespace, is_whitespace),
+            _ if is_ident_start(c) => return self.eat_while(DocKind::Ident, is_ident_continue),
+            '0'..='9' => {
+                return self.eat_while(DocKind::Number, |c| c.is_ascii_alphanumeric() || c == '.')
+            }
+            '"' | '\'' | '`' => return self.string(c),
+            '.' if self.rest.starts_with("...") => return self.eat_len(DocKind::Dots, 3),
+            '@' => DocKind::At,
+            ':' => DocKind::Colon,
+            ',' => DocKind::Comma,
+            '|' => DocKind::Pipe,
+            '?' => DocKind::Question,
+            '#' => DocKind::Hash,
+            '(' => DocKind::LParen,
+            ')' => DocKind::RParen,
+            '[' => DocKind::LBrack,
+            ']' => DocKind::RBrack,
+            '{' => DocKind::LCurly,
+            '}' => DocKind::RCurly,
+            '<' => DocKind::LAngle,
+            '>' => DocKind::RAngle,
+            _ => DocKind::Text,
+        };
+        self.eat_len(kind, c.len_utf8());
+    }
+
+    /// Eats a string up to its closing quote or the end of the line.
+    fn string(&mut self, quote: char) {
+        let body = &self.rest[1..];
+        let len = match body.find([quote, '\n', '\r']) {
+            Some(idx) if body[idx..].starts_with(quote) => idx + 2,
+            Some(idx) => idx + 1,
+            None => self.rest.len(),
+        };
+        self.eat_len(DocKind::String, len);
+    }
+
+    fn eat_while(&mut self, kind: DocKind, pred: impl Fn(char) -> bool) {
+        let len = self.rest.find(|c| !pred(c)).unwrap_or(self.rest.len());
+        self.eat_len(kind, len);
+    }
+
+    fn eat_len(&mut self, kind: DocKind, len: usize) {
+        if len == 0 {
+            return;
+        }
+        self.tokens.push(Token { kind, len: TextSize::of(&self.rest[..len]) });
+        self.rest = &self.rest[len..];
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+/// Dots are allowed in names, as in `io.File`.
+fn is_ident_continue(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
@@ -0,0 +1,450 @@
+//! Typed wrappers around the nodes of annotation trees, like those of `ast`.
+
+use super::{DocKind, DocNode, DocToken};
+
+pub trait DocAstNode {
+    fn can_cast(kind: DocKind) -> bool
+    where
+        Self: Sized;
+
+    fn cast(syntax: DocNode) -> Option<Self>
+    where
+        Self: Sized;
+
+    fn syntax(&self) -> &DocNode;
+}
+
+macro_rules! doc_nodes {
+    ($($(#[$attr:meta])* $name:ident,)*) => {$(
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $name {
+            syntax: DocNode,
+        }
+
+        impl DocAstNode for $name {
+            fn can_cast(kind: DocKind) -> bool {
+                kind == DocKind::$name
+            }
+
+            fn cast(syntax: DocNode) -> Option<Self> {
+                if Self::can_cast(syntax.kind()) {
+                    Some(Self { syntax })
+                } else {
+                    None
+                }
+            }
+
+            fn syntax(&self) -> &DocNode {
+                &self.syntax
+            }
+        }
+    )*};
+}
+
+doc_nodes! {
+    /// The annotations of a block of comments.
+    DocBlock,
+    /// Free text, either a whole line or what follows an annotation.
+    Description,
+    /// `---@param name type`
+    ParamTag,
+    /// `---@return type name, type name`
+    ReturnTag,
+    ReturnItem,
+    /// `---@class Name : Parent`
+    ClassTag,
+    /// `---@field name type` or `---@field [type] type`
+    FieldTag,
+    /// `---@type type, type`
+    TypeTag,
+    /// `---@alias Name type`, followed by `---| type` variants.
+    AliasTag,
+    AliasVariant,
+    /// `---@generic T : Parent`
+    GenericTag,
+    GenericParam,
+    /// Any other tag, such as `---@deprecated`.
+    OtherTag,
+    /// A named type such as `integer` or `table<string, T>`.
+    NameType,
+    /// `T[]`
+    ArrayType,
+    /// `T?`, which is `T | nil`.
+    OptionalType,
+    UnionType,
+    /// `fun(name: T, ...: T): T`
+    FunType,
+    FunParam,
+    /// `{ name: T, [K]: V }`
+    TableType,
+    TableField,
+    /// A string or number that is its own type, as in `"left" | "right"`.
+    LiteralType,
+    ParenType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DocTag {
+    Param(ParamTag),
+    Return(ReturnTag),
+    Class(ClassTag),
+    Field(FieldTag),
+    Type(TypeTag),
+    Alias(AliasTag),
+    Generic(GenericTag),
+    Other(OtherTag),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DocType {
+    Name(NameType),
+    Array(ArrayType),
+    Optional(OptionalType),
+    Union(UnionType),
+    Fun(FunType),
+    Table(TableType),
+    Literal(LiteralType),
+    Paren(ParenType),
+}
+
+macro_rules! doc_enum {
+    ($name:ident { $($variant:ident($node:ident),)* }) => {
+        impl DocAstNode for $name {
+            fn can_cast(kind: DocKind) -> bool {
+                matches!(kind, $(DocKind::$node)|*)
+            }
+
+            fn cast(syntax: DocNode) -> Option<Self> {
+                let res = match syntax.kind() {
+                    $(DocKind::$node => $name::$variant($node { syntax }),)*
+                    _ => return None,
+                };
+                Some(res)
+            }
+
+            fn syntax(&self) -> &DocNode {
+                match self {
+                    $($name::$variant(it) => it.syntax(),)*
+                }
+            }
+        }
+    };
+}
+
+doc_enum!(DocTag {
+    Param(ParamTag),
+    Return(ReturnTag),
+    Class(ClassTag),
+    Field(FieldTag),
+    Type(TypeTag),
+    Alias(AliasTag),
+    Generic(GenericTag),
+    Other(OtherTag),
+});
+
+doc_enum!(DocType {
+    Name(NameType),
+    Array(ArrayType),
+    Optional(OptionalType),
+    Union(UnionType),
+    Fun(FunType),
+    Table(TableType),
+    Literal(LiteralType),
+    Paren(ParenType),
+});
+
+fn child<N: DocAstNode>(parent: &DocNode) -> Option<N> {
+    parent.children().find_map(N::cast)
+}
+
+fn children<N: DocAstNode>(parent: &DocNode) -> impl Iterator<Item = N> {
+    parent.children().filter_map(N::cast)
+}
+
+fn token(parent: &DocNode, kind: DocKind) -> Option<DocToken> {
+    parent.children_with_tokens().filter_map(|it| it.into_token()).find(|it| it.kind() == kind)
+}
+
+/// The first identifier after the name of the tag.
+fn tag_name_token(tag: &DocNode) -> Option<DocToken> {
+    tag.children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| it.kind() == DocKind::Ident)
+        .nth(1)
+}
+
+impl DocBlock {
+    pub fn tags(&self) -> impl Iterator<Item = DocTag> {
+        children(&self.syntax)
+    }
+
+    /// The text of the lines that are not annotations.
+    pub fn description(&self) -> Option<String> {
+        let lines: Vec<_> = children::<Description>(&self.syntax).map(|it| it.text()).collect();
+        if lines.is_empty() {
+            return None;
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+impl Description {
+    /// The text, without the `#` that can start it.
+    pub fn text(&self) -> String {
+        let text = self.syntax.text().to_string();
+        let text = text.trim();
+        text.strip_prefix('#').map_or(text, |it| it.trim_start()).to_string()
+    }
+}
+
+impl DocTag {
+    /// The name of the tag, such as `param`.
+    pub fn name_token(&self) -> Option<DocToken> {
+        token(self.syntax(), DocKind::Ident)
+    }
+
+    pub fn description(&self) -> Option<Description> {
+        child(self.syntax())
+    }
+}
+
+impl ParamTag {
+    /// The name of the parameter, which is `...` for varargs.
+    pub fn name_token(&self) -> Option<DocToken> {
+        self.syntax
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| matches!(it.kind(), DocKind::Ident | DocKind::Dots))
+            .nth(1)
+    }
+
+    pub fn is_optional(&self) -> bool {
+        token(&self.syntax, DocKind::Question).is_some()
+    }
+
+    pub fn ty(&self) -> Option<DocType> {
+        child(&self.syntax)
+    }
+
+    pub fn description(&self) -> Option<Description> {
+        child(&self.syntax)
+    }
+}
+
+impl ReturnTag {
+    pub fn items(&self) -> impl Iterator<Item = ReturnItem> {
+        children(&self.syntax)
+    }
+
+    pub fn description(&self) -> Option<Description> {
+        child(&self.syntax)
+    }
+}
+
+impl ReturnItem {
+    pub fn ty(&self) -> Option<DocType> {
+        child(&self.syntax)
+    }
+
+    pub fn name_token(&self) -> Option<DocToken> {
+        token(&self.syntax, DocKind::Ident)
+    }
+}
+
+impl ClassTag {
+    pub fn name_token(&self) -> Option<DocToken> {
+        tag_name_token(&self.syntax)
+    }
+
+    pub fn parents(&self) -> impl Iterator<Item = DocType> {
+        children(&self.syntax)
+    }
+
+    pub fn description(&self) -> Option<Description> {
+        child(&self.syntax)
+    }
+}
+
+impl FieldTag {
+    /// The name of the field, unless it is indexed by a type.
+    pub fn name_token(&self) -> Option<DocToken> {
+        if self.key_ty().is_some() {
+            return None;
+        }
+        // the name follows the name of the tag and the visibility, if any
+        self.syntax
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() == DocKind::Ident)
+            .skip(1)
+            .last()
+    }
+
+    /// The type of the keys of `---@field [K] V`.
+    pub fn key_ty(&self) -> Option<DocType> {
+        token(&self.syntax, DocKind::LBrack)?;
+        child(&self.syntax)
+    }
+
+    pub fn is_optional(&self) -> bool {
+        token(&self.syntax, DocKind::Question).is_some()
+    }
+
+    pub fn ty(&self) -> Option<DocType> {
+        let skip = if token(&self.syntax, DocKind::LBrack).is_some() { 1 } else { 0 };
+        children(&self.syntax).nth(skip)
+    }
+
+    pub fn description(&self) -> Option<Description> {
+        child(&self.syntax)
+    }
+}
+
+impl TypeTag {
+    pub fn types(&self) -> impl Iterator<Item = DocType> {
+        children(&self.syntax)
+    }
+}
+
+impl AliasTag {
+    pub fn name_token(&self) -> Option<DocToken> {
+        tag_name_token(&self.syntax)
+    }
+
+    /// The type on the line of the tag, if any.
+    pub fn ty(&self) -> Option<DocType> {
+        child(&self.syntax)
+    }
+
+    pub fn variants(&self) -> impl Iterator<Item = AliasVariant> {
+        children(&self.syntax)
+    }
+}
+
+impl AliasVariant {
+    pub fn ty(&self) -> Option<DocType> {
+        child(&self.syntax)
+    }
+
+    pub fn description(&self) -> Option<Description> {
+        child(&self.syntax)
+    }
+}
+
+impl GenericTag {
+    pub fn params(&self) -> impl Iterator<Item = GenericParam> {
+        children(&self.syntax)
+    }
+}
+
+impl GenericParam {
+    pub fn name_token(&self) -> Option<DocToken> {
+        token(&self.syntax, DocKind::Ident)
+    }
+
+    pub fn bound(&self) -> Option<DocType> {
+        child(&self.syntax)
+    }
+}
+
+impl NameType {
+    pub fn name_token(&self) -> Option<DocToken> {
+        token(&self.syntax, DocKind::Ident)
+    }
+
+    /// The arguments in `<>`, as in `table<string, integer>`.
+    pub fn args(&self) -> impl Iterator<Item = DocType> {
+        self.syntax
+            .children()
+            .find(|it| it.kind() == DocKind::GenericArgs)
+            .into_iter()
+            .flat_map(|it| children(&it).collect::<Vec<_>>())
+    }
+}
+
+impl ArrayType {
+    pub fn elem(&self) -> Option<DocType> {
+        child(&self.syntax)
+    }
+}
+
+impl OptionalType {
+    pub fn inner(&self) -> Option<DocType> {
+        child(&self.syntax)
+    }
+}
+
+impl UnionType {
+    pub fn types(&self) -> impl Iterator<Item = DocType> {
+        children(&self.syntax)
+    }
+}
+
+impl FunType {
+    pub fn params(&self) -> impl Iterator<Item = FunParam> {
+        children(&self.syntax)
+    }
+
+    pub fn returns(&self) -> impl Iterator<Item = DocType> {
+        children(&self.syntax)
+    }
+}
+
+impl FunParam {
+    /// The name of the parameter, which is `...` for varargs.
+    pub fn name_token(&self) -> Option<DocToken> {
+        self.syntax
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| matches!(it.kind(), DocKind::Ident | DocKind::Dots))
+    }
+
+    pub fn is_optional(&self) -> bool {
+        token(&self.syntax, DocKind::Question).is_some()
+    }
+
+    pub fn ty(&self) -> Option<DocType> {
+        child(&self.syntax)
+    }
+}
+
+impl TableType {
+    pub fn fields(&self) -> impl Iterator<Item = TableField> {
+        children(&self.syntax)
+    }
+}
+
+impl TableField {
+    /// The name of the field, unless it is indexed by a type.
+    pub fn name_token(&self) -> Option<DocToken> {
+        token(&self.syntax, DocKind::Ident)
+    }
+
+    /// The type of the keys of `[K]: V`.
+    pub fn key_ty(&self) -> Option<DocType> {
+        token(&self.syntax, DocKind::LBrack)?;
+        child(&self.syntax)
+    }
+
+    pub fn is_optional(&self) -> bool {
+        token(&self.syntax, DocKind::Question).is_some()
+    }
+
+    pub fn ty(&self) -> Option<DocType> {
+        let skip = if token(&self.syntax, DocKind::LBrack).is_some() { 1 } else { 0 };
+        children(&self.syntax).nth(skip)
+    }
+}
+
+impl LiteralType {
+    pub fn token(&self) -> Option<DocToken> {
+        self.syntax.first_token()
+    }
+}
+
+impl ParenType {
+    pub fn inner(&self) -> Option<DocType> {
+        child(&self.syntax)
+    }
+}
@@ -0,0 +1,507 @@
+//! A recursive descent parser of annotations, building the green tree
+//! directly.
+//!
+//! Every annotation is on a single line, except for the `---|` variants of an
+//! `@alias`, so the end of a line ends whatever is being parsed and anything
+//! unexpected before it is a description.
+
+use rowan::{GreenNode, GreenNodeBuilder, TextRange, TextSize};
+
+use super::{lexer::Token, DocKind};
+
+pub(super) fn parse(text: &str, tokens: &[Token]) -> (GreenNode, Vec<(String, TextRange)>) {
+    let mut parser = Parser {
+        text,
+        tokens,
+        pos: 0,
+        offset: TextSize::from(0),
+        builder: GreenNodeBuilder::new(),
+        errors: Vec::new(),
+    };
+    parser.block();
+    (parser.builder.finish(), parser.errors)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+    offset: TextSize,
+    builder: GreenNodeBuilder<'static>,
+    errors: Vec<(String, TextRange)>,
+}
+
+impl Parser<'_> {
+    fn block(&mut self) {
+        self.builder.start_node(DocKind::DocBlock.into());
+        while self.pos < self.tokens.len() {
+            self.bump_line_start();
+            match self.current() {
+                Some(DocKind::At) => self.tag(),
+                Some(DocKind::Pipe) => {
+                    self.error("expected an @alias before its variants");
+                    self.alias_variant();
+                }
+                _ => self.description(),
+            }
+            self.description();
+            if self.at_line_end() {
+                self.bump_any();
+            }
+        }
+        self.builder.finish_node();
+    }
+
+    fn tag(&mut self) {
+        self.skip_whitespace();
+        let checkpoint = self.builder.checkpoint();
+        self.bump(DocKind::At);
+        let name = match self.current() {
+            Some(DocKind::Ident) => self.current_text().to_string(),
+            _ => {
+                self.error("expected the name of a tag");
+                String::new()
+            }
+        };
+        self.bump_any_on_line();
+        let kind = match name.as_str() {
+            "param" => {
+                self.param_tag();
+                DocKind::ParamTag
+            }
+            "return" => {
+                self.return_tag();
+                DocKind::ReturnTag
+            }
+            "class" => {
+                self.class_tag();
+                DocKind::ClassTag
+            }
+            "field" => {
+                self.field_tag();
+                DocKind::FieldTag
+            }
+            "type" => {
+                self.type_list();
+                DocKind::TypeTag
+            }
+            "alias" => {
+                self.alias_tag();
+                DocKind::AliasTag
+            }
+            "generic" => {
+                self.generic_tag();
+                DocKind::GenericTag
+            }
+            _ => DocKind::OtherTag,
+        };
+        self.description();
+        self.builder.start_node_at(checkpoint, kind.into());
+        self.builder.finish_node();
+    }
+
+    /// `@param name[?] type`
+    fn param_tag(&mut self) {
+        if !self.eat(DocKind::Ident) && !self.eat(DocKind::Dots) {
+            self.error("expected the name of a parameter");
+            return;
+        }
+        self.eat(DocKind::Question);
+        self.ty_or_error();
+    }
+
+    /// `@return type [name], type [name]`
+    fn return_tag(&mut self) {
+        loop {
+            self.start(DocKind::ReturnItem);
+            self.ty_or_error();
+            self.eat(DocKind::Ident);
+            self.finish();
+            if !self.eat(DocKind::Comma) {
+                break;
+            }
+        }
+    }
+
+    /// `@class Name : Parent, Parent`
+    fn class_tag(&mut self) {
+        if !self.eat(DocKind::Ident) {
+            self.error("expected the name of a class");
+            return;
+        }
+        if self.eat(DocKind::Colon) {
+            self.type_list();
+        }
+    }
+
+    /// `@field [visibility] name[?] type` or `@field [type] type`
+    fn field_tag(&mut self) {
+        if self.current() == Some(DocKind::Ident)
+            && matches!(self.current_text(), "public" | "protected" | "private" | "package")
+            && matches!(self.nth(1), Some(DocKind::Ident | DocKind::LBrack))
+        {
+            self.bump_any();
+        }
+        if self.eat(DocKind::LBrack) {
+            self.ty_or_error();
+            self.expect(DocKind::RBrack, "]");
+        } else if !self.eat(DocKind::Ident) {
+            self.error("expected the name of a field");
+            return;
+        }
+        self.eat(DocKind::Question);
+        self.ty_or_error();
+    }
+
+    /// `@alias Name [type]`, followed by lines of `---| type` variants.
+    fn alias_tag(&mut self) {
+        if !self.eat(DocKind::Ident) {
+            self.error("expected the name of an alias");
+            return;
+        }
+        if !self.at_line_end() && self.current() != Some(DocKind::Hash) {
+            self.ty_or_error();
+        }
+        while self.at_variant_line() {
+            self.description();
+            self.bump_any();
+            self.bump_line_start();
+            self.alias_variant();
+        }
+    }
+
+    /// `| type [description]`
+    fn alias_variant(&mut self) {
+        self.start(DocKind::AliasVariant);
+        self.bump(DocKind::Pipe);
+        self.ty_or_error();
+        self.description();
+        self.finish();
+    }
+
+    /// `@generic T, K : Parent`
+    fn generic_tag(&mut self) {
+        loop {
+            self.start(DocKind::GenericParam);
+            if self.expect(DocKind::Ident, "the name of a generic parameter")
+                && self.eat(DocKind::Colon)
+            {
+                self.ty_or_error();
+            }
+            self.finish();
+            if !self.eat(DocKind::Comma) {
+                break;
+            }
+        }
+    }
+
+    /// The rest of the line, which can start with a `#`.
+    fn description(&mut self) {
+        self.skip_whitespace();
+        if self.at_line_end() {
+            return;
+        }
+        self.start(DocKind::Description);
+        while !self.at_line_end() {
+            self.bump_any();
+        }
+        self.finish();
+    }
+
+    fn type_list(&mut self) {
+        self.ty_or_error();
+        while self.eat(DocKind::Comma) {
+            self.ty_or_error();
+        }
+    }
+
+    fn ty_or_error(&mut self) {
+        if !self.ty() {
+            self.error("expected a type");
+        }
+    }
+
+    /// `type | type`
+    fn ty(&mut self) -> bool {
+        self.skip_whitespace();
+        let checkpoint = self.builder.checkpoint();
+        // a leading `|` is allowed, as in the variants of an alias
+        let leading_pipe = self.eat(DocKind::Pipe);
+        if !self.postfix_ty() {
+            return false;
+        }
+        if self.current() != Some(DocKind::Pipe) {
+            if leading_pipe {
+                self.builder.start_node_at(checkpoint, DocKind::UnionType.into());
+                self.builder.finish_node();
+            }
+            return true;
+        }
+        while self.eat(DocKind::Pipe) {
+            if !self.postfix_ty() {
+                self.error("expected a type");
+            }
+        }
+        self.builder.start_node_at(checkpoint, DocKind::UnionType.into());
+        self.builder.finish_node();
+        true
+    }
+
+    /// `type[]` and `type?`
+    fn postfix_ty(&mut self) -> bool {
+        self.skip_whitespace();
+        let checkpoint = self.builder.checkpoint();
+        if !self.primary_ty() {
+            return false;
+        }
+        loop {
+            let kind = match self.current() {
+                Some(DocKind::LBrack) if self.nth(1) == Some(DocKind::RBrack) => {
+                    self.bump_any();
+                    self.bump_any();
+                    DocKind::ArrayType
+                }
+                Some(DocKind::Question) => {
+                    self.bump_any();
+                    DocKind::OptionalType
+                }
+                _ => return true,
+            };
+            self.builder.start_node_at(checkpoint, kind.into());
+            self.builder.finish_node();
+        }
+    }
+
+    fn primary_ty(&mut self) -> bool {
+        match self.current() {
+            Some(DocKind::Ident) if self.current_text() == "fun" => self.fun_ty(),
+            Some(DocKind::Ident) => {
+                self.start(DocKind::NameType);
+                self.bump_any();
+                if self.current() == Some(DocKind::LAngle) {
+                    self.start(DocKind::GenericArgs);
+                    self.bump_any();
+                    self.type_list();
+                    self.expect(DocKind::RAngle, ">");
+                    self.finish();
+                }
+                self.finish();
+            }
+            Some(DocKind::String | DocKind::Number) => {
+                self.start(DocKind::LiteralType);
+                self.bump_any();
+                self.finish();
+            }
+            Some(DocKind::LParen) => {
+                self.start(DocKind::ParenType);
+                self.bump_any();
+                self.ty_or_error();
+                self.expect(DocKind::RParen, ")");
+                self.finish();
+            }
+            Some(DocKind::LCurly) => self.table_ty(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// `fun(name: type, ...: type): type, type`
+    fn fun_ty(&mut self) {
+        self.start(DocKind::FunType);
+        self.bump_any();
+        if self.expect(DocKind::LParen, "(") {
+            while !self.at_line_end() && self.current() != Some(DocKind::RParen) {
+                self.start(DocKind::FunParam);
+                if !self.eat(DocKind::Ident) && !self.eat(DocKind::Dots) {
+                    self.error("expected the name of a parameter");
+                    self.finish();
+                    break;
+                }
+                self.eat(DocKind::Question);
+                if self.eat(DocKind::Colon) {
+                    self.ty_or_error();
+                }
+                self.finish();
+                if !self.eat(DocKind::Comma) {
+                    break;
+                }
+            }
+            self.expect(DocKind::RParen, ")");
+        }
+        if self.eat(DocKind::Colon) {
+            self.ty_or_error();
+            while self.current() == Some(DocKind::Comma) && !self.at_next_field() {
+                self.bump_any();
+                self.ty_or_error();
+            }
+        }
+        self.finish();
+    }
+
+    /// `{ name: type, [type]: type }`
+    fn table_ty(&mut self) {
+        self.start(DocKind::TableType);
+        self.bump_any();
+        while !self.at_line_end() && self.current() != Some(DocKind::RCurly) {
+            self.start(DocKind::TableField);
+            if self.eat(DocKind::LBrack) {
+                self.ty_or_error();
+                self.expect(DocKind::RBrack, "]");
+            } else if !self.eat(DocKind::Ident) {
+                self.error("expected the name of a field");
+                self.finish();
+                break;
+            }
+            self.eat(DocKind::Question);
+            if self.expect(DocKind::Colon, ":") {
+                self.ty_or_error();
+            }
+            self.finish();
+            if !self.eat(DocKind::Comma) {
+                break;
+            }
+        }
+        self.expect(DocKind::RCurly, "}");
+        self.finish();
+    }
+
+    /// Whether the next line is a `---|` variant of an alias.
+    fn at_variant_line(&self) -> bool {
+        let mut rest = self.tokens[self.pos..]
+            .iter()
+            .map(|it| it.kind)
+            .skip_while(|&it| it == DocKind::Whitespace);
+        rest.next() == Some(DocKind::Newline)
+            && rest.find(|&it| it != DocKind::Whitespace && it != DocKind::CommentMarker)
+                == Some(DocKind::Pipe)
+    }
+
+    /// Whether the comma at the current token separates the fields of a table
+    /// type or the parameters of a function type rather than the returns of a
+    /// function type, as in `{ f: fun(): integer, g: string }`.
+    fn at_next_field(&self) -> bool {
+        match self.nth(1) {
+            Some(DocKind::LBrack) => true,
+            Some(DocKind::Ident | DocKind::Dots) => {
+                matches!(self.nth(2), Some(DocKind::Colon | DocKind::Question))
+            }
+            _ => false,
+        }
+    }
+
+    /// Bumps the indentation and comment marker starting a line.
+    fn bump_line_start(&mut self) {
+        while matches!(self.nth_raw(0), Some(DocKind::Whitespace | DocKind::CommentMarker)) {
+            self.bump_raw();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.nth_raw(0) == Some(DocKind::Whitespace) {
+            self.bump_raw();
+        }
+    }
+
+    fn at_line_end(&self) -> bool {
+        matches!(self.current(), None | Some(DocKind::Newline))
+    }
+
+    /// The kind of the current token, skipping whitespace.
+    fn current(&self) -> Option<DocKind> {
+        self.nth(0)
+    }
+
+    /// The kind of the `n`th token from the current one, skipping
+    /// whitespace.
+    fn nth(&self, n: usize) -> Option<DocKind> {
+        self.tokens[self.pos..]
+            .iter()
+            .map(|it| it.kind)
+            .filter(|&it| it != DocKind::Whitespace)
+            .nth(n)
+    }
+
+    fn nth_raw(&self, n: usize) -> Option<DocKind> {
+        self.tokens.get(self.pos + n).map(|it| it.kind)
+    }
+
+    fn current_text(&self) -> &str {
+        let mut offset = self.offset;
+        for token in &self.tokens[self.pos..] {
+            if token.kind != DocKind::Whitespace {
+                return &self.text[TextRange::at(offset, token.len)];
+            }
+            offset += token.len;
+        }
+        ""
+    }
+
+    fn eat(&mut self, kind: DocKind) -> bool {
+        if self.current() != Some(kind) {
+            return false;
+        }
+        self.bump_any();
+        true
+    }
+
+    fn expect(&mut self, kind: DocKind, what: &str) -> bool {
+        if self.eat(kind) {
+            return true;
+        }
+        self.error(&format!("expected {}", what));
+        false
+    }
+
+    fn bump(&mut self, kind: DocKind) {
+        assert_eq!(self.current(), Some(kind));
+        self.bump_any();
+    }
+
+    /// Bumps the current token, unless it ends the line.
+    fn bump_any_on_line(&mut self) {
+        if !self.at_line_end() {
+            self.bump_any();
+        }
+    }
+
+    /// Bumps the whitespace before the current token and the token itself.
+    fn bump_any(&mut self) {
+        self.skip_whitespace();
+        self.bump_raw();
+    }
+
+    fn bump_raw(&mut self) {
+        if let Some(token) = self.tokens.get(self.pos) {
+            let text = &self.text[TextRange::at(self.offset, token.len)];
+            self.builder.token(token.kind.into(), text);
+            self.offset += token.len;
+            self.pos += 1;
+        }
+    }
+
+    fn start(&mut self, kind: DocKind) {
+        self.skip_whitespace();
+        self.builder.start_node(kind.into());
+    }
+
+    fn finish(&mut self) {
+        self.builder.finish_node();
+    }
+
+    fn error(&mut self, msg: &str) {
+        let len = match self.current() {
+            Some(DocKind::Newline) | None => TextSize::from(0),
+            Some(_) => self.tokens[self.pos..]
+                .iter()
+                .find(|it| it.kind != DocKind::Whitespace)
+                .map_or(TextSize::from(0), |it| it.len),
+        };
+        let start = self.offset
+            + self.tokens[self.pos..]
+                .iter()
+                .take_while(|it| it.kind == DocKind::Whitespace)
+                .map(|it| it.len)
+                .sum::<TextSize>();
+        self.errors.push((msg.to_string(), TextRange::at(start, len)));
+    }
+}
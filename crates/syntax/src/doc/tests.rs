@@ -0,0 +1,204 @@
+use test_utils::assert_eq_text;
+
+use super::*;
+use crate::{ast, LuaVersion, SourceFile, TextSize};
+
+fn parse(text: &str) -> DocParse {
+    DocParse::parse(text, TextSize::from(0))
+}
+
+fn check(text: &str, expected: &str) {
+    assert_eq_text!(expected.trim_start(), &parse(text).debug_dump());
+}
+
+fn type_text(ty: Option<DocType>) -> String {
+    ty.map_or_else(String::new, |it| it.syntax().text().to_string())
+}
+
+#[test]
+fn parse_function_annotations() {
+    check(
+        "---Adds numbers.\n---@param a integer\n---@param b? number # the step\n---@return integer sum",
+        r##"
+DocBlock@0..90
+  CommentMarker@0..3 "---"
+  Description@3..16
+    Text@3..16 "Adds numbers."
+  Newline@16..17 "\n"
+  CommentMarker@17..20 "---"
+  ParamTag@20..36
+    At@20..21 "@"
+    Ident@21..26 "param"
+    Whitespace@26..27 " "
+    Ident@27..28 "a"
+    Whitespace@28..29 " "
+    NameType@29..36
+      Ident@29..36 "integer"
+  Newline@36..37 "\n"
+  CommentMarker@37..40 "---"
+  ParamTag@40..67
+    At@40..41 "@"
+    Ident@41..46 "param"
+    Whitespace@46..47 " "
+    Ident@47..48 "b"
+    Question@48..49 "?"
+    Whitespace@49..50 " "
+    NameType@50..56
+      Ident@50..56 "number"
+    Whitespace@56..57 " "
+    Description@57..67
+      Hash@57..58 "#"
+      Whitespace@58..59 " "
+      Ident@59..62 "the"
+      Whitespace@62..63 " "
+      Ident@63..67 "step"
+  Newline@67..68 "\n"
+  CommentMarker@68..71 "---"
+  ReturnTag@71..90
+    At@71..72 "@"
+    Ident@72..78 "return"
+    Whitespace@78..79 " "
+    ReturnItem@79..90
+      NameType@79..86
+        Ident@79..86 "integer"
+      Whitespace@86..87 " "
+      Ident@87..90 "sum"
+"##,
+    );
+}
+
+#[test]
+fn parse_types() {
+    let parse = parse(
+        "---@type table<string, integer[]>, { x: number, [integer]: string? }, (\"a\" | \"b\"), fun(a: string, ...: any): boolean, nil",
+    );
+    assert!(parse.errors().is_empty(), "{:?}", parse.errors());
+    let tag = match parse.tree().tags().next() {
+        Some(DocTag::Type(it)) => it,
+        it => panic!("{:?}", it),
+    };
+    let types: Vec<_> = tag.types().collect();
+    assert_eq!(types.len(), 4);
+
+    match &types[0] {
+        DocType::Name(it) => {
+            assert_eq!(it.name_token().unwrap().text(), "table");
+            let args: Vec<_> = it.args().map(|it| type_text(Some(it))).collect();
+            assert_eq!(args, ["string", "integer[]"]);
+        }
+        it => panic!("{:?}", it),
+    }
+    match &types[1] {
+        DocType::Table(it) => {
+            let fields: Vec<_> = it.fields().collect();
+            assert_eq!(fields[0].name_token().unwrap().text(), "x");
+            assert_eq!(type_text(fields[1].key_ty()), "integer");
+            assert!(matches!(fields[1].ty(), Some(DocType::Optional(_))));
+        }
+        it => panic!("{:?}", it),
+    }
+    match &types[2] {
+        DocType::Paren(it) => match it.inner() {
+            Some(DocType::Union(it)) => assert_eq!(it.types().count(), 2),
+            it => panic!("{:?}", it),
+        },
+        it => panic!("{:?}", it),
+    }
+    match &types[3] {
+        DocType::Fun(it) => {
+            let params: Vec<_> = it
+                .params()
+                .map(|it| format!("{}: {}", it.name_token().unwrap().text(), type_text(it.ty())))
+                .collect();
+            assert_eq!(params, ["a: string", "...: any"]);
+            let returns: Vec<_> = it.returns().map(|it| type_text(Some(it))).collect();
+            assert_eq!(returns, ["boolean", "nil"]);
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn parse_classes_and_aliases() {
+    let parse = parse(
+        "---@class Point : Shape\n---@field x number\n---@field private y? number\n---@field [string] any\n---@alias Mode\n---| \"r\" # read\n---| \"w\"\n---@generic T : table\n---@deprecated use something else",
+    );
+    assert!(parse.errors().is_empty(), "{:?}", parse.errors());
+    let tags: Vec<_> = parse.tree().tags().collect();
+    match &tags[..] {
+        [DocTag::Class(class), DocTag::Field(x), DocTag::Field(y), DocTag::Field(index), DocTag::Alias(alias), DocTag::Generic(generic), DocTag::Other(other)] =>
+        {
+            assert_eq!(class.name_token().unwrap().text(), "Point");
+            assert_eq!(
+                class.parents().map(|it| type_text(Some(it))).collect::<Vec<_>>(),
+                ["Shape"]
+            );
+            assert_eq!(x.name_token().unwrap().text(), "x");
+            assert!(!x.is_optional());
+            assert_eq!(y.name_token().unwrap().text(), "y");
+            assert!(y.is_optional());
+            assert_eq!(type_text(y.ty()), "number");
+            assert!(index.name_token().is_none());
+            assert_eq!(type_text(index.key_ty()), "string");
+            assert_eq!(type_text(index.ty()), "any");
+            assert_eq!(alias.name_token().unwrap().text(), "Mode");
+            let variants: Vec<_> = alias
+                .variants()
+                .map(|it| (type_text(it.ty()), it.description().map(|it| it.text())))
+                .collect();
+            assert_eq!(
+                variants,
+                [("\"r\"".to_string(), Some("read".to_string())), ("\"w\"".to_string(), None)]
+            );
+            let param = generic.params().next().unwrap();
+            assert_eq!(param.name_token().unwrap().text(), "T");
+            assert_eq!(type_text(param.bound()), "table");
+            let other = DocTag::Other(other.clone());
+            assert_eq!(other.name_token().unwrap().text(), "deprecated");
+            assert_eq!(other.description().unwrap().text(), "use something else");
+        }
+        it => panic!("{:?}", it),
+    }
+}
+
+#[test]
+fn parse_errors() {
+    let parse = parse("---@param\n---@return integer,\n---@type fun(");
+    let errors: Vec<_> = parse.errors().iter().map(|it| it.to_string()).collect();
+    assert_eq!(errors, ["expected the name of a parameter", "expected a type", "expected )"]);
+    // the tree is lossless even with errors
+    assert_eq!(
+        parse.syntax_node().text().to_string(),
+        "---@param\n---@return integer,\n---@type fun("
+    );
+}
+
+#[test]
+fn doc_blocks_of_a_file() {
+    let text = "
+-- not an annotation
+---@class A
+---@field x integer
+
+---The description.
+---@param n integer
+local function f(n) end
+";
+    let file = SourceFile::parse(text, LuaVersion::default()).tree();
+    let blocks = doc_blocks(&file);
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[0].offset(), TextSize::of("\n-- not an annotation\n"));
+
+    let stmt = file.body().unwrap().stmts().next().unwrap();
+    assert!(matches!(stmt, ast::Stmt::LocalFunctionDefStmt(_)));
+    let block = doc_block(stmt.syntax()).unwrap();
+    assert_eq!(block, blocks[1]);
+    assert_eq!(block.tree().description().as_deref(), Some("The description."));
+    match block.tree().tags().next() {
+        Some(DocTag::Param(param)) => {
+            let range = block.file_range(param.name_token().unwrap().text_range());
+            assert_eq!(&text[range], "n");
+        }
+        it => panic!("{:?}", it),
+    }
+}
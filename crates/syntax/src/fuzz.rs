@@ -1,15 +1,76 @@
+use std::str::FromStr;
+
+use text_edit::Indel;
+
 use crate::{
     ast::SourceFile,
     lexer::{tokenize, Lexer},
+    AstNode, LuaVersion, SyntaxError, TextRange, TextSize,
 };
 
 pub fn check_parser(text: &str) {
-    let _ = SourceFile::parse(text);
+    let _ = SourceFile::parse(text, LuaVersion::default());
 }
 
 pub fn check_lexer<'a, L>(text: &'a str)
 where
     L: Lexer<'a>,
 {
-    let _ = tokenize::<L>(text);
+    let _ = tokenize::<L>(text, LuaVersion::default());
+}
+
+/// An edit that is reparsed incrementally and checked against a full parse.
+#[derive(Debug, Clone)]
+pub struct CheckReparse {
+    text: String,
+    edit: Indel,
+    edited_text: String,
+}
+
+impl CheckReparse {
+    pub fn new(text: String, edit: Indel) -> Option<Self> {
+        let (start, end) = (edit.delete.start().into(), edit.delete.end().into());
+        if end > text.len() || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            return None;
+        }
+        let mut edited_text = text.clone();
+        edit.apply(&mut edited_text);
+        Some(CheckReparse { text, edit, edited_text })
+    }
+
+    /// The data is the start and the length of the deletion and the inserted
+    /// text, each on their own line, followed by the text to edit.
+    pub fn from_data(data: &[u8]) -> Option<Self> {
+        let data = std::str::from_utf8(data).ok()?;
+        let mut lines = data.lines();
+        let delete_start = u32::from_str(lines.next()?).ok()?;
+        let delete_len = u32::from_str(lines.next()?).ok()?;
+        let insert = lines.next()?.to_string();
+        let text = lines.collect::<Vec<_>>().join("\n");
+        let delete = TextRange::at(delete_start.into(), delete_len.into());
+        CheckReparse::new(text, Indel::replace(delete, insert))
+    }
+
+    pub fn run(&self) {
+        let version = LuaVersion::default();
+        let parse = SourceFile::parse(&self.text, version);
+        let new_parse = parse.reparse(&self.edit, version);
+        let full_reparse = SourceFile::parse(&self.edited_text, version);
+
+        assert_eq!(
+            format!("{:#?}", new_parse.tree().syntax()),
+            format!("{:#?}", full_reparse.tree().syntax()),
+            "{:?}",
+            self
+        );
+        assert_eq!(sorted(new_parse.errors()), sorted(full_reparse.errors()), "{:?}", self);
+    }
+}
+
+/// Errors of an incremental reparse come in a different order.
+fn sorted(errors: &[SyntaxError]) -> Vec<(TextSize, TextSize, String)> {
+    let mut errors: Vec<_> =
+        errors.iter().map(|it| (it.range().start(), it.range().end(), it.to_string())).collect();
+    errors.sort();
+    errors
 }
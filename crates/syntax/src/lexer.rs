@@ -5,12 +5,12 @@ use std::iter;
 use parser::{SyntaxKind, Token};
 use rowan::{TextRange, TextSize};
 
-use crate::SyntaxError;
+use crate::{LuaVersion, SyntaxError};
 
 use self::error::{LexResult, SyntaxResult};
 
 pub trait Lexer<'a> {
-    fn new(text: &'a str) -> Self;
+    fn new(text: &'a str, version: LuaVersion) -> Self;
 
     fn next_syntax_kind(&mut self) -> LexResult<SyntaxKind>;
 
@@ -26,7 +26,7 @@ pub trait Lexer<'a> {
     fn len_consumed(&self) -> TextSize;
 }
 
-pub fn first_token<'a, L>(text: &'a str) -> Option<SyntaxResult<Token>>
+pub fn first_token<'a, L>(text: &'a str, version: LuaVersion) -> Option<SyntaxResult<Token>>
 where
     L: Lexer<'a>,
 {
@@ -34,25 +34,18 @@ where
         return None;
     }
 
-    let res = L::new(text).next_token();
+    let res = L::new(text, version).next_token();
     let range = TextRange::up_to(res.ok_ref().len);
     Some(res.map_err(|e| SyntaxError::new(e.to_string(), range)))
 }
 
-pub fn first_syntax_kind<'a, L>(text: &'a str) -> Option<SyntaxResult<SyntaxKind>>
-where
-    L: Lexer<'a>,
-{
-    Some(first_token::<L>(text)?.map(|token| token.kind))
-}
-
 /// Tokenize text into a vector of tokens and errors
-pub fn tokenize<'a, L>(text: &'a str) -> (Vec<Token>, Vec<SyntaxError>)
+pub fn tokenize<'a, L>(text: &'a str, version: LuaVersion) -> (Vec<Token>, Vec<SyntaxError>)
 where
     L: Lexer<'a>,
 {
     let mut errors = Vec::new();
-    let tokens = tokenizer::<L>(text)
+    let tokens = tokenizer::<L>(text, version)
         .map(|res| {
             let (token, err) = res.inner();
             errors.extend(err);
@@ -64,14 +57,17 @@ where
 }
 
 /// Returns a tokenizer iterator that will turn the source text into tokens
-pub fn tokenizer<'a, L>(mut text: &'a str) -> impl Iterator<Item = SyntaxResult<Token>> + '_
+pub fn tokenizer<'a, L>(
+    mut text: &'a str,
+    version: LuaVersion,
+) -> impl Iterator<Item = SyntaxResult<Token>> + 'a
 where
     L: Lexer<'a>,
 {
     let mut pos = TextSize::from(0);
 
     iter::from_fn(move || {
-        let res = first_token::<L>(text)?.map_err(|err| {
+        let res = first_token::<L>(text, version)?.map_err(|err| {
             let range = err.range();
             err.with_range(range + pos)
         });
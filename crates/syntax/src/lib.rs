@@ -1,8 +1,10 @@
 pub mod ast;
+pub mod doc;
 #[doc(hidden)]
 pub mod fuzz;
 mod lexer;
 mod lua_lexer;
+mod lua_version;
 mod parsing;
 mod ptr;
 mod syntax_node;
@@ -11,11 +13,13 @@ mod tests;
 mod token_text;
 mod validation;
 
-pub(crate) use lexer::{first_syntax_kind, first_token};
+pub(crate) use lexer::first_token;
 pub use lexer::{tokenize, tokenizer};
-pub use parser::{SyntaxKind, Token, N, T};
+pub use lua_lexer::{is_identifier, is_keyword};
+pub use lua_version::LuaVersion;
+pub use parser::{LuaOp, SyntaxKind, Token, N, T};
 pub use ptr::{AstPtr, SyntaxNodePtr};
-pub use rowan::{TextRange, TextSize, WalkEvent};
+pub use rowan::{Direction, NodeOrToken, TextRange, TextSize, WalkEvent};
 pub use syntax_node::{
     SyntaxElement, SyntaxElementChildren, SyntaxError, SyntaxNode, SyntaxNodeChildren, SyntaxToken,
 };
@@ -104,11 +108,11 @@ impl ast::Expr {
 pub use ast::SourceFile;
 
 impl SourceFile {
-    pub fn parse(text: &str) -> Parse<SourceFile> {
-        let (green, mut errors) = parsing::parse_text(text);
+    pub fn parse(text: &str, version: LuaVersion) -> Parse<SourceFile> {
+        let (green, mut errors) = parsing::parse_text(text, version);
         let root = SyntaxNode::new_root(green.clone());
 
-        errors.extend(validation::validate(&root));
+        errors.extend(validation::validate(&root, version));
 
         assert_eq!(root.kind(), SyntaxKind::SourceFile);
 
@@ -117,24 +121,24 @@ impl SourceFile {
 }
 
 impl Parse<SourceFile> {
-    pub fn reparse(&self, indel: &Indel) -> Parse<SourceFile> {
-        self.incremental_reparse(indel).unwrap_or_else(|| self.full_reparse(indel))
+    pub fn reparse(&self, indel: &Indel, version: LuaVersion) -> Parse<SourceFile> {
+        self.incremental_reparse(indel, version)
+            .unwrap_or_else(|| self.full_reparse(indel, version))
     }
 
-    fn incremental_reparse(&self, indel: &Indel) -> Option<Parse<SourceFile>> {
+    fn incremental_reparse(&self, indel: &Indel, version: LuaVersion) -> Option<Parse<SourceFile>> {
         // FIXME: validation errors are not handled here
-        parsing::incremental_reparse(self.tree().syntax(), indel, self.errors.to_vec()).map(
-            |(green_node, errors, _reparsed_range)| Parse {
+        parsing::incremental_reparse(self.tree().syntax(), indel, self.errors.to_vec(), version)
+            .map(|(green_node, errors, _reparsed_range)| Parse {
                 green: green_node,
                 errors: Arc::new(errors),
                 _ty: PhantomData,
-            },
-        )
+            })
     }
-    fn full_reparse(&self, indel: &Indel) -> Parse<SourceFile> {
+    fn full_reparse(&self, indel: &Indel, version: LuaVersion) -> Parse<SourceFile> {
         let mut text = self.tree().syntax().text().to_string();
         indel.apply(&mut text);
-        SourceFile::parse(&text)
+        SourceFile::parse(&text, version)
     }
 
     pub fn debug_dump(&self) -> String {
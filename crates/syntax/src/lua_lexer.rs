@@ -4,17 +4,17 @@ use crate::lexer::{
     error::{LexResult, SyntaxResult},
     Lexer,
 };
-use crate::{SyntaxError, SyntaxKind, T};
+use crate::{LuaVersion, SyntaxError, SyntaxKind, T};
 use accept::source::{CharSource, Source};
 use accept::{
-    combinators::{or, seq, Until, While, Not},
+    combinators::{or, seq, Not, Until, While},
     Accept, Acceptor, Any,
 };
 use parser::Token;
 
 macro_rules! done {
     ($expr:expr) => {
-        return LexResult::new($expr, None);
+        return LexResult::new($expr, None)
     };
 }
 
@@ -50,17 +50,37 @@ pub fn lex_first_token(text: &str) -> Option<SyntaxResult<Token>> {
     Some(res.map_err(|e| SyntaxError::new(e.to_string(), range)))
 }
 
+/// Whether `text` is a reserved word in `version`, which cannot be used as a
+/// name.
+pub fn is_keyword(text: &str, version: LuaVersion) -> bool {
+    match SyntaxKind::from_keyword(text) {
+        Some(T![goto]) => version.has_goto(),
+        Some(_) => true,
+        None => false,
+    }
+}
+
+/// Whether `text` lexes as a single identifier in `version`, reserved words
+/// are not identifiers.
+pub fn is_identifier(text: &str, version: LuaVersion) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if is_ident_start(c))
+        && chars.all(is_ident_continue)
+        && !is_keyword(text, version)
+}
+
 fn first_lex_result(text: &str) -> LexResult<Token> {
     LuaLexer::new(text).next_lex_result()
 }
 
 pub struct LuaLexer<'a> {
     source: CharSource<'a>,
+    version: LuaVersion,
 }
 
 impl<'a> Lexer<'a> for LuaLexer<'a> {
-    fn new(text: &'a str) -> LuaLexer<'a> {
-        LuaLexer { source: CharSource::new(text) }
+    fn new(text: &'a str, version: LuaVersion) -> LuaLexer<'a> {
+        LuaLexer { source: CharSource::new(text), version }
     }
 
     fn next_syntax_kind(&mut self) -> LexResult<SyntaxKind> {
@@ -74,7 +94,7 @@ impl<'a> Lexer<'a> for LuaLexer<'a> {
 
 impl<'a> LuaLexer<'a> {
     fn new(input: &'a str) -> LuaLexer<'a> {
-        LuaLexer { source: CharSource::new(input) }
+        LuaLexer { source: CharSource::new(input), version: LuaVersion::default() }
     }
 
     fn next_lex_result(mut self) -> LexResult<Token> {
@@ -101,7 +121,7 @@ impl<'a> LuaLexer<'a> {
                     self.source.bump('=');
                     done!(T![~=]);
                 }
-                _ => done!(T![unknown]),
+                _ => done!(T![~]),
             },
 
             '(' => T!['('],
@@ -116,6 +136,7 @@ impl<'a> LuaLexer<'a> {
             ']' => T![']'],
 
             ',' => T![,],
+            '.' if self.source.at(seq!('.', is_number)) => return self.number(),
             '.' => match self.source.bump_then(Any) {
                 '.' => match self.source.bump_then(Any) {
                     '.' => {
@@ -138,15 +159,27 @@ impl<'a> LuaLexer<'a> {
             '#' => T![#],
             '+' => T![+],
             '*' => T![*],
-            '/' => T![/],
+            '/' => match self.source.bump_then(Any) {
+                '/' => {
+                    self.source.bump('/');
+                    done!(T![/ /]);
+                }
+                _ => done!(T![/]),
+            },
             '%' => T![%],
             '^' => T![^],
+            '&' => T![&],
+            '|' => T![|],
 
             '>' => match self.source.bump_then(Any) {
                 '=' => {
                     self.source.bump('=');
                     done!(T![>=]);
                 }
+                '>' => {
+                    self.source.bump('>');
+                    done!(T![>>]);
+                }
                 _ => {
                     done!(T![>]);
                 }
@@ -156,6 +189,10 @@ impl<'a> LuaLexer<'a> {
                     self.source.bump('=');
                     done!(T![<=]);
                 }
+                '<' => {
+                    self.source.bump('<');
+                    done!(T![<<]);
+                }
                 _ => {
                     done!(T![<]);
                 }
@@ -187,23 +224,54 @@ impl<'a> LuaLexer<'a> {
     }
 
     fn number(&mut self) -> LexResult<SyntaxKind> {
-        assert!(self.source.at(is_number));
+        assert!(self.source.at(or!(is_number, '.')));
 
-        if self.source.accept(seq!('0', 'x')) {
-            self.source.accept(While(is_hex));
-            return LexResult::just(T![number]);
-        }
+        let hex = self.source.accept(or!(seq!('0', 'x'), seq!('0', 'X')));
+        let digit: fn(char) -> bool = if hex { is_hex } else { is_number };
+        let exponent = if hex { or!('p', 'P') } else { or!('e', 'E') };
 
-        self.source.accept(While(is_number));
+        let mut digits = self.source.accept_count(While(digit));
+        let mut is_float = false;
         if self.source.accept('.') {
-            self.source.accept(While(is_number));
+            is_float = true;
+            digits += self.source.accept_count(While(digit));
+        }
+        let mut malformed = digits == 0;
+        if self.source.accept(exponent) {
+            is_float = true;
+            self.source.accept(or!('+', '-'));
+            malformed |= self.source.accept_count(While(is_number)) == 0;
         }
-        if self.source.accept('e') || self.source.accept('E') {
-            self.source.accept('-');
-            self.source.accept(While(is_number));
+
+        let suffix_err = self.number_suffix(is_float);
+        // anything glued to a number, like `3abc`, is part of it
+        malformed |= self.source.accept_count(While(is_ident_continue)) > 0;
+
+        if malformed {
+            bail!(T![number], "Malformed number");
         }
+        LexResult::new(T![number], suffix_err)
+    }
 
-        done!(T![number]);
+    /// Bumps the `LL`, `ULL` or `i` of LuaJIT's numbers, which are errors in
+    /// the other versions.
+    fn number_suffix(&mut self, is_float: bool) -> Option<String> {
+        let rest = self.source.rest();
+        let suffix = ["ull", "ll", "i"].iter().find(|suffix| {
+            rest.get(..suffix.len()).is_some_and(|it| it.eq_ignore_ascii_case(suffix))
+                && !rest[suffix.len()..].starts_with(is_ident_continue)
+        })?;
+
+        for _ in 0..suffix.len() {
+            self.source.bump(Any);
+        }
+        if is_float && *suffix != "i" {
+            return Some("Malformed number".to_string());
+        }
+        if !self.version.has_number_suffixes() {
+            return Some("Number suffixes are only supported by LuaJIT".to_string());
+        }
+        None
     }
 
     fn whitespace(&mut self) -> SyntaxKind {
@@ -217,10 +285,11 @@ impl<'a> LuaLexer<'a> {
     fn comment(&mut self) -> LexResult<SyntaxKind> {
         self.source.bump('-');
 
-        if self.source.accept('[') {
-            if self.source.at('[') {
-                self.bracket_enclosed();
-            }
+        // a long comment, as in `--[[ ... ]]` or `--[==[ ... ]==]`
+        let rest = self.source.rest();
+        if rest.starts_with('[') && rest[1..].trim_start_matches('=').starts_with('[') {
+            self.source.bump('[');
+            return self.multiline_comment();
         }
 
         self.source.accept(Until('\n'));
@@ -305,9 +374,19 @@ impl<'a> LuaLexer<'a> {
                     self.source.bump(delimit);
                     break;
                 }
+                // escapes can span lines, as in `\<newline>` or `\z`
+                '\\' => {
+                    self.source.bump('\\');
+                    if self.source.accept('z') {
+                        self.source.accept(While(is_whitespace));
+                    } else if !self.source.is_eof() {
+                        self.source.bump(Any);
+                    }
+                    continue;
+                }
                 _ => (),
             }
-            self.source.bump(or!(seq!('\\', delimit), Any));
+            self.source.bump(Any);
         }
 
         LexResult::just(T![str])
@@ -321,7 +400,11 @@ impl<'a> LuaLexer<'a> {
         self.source.accept(While(is_ident_continue));
 
         let text = &text[0..(self.source.pos() - start) as usize];
-        SyntaxKind::from_keyword(text).unwrap_or(T![ident])
+        match SyntaxKind::from_keyword(text) {
+            Some(T![goto]) if !self.version.has_goto() => T![ident],
+            Some(kw) => kw,
+            None => T![ident],
+        }
     }
 }
 
@@ -354,7 +437,7 @@ const fn is_hex(c: char) -> bool {
 mod tests {
     use super::*;
     use crate::lexer::tokenize;
-    use accept::combinators::{Repeat, Not};
+    use accept::combinators::{Not, Repeat};
 
     #[test]
     fn accept_tuple() {
@@ -412,6 +495,6 @@ mod tests {
 
     #[test]
     fn run_current() {
-        tokenize::<LuaLexer>("local hello = 5");
+        tokenize::<LuaLexer>("local hello = 5", LuaVersion::default());
     }
 }
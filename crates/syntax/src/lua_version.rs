@@ -0,0 +1,75 @@
+use std::{fmt, str::FromStr};
+
+/// The dialect of Lua a file is written in, which decides what the lexer and
+/// validation accept.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LuaVersion {
+    Lua51,
+    Lua52,
+    Lua53,
+    #[default]
+    Lua54,
+    LuaJit,
+}
+
+impl LuaVersion {
+    /// `goto` statements and `::labels::`, `goto` is a plain name before 5.2.
+    pub fn has_goto(self) -> bool {
+        !matches!(self, LuaVersion::Lua51)
+    }
+
+    /// The `&`, `|`, `~`, `<<` and `>>` operators.
+    pub fn has_bitwise_ops(self) -> bool {
+        matches!(self, LuaVersion::Lua53 | LuaVersion::Lua54)
+    }
+
+    /// The `//` operator.
+    pub fn has_floor_div(self) -> bool {
+        matches!(self, LuaVersion::Lua53 | LuaVersion::Lua54)
+    }
+
+    /// The integer subtype of numbers, every number is a float before 5.3.
+    pub fn has_integers(self) -> bool {
+        matches!(self, LuaVersion::Lua53 | LuaVersion::Lua54)
+    }
+
+    /// The `<const>` and `<close>` attributes of locals.
+    pub fn has_attribs(self) -> bool {
+        matches!(self, LuaVersion::Lua54)
+    }
+
+    /// The `LL`, `ULL` and `i` suffixes of LuaJIT's 64-bit integer and
+    /// imaginary numbers.
+    pub fn has_number_suffixes(self) -> bool {
+        matches!(self, LuaVersion::LuaJit)
+    }
+}
+
+impl fmt::Display for LuaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            LuaVersion::Lua51 => "Lua 5.1",
+            LuaVersion::Lua52 => "Lua 5.2",
+            LuaVersion::Lua53 => "Lua 5.3",
+            LuaVersion::Lua54 => "Lua 5.4",
+            LuaVersion::LuaJit => "LuaJIT",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for LuaVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<LuaVersion, String> {
+        let version = match s.to_lowercase().as_str() {
+            "5.1" | "lua 5.1" | "lua51" => LuaVersion::Lua51,
+            "5.2" | "lua 5.2" | "lua52" => LuaVersion::Lua52,
+            "5.3" | "lua 5.3" | "lua53" => LuaVersion::Lua53,
+            "5.4" | "lua 5.4" | "lua54" => LuaVersion::Lua54,
+            "luajit" | "jit" => LuaVersion::LuaJit,
+            _ => return Err(format!("unknown Lua version `{}`", s)),
+        };
+        Ok(version)
+    }
+}
@@ -8,14 +8,14 @@ use crate::{lexer::tokenize, lua_lexer::LuaLexer};
 use rowan::GreenNode;
 use text_token_source::TextTokenSource;
 
-use crate::SyntaxError;
+use crate::{LuaVersion, SyntaxError};
 
 use self::text_tree_sink::DeferedTextTreeSink;
 
 const DEFER_AMOUNT: u8 = 2;
 
-pub(crate) fn parse_text(text: &str) -> (GreenNode, Vec<SyntaxError>) {
-    let (tokens, errors) = tokenize::<LuaLexer>(text);
+pub(crate) fn parse_text(text: &str, version: LuaVersion) -> (GreenNode, Vec<SyntaxError>) {
+    let (tokens, errors) = tokenize::<LuaLexer>(text, version);
 
     let mut token_source = TextTokenSource::new(text, &tokens);
     let tree_sink = DeferedTextTreeSink::new(text, &tokens, DEFER_AMOUNT);
@@ -2,24 +2,39 @@ use parser::{Reparser, Token};
 use text_edit::Indel;
 
 use crate::{
-    first_syntax_kind,
+    ast::{self, AstNode},
+    first_token,
     lua_lexer::LuaLexer,
     parsing::{text_token_source::TextTokenSource, text_tree_sink::TextTreeSink},
     syntax_node::{GreenToken, NodeOrToken},
-    tokenize, GreenNode, SyntaxElement, SyntaxError, SyntaxNode, TextRange, T,
+    tokenize,
+    validation::validate,
+    GreenNode, LuaVersion, SyntaxElement, SyntaxError, SyntaxKind, SyntaxNode, SyntaxToken,
+    TextRange, TextSize, N, T,
 };
 
 pub(crate) fn incremental_reparse(
     node: &SyntaxNode,
     edit: &Indel,
     errors: Vec<SyntaxError>,
+    version: LuaVersion,
 ) -> Option<(GreenNode, Vec<SyntaxError>, TextRange)> {
-    if let Some((green, new_errors, old_range)) = reparse_token(node, &edit) {
-        return Some((green, merge_errors(errors, new_errors, old_range, edit), old_range));
+    // errors after the edit mention lines that would move
+    let deleted_lines = node.text().slice(edit.delete).to_string().matches('\n').count();
+    if deleted_lines != edit.insert.matches('\n').count()
+        && errors.iter().any(|it| {
+            it.range().start() >= edit.delete.end() && it.to_string().contains(" at line ")
+        })
+    {
+        return None;
+    }
+
+    if let Some(it) = reparse_token(node, edit, &errors, version) {
+        return Some(it);
     }
 
-    if let Some((green, new_errors, old_range)) = reparse_block(node, &edit) {
-        return Some((green, merge_errors(errors, new_errors, old_range, edit), old_range));
+    if let Some(it) = reparse_node(node, edit, &errors, version) {
+        return Some(it);
     }
 
     None
@@ -28,57 +43,317 @@ pub(crate) fn incremental_reparse(
 fn reparse_token(
     root: &SyntaxNode,
     edit: &Indel,
+    old_errors: &[SyntaxError],
+    version: LuaVersion,
 ) -> Option<(GreenNode, Vec<SyntaxError>, TextRange)> {
     let prev_token = root.covering_element(edit.delete).as_token()?.clone();
     let prev_token_kind = prev_token.kind();
     match prev_token_kind {
         T![whitespace] | T![comment] | T![ident] | T![str] => {
             if prev_token_kind == T![whitespace] || prev_token_kind == T![comment] {
+                // with an unclosed block, blocks are paired by indentation
+                if old_errors.iter().any(is_unclosed_block_error) {
+                    return None;
+                }
                 // removing a new line may extends previous token
                 let deleted_range = edit.delete - prev_token.text_range().start();
                 if prev_token.text()[deleted_range].contains('\n') {
                     return None;
                 }
+                // the parser recovers from errors up to the end of the line
+                let range = prev_token.text_range();
+                if edit.insert.contains('\n')
+                    && old_errors.iter().any(|it| it.range().intersect(range).is_some())
+                {
+                    return None;
+                }
+            }
+
+            // the errors of a malformed token, such as an unterminated string,
+            // can't be told apart from those of its parents
+            let (old_token, old_err) = first_token::<LuaLexer>(prev_token.text(), version)?.inner();
+            if old_token.len != prev_token.text_range().len() || old_err.is_some() {
+                return None;
             }
 
-            let new_text = get_text_after_edit(prev_token.clone().into(), &edit);
-            let (new_token_kind, new_err) = first_syntax_kind::<LuaLexer>(&new_text)?.inner();
+            let new_text = get_text_after_edit(prev_token.clone().into(), edit);
+            let (new_token, new_err) = first_token::<LuaLexer>(&new_text, version)?.inner();
 
-            if new_token_kind != prev_token_kind {
+            // the edit has to stay within a single token of the same kind, and an
+            // unterminated string or comment would run into the tokens after it
+            if new_token.kind != prev_token_kind
+                || new_token.len != TextSize::of(&new_text)
+                || new_err.is_some()
+            {
                 return None;
             }
 
             let new_token = GreenToken::new(rowan::SyntaxKind(prev_token_kind.into()), &new_text);
-            Some((
-                prev_token.replace_with(new_token),
-                new_err.into_iter().collect(),
-                prev_token.text_range(),
-            ))
+            let new_green = prev_token.replace_with(new_token);
+
+            // names and strings are checked by the validation of the statement
+            // they are in, which is redone in place of its old errors
+            let old_range = prev_token.text_range();
+            let old_validation = validate(&enclosing_stmt(&prev_token), version);
+            let mut errors = Vec::new();
+            for error in old_errors.iter().filter(|it| !old_validation.contains(it)) {
+                let range = error.range();
+                if range.end() <= old_range.start() {
+                    errors.push(error.clone());
+                } else if range.start() >= old_range.end() {
+                    let shift = |offset| offset + TextSize::of(&edit.insert) - edit.delete.len();
+                    errors.push(
+                        error
+                            .clone()
+                            .with_range(TextRange::new(shift(range.start()), shift(range.end()))),
+                    );
+                } else if range.contains_range(old_range) {
+                    let end = range.end() + TextSize::of(&edit.insert) - edit.delete.len();
+                    errors.push(error.clone().with_range(TextRange::new(range.start(), end)));
+                } else {
+                    // a parser or lexer error inside of the token
+                    return None;
+                }
+            }
+            let new_root = SyntaxNode::new_root(new_green.clone());
+            let new_token = new_root.token_at_offset(old_range.start()).right_biased()?;
+            errors.extend(validate(&enclosing_stmt(&new_token), version));
+            Some((new_green, errors, old_range))
         }
         _ => None,
     }
 }
 
-fn reparse_block(
+/// Reparses the smallest block, statement or table constructor around the
+/// edit that still parses to a single node with the same neighbours.
+fn reparse_node(
+    root: &SyntaxNode,
+    edit: &Indel,
+    errors: &[SyntaxError],
+    version: LuaVersion,
+) -> Option<(GreenNode, Vec<SyntaxError>, TextRange)> {
+    // with an unclosed block, blocks are paired by indentation which depends on
+    // the whole file
+    if errors.iter().any(is_unclosed_block_error) {
+        return None;
+    }
+
+    let covering = root.covering_element(edit.delete);
+    let mut ancestors = match covering {
+        NodeOrToken::Node(node) => Some(node),
+        NodeOrToken::Token(token) => Some(token.parent()),
+    };
+    while let Some(node) = ancestors {
+        if let Some(reparser) = Reparser::for_node(node.kind()) {
+            if let Some(it) = reparse_with(root, &node, reparser, edit, errors, version) {
+                return Some(it);
+            }
+        }
+        ancestors = node.parent();
+    }
+    None
+}
+
+fn reparse_with(
     root: &SyntaxNode,
+    node: &SyntaxNode,
+    reparser: Reparser,
     edit: &Indel,
+    old_errors: &[SyntaxError],
+    version: LuaVersion,
 ) -> Option<(GreenNode, Vec<SyntaxError>, TextRange)> {
-    let (node, reparser) = find_reparsable_node(root, edit.delete)?;
+    let old_range = node.text_range();
     let text = get_text_after_edit(node.clone().into(), edit);
+    let (mut tokens, _) = tokenize::<LuaLexer>(&text, version);
+
+    // trivia around a node belongs to its parent, and the first token decides
+    // where the previous statement stops
+    let first = tokens.first()?;
+    let last = tokens.last()?;
+    if first.kind.is_trivia() || last.kind.is_trivia() || Some(first.kind) != first_kind(node) {
+        return None;
+    }
+    if !brackets_are_balanced(&tokens)
+        || !parser::is_balanced(tokens.iter().map(|it| it.kind))
+        || !parser::is_balanced(node_tokens(node).map(|it| it.kind()))
+    {
+        return None;
+    }
 
-    let (tokens, new_lexer_errors) = tokenize::<LuaLexer>(&text);
-    if !is_balanced(&tokens) {
+    // the node has to stop before the token after it, just like it does in the
+    // whole file, so that token is parsed along with it
+    let next = node_tokens(node).last().and_then(|it| next_non_trivia(&it));
+    let lookahead = match node.kind() {
+        N![Block] => match next {
+            Some(next) if matches!(next.kind(), T![end] | T![else] | T![elseif] | T![until]) => {
+                Some(next)
+            }
+            _ => return None,
+        },
+        _ => match next {
+            Some(next) if is_block_opener(next.kind()) => return None,
+            next => next,
+        },
+    };
+    // an unterminated string or a token glued to the next one lexes
+    // differently next to what follows, so the text is lexed up to the next
+    // token, or to the end of the file
+    let mut full_text = text.clone();
+    let mut full_tokens = tokens.clone();
+    let last_token = node.descendants_with_tokens().filter_map(|it| it.into_token()).last();
+    let mut token = last_token.and_then(|it| token_after(&it));
+    while let Some(it) = token {
+        full_text.push_str(it.text());
+        full_tokens.push(Token::new(it.kind(), it.text_range().len()));
+        if !it.kind().is_trivia() {
+            break;
+        }
+        token = token_after(&it);
+    }
+    let (relexed, errors) = tokenize::<LuaLexer>(&full_text, version);
+    if relexed != full_tokens {
+        return None;
+    }
+    let len = TextSize::of(text.as_str());
+    let lexer_errors: Vec<_> = errors.into_iter().filter(|it| it.range().start() < len).collect();
+
+    // trailing trivia at the end of the file belongs to the root instead
+    if lookahead.is_some() {
+        tokens = full_tokens;
+    } else {
+        full_text = text.clone();
+    }
+
+    let line = line_of(root, old_range.start());
+    let mut token_source = TextTokenSource::new(&full_text, &tokens).starting_at_line(line);
+    let tree_sink = TextTreeSink::new(&full_text, &tokens);
+    let (green, parser_errors) =
+        reparser.parse(&mut token_source, tree_sink, lookahead.is_some() as usize)?;
+    if SyntaxKind::from(green.kind().0) != node.kind()
+        || TextSize::of(text.as_str()) != green.text_len()
+    {
         return None;
     }
+    // a node missing something at the end of the file reaches over the
+    // trivia after it to where the error is reported
+    if lookahead.is_none() && parser_errors.iter().any(|it| it.range().end() >= len) {
+        return None;
+    }
+
+    let new_green = node.replace_with(green.clone());
+    let new_root = SyntaxNode::new_root(new_green.clone());
+    let new_range = TextRange::at(old_range.start(), green.text_len());
+    let new_node = new_root
+        .covering_element(new_range)
+        .ancestors()
+        .find(|it| it.text_range() == new_range && it.kind() == node.kind())?;
+
+    let ends_closed = matches!(
+        node_tokens(node).last().map(|it| it.kind()),
+        Some(T!['}'] | T![')'] | T![']'] | T![end])
+    );
+    let mut errors = merge_errors(
+        old_errors.to_vec(),
+        parser_errors.into_iter().chain(lexer_errors).collect(),
+        old_range,
+        edit,
+        ends_closed,
+    );
+    errors.extend(validate(&new_node, version));
+
+    Some((new_green, errors, old_range))
+}
+
+/// The statement that is validated along with the token, or the root when the
+/// token is not in one.
+fn enclosing_stmt(token: &SyntaxToken) -> SyntaxNode {
+    let mut ancestors = token.parent().ancestors().peekable();
+    while let Some(node) = ancestors.next() {
+        if ast::Stmt::can_cast(node.kind()) || ancestors.peek().is_none() {
+            return node;
+        }
+    }
+    unreachable!("a token always has a parent")
+}
+
+/// Openers after the node would change how its blocks pair up.
+fn is_block_opener(kind: SyntaxKind) -> bool {
+    matches!(kind, T![function] | T![if] | T![while] | T![for] | T![do] | T![repeat])
+}
+
+fn brackets_are_balanced(tokens: &[Token]) -> bool {
+    let mut stack = Vec::new();
+    for token in tokens {
+        let closing = match token.kind {
+            T!['('] => T![')'],
+            T!['{'] => T!['}'],
+            T!['['] => T![']'],
+            T![')'] | T!['}'] | T![']'] => {
+                if stack.pop() != Some(token.kind) {
+                    return false;
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        stack.push(closing);
+    }
+    stack.is_empty()
+}
 
-    let mut token_source = TextTokenSource::new(&text, &tokens);
-    let tree_sink = TextTreeSink::new(&text, &tokens);
+/// Whether the error is the parser's report of a block without `end`.
+fn is_unclosed_block_error(error: &SyntaxError) -> bool {
+    let message = error.to_string();
+    message.contains(" at line ") && (message.ends_with("`end`") || message.ends_with("`until`"))
+}
+
+fn node_tokens(node: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    node.descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| !it.kind().is_trivia())
+}
+
+fn first_kind(node: &SyntaxNode) -> Option<SyntaxKind> {
+    node_tokens(node).next().map(|it| it.kind())
+}
 
-    let (green, mut new_parser_errors) = reparser.parse(&mut token_source, tree_sink);
+fn next_non_trivia(token: &SyntaxToken) -> Option<SyntaxToken> {
+    let mut next = token_after(token);
+    while let Some(token) = &next {
+        if !token.kind().is_trivia() {
+            break;
+        }
+        next = token_after(token);
+    }
+    next
+}
 
-    new_parser_errors.extend(new_lexer_errors);
+/// The token after `token`, skipping over the empty nodes that
+/// `SyntaxToken::next_token` stops at.
+fn token_after(token: &SyntaxToken) -> Option<SyntaxToken> {
+    let mut element = SyntaxElement::from(token.clone());
+    loop {
+        while let Some(sibling) = element.next_sibling_or_token() {
+            let first = match &sibling {
+                NodeOrToken::Token(it) => Some(it.clone()),
+                NodeOrToken::Node(it) => {
+                    it.descendants_with_tokens().find_map(|it| it.into_token())
+                }
+            };
+            if first.is_some() {
+                return first;
+            }
+            element = sibling;
+        }
+        element = element.parent()?.into();
+    }
+}
 
-    Some((node.replace_with(green), new_parser_errors, node.text_range()))
+/// The zero based line of `offset`.
+fn line_of(root: &SyntaxNode, offset: TextSize) -> u32 {
+    let mut line = 0;
+    root.text().slice(..offset).for_each_chunk(|chunk| line += chunk.matches('\n').count());
+    line as u32
 }
 
 fn get_text_after_edit(element: SyntaxElement, edit: &Indel) -> String {
@@ -92,25 +367,20 @@ fn get_text_after_edit(element: SyntaxElement, edit: &Indel) -> String {
     text
 }
 
-fn find_reparsable_node(node: &SyntaxNode, range: TextRange) -> Option<(SyntaxNode, Reparser)> {
-    let node = node.covering_element(range);
-
-    node.ancestors().find_map(|node| Reparser::for_node(node.kind()).map(|r| (node, r)))
-}
-
-fn is_balanced(tokens: &[Token]) -> bool {
-    if tokens.is_empty() || tokens.last().unwrap().kind != T![end] {
-        return false;
-    }
-    true
-}
-
+/// Keeps the old errors outside of the reparsed range, moving the ones after it
+/// and stretching the ones of its ancestors, and adds the new errors.
+/// `after_at_end` decides whether an empty error at the very end of the range
+/// comes after it.
 fn merge_errors(
     old_errors: Vec<SyntaxError>,
     new_errors: Vec<SyntaxError>,
     range_before_reparse: TextRange,
     edit: &Indel,
+    after_at_end: bool,
 ) -> Vec<SyntaxError> {
+    let (start, end) = (range_before_reparse.start(), range_before_reparse.end());
+    // the offset is after the edit so this can't underflow
+    let shift = |offset: TextSize| offset + TextSize::of(&edit.insert) - edit.delete.len();
     let mut res = Vec::new();
 
     for old_err in old_errors {
@@ -118,15 +388,24 @@ fn merge_errors(
 
         // checking if errors were before or after the reparse
         // we do not keep errors inside of the reparse
-        if old_err_range.end() <= range_before_reparse.start() {
+        if old_err_range.end() <= start {
             res.push(old_err);
-        } else if old_err_range.start() >= range_before_reparse.end() {
-            res.push(old_err.with_range(old_err_range + edit.offset()));
+        } else if old_err_range.start() > end
+            || (old_err_range.start() == end && (after_at_end || !old_err_range.is_empty()))
+        {
+            let range = TextRange::new(shift(old_err_range.start()), shift(old_err_range.end()));
+            res.push(old_err.with_range(range));
+        } else if old_err_range.start() <= start
+            && old_err_range.end() >= end
+            && old_err_range != range_before_reparse
+        {
+            let range = TextRange::new(old_err_range.start(), shift(old_err_range.end()));
+            res.push(old_err.with_range(range));
         }
     }
 
     res.extend(new_errors.into_iter().map(|new_err| {
-        let offseted_range = new_err.range() + range_before_reparse.start();
+        let offseted_range = new_err.range() + start;
         new_err.with_range(offseted_range)
     }));
 
@@ -135,7 +414,9 @@ fn merge_errors(
 
 #[cfg(test)]
 mod tests {
-    use crate::{AstNode, Parse, SourceFile};
+    use crate::{fuzz::CheckReparse, AstNode, Parse, SourceFile};
+    use std::{fs, path::Path};
+
     use test_utils::{assert_eq_text, extract_range};
 
     use super::*;
@@ -149,11 +430,13 @@ mod tests {
             after
         };
 
-        let fully_reparsed = SourceFile::parse(&after);
+        let version = LuaVersion::default();
+        let fully_reparsed = SourceFile::parse(&after, version);
         let incrementally_reparsed: Parse<SourceFile> = {
-            let before = SourceFile::parse(&before);
+            let before = SourceFile::parse(&before, version);
             let (green, new_errors, range) =
-                incremental_reparse(before.tree().syntax(), &edit, before.errors.to_vec()).unwrap();
+                incremental_reparse(before.tree().syntax(), &edit, before.errors.to_vec(), version)
+                    .unwrap();
             assert_eq!(range.len(), reparsed_len.into(), "reparsed fragment has wrong length");
             Parse::new(green, new_errors)
         };
@@ -201,5 +484,184 @@ local person = "$0$0"
             "my name",
             2,
         );
+
+        check(
+            r#"
+local person = "$0$0"
+              "#,
+            r"\q",
+            2,
+        );
+    }
+
+    #[test]
+    fn reparse_blocks() {
+        check(
+            r"
+local function f()
+    local a = $01$0
+    return a
+end
+",
+            "{ 1, 2 }\n    print(a)",
+            24,
+        );
+
+        check(
+            r"
+if a then
+    print(a)
+else
+    print($0b$0)
+end
+",
+            "b)\n    print(c",
+            8,
+        );
+    }
+
+    #[test]
+    fn reparse_stmts() {
+        check(
+            r"
+local a = 1
+while a do
+    a = $0false$0
+end
+local b = 2
+",
+            "nil",
+            9,
+        );
+
+        check(
+            r"
+print(1)
+x = $0y$0
+print(2)
+",
+            "z + 1",
+            5,
+        );
+    }
+
+    #[test]
+    fn reparse_table_constructors() {
+        check(
+            r"
+local data = {
+    { name = 'a', value = 1 },
+    { name = 'b', value = $02$0 },
+}
+",
+            "3, extra = true",
+            25,
+        );
+    }
+
+    #[test]
+    fn reparse_matches_full_parse() {
+        const SOURCE: &str = r#"
+local data = {
+    { name = "a", value = 1, tags = { "x", "y" } },
+    { name = "b", value = function(x) return x * 2 end },
+}
+
+local function sum(t)
+    local total = 0
+    for _, it in ipairs(t) do
+        if type(it.value) == "number" then
+            total = total + it.value
+        elseif it.value then
+            total = total + it.value(1)
+        end
+    end
+    return total
+end
+
+repeat
+    local x = sum(data)
+until x > 0
+"#;
+        const INSERTS: &[&str] = &[
+            "",
+            " ",
+            "\n",
+            "x",
+            "1",
+            "(",
+            ")",
+            "{",
+            "}",
+            ",",
+            ";",
+            "=",
+            ".",
+            "+",
+            "~",
+            "<",
+            ">",
+            "<const>",
+            "end",
+            "do ",
+            "then ",
+            "until x",
+            "function() ",
+            "if a then ",
+            "local ",
+            "return ",
+            "'s'",
+            "--c\n",
+            "::l::",
+            "\\q",
+            "\"",
+            "[[",
+            "]]",
+        ];
+
+        let mut sources = vec![SOURCE.to_string()];
+        for dir in ["ok", "err"] {
+            let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("snapshots/parser").join(dir);
+            let mut paths: Vec<_> = fs::read_dir(dir)
+                .unwrap()
+                .map(|it| it.unwrap().path())
+                .filter(|it| it.extension().unwrap_or_default() == "lua")
+                .collect();
+            paths.sort();
+            sources.extend(paths.iter().map(|it| fs::read_to_string(it).unwrap()));
+        }
+
+        // changing the indentation changes how unclosed blocks are paired
+        let missing_end = include_str!("../../snapshots/parser/err/missing_end.lua");
+        for (start, end, insert) in [(55, 55, " "), (163, 168, "")] {
+            let edit = Indel::replace(TextRange::new(start.into(), end.into()), insert.to_string());
+            CheckReparse::new(missing_end.to_string(), edit).unwrap().run();
+        }
+
+        for seed in 1..=4u64 {
+            // a xorshift, so that failures can be reproduced
+            let mut state = 0x2545_f491_4f6c_dd1d_u64.wrapping_mul(seed);
+            let mut rand = |n: usize| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as usize % n
+            };
+
+            for text in &sources {
+                for _ in 0..30 {
+                    let start = rand(text.len() + 1);
+                    let end = (start + rand(8)).min(text.len());
+                    let insert = INSERTS[rand(INSERTS.len())].to_string();
+                    let edit = Indel::replace(
+                        TextRange::new((start as u32).into(), (end as u32).into()),
+                        insert,
+                    );
+                    if let Some(check) = CheckReparse::new(text.clone(), edit) {
+                        check.run();
+                    }
+                }
+            }
+        }
     }
 }
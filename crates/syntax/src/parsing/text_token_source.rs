@@ -1,6 +1,6 @@
 //! See `TextTokenSource` docs.
 
-use parser::{Token, TokenSource, T};
+use parser::{Token, TokenPos, TokenSource, T};
 
 use rowan::{TextRange, TextSize};
 
@@ -9,6 +9,8 @@ pub(crate) struct TextTokenSource<'t> {
     text: &'t str,
 
     token_offset_pairs: Vec<(Token, TextSize)>,
+    /// The position of every token in `token_offset_pairs`
+    positions: Vec<TokenPos>,
     // tokens: Vec<Token>,
 
     // /// Current token and position
@@ -30,9 +32,20 @@ impl<'t> TextTokenSource<'t> {
             })
             .collect();
 
+        let positions = token_positions(text, raw_tokens);
+
         let first = mk_token(0, &token_offset_pairs, TextRange::new(0.into(), 0.into()));
 
-        TextTokenSource { text, token_offset_pairs, curr: (first, 0) }
+        TextTokenSource { text, token_offset_pairs, positions, curr: (first, 0) }
+    }
+
+    /// Offsets the lines of every token, for text that doesn't start at the
+    /// beginning of its file.
+    pub(crate) fn starting_at_line(mut self, line: u32) -> Self {
+        for pos in self.positions.iter_mut() {
+            pos.line += line;
+        }
+        self
     }
 
     fn curr_range(&self) -> TextRange {
@@ -60,6 +73,18 @@ impl<'t> TokenSource for TextTokenSource<'t> {
         mk_token(self.curr.1 + n, &self.token_offset_pairs, self.curr_range())
     }
 
+    fn lookahead_nth_pos(&self, n: usize) -> TokenPos {
+        match self.positions.get(self.curr.1 + n) {
+            Some(pos) => *pos,
+            // eof is on a line of its own
+            None => TokenPos {
+                line: self.positions.last().map_or(0, |it| it.line + 1),
+                indent: 0,
+                line_start: true,
+            },
+        }
+    }
+
     fn bump(&mut self) -> Option<Token> {
         if self.curr.0.kind == T![eof] {
             return None;
@@ -75,6 +100,38 @@ impl<'t> TokenSource for TextTokenSource<'t> {
     }
 }
 
+/// Computes the position of every non trivia token.
+fn token_positions(text: &str, raw_tokens: &[Token]) -> Vec<TokenPos> {
+    let mut positions = Vec::new();
+    let mut offset = TextSize::from(0);
+    let mut pos = TokenPos { line: 0, indent: 0, line_start: true };
+
+    for token in raw_tokens {
+        let token_text = &text[TextRange::at(offset, token.len)];
+        offset += token.len;
+
+        if token.kind == T![whitespace] {
+            if let Some(last_line) = token_text.rfind('\n') {
+                pos.line += token_text.matches('\n').count() as u32;
+                pos.indent = token_text[last_line + 1..].chars().count() as u32;
+                pos.line_start = true;
+            } else if pos.line_start {
+                pos.indent += token_text.chars().count() as u32;
+            }
+            continue;
+        }
+
+        if !token.kind.is_trivia() {
+            positions.push(pos);
+        }
+        // strings and comments can span lines
+        pos.line += token_text.matches('\n').count() as u32;
+        pos.line_start = false;
+    }
+
+    positions
+}
+
 fn mk_token(pos: usize, tokens: &[(Token, TextSize)], eof_range: TextRange) -> Token {
     match tokens.get(pos) {
         Some((token, _offset)) => *token,
@@ -9,6 +9,8 @@ pub struct TextTreeSink<'a> {
     tokens: &'a [Token],
     text_pos: TextSize,
     token_pos: usize,
+    /// The range of the last non trivia token
+    last_token_range: TextRange,
     inner: SyntaxTreeBuilder,
     error_ranges: Vec<TextRange>,
 }
@@ -18,6 +20,7 @@ impl<'a> TreeSink for TextTreeSink<'a> {
 
     fn token(&mut self) {
         self.eat_trivias();
+        self.last_token_range = TextRange::at(self.text_pos, self.current_token().len);
         self.do_token();
     }
 
@@ -45,6 +48,10 @@ impl<'a> TreeSink for TextTreeSink<'a> {
         self.inner.error(error, TextRange::empty(self.text_pos))
     }
 
+    fn token_error(&mut self, error: ParseError) {
+        self.inner.error(error, self.last_token_range)
+    }
+
     fn finish(self) -> (GreenNode, Vec<SyntaxError>) {
         self.inner.finish_raw()
     }
@@ -57,6 +64,7 @@ impl<'a> TextTreeSink<'a> {
             tokens,
             text_pos: 0.into(),
             token_pos: 0,
+            last_token_range: TextRange::empty(0.into()),
             inner: SyntaxTreeBuilder::default(),
             error_ranges: Vec::new(),
         }
@@ -158,6 +166,10 @@ impl<'a> TreeSink for DeferedTextTreeSink<'a> {
         self.sink.error(error)
     }
 
+    fn token_error(&mut self, error: ParseError) {
+        self.sink.token_error(error)
+    }
+
     fn start_error_node(&mut self) {
         assert!(self.started == self.defer, "There should be no errors when starting");
 
@@ -1,4 +1,5 @@
 use std::{
+    fmt,
     hash::{Hash, Hasher},
     iter::successors,
     marker::PhantomData,
@@ -48,6 +49,12 @@ impl<N: AstNode> Clone for AstPtr<N> {
     }
 }
 
+impl<N: AstNode + fmt::Debug> fmt::Debug for AstPtr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AstPtr").field("raw", &self.raw).finish()
+    }
+}
+
 impl<N: AstNode> Eq for AstPtr<N> {}
 
 impl<N: AstNode> PartialEq for AstPtr<N> {
@@ -86,9 +93,9 @@ impl<N: AstNode> AstPtr<N> {
 
 #[test]
 fn test_syntax_ptr() {
-    use crate::{ast, AstNode, SourceFile};
+    use crate::{ast, AstNode, LuaVersion, SourceFile};
 
-    let file = SourceFile::parse("local function foo(n) end").ok().unwrap();
+    let file = SourceFile::parse("local function foo(n) end", LuaVersion::default()).ok().unwrap();
     let function = file.syntax().descendants().find_map(ast::LocalFunctionDefStmt::cast).unwrap();
     let ptr = SyntaxNodePtr::new(function.syntax());
     let function_syntax = ptr.to_node(file.syntax());
@@ -11,14 +11,14 @@ use std::{
 #[test]
 fn parser() {
     dir_tests(snapshots_dir(), &["parser/ok"], |path, text| {
-        let parse = SourceFile::parse(text);
+        let parse = SourceFile::parse(text, LuaVersion::default());
         let errors = parse.errors();
         assert_errors_are_absent(errors, path);
         dump_parse(parse)
     });
 
     dir_tests(snapshots_dir(), &["parser/err"], |path, text| {
-        let parse = SourceFile::parse(text);
+        let parse = SourceFile::parse(text, LuaVersion::default());
         let errors = parse.errors();
         assert_errors_are_present(errors, path);
         dump_parse(parse)
@@ -28,14 +28,14 @@ fn parser() {
 #[test]
 fn lexer() {
     dir_tests(snapshots_dir(), &["lexer/ok"], |path, text| {
-        let (tokens, errors) = tokenize::<LuaLexer>(text);
+        let (tokens, errors) = tokenize::<LuaLexer>(text, LuaVersion::default());
         assert_errors_are_absent(&errors, path);
         assert_unknowns_are_absent(&tokens, path);
         format!("{:#?}", tokens)
     });
 
     dir_tests(snapshots_dir(), &["lexer/err"], |path, text| {
-        let (tokens, errors) = tokenize::<LuaLexer>(text);
+        let (tokens, errors) = tokenize::<LuaLexer>(text, LuaVersion::default());
         assert_errors_or_unknowns_are_present(&errors, &tokens, path);
         format!("{:#?}\n\n{:#?}", tokens, errors)
     })
@@ -181,7 +181,7 @@ fn project_root() -> PathBuf {
 
 #[test]
 fn nothing() {
-    insta::assert_debug_snapshot!(SourceFile::parse("").syntax_node(), @r###"
+    insta::assert_debug_snapshot!(SourceFile::parse("", LuaVersion::default()).syntax_node(), @r###"
     SourceFile@0..0
       Block@0..0
     "###)
@@ -189,10 +189,126 @@ fn nothing() {
 
 #[test]
 fn fuzz1() {
-    SourceFile::parse("e,,");
+    SourceFile::parse("e,,", LuaVersion::default());
 }
 
 #[test]
 fn local_only() {
-    SourceFile::parse("local");
+    SourceFile::parse("local", LuaVersion::default());
+}
+
+fn version_errors(text: &str, version: LuaVersion) -> Vec<String> {
+    let parse = SourceFile::parse(text, version);
+    parse.errors().iter().map(|it| format!("{:?}: {}", it.range(), it)).collect()
+}
+
+#[test]
+fn lua_version_operators() {
+    let text = "local x = a & b // c | ~d";
+    assert!(version_errors(text, LuaVersion::Lua54).is_empty());
+    assert_eq!(
+        version_errors(text, LuaVersion::LuaJit),
+        [
+            "21..22: `|` is not supported in LuaJIT",
+            "12..13: `&` is not supported in LuaJIT",
+            "16..18: `//` is not supported in LuaJIT",
+            "23..24: `~` is not supported in LuaJIT",
+        ]
+    );
+}
+
+#[test]
+fn lua_version_goto() {
+    assert!(version_errors("goto done ::done::", LuaVersion::Lua52).is_empty());
+    assert!(version_errors("local goto = 1 print(goto)", LuaVersion::Lua51).is_empty());
+    assert_eq!(
+        version_errors("::done::", LuaVersion::Lua51),
+        ["0..8: Labels are not supported in Lua 5.1"]
+    );
+}
+
+#[test]
+fn lua_version_number_suffixes() {
+    assert!(version_errors("local x = 1LL + 0x10ull + 2i", LuaVersion::LuaJit).is_empty());
+    assert_eq!(
+        version_errors("local x = 1LL", LuaVersion::Lua53),
+        ["10..13: Number suffixes are only supported by LuaJIT"]
+    );
+}
+
+#[test]
+fn local_attribs() {
+    assert_eq!(
+        version_errors("local a <close>, b <close>, c <static> = f()", LuaVersion::Lua54),
+        [
+            "19..26: Only one variable of a declaration can be `<close>`",
+            "30..38: Unknown attribute `static`, expected `const` or `close`",
+        ]
+    );
+    assert_eq!(
+        version_errors("local a <const> = 1", LuaVersion::Lua53),
+        ["8..15: Attributes are not supported in Lua 5.3"]
+    );
+}
+
+#[test]
+fn return_must_be_last() {
+    assert!(version_errors("if a then return 1; end return", LuaVersion::default()).is_empty());
+    assert_eq!(
+        version_errors("return 1; local x = 2", LuaVersion::default()),
+        ["0..8: `return` must be the last statement of a block"]
+    );
+}
+
+#[test]
+fn number_values() {
+    use ast::{NumberError, NumberValue};
+
+    let value = |text: &str| {
+        let parse = SourceFile::parse(&format!("return {}", text), LuaVersion::LuaJit);
+        let literal = parse.tree().syntax().descendants().find_map(ast::Literal::cast).unwrap();
+        literal.number_value().unwrap()
+    };
+    assert_eq!(value("42"), Ok(NumberValue::Int(42)));
+    assert_eq!(value("0XfF"), Ok(NumberValue::Int(255)));
+    assert_eq!(value("0xffffffffffffffff"), Ok(NumberValue::Int(-1)));
+    assert_eq!(value("9223372036854775808"), Ok(NumberValue::Float(9223372036854775808.0)));
+    assert_eq!(value(".5"), Ok(NumberValue::Float(0.5)));
+    assert_eq!(value("1e+2"), Ok(NumberValue::Float(100.0)));
+    assert_eq!(value("0x1.8p3"), Ok(NumberValue::Float(12.0)));
+    assert_eq!(value("0xA.8P-1"), Ok(NumberValue::Float(5.25)));
+    assert_eq!(value("18446744073709551615ULL"), Ok(NumberValue::Int(-1)));
+    assert_eq!(value("2i"), Ok(NumberValue::Imaginary(2.0)));
+    assert_eq!(value("9223372036854775808LL"), Err(NumberError::Overflow));
+    assert_eq!(value("0x1p"), Err(NumberError::Malformed));
+}
+
+#[test]
+fn number_errors() {
+    assert_eq!(
+        version_errors("local x = 9223372036854775808LL", LuaVersion::LuaJit),
+        ["10..31: Integer is too large for 64 bits"]
+    );
+    assert_eq!(version_errors("local x = 1.5LL", LuaVersion::LuaJit), ["10..15: Malformed number"]);
+}
+
+#[test]
+fn string_values() {
+    let value = |text: &str| {
+        let parse = SourceFile::parse(&format!("return {}", text), LuaVersion::default());
+        assert_eq!(parse.errors(), &[] as &[SyntaxError]);
+        let literal = parse.tree().syntax().descendants().find_map(ast::Literal::cast).unwrap();
+        match literal.kind() {
+            ast::LiteralKind::Str(it) => it.value().into_owned(),
+            it => panic!("{:?}", it),
+        }
+    };
+    assert_eq!(value(r#""plain""#), "plain");
+    assert_eq!(value(r#"'\a\t\"\'\\'"#), "\x07\t\"'\\");
+    assert_eq!(value(r#""\65\066\0677""#), "ABC7");
+    assert_eq!(value(r#""\x41\u{48}\u{20AC}""#), "AH\u{20AC}");
+    assert_eq!(value("\"a\\z  \n   b\""), "ab");
+    assert_eq!(value("\"a\\\nb\""), "a\nb");
+    assert_eq!(value("[==[\nfirst\\n]]\n]==]"), "first\\n]]\n");
+    assert_eq!(value(r#""\xff""#), "\u{FFFD}");
 }
@@ -3,8 +3,12 @@ use std::str::{CharIndices, Chars};
 use rowan::{TextLen, TextRange, TextSize};
 
 use crate::{
-    ast::{self, AstNode},
-    match_ast, SyntaxError, SyntaxNode, T,
+    ast::{
+        self,
+        token_ext::{decode_escape, unquote},
+        AstNode,
+    },
+    match_ast, LuaOp, LuaVersion, SyntaxError, SyntaxNode, SyntaxToken,
 };
 
 /// A helper macro to like the ? operator but pushes to acc when there is an error.
@@ -20,7 +24,7 @@ macro_rules! try_acc {
     };
 }
 
-pub(crate) fn validate(root: &SyntaxNode) -> Vec<SyntaxError> {
+pub(crate) fn validate(root: &SyntaxNode, version: LuaVersion) -> Vec<SyntaxError> {
     let mut errors = Vec::new();
     let acc = &mut errors;
 
@@ -32,15 +36,69 @@ pub(crate) fn validate(root: &SyntaxNode) -> Vec<SyntaxError> {
                 ast::ExprStmt(it)
                     | ast::Literal(it)
                     | ast::AssignStmt(it)
+                    | ast::LocalAssignStmt(it)
+                    | ast::ReturnStmt(it)
                     => it.validate(acc),
                 _ => (),
             }
         }
+        validate_version(&node, version, acc);
     }
 
     errors
 }
 
+/// Checks that the constructs of newer versions aren't used in older ones.
+fn validate_version(node: &SyntaxNode, version: LuaVersion, acc: &mut Vec<SyntaxError>) {
+    match_ast! {
+        match node {
+            ast::InfixExpr(it) => {
+                if let Some((token, op)) = it.op_details() {
+                    validate_op(token, op, version, acc);
+                }
+            },
+            ast::PrefixExpr(it) => {
+                if let Some((token, op)) = it.op_details() {
+                    validate_op(token, op, version, acc);
+                }
+            },
+            ast::Attrib(it) => {
+                if !version.has_attribs() {
+                    acc.push(SyntaxError::new(
+                        format!("Attributes are not supported in {}", version),
+                        it.range(),
+                    ));
+                }
+            },
+            ast::LabelStmt(it) => {
+                if !version.has_goto() {
+                    acc.push(SyntaxError::new(
+                        format!("Labels are not supported in {}", version),
+                        it.range(),
+                    ));
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+fn validate_op(token: SyntaxToken, op: LuaOp, version: LuaVersion, acc: &mut Vec<SyntaxError>) {
+    let supported = match op {
+        LuaOp::BitAnd | LuaOp::BitOr | LuaOp::BitXor | LuaOp::Shl | LuaOp::Shr => {
+            version.has_bitwise_ops()
+        }
+        LuaOp::FloorDiv => version.has_floor_div(),
+        _ => true,
+    };
+    if !supported {
+        acc.push(SyntaxError::new(
+            format!("`{}` is not supported in {}", token.text(), version),
+            token.text_range(),
+        ));
+    }
+}
+
 /// A trait to validate parts of an ast
 trait Validate: AstNode {
     /// Validate this part of the ast
@@ -116,17 +174,63 @@ impl Validate for ast::AssignStmt {
     }
 }
 
+impl Validate for ast::LocalAssignStmt {
+    fn validate(self, acc: &mut Vec<SyntaxError>) {
+        let attribs = self.multi_name().into_iter().flat_map(|it| it.attribs());
+        let mut has_close = false;
+        for attrib in attribs {
+            let ident = match attrib.ident_token() {
+                Some(it) => it,
+                None => continue,
+            };
+            match attrib.kind() {
+                Some(ast::AttribKind::Close) if has_close => acc.push(SyntaxError::new(
+                    "Only one variable of a declaration can be `<close>`".to_string(),
+                    attrib.range(),
+                )),
+                Some(ast::AttribKind::Close) => has_close = true,
+                Some(ast::AttribKind::Const) => (),
+                None => acc.push(SyntaxError::new(
+                    format!("Unknown attribute `{}`, expected `const` or `close`", ident.text()),
+                    attrib.range(),
+                )),
+            }
+        }
+    }
+}
+
+impl Validate for ast::ReturnStmt {
+    fn validate(self, acc: &mut Vec<SyntaxError>) {
+        if self.syntax().next_sibling().is_some() {
+            acc.push(SyntaxError::new(
+                "`return` must be the last statement of a block".to_string(),
+                self.range(),
+            ));
+        }
+    }
+}
+
 impl Validate for ast::Literal {
     fn validate(self, acc: &mut Vec<SyntaxError>) {
         let token = self.token();
         let text = token.text();
         match self.kind() {
-            ast::LiteralKind::Str(_) => {
-                if let Some((offset, unquoted)) = unquote(&text) {
+            // long bracket strings don't have escapes
+            ast::LiteralKind::Str(_) if !text.starts_with('[') => {
+                if let Some((offset, unquoted)) = unquote(text) {
                     unescape(unquoted, token.text_range().start() + offset, acc)
                 }
             }
-            ast::LiteralKind::Number(_)
+            ast::LiteralKind::Number(it) => {
+                // malformed numbers are already reported by the lexer
+                if it.value() == Err(ast::NumberError::Overflow) {
+                    acc.push(SyntaxError::new(
+                        "Integer is too large for 64 bits".to_string(),
+                        token.text_range(),
+                    ));
+                }
+            }
+            ast::LiteralKind::Str(_)
             | ast::LiteralKind::Bool(_)
             | ast::LiteralKind::Nil
             | ast::LiteralKind::Vararg => (),
@@ -134,42 +238,6 @@ impl Validate for ast::Literal {
     }
 }
 
-/// Unquotes a lua string. This will panic if something wrong happens
-/// because the string passed in should be correctly quoted, or we have a problem with
-/// the lexer.
-fn unquote(mut text: &str) -> Option<(TextSize, &str)> {
-    let delimit = text.chars().next().unwrap();
-
-    if delimit == '\'' || delimit == '\"' {
-        if !text.ends_with(delimit) || text.len() == 1 {
-            return None;
-        }
-        Some((1.into(), &text[1..text.len() - 1]))
-    } else if delimit == '[' {
-        // unquote front
-        text = &text[1..];
-        let equals = text.bytes().take_while(|c| *c == '=' as u8).count();
-        text = &text[equals..];
-        text = if Some('[' as u8) == text.bytes().next() { &text[1..] } else { &text };
-
-        // unquote back
-        if text.bytes().last() == Some(']' as u8) {
-            text = &text[..text.len() - 1];
-            let back_equals = text.bytes().rev().take_while(|c| *c == '=' as u8).count();
-            // assert_eq!(equals, back_equals, "Front and back equals must be the same");
-            text = &text[..text.len() - back_equals];
-            if text.bytes().last() == Some(']' as u8) {
-                text = &text[..text.len() - 1]
-            }
-        };
-
-        let offset = TextSize::from(2 + equals as u32);
-        Some((offset, text))
-    } else {
-        unreachable!();
-    }
-}
-
 const ESCAPE_MSG: &str = "Invalid escape sequence";
 
 fn unescape(s: &str, start: TextSize, acc: &mut Vec<SyntaxError>) {
@@ -191,21 +259,9 @@ fn unescape_once(
 ) {
     let pos = text_size - chars.as_str().text_len() - first.text_len();
 
-    let make_range = |second: Option<TextSize>| {
-        TextRange::at(pos, TextSize::from(1) + second.unwrap_or(0.into())) + offset
-    };
-
-    if first == '\\' {
-        match chars.next() {
-            None => acc.push(SyntaxError::new(ESCAPE_MSG.to_string(), make_range(None))),
-            Some(second) => match second {
-                'a' | 'b' | 'f' | 'n' | 'r' | 't' | 'v' | '\\' | '\"' | '\'' | '[' | ']' => (),
-                _ => acc.push(SyntaxError::new(
-                    ESCAPE_MSG.to_string(),
-                    make_range(Some(second.text_len())),
-                )),
-            },
-        }
+    if first == '\\' && decode_escape(chars, &mut Vec::new()).is_err() {
+        let len = text_size - chars.as_str().text_len() - pos;
+        acc.push(SyntaxError::new(ESCAPE_MSG.to_string(), TextRange::at(pos, len) + offset));
     }
 }
 
@@ -1,4 +1,7 @@
-use syntax::ast::{self, AstNode};
+use syntax::{
+    ast::{self, AstNode},
+    LuaVersion,
+};
 
 fn stmt(program: ast::SourceFile) -> ast::Stmt {
     program.body().unwrap().stmts().next().unwrap()
@@ -14,6 +17,7 @@ local function testing(first, second, third)
     local new = first + second
     return new^324 .. "adfadsf"
 end"#,
+        LuaVersion::default(),
     )
     .ok()
     .unwrap();
@@ -1,4 +1,4 @@
-use syntax::SourceFile;
+use syntax::{LuaVersion, SourceFile};
 
 #[test]
 #[ignore]
@@ -137,5 +137,5 @@ fn stack_overflow() {
     ];
 
     let s = std::str::from_utf8(&array).unwrap();
-    SourceFile::parse(s);
+    SourceFile::parse(s, LuaVersion::default());
 }
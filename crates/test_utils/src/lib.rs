@@ -65,6 +65,62 @@ fn try_extract_range(text: &str) -> Option<(TextRange, String)> {
     Some((TextRange::new(start, end), text))
 }
 
+/// A file of a multi-file fixture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureFile {
+    pub path: String,
+    pub text: String,
+}
+
+/// Splits a fixture into files, each of which starts with a `//- /path.lua`
+/// header line. A fixture without any header is a single `/main.lua` file.
+pub fn parse_fixture(fixture: &str) -> Vec<FixtureFile> {
+    let fixture = fixture.strip_prefix('\n').unwrap_or(fixture);
+    if !fixture.trim_start().starts_with("//-") {
+        return vec![FixtureFile { path: "/main.lua".to_string(), text: fixture.to_string() }];
+    }
+
+    let mut res: Vec<FixtureFile> = Vec::new();
+    for line in fixture.split_inclusive('\n') {
+        match line.trim_start().strip_prefix("//-") {
+            Some(path) => {
+                res.push(FixtureFile { path: path.trim().to_string(), text: String::new() })
+            }
+            None => match res.last_mut() {
+                Some(file) => file.text.push_str(line),
+                None => assert!(line.trim().is_empty(), "text before the first file header"),
+            },
+        }
+    }
+    res
+}
+
+/// Extracts the ranges marked by annotation comments such as `--^^^ text`,
+/// where the carets are below the text of the previous line they point at.
+/// Returns each range along with the text following the carets.
+pub fn extract_annotations(text: &str) -> Vec<(TextRange, String)> {
+    let mut res = Vec::new();
+    let mut prev_line_start = None;
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let annotation = line.trim_start().strip_prefix("--").filter(|it| it.starts_with('^'));
+        match (annotation, prev_line_start) {
+            (Some(annotation), Some(prev_line_start)) => {
+                let start = line.find('^').unwrap();
+                let len = annotation.chars().take_while(|&c| c == '^').count();
+                let range = TextRange::at(
+                    TextSize::from((prev_line_start + start) as u32),
+                    TextSize::from(len as u32),
+                );
+                res.push((range, annotation[len..].trim().to_string()));
+            }
+            _ => prev_line_start = Some(line_start),
+        }
+        line_start += line.len();
+    }
+    res
+}
+
 pub fn format_diff(chunks: Vec<dissimilar::Chunk>) -> String {
     let mut buf = String::new();
     for chunk in chunks {
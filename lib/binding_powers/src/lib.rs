@@ -44,7 +44,9 @@ pub enum PrecType {
 pub struct BindingPowers<const N: usize>([Precedences; N]);
 
 impl<const N: usize> BindingPowers<N> {
-    pub const fn new(kinds: &[(usize, PrecType)]) -> BindingPowers<N> {
+    /// Every entry of `kinds` binds tighter than the ones before it, unless
+    /// it is marked as having the same level as the entry before it.
+    pub const fn new(kinds: &[(usize, PrecType, bool)]) -> BindingPowers<N> {
         let mut res = [Precedences { prefix: None, infix: None, postfix: None }; N];
 
         let mut counter = 1;
@@ -56,12 +58,14 @@ impl<const N: usize> BindingPowers<N> {
             }
 
             let kind_assoc = kinds[idx];
+            let same_level = kind_assoc.2;
 
             match kind_assoc.1 {
                 PrecType::Infix(assoc) => {
-                    counter += 1;
-                    let first = counter;
-                    counter += 1;
+                    if !same_level {
+                        counter += 2;
+                    }
+                    let first = counter - 1;
                     let second = counter;
 
                     let infix = match assoc {
@@ -72,14 +76,18 @@ impl<const N: usize> BindingPowers<N> {
                 }
                 PrecType::Prefix => {
                     let second = {
-                        counter += 1;
+                        if !same_level {
+                            counter += 1;
+                        }
                         counter
                     };
                     res[kind_assoc.0].prefix = Some(((), second));
                 }
                 PrecType::Postfix => {
                     let first = {
-                        counter += 1;
+                        if !same_level {
+                            counter += 1;
+                        }
                         counter
                     };
                     res[kind_assoc.0].postfix = Some((first, ()));
@@ -135,7 +143,10 @@ macro_rules! precedences {
             $crate::__deduplicate_enum!{ $name $($variant)+ }
 
             const __BINDING_POWERS: BindingPowers<{$name::__LAST as usize}>= BindingPowers::new(&[
-                $(($name::$variant as usize, $crate::precedences!($($stuff)+))),+
+                $({
+                    let (prec_type, same_level) = $crate::precedences!($($stuff)+);
+                    ($name::$variant as usize, prec_type, same_level)
+                }),+
             ]);
 
             impl Operator for $name {
@@ -161,11 +172,20 @@ macro_rules! precedences {
         $vis use __precedences_mod::$name;
     };
 
+    (Prefix, Same) => {
+        ($crate::PrecType::Prefix, true)
+    };
+    (Postfix, Same) => {
+        ($crate::PrecType::Postfix, true)
+    };
     ($prec_type:ident) => {
-        $crate::PrecType::$prec_type
+        ($crate::PrecType::$prec_type, false)
     };
     ($prec_type:ident, $assoc:ident) => {
-        ($crate::PrecType::$prec_type($assoc))
+        ($crate::PrecType::$prec_type($assoc), false)
+    };
+    ($prec_type:ident, $assoc:ident, Same) => {
+        ($crate::PrecType::$prec_type($assoc), true)
     };
 }
 
@@ -196,6 +216,30 @@ mod tests {
         assert_eq!(Op::Power.infix_power(), (6, 5));
     }
 
+    #[test]
+    fn same_level() {
+        precedences! {
+            enum Op {
+                #[Infix, Left]
+                Plus,
+
+                #[Infix, Left, Same]
+                Minus,
+
+                #[Prefix]
+                Not,
+
+                #[Prefix, Same]
+                Len,
+            }
+        }
+
+        assert_eq!(Op::Plus.infix_power(), (2, 3));
+        assert_eq!(Op::Minus.infix_power(), (2, 3));
+        assert_eq!(Op::Not.prefix_power(), ((), 4));
+        assert_eq!(Op::Len.prefix_power(), ((), 4));
+    }
+
     #[test]
     fn lua() {
         precedences! {
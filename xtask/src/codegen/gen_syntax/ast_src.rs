@@ -1,3 +1,5 @@
+use std::iter;
+
 use heck::SnakeCase;
 use quote::{format_ident, quote};
 use ungrammar::{Grammar, Rule};
@@ -45,7 +47,11 @@ fn node_is_manually_implemented(node: &str) -> bool {
 
 impl Field {
     pub fn is_many(&self) -> bool {
-        matches!(self, Field::Node { cardinality: Cardinality::Many, .. })
+        matches!(
+            self,
+            Field::Node { cardinality: Cardinality::Many, .. }
+                | Field::Node { cardinality: Cardinality::ManyTrailing, .. }
+        )
     }
 
     pub fn is_many_trailing(&self) -> bool {
@@ -90,7 +96,7 @@ impl Field {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) enum Cardinality {
     Optional,
     ManyTrailing,
@@ -211,10 +217,20 @@ fn lower_comma_list(
     label: Option<&String>,
     rule: &Rule,
 ) -> bool {
-    let rule = match rule {
-        Rule::Seq(it) => it,
+    let rule: Vec<&Rule> = match rule {
+        Rule::Seq(it) => it.iter().collect(),
         _ => return false,
     };
+    // an optional node can follow every element, as in `Name Attrib? (',' Name Attrib?)*`
+    let (rule, extra) = match rule.as_slice() {
+        [first, Rule::Opt(extra), rest @ ..] => match &**extra {
+            Rule::Node(extra) => {
+                (iter::once(*first).chain(rest.iter().copied()).collect(), Some(extra))
+            }
+            _ => return false,
+        },
+        _ => (rule, None),
+    };
     let (node, repeat, trailing_comma, cardinality) = match rule.as_slice() {
         [Rule::Node(node), Rule::Rep(repeat), Rule::Opt(trailing_comma)] => {
             (node, repeat, Some(trailing_comma), Cardinality::ManyTrailing)
@@ -223,18 +239,25 @@ fn lower_comma_list(
         _ => return false,
     };
     let repeat = match &**repeat {
-        Rule::Seq(it) => it,
+        Rule::Seq(it) => it.as_slice(),
         _ => return false,
     };
-    match repeat.as_slice() {
+    let repeat = match (repeat, extra) {
+        ([rest @ .., Rule::Opt(opt)], Some(extra)) if **opt == Rule::Node(*extra) => rest,
+        (_, Some(_)) => return false,
+        (repeat, None) => repeat,
+    };
+    match repeat {
         [comma, Rule::Node(n)] if Some(comma) == trailing_comma.map(|t| &**t) && n == node => (),
         [_comma, Rule::Node(n)] if n == node => (),
         _ => return false,
     }
-    let ty = grammar[*node].name.clone();
-    let name = label.cloned().unwrap_or_else(|| pluralize(&ty.to_snake_case()));
-    let field = Field::Node { name, ty, cardinality };
-    acc.push(field);
+    for node in iter::once(node).chain(extra) {
+        let ty = grammar[*node].name.clone();
+        let name = label.cloned().unwrap_or_else(|| pluralize(&ty.to_snake_case()));
+        let field = Field::Node { name, ty, cardinality: cardinality.clone() };
+        acc.push(field);
+    }
     true
 }
 
@@ -45,6 +45,10 @@ fn gen_syntax_kinds(kinds_src: &KindsSrc, ast_src: &AstSrc) -> Result<String> {
             let value = if "{}[]()".contains(token) {
                 let c = token.chars().next().unwrap();
                 quote! { #c }
+            } else if token == "//" {
+                // a joint `//` would start a comment, so this one is `T![/ /]`
+                let c = Punct::new('/', Spacing::Alone);
+                quote! { #c #c }
             } else {
                 let cs = token.chars().map(|c| Punct::new(c, Spacing::Joint));
                 quote! { #(#cs)* }
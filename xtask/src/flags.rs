@@ -12,6 +12,9 @@ xflags::xflags! {
             /// run the lexer fuzz tests instead
             optional -l, --lexer
 
+            /// check incremental reparsing against full parses instead
+            optional --reparse
+
             /// reset the corpus and artifacts
             optional -r, --reset
         }
@@ -43,6 +46,7 @@ pub struct Help {
 #[derive(Debug)]
 pub struct FuzzTests {
     pub lexer: bool,
+    pub reparse: bool,
     pub reset: bool,
 }
 
@@ -24,7 +24,13 @@ impl flags::FuzzTests {
             bail!("fuzz tests require nightly rustc")
         }
 
-        let target = if self.lexer { "lexer" } else { "parser" };
+        let target = if self.lexer {
+            "lexer"
+        } else if self.reparse {
+            "reparse"
+        } else {
+            "parser"
+        };
 
         cmd!("cargo fuzz run {target}").run()?;
 